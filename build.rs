@@ -0,0 +1,18 @@
+use std::process::Command;
+
+fn main() {
+    // Docker builds have no .git directory, so allow GIT_SHA to be passed in
+    let sha = std::env::var("GIT_SHA").ok().or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    });
+
+    println!("cargo:rustc-env=GIT_SHA={}", sha.unwrap_or_else(|| "unknown".to_string()));
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+}
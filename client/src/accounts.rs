@@ -0,0 +1,45 @@
+use bson::oid::ObjectId;
+use bytes::Bytes;
+use hyper::Method;
+
+use crate::{
+    client::{Call, Client},
+    error::ClientError,
+    models::{
+        account::Account,
+        import::{ImportMode, ImportReport},
+    },
+    records::RecordFormat,
+};
+
+impl Client {
+    /// Creates an account. Sent with an `Idempotency-Key`, so it is retried safely.
+    pub async fn create_account(&self, account: &Account) -> Result<Account, ClientError> {
+        let call = Call::new(Method::POST, self.url(&["accounts"])).json(account)?.idempotent();
+        self.fetch(call).await
+    }
+
+    pub async fn get_accounts(&self) -> Result<Vec<Account>, ClientError> {
+        self.get(&["accounts"]).await
+    }
+
+    pub async fn get_account(&self, id: &ObjectId) -> Result<Account, ClientError> {
+        self.get(&["accounts", &id.to_hex()]).await
+    }
+
+    pub async fn update_account(&self, id: &ObjectId, account: &Account) -> Result<Account, ClientError> {
+        self.put(&["accounts", &id.to_hex()], account).await
+    }
+
+    pub async fn delete_account(&self, id: &ObjectId) -> Result<bool, ClientError> {
+        self.delete(&["accounts", &id.to_hex()]).await
+    }
+
+    pub async fn import_accounts(&self, format: RecordFormat, rows: Bytes, mode: ImportMode) -> Result<ImportReport, ClientError> {
+        self.import("accounts:import", format, rows, mode).await
+    }
+
+    pub async fn export_accounts(&self, format: RecordFormat) -> Result<Bytes, ClientError> {
+        self.export("accounts:export", format).await
+    }
+}
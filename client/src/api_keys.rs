@@ -0,0 +1,26 @@
+use bson::oid::ObjectId;
+
+use crate::{
+    client::Client,
+    error::ClientError,
+    models::api_key::{ApiKeySummary, CreateApiKey, CreatedApiKey},
+};
+
+impl Client {
+    /// Mints a key. The plaintext key is only ever returned here.
+    pub async fn create_api_key(&self, request: &CreateApiKey) -> Result<CreatedApiKey, ClientError> {
+        self.post(&["api-keys"], request).await
+    }
+
+    pub async fn get_api_keys(&self) -> Result<Vec<ApiKeySummary>, ClientError> {
+        self.get(&["api-keys"]).await
+    }
+
+    pub async fn get_api_key(&self, id: &ObjectId) -> Result<ApiKeySummary, ClientError> {
+        self.get(&["api-keys", &id.to_hex()]).await
+    }
+
+    pub async fn revoke_api_key(&self, id: &ObjectId) -> Result<bool, ClientError> {
+        self.delete(&["api-keys", &id.to_hex()]).await
+    }
+}
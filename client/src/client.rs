@@ -0,0 +1,213 @@
+use bytes::Bytes;
+use hyper::{header::CONTENT_TYPE, Method, StatusCode};
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+use tracing::debug;
+use url::Url;
+
+use crate::{
+    error::{retry_after, ClientError},
+    retry::{is_repeatable, is_transient, RetryPolicy},
+    transport::{Response, Transport},
+};
+
+const API_KEY_HEADER: &str = "x-api-key";
+const ORG_ID_HEADER: &str = "x-org-id";
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+// Exports are returned whole
+const MAX_RESPONSE_BYTES: usize = 256 * 1024 * 1024;
+const IDEMPOTENCY_KEY_LENGTH: usize = 32;
+// The client's models are this version's
+const API_VERSION: &str = "v1";
+
+/// One call to the API, sent again as the retry policy allows.
+pub(crate) struct Call {
+    pub method: Method,
+    pub url: Url,
+    pub body: Option<Bytes>,
+    pub content_type: Option<&'static str>,
+    // Makes a POST safe to retry, the server replays its first response
+    pub idempotency_key: Option<String>,
+}
+
+impl Call {
+    pub fn new(method: Method, url: Url) -> Self {
+        Self {
+            method,
+            url,
+            body: None,
+            content_type: None,
+            idempotency_key: None,
+        }
+    }
+
+    pub fn json<B: Serialize>(mut self, body: &B) -> Result<Self, ClientError> {
+        let body = serde_json::to_vec(body).map_err(|e| ClientError::Encode(e.to_string()))?;
+        self.body = Some(Bytes::from(body));
+        self.content_type = Some("application/json");
+        Ok(self)
+    }
+
+    pub fn raw(mut self, body: Bytes, content_type: &'static str) -> Self {
+        self.body = Some(body);
+        self.content_type = Some(content_type);
+        self
+    }
+
+    pub fn idempotent(mut self) -> Self {
+        let key: String = OsRng.sample_iter(&Alphanumeric).take(IDEMPOTENCY_KEY_LENGTH).map(char::from).collect();
+        self.idempotency_key = Some(key);
+        self
+    }
+}
+
+/// Typed client for the API, authenticating with an API key and acting for
+/// one organization when `with_org` is set.
+#[derive(Debug, Clone)]
+pub struct Client {
+    pub(crate) http: Transport,
+    base_url: Url,
+    api_key: String,
+    org_id: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl Client {
+    /// Client for the API served at `base_url`, e.g. `https://api.example.com`.
+    pub fn new(base_url: &str, api_key: impl Into<String>) -> Result<Self, ClientError> {
+        let base_url = Transport::validate_url(base_url).map_err(ClientError::BadRequest)?;
+        if base_url.cannot_be_a_base() {
+            return Err(ClientError::BadRequest(format!("{} cannot be a base URL", base_url)));
+        }
+        Ok(Self {
+            http: Self::http(DEFAULT_TIMEOUT),
+            base_url,
+            api_key: api_key.into(),
+            org_id: None,
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    fn http(timeout: Duration) -> Transport {
+        Transport::new(timeout, MAX_RESPONSE_BYTES)
+    }
+
+    /// Acts for the organization `org_id`, as platform keys must for tenant routes.
+    pub fn with_org(mut self, org_id: impl Into<String>) -> Self {
+        self.org_id = Some(org_id.into());
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Time allowed for each attempt of a call.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.http = Self::http(timeout);
+        self
+    }
+
+    /// URL of an API route, under the version the client speaks.
+    pub(crate) fn url(&self, segments: &[&str]) -> Url {
+        self.root_url(&[&[API_VERSION], segments].concat())
+    }
+
+    /// URL of a route served outside the API versions, like the probes.
+    pub(crate) fn root_url(&self, segments: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("checked in Client::new")
+            .pop_if_empty()
+            .extend(segments);
+        url
+    }
+
+    /// Sends `call` until it succeeds, fails for good or runs out of retries,
+    /// and returns the last response whatever its status.
+    pub(crate) async fn send(&self, call: Call) -> Result<Response, ClientError> {
+        self.send_with(call, self.retry).await
+    }
+
+    pub(crate) async fn send_once(&self, call: Call) -> Result<Response, ClientError> {
+        self.send_with(call, RetryPolicy::none()).await
+    }
+
+    /// Headers every call is sent with.
+    pub(crate) fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![(API_KEY_HEADER, self.api_key.clone())];
+        if let Some(org_id) = &self.org_id {
+            headers.push((ORG_ID_HEADER, org_id.clone()));
+        }
+        headers
+    }
+
+    async fn send_with(&self, call: Call, retry: RetryPolicy) -> Result<Response, ClientError> {
+        let mut headers = self.headers();
+        if let Some(content_type) = call.content_type {
+            headers.push((CONTENT_TYPE.as_str(), content_type.to_string()));
+        }
+        if let Some(key) = &call.idempotency_key {
+            headers.push((IDEMPOTENCY_KEY_HEADER, key.clone()));
+        }
+        let repeatable = is_repeatable(&call.method, call.idempotency_key.is_some());
+
+        let mut retries = 0;
+        loop {
+            let result = self
+                .http
+                .execute(call.method.clone(), &call.url, &headers, call.body.clone())
+                .await;
+            let retry_after = match &result {
+                Ok(response) if response.status == StatusCode::TOO_MANY_REQUESTS => retry_after(response),
+                Ok(response) if repeatable && is_transient(response.status) => None,
+                Err(_) if repeatable => None,
+                _ => return result.map_err(ClientError::Transport),
+            };
+            if retries >= retry.max_retries {
+                return result.map_err(ClientError::Transport);
+            }
+            retries += 1;
+            let delay = retry.delay(retries, retry_after);
+            debug!(method = %call.method, url = %call.url, retries, ?delay, "Retrying API call");
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Sends `call` and decodes a successful response as `T`.
+    pub(crate) async fn fetch<T: DeserializeOwned>(&self, call: Call) -> Result<T, ClientError> {
+        let response = self.send(call).await?;
+        decode(success(response)?)
+    }
+
+    pub(crate) async fn get<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T, ClientError> {
+        self.fetch(Call::new(Method::GET, self.url(segments))).await
+    }
+
+    pub(crate) async fn post<B: Serialize, T: DeserializeOwned>(&self, segments: &[&str], body: &B) -> Result<T, ClientError> {
+        self.fetch(Call::new(Method::POST, self.url(segments)).json(body)?).await
+    }
+
+    pub(crate) async fn put<B: Serialize, T: DeserializeOwned>(&self, segments: &[&str], body: &B) -> Result<T, ClientError> {
+        self.fetch(Call::new(Method::PUT, self.url(segments)).json(body)?).await
+    }
+
+    pub(crate) async fn delete(&self, segments: &[&str]) -> Result<bool, ClientError> {
+        self.fetch(Call::new(Method::DELETE, self.url(segments))).await
+    }
+}
+
+pub(crate) fn success(response: Response) -> Result<Response, ClientError> {
+    match response.status.is_success() {
+        true => Ok(response),
+        false => Err(ClientError::from_response(&response)),
+    }
+}
+
+pub(crate) fn decode<T: DeserializeOwned>(response: Response) -> Result<T, ClientError> {
+    serde_json::from_slice(&response.body).map_err(|e| ClientError::Decode(e.to_string()))
+}
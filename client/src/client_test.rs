@@ -0,0 +1,226 @@
+use axum::{
+    extract::Request,
+    http::{header::RETRY_AFTER, StatusCode},
+    middleware::{from_fn, Next},
+    response::IntoResponse,
+    Router,
+};
+use chrono::NaiveDate;
+use futures_util::{Stream, StreamExt};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use telegram_ton_api::{
+    app,
+    repository::outbox_repository::OutboxRepository,
+    service::event_bus::EventBus,
+    test_support::{test_db, test_state, unreachable_db, BOOTSTRAP_KEY},
+};
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    models::{
+        ad_platform::{AdCredentialEntry, AdPlatformCredential, Conversion, SpendQuery, TiktokCredential},
+        batch::BatchStatus,
+        event::{EventData, EventEntity, EventStreamQuery, StreamedEvent},
+        event_id::ConversionStatus,
+        health::CheckStatus,
+        import::ImportMode,
+        project::Project,
+        webhook::WebhookEvent,
+    },
+    Client, ClientError, ObjectId, RecordFormat, RetryPolicy,
+};
+
+async fn spawn(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+fn no_wait() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 3,
+        base_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+    }
+}
+
+#[tokio::test]
+async fn test_errors_mirror_the_server() {
+    let url = spawn(app::router(test_state(unreachable_db().await), CorsLayer::permissive())).await;
+    let client = Client::new(&url, BOOTSTRAP_KEY).unwrap();
+
+    client.healthz().await.unwrap();
+    assert_eq!(client.version().await.unwrap().version, env!("CARGO_PKG_VERSION"));
+    let readiness = client.readyz().await.unwrap();
+    assert_eq!((readiness.status, readiness.checks["mongodb"].status), (CheckStatus::Down, CheckStatus::Down));
+
+    // Platform keys must name the organization they act for
+    let error = client.get_projects().await.unwrap_err();
+    assert!(matches!(&error, ClientError::BadRequest(message) if message.contains("X-Org-Id")), "{:?}", error);
+    assert_eq!(error.status(), Some(StatusCode::BAD_REQUEST));
+
+    let error = client.clone().with_org("not-an-id").get_project(&ObjectId::new()).await.unwrap_err();
+    assert!(matches!(error, ClientError::BadRequest(_)), "{:?}", error);
+
+    let error = Client::new(&url, "unknown-key").unwrap().get_projects().await.unwrap_err();
+    assert!(matches!(error, ClientError::Unauthorized), "{:?}", error);
+
+    let error = Client::new("http://127.0.0.1:1", BOOTSTRAP_KEY).unwrap().with_retry(no_wait()).healthz().await.unwrap_err();
+    assert!(matches!(error, ClientError::Transport(_)), "{:?}", error);
+    assert!(Client::new("ftp://example.com", BOOTSTRAP_KEY).is_err());
+}
+
+#[tokio::test]
+async fn test_retries_follow_the_policy() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    // Every request is answered 503 twice and then 429 once before the router sees it
+    let flaky = from_fn(move |request: Request, next: Next| {
+        let calls = counter.clone();
+        async move {
+            match calls.fetch_add(1, Ordering::SeqCst) % 4 {
+                0 | 1 => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+                2 => (StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, "0")]).into_response(),
+                _ => next.run(request).await,
+            }
+        }
+    });
+    let router = app::router(test_state(unreachable_db().await), CorsLayer::permissive()).layer(flaky);
+    let client = Client::new(&spawn(router).await, BOOTSTRAP_KEY).unwrap().with_retry(no_wait());
+
+    assert_eq!(client.version().await.unwrap().version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 4);
+
+    // POSTs without an Idempotency-Key are not repeated on server errors
+    let error = client.issue_event_id(&ObjectId::new(), "Purchase").await.unwrap_err();
+    assert_eq!(error.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+
+    let error = client.clone().with_retry(RetryPolicy::none()).version().await.unwrap_err();
+    assert!(matches!(error, ClientError::Unexpected { .. }), "{:?}", error);
+    calls.store(2, Ordering::SeqCst);
+    let error = client.with_retry(RetryPolicy::none()).version().await.unwrap_err();
+    assert!(matches!(error, ClientError::RateLimited { retry_after: Some(delay) } if delay.is_zero()), "{:?}", error);
+}
+
+fn project(name: &str) -> Project {
+    serde_json::from_value(serde_json::json!({
+        "name": name,
+        "is_active": true,
+        "is_logging": false,
+        "created_at": { "$date": { "$numberLong": "0" } },
+        "updated_at": { "$date": { "$numberLong": "0" } },
+    }))
+    .unwrap()
+}
+
+fn tiktok() -> AdCredentialEntry {
+    AdCredentialEntry {
+        key: "tiktok".to_string(),
+        credential: AdPlatformCredential::Tiktok(TiktokCredential {
+            app_id: "app".to_string(),
+            app_secret: "secret".to_string(),
+            access_token: "tiktok-token".to_string(),
+            advertiser_id: "7000000001".to_string(),
+            pixel_code: Some("PIXEL".to_string()),
+        }),
+    }
+}
+
+#[tokio::test]
+async fn test_client_manages_projects() {
+    let db = test_db("_client").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let url = spawn(app::router(test_state(db), CorsLayer::permissive())).await;
+    let platform = Client::new(&url, BOOTSTRAP_KEY).unwrap();
+    let org = platform.create_organization("Client").await.unwrap();
+    let client = platform.with_org(org.id.unwrap().to_hex());
+
+    let created = client.create_project(&project("Client")).await.unwrap();
+    let id = created.id.unwrap();
+    assert_eq!(client.get_project(&id).await.unwrap().name, "Client");
+    assert_eq!(client.get_projects().await.unwrap().len(), 1);
+    client.activate_project(&id).await.unwrap();
+
+    let batch = client.batch_get_projects(vec![id.to_hex(), "nope".to_string()]).await.unwrap();
+    let statuses: Vec<_> = batch.results.iter().map(|result| result.status).collect();
+    assert_eq!(statuses, [BatchStatus::Found, BatchStatus::InvalidId]);
+
+    client.add_ad_credential(&id, &tiktok()).await.unwrap();
+    assert!(matches!(client.add_ad_credential(&id, &tiktok()).await, Err(ClientError::Conflict(_))));
+    let conversion: Conversion = serde_json::from_value(serde_json::json!({ "event_name": "CompletePayment" })).unwrap();
+    let receipt = client.send_conversion(&id, "tiktok", &conversion).await.unwrap();
+    assert_eq!(receipt.status, ConversionStatus::Sent);
+    let query = SpendQuery {
+        since: NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+        until: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+    };
+    assert!(matches!(client.get_spend(&id, "tiktok", &query).await, Err(ClientError::BadRequest(_))));
+
+    client.issue_event_id(&id, "CompletePayment").await.unwrap();
+    let stats = client.get_dedup_stats(&id).await.unwrap();
+    assert_eq!((stats.issued, stats.server_events), (1, 1));
+
+    let exported = client.export_projects(RecordFormat::Ndjson).await.unwrap();
+    let report = client.import_projects(RecordFormat::Ndjson, "{}\n".into(), ImportMode::Atomic).await.unwrap();
+    assert_eq!((report.failed, report.imported), (1, 0));
+    assert_eq!(exported.iter().filter(|&&b| b == b'\n').count(), 1);
+
+    assert!(client.delete_project(&id).await.unwrap());
+    assert!(matches!(client.get_project(&id).await, Err(ClientError::NotFound)));
+}
+
+async fn next_event(events: &mut (impl Stream<Item = Result<StreamedEvent, ClientError>> + Unpin)) -> StreamedEvent {
+    let next = tokio::time::timeout(Duration::from_secs(5), events.next()).await;
+    next.expect("no event in time").expect("stream ended").unwrap()
+}
+
+#[tokio::test]
+async fn test_client_follows_the_event_stream() {
+    let db = test_db("_client_events").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let state = test_state(db.clone());
+    // Without a replica set, streams are fed by the event bus
+    let bus = EventBus::new(OutboxRepository::new(db)).subscribe(Arc::new(state.event_stream_service.clone()));
+    let url = spawn(app::router(state, CorsLayer::permissive())).await;
+    let platform = Client::new(&url, BOOTSTRAP_KEY).unwrap();
+    let org = platform.create_organization("Events").await.unwrap();
+    let client = platform.with_org(org.id.unwrap().to_hex());
+
+    let query = EventStreamQuery {
+        entity: Some(EventEntity::Project),
+        id: None,
+    };
+    let mut events = Box::pin(client.stream_events(&query, None).await.unwrap());
+    let first = client.create_project(&project("First")).await.unwrap();
+    let second = client.create_project(&project("Second")).await.unwrap();
+    client.delete_project(&first.id.unwrap()).await.unwrap();
+    bus.dispatch_due().await.unwrap();
+
+    let created = next_event(&mut events).await;
+    assert_eq!(created.payload.event, WebhookEvent::ProjectCreated);
+    assert!(matches!(&created.payload.data, EventData::Project(project) if project.name == "First"));
+    next_event(&mut events).await;
+    let deleted = next_event(&mut events).await;
+    assert!(matches!(&deleted.payload.data, EventData::Deleted { id } if *id == first.id.unwrap().to_hex()));
+
+    // Resuming replays what came after the given event
+    let mut resumed = Box::pin(client.stream_events(&query, Some(&created.id)).await.unwrap());
+    let next = next_event(&mut resumed).await;
+    assert!(matches!(&next.payload.data, EventData::Project(project) if project.id == second.id));
+
+    let query = EventStreamQuery {
+        entity: None,
+        id: Some("42".to_string()),
+    };
+    assert!(matches!(client.stream_events(&query, None).await, Err(ClientError::BadRequest(_))));
+}
@@ -0,0 +1,96 @@
+use hyper::{header::RETRY_AFTER, StatusCode};
+use serde::Deserialize;
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::transport::Response;
+
+/// Errors of API calls. The variants answered by the server mirror the
+/// server's `ApiError` by status code.
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Resource not found")]
+    NotFound,
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Unauthorized")]
+    Unauthorized,
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Unprocessable entity: {0}")]
+    UnprocessableEntity(String),
+    #[error("Rate limit exceeded")]
+    RateLimited { retry_after: Option<Duration> },
+    // Also how database errors on the server surface
+    #[error("Internal server error: {0}")]
+    InternalServerError(String),
+    #[error("Bad gateway: {0}")]
+    BadGateway(String),
+    #[error("Unexpected status {status}: {message}")]
+    Unexpected { status: StatusCode, message: String },
+    #[error("Invalid request body: {0}")]
+    Encode(String),
+    // The request could not be sent or its response not read
+    #[error("Request failed: {0}")]
+    Transport(String),
+    #[error("Invalid response: {0}")]
+    Decode(String),
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl ClientError {
+    /// Error for a response whose status is not a success.
+    pub(crate) fn from_response(response: &Response) -> Self {
+        let message = match serde_json::from_slice::<ErrorBody>(&response.body) {
+            Ok(body) => body.error,
+            Err(_) => String::from_utf8_lossy(&response.body).into_owned(),
+        };
+        match response.status {
+            StatusCode::NOT_FOUND => ClientError::NotFound,
+            StatusCode::BAD_REQUEST => ClientError::BadRequest(message),
+            StatusCode::UNAUTHORIZED => ClientError::Unauthorized,
+            StatusCode::FORBIDDEN => ClientError::Forbidden(message),
+            StatusCode::CONFLICT => ClientError::Conflict(message),
+            StatusCode::UNPROCESSABLE_ENTITY => ClientError::UnprocessableEntity(message),
+            StatusCode::TOO_MANY_REQUESTS => ClientError::RateLimited {
+                retry_after: retry_after(response),
+            },
+            StatusCode::INTERNAL_SERVER_ERROR => ClientError::InternalServerError(message),
+            StatusCode::BAD_GATEWAY => ClientError::BadGateway(message),
+            status => ClientError::Unexpected { status, message },
+        }
+    }
+
+    /// Status the server answered with, `None` when there was no usable answer.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::NotFound => Some(StatusCode::NOT_FOUND),
+            ClientError::BadRequest(_) => Some(StatusCode::BAD_REQUEST),
+            ClientError::Unauthorized => Some(StatusCode::UNAUTHORIZED),
+            ClientError::Forbidden(_) => Some(StatusCode::FORBIDDEN),
+            ClientError::Conflict(_) => Some(StatusCode::CONFLICT),
+            ClientError::UnprocessableEntity(_) => Some(StatusCode::UNPROCESSABLE_ENTITY),
+            ClientError::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            ClientError::InternalServerError(_) => Some(StatusCode::INTERNAL_SERVER_ERROR),
+            ClientError::BadGateway(_) => Some(StatusCode::BAD_GATEWAY),
+            ClientError::Unexpected { status, .. } => Some(*status),
+            ClientError::Encode(_) | ClientError::Transport(_) | ClientError::Decode(_) => None,
+        }
+    }
+}
+
+/// `Retry-After` of a response, in seconds as the rate limiter sends it.
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs)
+}
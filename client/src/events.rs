@@ -0,0 +1,139 @@
+use futures_util::{stream, Stream};
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, header::ACCEPT, Method};
+use std::{collections::VecDeque, mem};
+
+use crate::{
+    client::Client,
+    error::ClientError,
+    models::event::{EventPayload, EventStreamQuery, StreamedEvent},
+};
+
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+impl Client {
+    /// Opens `GET /events/stream`, resuming after `last_event_id` when given.
+    /// The stream ends when the server closes it or the connection fails; open
+    /// it again with the id of the last event read to carry on. Opening is not
+    /// retried.
+    pub async fn stream_events(
+        &self,
+        query: &EventStreamQuery,
+        last_event_id: Option<&str>,
+    ) -> Result<impl Stream<Item = Result<StreamedEvent, ClientError>> + Send + 'static, ClientError> {
+        let mut url = self.url(&["events", "stream"]);
+        if let Some(entity) = query.entity {
+            url.query_pairs_mut().append_pair("entity", entity.as_str());
+        }
+        if let Some(id) = &query.id {
+            url.query_pairs_mut().append_pair("id", id);
+        }
+        let mut headers = self.headers();
+        headers.push((ACCEPT.as_str(), "text/event-stream".to_string()));
+        if let Some(id) = last_event_id {
+            headers.push((LAST_EVENT_ID_HEADER, id.to_string()));
+        }
+
+        let response = self.http.open(Method::GET, &url, &headers).await.map_err(ClientError::Transport)?;
+        if !response.status.is_success() {
+            let response = response.collect().await.map_err(ClientError::Transport)?;
+            return Err(ClientError::from_response(&response));
+        }
+        Ok(events(response.body))
+    }
+}
+
+fn events(body: Incoming) -> impl Stream<Item = Result<StreamedEvent, ClientError>> + Send + 'static {
+    let state = (Some(body), SseDecoder::default(), VecDeque::new());
+    stream::unfold(state, |(mut body, mut decoder, mut pending)| async move {
+        loop {
+            if let Some(event) = pending.pop_front() {
+                return Some((decode(event), (body, decoder, pending)));
+            }
+            match body.as_mut()?.frame().await {
+                Some(Ok(frame)) => {
+                    if let Ok(data) = frame.into_data() {
+                        pending.extend(decoder.push(&data));
+                    }
+                }
+                Some(Err(e)) => {
+                    let error = ClientError::Transport(format!("event stream failed: {}", e));
+                    return Some((Err(error), (None, decoder, pending)));
+                }
+                None => return None,
+            }
+        }
+    })
+}
+
+fn decode(event: SseEvent) -> Result<StreamedEvent, ClientError> {
+    let id = event.id.ok_or_else(|| ClientError::Decode("event without an id".to_string()))?;
+    let payload: EventPayload = serde_json::from_str(&event.data).map_err(|e| ClientError::Decode(e.to_string()))?;
+    if event.event.is_some_and(|name| name != payload.event.as_str()) {
+        return Err(ClientError::Decode(format!("event {} is named differently in its data", id)));
+    }
+    Ok(StreamedEvent { id, payload })
+}
+
+/// One server-sent event, made of the `id`, `event` and `data` lines before
+/// a blank line.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SseEvent {
+    pub id: Option<String>,
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Splits a `text/event-stream` body into events as its chunks arrive.
+/// Comments, like the server's keep-alives, and unknown fields are skipped.
+#[derive(Debug, Default)]
+pub(crate) struct SseDecoder {
+    buffer: Vec<u8>,
+    // Carried over to later events until another id is sent
+    id: Option<String>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    /// Adds a chunk and returns the events it completed.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(event) = self.line(line.trim_end_matches(['\n', '\r'])) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    fn line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            let event = self.event.take();
+            if self.data.is_empty() {
+                return None;
+            }
+            let data = mem::take(&mut self.data).join("\n");
+            return Some(SseEvent {
+                id: self.id.clone(),
+                event,
+                data,
+            });
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "id" => self.id = Some(value.to_string()),
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            // Comments have an empty field name
+            _ => {}
+        }
+        None
+    }
+}
@@ -0,0 +1,15 @@
+use crate::events::{SseDecoder, SseEvent};
+
+#[test]
+fn test_events_are_split_across_chunks() {
+    let mut decoder = SseDecoder::default();
+    assert!(decoder.push(b": keep-alive\n\nid: 1\nevent: project.cre").is_empty());
+    let events = decoder.push(b"ated\ndata: {\"a\":\r\ndata:1}\n\ndata: x\n\n");
+    let event = |id: &str, event: Option<&str>, data: &str| SseEvent {
+        id: Some(id.to_string()),
+        event: event.map(str::to_string),
+        data: data.to_string(),
+    };
+    // The id carries over to events sent without one
+    assert_eq!(events, [event("1", Some("project.created"), "{\"a\":\n1}"), event("1", None, "x")]);
+}
@@ -0,0 +1,29 @@
+use hyper::{Method, StatusCode};
+
+use crate::{
+    client::{decode, success, Call, Client},
+    error::ClientError,
+    models::health::{ReadinessReport, VersionInfo},
+};
+
+impl Client {
+    /// Succeeds when the server is up.
+    pub async fn healthz(&self) -> Result<(), ClientError> {
+        success(self.send(Call::new(Method::GET, self.root_url(&["healthz"]))).await?)?;
+        Ok(())
+    }
+
+    /// The server's readiness checks, also when it is not ready. Not retried,
+    /// so that a probe reports what it saw.
+    pub async fn readyz(&self) -> Result<ReadinessReport, ClientError> {
+        let response = self.send_once(Call::new(Method::GET, self.root_url(&["readyz"]))).await?;
+        match response.status {
+            StatusCode::OK | StatusCode::SERVICE_UNAVAILABLE => decode(response),
+            _ => Err(ClientError::from_response(&response)),
+        }
+    }
+
+    pub async fn version(&self) -> Result<VersionInfo, ClientError> {
+        self.fetch(Call::new(Method::GET, self.root_url(&["version"]))).await
+    }
+}
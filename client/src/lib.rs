@@ -0,0 +1,29 @@
+//! Typed client for the Telegram TON API. Requests and responses are the
+//! client's own [`models`] of the `/v1` wire shapes, so the client builds
+//! without the server.
+
+mod accounts;
+mod api_keys;
+mod client;
+mod error;
+mod events;
+mod health;
+pub mod models;
+mod organizations;
+mod projects;
+mod records;
+mod retry;
+mod transport;
+mod webhooks;
+#[cfg(test)]
+mod client_test;
+#[cfg(test)]
+mod events_test;
+#[cfg(test)]
+mod retry_test;
+
+pub use bson::oid::ObjectId;
+pub use client::Client;
+pub use error::ClientError;
+pub use records::RecordFormat;
+pub use retry::RetryPolicy;
@@ -0,0 +1,132 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::models::project::FacebookCredential;
+
+/// The ad networks a project can hold credentials for.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AdPlatform {
+    Facebook,
+    Tiktok,
+    GoogleAds,
+}
+
+impl AdPlatform {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AdPlatform::Facebook => "facebook",
+            AdPlatform::Tiktok => "tiktok",
+            AdPlatform::GoogleAds => "google_ads",
+        }
+    }
+}
+
+impl fmt::Display for AdPlatform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TiktokCredential {
+    pub app_id: String,
+    pub app_secret: String,
+    pub access_token: String,
+    pub advertiser_id: String,
+    // Events API pixel, conversions cannot be sent without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixel_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoogleAdsCredential {
+    pub developer_token: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: String,
+    // Ten digits, with or without the dashes the Google Ads UI shows
+    pub customer_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login_customer_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversion_action_id: Option<String>,
+}
+
+/// A project's credential for one ad platform, tagged with `platform`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "platform", rename_all = "snake_case")]
+pub enum AdPlatformCredential {
+    Facebook(FacebookCredential),
+    Tiktok(TiktokCredential),
+    GoogleAds(GoogleAdsCredential),
+}
+
+impl AdPlatformCredential {
+    pub fn platform(&self) -> AdPlatform {
+        match self {
+            AdPlatformCredential::Facebook(_) => AdPlatform::Facebook,
+            AdPlatformCredential::Tiktok(_) => AdPlatform::Tiktok,
+            AdPlatformCredential::GoogleAds(_) => AdPlatform::GoogleAds,
+        }
+    }
+
+    pub fn as_facebook(&self) -> Option<&FacebookCredential> {
+        match self {
+            AdPlatformCredential::Facebook(credential) => Some(credential),
+            _ => None,
+        }
+    }
+}
+
+/// A credential together with its key in `ad_credentials`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdCredentialEntry {
+    pub key: String,
+    #[serde(flatten)]
+    pub credential: AdPlatformCredential,
+}
+
+/// A conversion reported to the platform of a credential.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Conversion {
+    // Platform event name, e.g. `Purchase` or `CompletePayment`
+    pub event_name: String,
+    // Shared with the browser pixel so that the platform counts the event once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+    #[serde(default = "Utc::now")]
+    pub event_time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    // fbclid, ttclid or gclid of the ad click
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click_id: Option<String>,
+    // Sent to the platforms SHA-256 hashed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+}
+
+/// What an ad account spent between two dates, both included.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Spend {
+    pub platform: AdPlatform,
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    pub amount: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SpendQuery {
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+}
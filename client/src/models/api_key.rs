@@ -0,0 +1,79 @@
+use bson::oid::ObjectId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Scope {
+    #[serde(rename = "projects:read")]
+    ProjectsRead,
+    #[serde(rename = "projects:write")]
+    ProjectsWrite,
+    #[serde(rename = "accounts:admin")]
+    AccountsAdmin,
+    #[serde(rename = "secrets:reveal")]
+    SecretsReveal,
+    #[serde(rename = "keys:admin")]
+    KeysAdmin,
+    #[serde(rename = "orgs:admin")]
+    OrgsAdmin,
+    #[serde(rename = "webhooks:admin")]
+    WebhooksAdmin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::ProjectsRead => "projects:read",
+            Scope::ProjectsWrite => "projects:write",
+            Scope::AccountsAdmin => "accounts:admin",
+            Scope::SecretsReveal => "secrets:reveal",
+            Scope::KeysAdmin => "keys:admin",
+            Scope::OrgsAdmin => "orgs:admin",
+            Scope::WebhooksAdmin => "webhooks:admin",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateApiKey {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    // Only platform keys may choose the organization, org keys mint into their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<ObjectId>,
+    // Only the bootstrap key and organization owners may mint bot frontend keys
+    #[serde(default)]
+    pub bot_frontend: bool,
+}
+
+/// API key as returned by the API, without the hash.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiKeySummary {
+    pub id: Option<String>,
+    pub name: String,
+    pub org_id: Option<String>,
+    pub account_id: Option<String>,
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    #[serde(default)]
+    pub bot_frontend: bool,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Returned once when a key is minted, the plaintext key cannot be retrieved later.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreatedApiKey {
+    pub key: String,
+    pub api_key: ApiKeySummary,
+}
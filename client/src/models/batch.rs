@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::project::{Project, StatusReason};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchGetProjects {
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchUpdateProjects {
+    pub ids: Vec<String>,
+    pub operation: BatchOperation,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchOperation {
+    Activate,
+    Suspend {
+        reason: StatusReason,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+    },
+    Resume,
+    Cancel {
+        reason: StatusReason,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+    },
+    /// Moves `expires_at` to `until`, or `days` past the later of the current
+    /// expiry and now. Expired projects are renewed, other statuses are kept.
+    Extend {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        days: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        until: Option<DateTime<Utc>>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Found,
+    Updated,
+    Unchanged,
+    Conflict,
+    NotFound,
+    InvalidId,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchResult {
+    pub id: String,
+    pub status: BatchStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<Project>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One result per requested id, in request order.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchResponse {
+    pub results: Vec<BatchResult>,
+}
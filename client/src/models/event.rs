@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::{account::Account, project::Project, webhook::WebhookEvent};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventEntity {
+    Project,
+    Account,
+}
+
+impl EventEntity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventEntity::Project => "project",
+            EventEntity::Account => "account",
+        }
+    }
+}
+
+/// Query of `GET /events/stream`. Account events are only sent to keys
+/// holding `accounts:admin`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct EventStreamQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity: Option<EventEntity>,
+    // Only events about the entity with this id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+/// An event read from the stream. `id` is what the stream resumes after when
+/// opened again with it as the last event id.
+#[derive(Debug, Clone)]
+pub struct StreamedEvent {
+    pub id: String,
+    pub payload: EventPayload,
+}
+
+/// The webhook payload of an event.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawEventPayload")]
+pub struct EventPayload {
+    // Outbox entry id, the same in the webhook delivery of the event
+    pub id: String,
+    pub event: WebhookEvent,
+    pub org_id: String,
+    pub created_at: DateTime<Utc>,
+    pub data: EventData,
+}
+
+/// What an event is about, by its name: the project or account as it is now,
+/// or the id of the one that was deleted.
+#[derive(Debug, Clone)]
+pub enum EventData {
+    Project(Box<Project>),
+    Account(Box<Account>),
+    Deleted { id: String },
+}
+
+#[derive(Deserialize)]
+struct RawEventPayload {
+    id: String,
+    event: WebhookEvent,
+    org_id: String,
+    created_at: DateTime<Utc>,
+    data: Value,
+}
+
+#[derive(Deserialize)]
+struct DeletedId {
+    id: String,
+}
+
+impl TryFrom<RawEventPayload> for EventPayload {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawEventPayload) -> Result<Self, Self::Error> {
+        let data = match raw.event {
+            WebhookEvent::ProjectCreated | WebhookEvent::ProjectUpdated | WebhookEvent::ProjectExpired => {
+                EventData::Project(serde_json::from_value(raw.data)?)
+            }
+            WebhookEvent::AccountCreated | WebhookEvent::AccountUpdated => EventData::Account(serde_json::from_value(raw.data)?),
+            WebhookEvent::ProjectDeleted | WebhookEvent::AccountDeleted => {
+                let DeletedId { id } = serde_json::from_value(raw.data)?;
+                EventData::Deleted { id }
+            }
+        };
+        Ok(Self {
+            id: raw.id,
+            event: raw.event,
+            org_id: raw.org_id,
+            created_at: raw.created_at,
+            data,
+        })
+    }
+}
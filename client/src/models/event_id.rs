@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Body of `POST /projects/:id/event-ids`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventIdRequest {
+    pub event_name: String,
+}
+
+/// An event id to fire the pixel with, e.g. `fbq('track', event_name, {}, { eventID: event_id })`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IssuedEventId {
+    pub event_id: String,
+    pub event_name: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// What became of a server event.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversionStatus {
+    Sent,
+    // Already sent with this event id within the dedup window, not sent again
+    Duplicate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConversionReceipt {
+    pub event_id: String,
+    pub status: ConversionStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DedupStats {
+    pub window_hours: u64,
+    pub issued: u64,
+    pub server_events: u64,
+    pub matched: u64,
+    pub server_only: u64,
+    pub duplicates_suppressed: u64,
+    // Share of server events the platform can deduplicate against a pixel fire
+    pub dedup_rate: f64,
+    // Share of server events received that were suppressed as duplicates
+    pub duplicate_rate: f64,
+}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DependencyCheck {
+    pub status: CheckStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReadinessReport {
+    pub status: CheckStatus,
+    pub checks: BTreeMap<String, DependencyCheck>,
+}
+
+impl ReadinessReport {
+    pub fn is_ready(&self) -> bool {
+        self.status == CheckStatus::Up
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VersionInfo {
+    pub version: String,
+    pub git_sha: String,
+    pub started_at: DateTime<Utc>,
+    pub uptime_seconds: u64,
+}
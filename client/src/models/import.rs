@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Nothing is written unless every row is valid
+    #[default]
+    Atomic,
+    /// Valid rows are written, invalid ones are reported and skipped
+    BestEffort,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RowError {
+    /// 1-based position among the data rows, a CSV header does not count
+    pub row: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    pub total: usize,
+    pub imported: usize,
+    pub failed: usize,
+    pub errors: Vec<RowError>,
+}
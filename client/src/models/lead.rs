@@ -0,0 +1,34 @@
+use bson::{
+    oid::ObjectId,
+    serde_helpers::{chrono_datetime_as_bson_datetime, chrono_datetime_as_bson_datetime_optional},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Lead {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
+    pub project_id: ObjectId,
+    pub leadgen_id: String,
+    pub page_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ad_id: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<LeadField>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub received_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "chrono_datetime_as_bson_datetime_optional")]
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LeadField {
+    pub name: String,
+    #[serde(default)]
+    pub values: Vec<String>,
+}
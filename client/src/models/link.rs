@@ -0,0 +1,80 @@
+use bson::{oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Utm {
+    pub source: String,
+    pub medium: String,
+    pub campaign: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrackedLink {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
+    pub project_id: ObjectId,
+    pub credential_key: String,
+    pub code: String,
+    pub utm: Utm,
+    pub destination: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LinkView {
+    #[serde(flatten)]
+    pub link: TrackedLink,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_url: Option<String>,
+}
+
+/// Body of `POST /projects/:id/links`. Missing UTM parameters take the
+/// server's defaults, `content` the credential's account suffix.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct NewLink {
+    pub credential_key: String,
+    pub campaign: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub medium: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LinkStats {
+    pub link_id: ObjectId,
+    pub code: String,
+    pub clicks: u64,
+    pub fbclid_clicks: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_click_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_click_at: Option<DateTime<Utc>>,
+    // UTC days, oldest first, days without clicks left out
+    pub by_day: Vec<DailyClicks>,
+    pub top_referrers: Vec<ReferrerClicks>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DailyClicks {
+    pub date: String,
+    pub clicks: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReferrerClicks {
+    pub referrer: String,
+    pub clicks: u64,
+}
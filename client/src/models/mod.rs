@@ -0,0 +1,17 @@
+//! Requests and responses of the `/v1` API, in the shapes the server sends
+//! and reads. Ids are BSON object ids and `created_at` style timestamps BSON
+//! dates, both in MongoDB extended JSON (`{"$oid": ...}`, `{"$date": ...}`).
+
+pub mod account;
+pub mod ad_platform;
+pub mod api_key;
+pub mod batch;
+pub mod event;
+pub mod event_id;
+pub mod health;
+pub mod import;
+pub mod lead;
+pub mod link;
+pub mod organization;
+pub mod project;
+pub mod webhook;
@@ -0,0 +1,49 @@
+use bson::{oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum OrgRole {
+    Viewer,
+    Admin,
+    Owner,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct OrganizationMember {
+    pub account_id: ObjectId,
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Organization {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub name: String,
+    #[serde(default)]
+    pub members: Vec<OrganizationMember>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Organization {
+    pub fn role_of(&self, account_id: &ObjectId) -> Option<OrgRole> {
+        self.members
+            .iter()
+            .find(|m| &m.account_id == account_id)
+            .map(|m| m.role)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateOrganization {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetMemberRole {
+    pub role: OrgRole,
+}
@@ -0,0 +1,179 @@
+use bson::{
+    oid::ObjectId,
+    serde_helpers::{chrono_datetime_as_bson_datetime, chrono_datetime_as_bson_datetime_optional},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt};
+
+use crate::models::ad_platform::AdPlatformCredential;
+
+// Placeholder sent instead of secrets to callers without `secrets:reveal`
+pub const MASKED_SECRET: &str = "********";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FacebookCredential {
+    pub app_id: String,
+    pub app_secret: String,
+    pub access_token: String,
+    pub ad_account_id: String,
+    pub account_suffix: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixel_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    // Written by the server's token health checker, ignored when sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_health: Option<TokenHealth>,
+}
+
+/// What Facebook's `debug_token` last reported about a credential's access token.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TokenHealth {
+    pub valid: bool,
+    // Absent for tokens that never expire
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "chrono_datetime_as_bson_datetime_optional")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub checked_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "chrono_datetime_as_bson_datetime_optional")]
+    pub warned_at: Option<DateTime<Utc>>,
+}
+
+/// A credential together with its key in `facebook_credentials`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FacebookCredentialEntry {
+    pub key: String,
+    #[serde(flatten)]
+    pub credential: FacebookCredential,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Package {
+    pub name: String,
+    pub description: String,
+}
+
+/// Lifecycle of a project, changed through the status actions.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectStatus {
+    #[default]
+    Draft,
+    Active,
+    Suspended,
+    Expired,
+    Cancelled,
+}
+
+impl ProjectStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProjectStatus::Draft => "draft",
+            ProjectStatus::Active => "active",
+            ProjectStatus::Suspended => "suspended",
+            ProjectStatus::Expired => "expired",
+            ProjectStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for ProjectStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectAction {
+    Activate,
+    Suspend,
+    Resume,
+    // Taken by the server once `expires_at` has passed
+    Expire,
+    // Taken when the expiry of an expired project is moved forward
+    Renew,
+    Cancel,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusReason {
+    NonPayment,
+    ClientRequest,
+    PolicyViolation,
+    Fraud,
+    Other,
+}
+
+/// Body of a status change request. Suspending and cancelling need a reason.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StatusChange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<StatusReason>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// One entry of a project's status history.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StatusTransition {
+    pub action: ProjectAction,
+    pub from: ProjectStatus,
+    pub to: ProjectStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<StatusReason>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub at: DateTime<Utc>,
+}
+
+/// A project as `/v1` sends it, leaving out the `facebook_credentials` view
+/// of `ad_credentials`. Projects are created `draft` or `active`, after that
+/// `status` only changes through the status actions.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Project {
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    // Set by the server from the caller's tenant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
+    pub name: String,
+    #[serde(default)]
+    pub telegram_chat_id: Option<String>,
+    #[serde(default)]
+    pub ad_credentials: HashMap<String, AdPlatformCredential>,
+    #[serde(default)]
+    pub package: Option<Package>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub status: ProjectStatus,
+    // Mirrors `status == active`
+    #[serde(default)]
+    pub is_active: bool,
+    #[serde(default)]
+    pub status_history: Vec<StatusTransition>,
+    pub is_logging: bool,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Project {
+    /// The project's Facebook credentials by key.
+    pub fn facebook_credentials(&self) -> impl Iterator<Item = (&String, &FacebookCredential)> {
+        self.ad_credentials
+            .iter()
+            .filter_map(|(key, credential)| Some((key, credential.as_facebook()?)))
+    }
+}
@@ -0,0 +1,31 @@
+use bson::oid::ObjectId;
+
+use crate::{
+    client::Client,
+    error::ClientError,
+    models::organization::{CreateOrganization, OrgRole, Organization, SetMemberRole},
+};
+
+impl Client {
+    pub async fn create_organization(&self, name: &str) -> Result<Organization, ClientError> {
+        let request = CreateOrganization { name: name.to_string() };
+        self.post(&["organizations"], &request).await
+    }
+
+    pub async fn get_organizations(&self) -> Result<Vec<Organization>, ClientError> {
+        self.get(&["organizations"]).await
+    }
+
+    pub async fn get_organization(&self, id: &ObjectId) -> Result<Organization, ClientError> {
+        self.get(&["organizations", &id.to_hex()]).await
+    }
+
+    pub async fn set_member_role(&self, id: &ObjectId, account_id: &ObjectId, role: OrgRole) -> Result<Organization, ClientError> {
+        let segments = ["organizations", &id.to_hex(), "members", &account_id.to_hex()];
+        self.put(&segments, &SetMemberRole { role }).await
+    }
+
+    pub async fn remove_member(&self, id: &ObjectId, account_id: &ObjectId) -> Result<bool, ClientError> {
+        self.delete(&["organizations", &id.to_hex(), "members", &account_id.to_hex()]).await
+    }
+}
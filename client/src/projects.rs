@@ -0,0 +1,202 @@
+use bson::oid::ObjectId;
+use bytes::Bytes;
+use hyper::{Method, StatusCode};
+use std::collections::HashMap;
+
+use crate::{
+    client::{decode, Call, Client},
+    error::ClientError,
+    models::{
+        ad_platform::{AdCredentialEntry, AdPlatformCredential, Conversion, Spend, SpendQuery},
+        batch::{BatchGetProjects, BatchOperation, BatchResponse, BatchUpdateProjects},
+        event_id::{ConversionReceipt, DedupStats, EventIdRequest, IssuedEventId},
+        import::{ImportMode, ImportReport},
+        lead::Lead,
+        link::{LinkStats, LinkView, NewLink},
+        project::{FacebookCredential, FacebookCredentialEntry, Project, StatusChange},
+    },
+    records::RecordFormat,
+};
+
+impl Client {
+    /// Creates a project. Sent with an `Idempotency-Key`, so it is retried safely.
+    pub async fn create_project(&self, project: &Project) -> Result<Project, ClientError> {
+        let call = Call::new(Method::POST, self.url(&["projects"])).json(project)?.idempotent();
+        self.fetch(call).await
+    }
+
+    pub async fn get_projects(&self) -> Result<Vec<Project>, ClientError> {
+        self.get(&["projects"]).await
+    }
+
+    pub async fn get_project(&self, id: &ObjectId) -> Result<Project, ClientError> {
+        self.get(&["projects", &id.to_hex()]).await
+    }
+
+    pub async fn update_project(&self, id: &ObjectId, project: &Project) -> Result<Project, ClientError> {
+        self.put(&["projects", &id.to_hex()], project).await
+    }
+
+    pub async fn delete_project(&self, id: &ObjectId) -> Result<bool, ClientError> {
+        self.delete(&["projects", &id.to_hex()]).await
+    }
+
+    pub async fn activate_project(&self, id: &ObjectId) -> Result<Project, ClientError> {
+        self.transition(id, "activate", &StatusChange::default()).await
+    }
+
+    /// Suspends an active project, `change` must carry a reason.
+    pub async fn suspend_project(&self, id: &ObjectId, change: &StatusChange) -> Result<Project, ClientError> {
+        self.transition(id, "suspend", change).await
+    }
+
+    pub async fn resume_project(&self, id: &ObjectId) -> Result<Project, ClientError> {
+        self.transition(id, "resume", &StatusChange::default()).await
+    }
+
+    /// Cancels a project for good, `change` must carry a reason.
+    pub async fn cancel_project(&self, id: &ObjectId, change: &StatusChange) -> Result<Project, ClientError> {
+        self.transition(id, "cancel", change).await
+    }
+
+    async fn transition(&self, id: &ObjectId, action: &str, change: &StatusChange) -> Result<Project, ClientError> {
+        self.post(&["projects", &id.to_hex(), action], change).await
+    }
+
+    pub async fn batch_get_projects(&self, ids: Vec<String>) -> Result<BatchResponse, ClientError> {
+        self.post(&["projects:batchGet"], &BatchGetProjects { ids }).await
+    }
+
+    pub async fn batch_update_projects(&self, ids: Vec<String>, operation: BatchOperation) -> Result<BatchResponse, ClientError> {
+        self.post(&["projects:batchUpdate"], &BatchUpdateProjects { ids, operation }).await
+    }
+
+    /// Imports NDJSON or CSV rows. An atomic import with invalid rows writes
+    /// nothing; its report is returned all the same.
+    pub async fn import_projects(&self, format: RecordFormat, rows: Bytes, mode: ImportMode) -> Result<ImportReport, ClientError> {
+        self.import("projects:import", format, rows, mode).await
+    }
+
+    pub async fn export_projects(&self, format: RecordFormat) -> Result<Bytes, ClientError> {
+        self.export("projects:export", format).await
+    }
+
+    pub(crate) async fn import(&self, verb: &str, format: RecordFormat, rows: Bytes, mode: ImportMode) -> Result<ImportReport, ClientError> {
+        let mut url = self.url(&[verb]);
+        url.query_pairs_mut().append_pair("mode", mode_param(mode));
+        let response = self.send(Call::new(Method::POST, url).raw(rows, format.content_type())).await?;
+        match response.status {
+            StatusCode::OK | StatusCode::UNPROCESSABLE_ENTITY => decode(response),
+            _ => Err(ClientError::from_response(&response)),
+        }
+    }
+
+    pub(crate) async fn export(&self, verb: &str, format: RecordFormat) -> Result<Bytes, ClientError> {
+        let mut url = self.url(&[verb]);
+        url.query_pairs_mut().append_pair("format", format.extension());
+        let response = self.send(Call::new(Method::GET, url)).await?;
+        match response.status.is_success() {
+            true => Ok(response.body),
+            false => Err(ClientError::from_response(&response)),
+        }
+    }
+
+    pub async fn get_ad_credentials(&self, id: &ObjectId) -> Result<HashMap<String, AdPlatformCredential>, ClientError> {
+        self.get(&["projects", &id.to_hex(), "ad-credentials"]).await
+    }
+
+    pub async fn add_ad_credential(&self, id: &ObjectId, entry: &AdCredentialEntry) -> Result<AdCredentialEntry, ClientError> {
+        self.post(&["projects", &id.to_hex(), "ad-credentials"], entry).await
+    }
+
+    pub async fn replace_ad_credential(
+        &self,
+        id: &ObjectId,
+        key: &str,
+        credential: &AdPlatformCredential,
+    ) -> Result<AdCredentialEntry, ClientError> {
+        self.put(&["projects", &id.to_hex(), "ad-credentials", key], credential).await
+    }
+
+    pub async fn remove_ad_credential(&self, id: &ObjectId, key: &str) -> Result<bool, ClientError> {
+        self.delete(&["projects", &id.to_hex(), "ad-credentials", key]).await
+    }
+
+    pub async fn get_facebook_credentials(&self, id: &ObjectId) -> Result<HashMap<String, FacebookCredential>, ClientError> {
+        self.get(&["projects", &id.to_hex(), "facebook-credentials"]).await
+    }
+
+    pub async fn add_facebook_credential(
+        &self,
+        id: &ObjectId,
+        entry: &FacebookCredentialEntry,
+    ) -> Result<FacebookCredentialEntry, ClientError> {
+        self.post(&["projects", &id.to_hex(), "facebook-credentials"], entry).await
+    }
+
+    pub async fn replace_facebook_credential(
+        &self,
+        id: &ObjectId,
+        key: &str,
+        credential: &FacebookCredential,
+    ) -> Result<FacebookCredentialEntry, ClientError> {
+        self.put(&["projects", &id.to_hex(), "facebook-credentials", key], credential).await
+    }
+
+    pub async fn remove_facebook_credential(&self, id: &ObjectId, key: &str) -> Result<bool, ClientError> {
+        self.delete(&["projects", &id.to_hex(), "facebook-credentials", key]).await
+    }
+
+    /// Reports a conversion through the credential under `key`. It is not
+    /// retried on server errors; send it with an `event_id` and repeat it
+    /// yourself, the server suppresses duplicates.
+    pub async fn send_conversion(&self, id: &ObjectId, key: &str, conversion: &Conversion) -> Result<ConversionReceipt, ClientError> {
+        self.post(&["projects", &id.to_hex(), "ad-credentials", key, "conversions"], conversion).await
+    }
+
+    pub async fn get_spend(&self, id: &ObjectId, key: &str, query: &SpendQuery) -> Result<Spend, ClientError> {
+        let mut url = self.url(&["projects", &id.to_hex(), "ad-credentials", key, "spend"]);
+        url.query_pairs_mut()
+            .append_pair("since", &query.since.to_string())
+            .append_pair("until", &query.until.to_string());
+        self.fetch(Call::new(Method::GET, url)).await
+    }
+
+    pub async fn issue_event_id(&self, id: &ObjectId, event_name: &str) -> Result<IssuedEventId, ClientError> {
+        let request = EventIdRequest {
+            event_name: event_name.to_string(),
+        };
+        self.post(&["projects", &id.to_hex(), "event-ids"], &request).await
+    }
+
+    pub async fn get_dedup_stats(&self, id: &ObjectId) -> Result<DedupStats, ClientError> {
+        self.get(&["projects", &id.to_hex(), "event-ids", "stats"]).await
+    }
+
+    pub async fn get_leads(&self, id: &ObjectId) -> Result<Vec<Lead>, ClientError> {
+        self.get(&["projects", &id.to_hex(), "leads"]).await
+    }
+
+    pub async fn create_link(&self, id: &ObjectId, link: &NewLink) -> Result<LinkView, ClientError> {
+        self.post(&["projects", &id.to_hex(), "links"], link).await
+    }
+
+    pub async fn get_links(&self, id: &ObjectId) -> Result<Vec<LinkView>, ClientError> {
+        self.get(&["projects", &id.to_hex(), "links"]).await
+    }
+
+    pub async fn delete_link(&self, id: &ObjectId, link_id: &ObjectId) -> Result<bool, ClientError> {
+        self.delete(&["projects", &id.to_hex(), "links", &link_id.to_hex()]).await
+    }
+
+    pub async fn get_link_stats(&self, id: &ObjectId, link_id: &ObjectId) -> Result<LinkStats, ClientError> {
+        self.get(&["projects", &id.to_hex(), "links", &link_id.to_hex(), "stats"]).await
+    }
+}
+
+fn mode_param(mode: ImportMode) -> &'static str {
+    match mode {
+        ImportMode::Atomic => "atomic",
+        ImportMode::BestEffort => "best_effort",
+    }
+}
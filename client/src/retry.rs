@@ -0,0 +1,56 @@
+use hyper::{Method, StatusCode};
+use std::time::Duration;
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(200);
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// How failed calls are retried. A rate limited call is always retried after
+/// its `Retry-After`. Server errors and failed connections are only retried
+/// for calls that are safe to repeat: `GET`, `PUT` and `DELETE`, and `POST`s
+/// sent with an `Idempotency-Key`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `retry`, counting from 1: the server's
+    /// `Retry-After` when it sent one, otherwise doubling from `base_delay`.
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        let backoff = || self.base_delay.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+        retry_after.unwrap_or_else(backoff).min(self.max_delay)
+    }
+}
+
+/// Whether a call can be sent again without risking doing it twice.
+pub(crate) fn is_repeatable(method: &Method, idempotent: bool) -> bool {
+    idempotent || matches!(*method, Method::GET | Method::PUT | Method::DELETE)
+}
+
+/// Statuses worth retrying a repeatable call on.
+pub(crate) fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
@@ -0,0 +1,28 @@
+use hyper::{Method, StatusCode};
+use std::time::Duration;
+
+use crate::retry::{is_repeatable, is_transient, RetryPolicy};
+
+#[test]
+fn test_delays_double_up_to_the_maximum() {
+    let policy = RetryPolicy {
+        max_retries: 5,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(500),
+    };
+    let delays: Vec<_> = (1..=4).map(|retry| policy.delay(retry, None)).collect();
+    assert_eq!(delays, [100, 200, 400, 500].map(Duration::from_millis));
+    assert_eq!(policy.delay(1, Some(Duration::from_millis(300))), Duration::from_millis(300));
+    assert_eq!(policy.delay(1, Some(Duration::from_secs(60))), Duration::from_millis(500));
+}
+
+#[test]
+fn test_only_repeatable_calls_are_retried_on_server_errors() {
+    assert!(is_repeatable(&Method::GET, false));
+    assert!(is_repeatable(&Method::DELETE, false));
+    assert!(!is_repeatable(&Method::POST, false));
+    assert!(is_repeatable(&Method::POST, true));
+
+    assert!(is_transient(StatusCode::SERVICE_UNAVAILABLE));
+    assert!(!is_transient(StatusCode::INTERNAL_SERVER_ERROR));
+}
@@ -0,0 +1,211 @@
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    body::Incoming,
+    client::conn::http1,
+    header::{CONTENT_LENGTH, HOST, USER_AGENT},
+    HeaderMap, Method, Request, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use std::{
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName},
+    TlsConnector,
+};
+use url::Url;
+
+const CLIENT_USER_AGENT: &str = concat!("telegram-ton-api-client/", env!("CARGO_PKG_VERSION"));
+
+static TLS: LazyLock<TlsConnector> = LazyLock::new(|| {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+});
+
+/// Minimal HTTP/1.1 transport for API calls. Every request uses a fresh
+/// connection.
+#[derive(Debug, Clone)]
+pub(crate) struct Transport {
+    timeout: Duration,
+    // Responses larger than this are cut off instead of buffered
+    max_response_bytes: usize,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// A response whose body is read as it arrives, for the event stream.
+pub(crate) struct StreamingResponse {
+    pub status: StatusCode,
+    pub body: Incoming,
+    headers: HeaderMap,
+    max_response_bytes: usize,
+}
+
+impl StreamingResponse {
+    /// Reads the whole body, for responses that turned out to be errors.
+    pub async fn collect(self) -> Result<Response, String> {
+        let body = read(self.body, self.max_response_bytes).await?;
+        Ok(Response {
+            status: self.status,
+            headers: self.headers,
+            body,
+        })
+    }
+}
+
+impl Transport {
+    pub fn new(timeout: Duration, max_response_bytes: usize) -> Self {
+        Self {
+            timeout,
+            max_response_bytes,
+        }
+    }
+
+    /// Checks that `url` is something this transport can call.
+    pub fn validate_url(url: &str) -> Result<Url, String> {
+        let url = Url::parse(url).map_err(|e| format!("invalid URL: {}", e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("URL must use http or https".to_string());
+        }
+        if url.host_str().is_none() {
+            return Err("URL must have a host".to_string());
+        }
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err("URL must not contain credentials".to_string());
+        }
+        Ok(url)
+    }
+
+    /// Sends a request and reads its response, all within the timeout.
+    pub async fn execute(
+        &self,
+        method: Method,
+        url: &Url,
+        headers: &[(&str, String)],
+        body: Option<Bytes>,
+    ) -> Result<Response, String> {
+        let exchange = async {
+            let response = self.send(method, url, headers, body).await?;
+            let (parts, body) = response.into_parts();
+            let body = read(body, self.max_response_bytes).await?;
+            Ok(Response {
+                status: parts.status,
+                headers: parts.headers,
+                body,
+            })
+        };
+        tokio::time::timeout(self.timeout, exchange)
+            .await
+            .map_err(|_| format!("timed out after {:?}", self.timeout))?
+    }
+
+    /// Sends a request and returns once the response head arrived, which the
+    /// timeout applies to. The body is read for as long as the server sends it.
+    pub async fn open(&self, method: Method, url: &Url, headers: &[(&str, String)]) -> Result<StreamingResponse, String> {
+        let response = tokio::time::timeout(self.timeout, self.send(method, url, headers, None))
+            .await
+            .map_err(|_| format!("timed out after {:?}", self.timeout))??;
+        let (parts, body) = response.into_parts();
+        Ok(StreamingResponse {
+            status: parts.status,
+            body,
+            headers: parts.headers,
+            max_response_bytes: self.max_response_bytes,
+        })
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        url: &Url,
+        headers: &[(&str, String)],
+        body: Option<Bytes>,
+    ) -> Result<hyper::Response<Incoming>, String> {
+        let host = url.host_str().ok_or("URL must have a host")?.to_string();
+        let port = url.port_or_known_default().ok_or("URL has no port")?;
+
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let host_header = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.clone(),
+        };
+        let mut request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(HOST, host_header)
+            .header(USER_AGENT, CLIENT_USER_AGENT);
+        if let Some(body) = &body {
+            request = request.header(CONTENT_LENGTH, body.len());
+        }
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        let request = request
+            .body(Full::new(body.unwrap_or_default()))
+            .map_err(|e| e.to_string())?;
+
+        let address = host.trim_start_matches('[').trim_end_matches(']');
+        let tcp = TcpStream::connect((address, port))
+            .await
+            .map_err(|e| format!("connect failed: {}", e))?;
+        if url.scheme() == "https" {
+            let name = ServerName::try_from(address).map_err(|_| format!("invalid TLS server name {:?}", host))?;
+            let tls = TLS
+                .connect(name, tcp)
+                .await
+                .map_err(|e| format!("TLS handshake failed: {}", e))?;
+            exchange(tls, request).await
+        } else {
+            exchange(tcp, request).await
+        }
+    }
+}
+
+async fn exchange<S>(stream: S, request: Request<Full<Bytes>>) -> Result<hyper::Response<Incoming>, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(|e| format!("HTTP handshake failed: {}", e))?;
+    // Ends once the response body is read or dropped
+    tokio::spawn(connection);
+
+    sender
+        .send_request(request)
+        .await
+        .map_err(|e| format!("request failed: {}", e))
+}
+
+async fn read(body: Incoming, max_response_bytes: usize) -> Result<Bytes, String> {
+    http_body_util::Limited::new(body, max_response_bytes)
+        .collect()
+        .await
+        .map(|body| body.to_bytes())
+        .map_err(|e| format!("failed to read response: {}", e))
+}
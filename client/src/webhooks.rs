@@ -0,0 +1,38 @@
+use bson::oid::ObjectId;
+use hyper::Method;
+
+use crate::{
+    client::{success, Call, Client},
+    error::ClientError,
+    models::webhook::{CreateWebhook, CreatedWebhook, WebhookDelivery, WebhookSummary},
+};
+
+impl Client {
+    /// Subscribes a URL to events. The signing secret is only ever returned here.
+    pub async fn create_webhook(&self, request: &CreateWebhook) -> Result<CreatedWebhook, ClientError> {
+        self.post(&["webhooks"], request).await
+    }
+
+    pub async fn get_webhooks(&self) -> Result<Vec<WebhookSummary>, ClientError> {
+        self.get(&["webhooks"]).await
+    }
+
+    pub async fn get_webhook(&self, id: &ObjectId) -> Result<WebhookSummary, ClientError> {
+        self.get(&["webhooks", &id.to_hex()]).await
+    }
+
+    pub async fn delete_webhook(&self, id: &ObjectId) -> Result<bool, ClientError> {
+        self.delete(&["webhooks", &id.to_hex()]).await
+    }
+
+    pub async fn get_deliveries(&self, id: &ObjectId) -> Result<Vec<WebhookDelivery>, ClientError> {
+        self.get(&["webhooks", &id.to_hex(), "deliveries"]).await
+    }
+
+    /// Queues a delivery to be sent again.
+    pub async fn redeliver(&self, id: &ObjectId, delivery_id: &ObjectId) -> Result<(), ClientError> {
+        let url = self.url(&["webhooks", &id.to_hex(), "deliveries", &delivery_id.to_hex(), "redeliver"]);
+        success(self.send(Call::new(Method::POST, url)).await?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    app,
+    test_support::{test_db, test_state, unreachable_db, BOOTSTRAP_KEY},
+};
+
+async fn send(app: &Router, method: &str, uri: &str, org_id: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri).header("x-api-key", BOOTSTRAP_KEY);
+    if let Some(org_id) = org_id {
+        request = request.header("x-org-id", org_id);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+fn tiktok() -> Value {
+    json!({
+        "key": "tiktok",
+        "platform": "tiktok",
+        "app_id": "app",
+        "app_secret": "secret",
+        "access_token": "tiktok-token",
+        "advertiser_id": "7000000001",
+        "pixel_code": "PIXEL",
+    })
+}
+
+#[tokio::test]
+async fn test_ad_credential_routes_require_a_key() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+    let collection = "/projects/65f000000000000000000000/ad-credentials";
+    let key = format!("{}/main", collection);
+    let conversions = format!("{}/conversions", key);
+    let event_ids = "/projects/65f000000000000000000000/event-ids";
+    let dedup_stats = format!("{}/stats", event_ids);
+    let spend = format!("{}/spend?since=2024-05-01&until=2024-05-31", key);
+
+    for (method, uri) in [
+        ("GET", collection),
+        ("POST", collection),
+        ("PUT", &key),
+        ("DELETE", &key),
+        ("POST", &conversions),
+        ("GET", &spend),
+        ("POST", event_ids),
+        ("GET", &dedup_stats),
+    ] {
+        let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::UNAUTHORIZED, "{} {}", method, uri);
+    }
+}
+
+#[tokio::test]
+async fn test_ad_credentials_are_managed_per_platform() {
+    let db = test_db("_ad_credentials").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let app = app::router(test_state(db), CorsLayer::permissive());
+    let (_, org) = send(&app, "POST", "/organizations", None, Some(json!({ "name": "Ads" }))).await;
+    let org = org["_id"]["$oid"].as_str().unwrap().to_string();
+    let org_id = Some(org.as_str());
+
+    let project = json!({
+        "name": "Ads",
+        "is_active": true,
+        "is_logging": false,
+        "created_at": { "$date": { "$numberLong": "0" } },
+        "updated_at": { "$date": { "$numberLong": "0" } },
+    });
+    let (status, created) = send(&app, "POST", "/projects", org_id, Some(project)).await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+    let id = created["_id"]["$oid"].as_str().unwrap().to_string();
+    let uri = format!("/projects/{}/ad-credentials", id);
+
+    let (status, added) = send(&app, "POST", &uri, org_id, Some(tiktok())).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", added);
+    assert_eq!((added["key"].as_str(), added["platform"].as_str()), (Some("tiktok"), Some("tiktok")));
+    let mut invalid = tiktok();
+    invalid["key"] = json!("other");
+    invalid["advertiser_id"] = json!("act_1");
+    let (status, _) = send(&app, "POST", &uri, org_id, Some(invalid)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // A secret in the stored ciphertext format could never be read back
+    let mut sealed = tiktok();
+    sealed["key"] = json!("sealed");
+    sealed["app_secret"] = json!("enc:v1:bogus:AAAA");
+    let (status, _) = send(&app, "POST", &uri, org_id, Some(sealed)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Facebook routes neither list nor replace credentials of other platforms
+    let facebook_uri = format!("/projects/{}/facebook-credentials", id);
+    let (_, listed) = send(&app, "GET", &facebook_uri, org_id, None).await;
+    assert_eq!(listed, json!({}));
+    let facebook = json!({
+        "app_id": "app",
+        "app_secret": "secret",
+        "access_token": "token",
+        "ad_account_id": "act_1",
+        "account_suffix": "",
+    });
+    let (status, _) = send(&app, "PUT", &format!("{}/tiktok", facebook_uri), org_id, Some(facebook)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (_, removed) = send(&app, "DELETE", &format!("{}/tiktok", facebook_uri), org_id, None).await;
+    assert_eq!(removed, json!(false));
+
+    let conversion = json!({ "event_name": "CompletePayment", "value": 10.0, "currency": "USD" });
+    let (status, receipt) = send(&app, "POST", &format!("{}/tiktok/conversions", uri), org_id, Some(conversion)).await;
+    assert_eq!(status, StatusCode::OK, "{}", receipt);
+    assert_eq!(receipt["status"], "sent");
+    assert!(receipt["event_id"].as_str().unwrap().starts_with("ev_"));
+    let (status, _) = send(&app, "GET", &format!("{}/tiktok/spend?since=2024-05-01&until=2024-05-31", uri), org_id, None).await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+
+    let (_, removed) = send(&app, "DELETE", &format!("{}/tiktok", uri), org_id, None).await;
+    assert_eq!(removed, json!(true));
+    let (_, listed) = send(&app, "GET", &uri, org_id, None).await;
+    assert_eq!(listed, json!({}));
+}
+
+#[tokio::test]
+async fn test_event_ids_are_deduplicated() {
+    let db = test_db("_event_ids").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let app = app::router(test_state(db), CorsLayer::permissive());
+    let (_, org) = send(&app, "POST", "/organizations", None, Some(json!({ "name": "Dedup" }))).await;
+    let org = org["_id"]["$oid"].as_str().unwrap().to_string();
+    let org_id = Some(org.as_str());
+
+    let project = json!({
+        "name": "Dedup",
+        "is_active": true,
+        "is_logging": false,
+        "created_at": { "$date": { "$numberLong": "0" } },
+        "updated_at": { "$date": { "$numberLong": "0" } },
+    });
+    let (_, created) = send(&app, "POST", "/projects", org_id, Some(project)).await;
+    let id = created["_id"]["$oid"].as_str().unwrap().to_string();
+    send(&app, "POST", &format!("/projects/{}/ad-credentials", id), org_id, Some(tiktok())).await;
+    let conversions = format!("/projects/{}/ad-credentials/tiktok/conversions", id);
+
+    let (status, issued) =
+        send(&app, "POST", &format!("/projects/{}/event-ids", id), org_id, Some(json!({ "event_name": "CompletePayment" }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", issued);
+    let event_id = issued["event_id"].as_str().unwrap();
+
+    let conversion = json!({ "event_name": "CompletePayment", "event_id": event_id, "value": 10.0, "currency": "USD" });
+    let (_, first) = send(&app, "POST", &conversions, org_id, Some(conversion.clone())).await;
+    assert_eq!(first, json!({ "event_id": event_id, "status": "sent" }));
+    let (_, second) = send(&app, "POST", &conversions, org_id, Some(conversion)).await;
+    assert_eq!(second, json!({ "event_id": event_id, "status": "duplicate" }));
+
+    let renamed = json!({ "event_name": "AddToCart", "event_id": event_id });
+    let (status, _) = send(&app, "POST", &conversions, org_id, Some(renamed)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, unmatched) = send(&app, "POST", &conversions, org_id, Some(json!({ "event_name": "CompletePayment" }))).await;
+    assert_eq!(unmatched["status"], "sent");
+
+    let (status, stats) = send(&app, "GET", &format!("/projects/{}/event-ids/stats", id), org_id, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", stats);
+    assert_eq!(
+        (&stats["issued"], &stats["server_events"], &stats["matched"], &stats["server_only"], &stats["duplicates_suppressed"]),
+        (&json!(1), &json!(2), &json!(1), &json!(1), &json!(1))
+    );
+    assert_eq!((stats["dedup_rate"].as_f64(), stats["window_hours"].as_u64()), (Some(0.5), Some(48)));
+}
@@ -0,0 +1,485 @@
+use axum::{async_trait, body::Bytes};
+use chrono::NaiveDate;
+use hyper::Method;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use url::{form_urlencoded, Url};
+#[cfg(any(test, feature = "test-support"))]
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    config::AdPlatformsConfig,
+    http_client::HttpClient,
+    models::{
+        ad_platform::{google_customer_id, AdPlatform, AdPlatformCredential, Conversion, GoogleAdsCredential, Spend, TiktokCredential},
+        project::FacebookCredential,
+    },
+};
+
+const AD_PLATFORM_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// What can be done with a credential of any ad platform. Errors are
+/// transport failures or errors reported by the platform.
+#[async_trait]
+pub trait AdPlatformClient: Send + Sync {
+    async fn send_conversion(&self, credential: &AdPlatformCredential, conversion: &Conversion) -> Result<(), String>;
+
+    /// What the credential's ad account spent from `since` to `until`, both included.
+    async fn fetch_spend(&self, credential: &AdPlatformCredential, since: NaiveDate, until: NaiveDate) -> Result<Spend, String>;
+}
+
+fn sha256_hex(value: &str) -> String {
+    hex::encode(Sha256::digest(value.as_bytes()))
+}
+
+// Platforms match hashed emails trimmed and lowercased
+fn hashed_email(email: &str) -> String {
+    sha256_hex(&email.trim().to_lowercase())
+}
+
+// Digits with the country code, prefixed with `+` where the platform wants E.164
+fn hashed_phone(phone: &str, plus: bool) -> String {
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+    match plus {
+        true => sha256_hex(&format!("+{}", digits)),
+        false => sha256_hex(&digits),
+    }
+}
+
+fn insert_some(object: &mut Map<String, Value>, key: &str, value: Option<impl Into<Value>>) {
+    if let Some(value) = value {
+        object.insert(key.to_string(), value.into());
+    }
+}
+
+fn require_digits<'a>(field: &str, value: Option<&'a str>) -> Result<&'a str, String> {
+    match value {
+        Some(value) if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) => Ok(value),
+        Some(value) => Err(format!("invalid {} {:?}", field, value)),
+        None => Err(format!("the credential has no {}", field)),
+    }
+}
+
+// Facebook and Google put errors in `error.message`, OAuth in `error_description`
+fn error_message(body: &[u8]) -> Option<String> {
+    let body: Value = serde_json::from_slice(body).ok()?;
+    let message = [&body["error"]["message"], &body["error_description"], &body["message"]]
+        .into_iter()
+        .find_map(|message| message.as_str().map(str::to_string));
+    message
+}
+
+fn parse_amount(value: &str) -> Result<f64, String> {
+    value.parse().map_err(|_| format!("unexpected spend {:?}", value))
+}
+
+#[derive(Deserialize)]
+struct FacebookInsights {
+    #[serde(default)]
+    data: Vec<FacebookInsight>,
+}
+
+#[derive(Deserialize)]
+struct FacebookInsight {
+    spend: String,
+    account_currency: Option<String>,
+}
+
+// TikTok answers 200 and reports errors in `code`
+#[derive(Deserialize)]
+struct TiktokResponse<T> {
+    code: i64,
+    #[serde(default)]
+    message: String,
+    data: Option<T>,
+}
+
+#[derive(Deserialize)]
+struct TiktokReport {
+    #[serde(default)]
+    list: Vec<TiktokReportRow>,
+}
+
+#[derive(Deserialize)]
+struct TiktokReportRow {
+    metrics: TiktokMetrics,
+}
+
+#[derive(Deserialize)]
+struct TiktokMetrics {
+    spend: String,
+}
+
+#[derive(Deserialize)]
+struct GoogleToken {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleUploadResponse {
+    partial_failure_error: Option<GoogleStatus>,
+}
+
+#[derive(Deserialize)]
+struct GoogleStatus {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct GoogleSearchResponse {
+    #[serde(default)]
+    results: Vec<GoogleSearchRow>,
+}
+
+#[derive(Deserialize)]
+struct GoogleSearchRow {
+    customer: Option<GoogleCustomer>,
+    metrics: GoogleMetrics,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleCustomer {
+    currency_code: Option<String>,
+}
+
+// int64 values come as strings in the REST API
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleMetrics {
+    #[serde(default)]
+    cost_micros: Option<String>,
+}
+
+/// `AdPlatformClient` calling the platforms' HTTP APIs, or whatever the
+/// configured URLs point at.
+#[derive(Debug, Clone)]
+pub struct HttpAdPlatformClient {
+    http: HttpClient,
+    graph_api_url: String,
+    tiktok_api_url: String,
+    google_ads_api_url: String,
+    google_oauth_token_url: String,
+}
+
+impl HttpAdPlatformClient {
+    pub fn new(graph_api_url: &str, config: &AdPlatformsConfig) -> Self {
+        Self {
+            // The URLs come from the operator, not from API callers
+            http: HttpClient::new(AD_PLATFORM_TIMEOUT, true),
+            graph_api_url: graph_api_url.trim_end_matches('/').to_string(),
+            tiktok_api_url: config.tiktok_api_url.trim_end_matches('/').to_string(),
+            google_ads_api_url: config.google_ads_api_url.trim_end_matches('/').to_string(),
+            google_oauth_token_url: config.google_oauth_token_url.clone(),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        platform: AdPlatform,
+        method: Method,
+        url: &str,
+        headers: &[(&str, String)],
+        body: Option<Bytes>,
+    ) -> Result<T, String> {
+        let (status, response) = self.http.request(method, url, headers, body).await?;
+        if !status.is_success() {
+            return Err(match error_message(&response) {
+                Some(message) => format!("{} API error: {}", platform, message),
+                None => format!("{} API responded with {}", platform, status),
+            });
+        }
+        serde_json::from_slice(&response).map_err(|e| format!("unexpected {} API response: {}", platform, e))
+    }
+
+    fn url(base: &str, path: &str, query: &[(&str, &str)]) -> Result<Url, String> {
+        let mut url = Url::parse(&format!("{}/{}", base, path)).map_err(|e| e.to_string())?;
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        Ok(url)
+    }
+
+    async fn facebook_conversion(&self, credential: &FacebookCredential, conversion: &Conversion) -> Result<(), String> {
+        let pixel_id = require_digits("pixel_id", credential.pixel_id.as_deref())?;
+        let mut user_data = Map::new();
+        insert_some(&mut user_data, "em", conversion.email.as_deref().map(|email| vec![hashed_email(email)]));
+        insert_some(&mut user_data, "ph", conversion.phone.as_deref().map(|phone| vec![hashed_phone(phone, false)]));
+        insert_some(
+            &mut user_data,
+            "fbc",
+            conversion
+                .click_id
+                .as_ref()
+                .map(|fbclid| format!("fb.1.{}.{}", conversion.event_time.timestamp_millis(), fbclid)),
+        );
+        let mut custom_data = Map::new();
+        insert_some(&mut custom_data, "value", conversion.value);
+        insert_some(&mut custom_data, "currency", conversion.currency.clone());
+        let mut event = Map::new();
+        event.insert("event_name".to_string(), conversion.event_name.clone().into());
+        event.insert("event_time".to_string(), conversion.event_time.timestamp().into());
+        event.insert("action_source".to_string(), "website".into());
+        insert_some(&mut event, "event_id", conversion.event_id.clone());
+        insert_some(&mut event, "event_source_url", conversion.source_url.clone());
+        event.insert("user_data".to_string(), user_data.into());
+        event.insert("custom_data".to_string(), custom_data.into());
+
+        let url = Self::url(&self.graph_api_url, &format!("{}/events", pixel_id), &[("access_token", &credential.access_token)])?;
+        let body = Bytes::from(json!({ "data": [event] }).to_string());
+        self.call::<Value>(AdPlatform::Facebook, Method::POST, url.as_str(), &[], Some(body)).await?;
+        Ok(())
+    }
+
+    async fn facebook_spend(&self, credential: &FacebookCredential, since: NaiveDate, until: NaiveDate) -> Result<Spend, String> {
+        let account = require_digits("ad_account_id", Some(credential.ad_account_id.trim_start_matches("act_")))?;
+        let time_range = json!({ "since": since.to_string(), "until": until.to_string() }).to_string();
+        let url = Self::url(
+            &self.graph_api_url,
+            &format!("act_{}/insights", account),
+            &[
+                ("fields", "spend,account_currency"),
+                ("time_range", &time_range),
+                ("access_token", &credential.access_token),
+            ],
+        )?;
+        let insights: FacebookInsights = self.call(AdPlatform::Facebook, Method::GET, url.as_str(), &[], None).await?;
+        let mut amount = 0.0;
+        for insight in &insights.data {
+            amount += parse_amount(&insight.spend)?;
+        }
+        Ok(Spend {
+            platform: AdPlatform::Facebook,
+            since,
+            until,
+            amount,
+            currency: insights.data.into_iter().find_map(|insight| insight.account_currency),
+        })
+    }
+
+    async fn tiktok_call<T: DeserializeOwned>(
+        &self,
+        credential: &TiktokCredential,
+        method: Method,
+        url: &str,
+        body: Option<Bytes>,
+    ) -> Result<Option<T>, String> {
+        let headers = [("access-token", credential.access_token.clone())];
+        let response: TiktokResponse<T> = self.call(AdPlatform::Tiktok, method, url, &headers, body).await?;
+        match response.code {
+            0 => Ok(response.data),
+            code => Err(format!("tiktok API error {}: {}", code, response.message)),
+        }
+    }
+
+    async fn tiktok_conversion(&self, credential: &TiktokCredential, conversion: &Conversion) -> Result<(), String> {
+        let pixel_code = credential.pixel_code.as_deref().ok_or("the credential has no pixel_code")?;
+        let mut user = Map::new();
+        insert_some(&mut user, "email", conversion.email.as_deref().map(hashed_email));
+        insert_some(&mut user, "phone", conversion.phone.as_deref().map(|phone| hashed_phone(phone, true)));
+        insert_some(&mut user, "ttclid", conversion.click_id.clone());
+        let mut properties = Map::new();
+        insert_some(&mut properties, "value", conversion.value);
+        insert_some(&mut properties, "currency", conversion.currency.clone());
+        let mut event = Map::new();
+        event.insert("event".to_string(), conversion.event_name.clone().into());
+        event.insert("event_time".to_string(), conversion.event_time.timestamp().into());
+        insert_some(&mut event, "event_id", conversion.event_id.clone());
+        event.insert("user".to_string(), user.into());
+        event.insert("properties".to_string(), properties.into());
+        insert_some(&mut event, "page", conversion.source_url.as_ref().map(|url| json!({ "url": url })));
+
+        let url = Self::url(&self.tiktok_api_url, "event/track/", &[])?;
+        let body = json!({ "event_source": "web", "event_source_id": pixel_code, "data": [event] });
+        self.tiktok_call::<Value>(credential, Method::POST, url.as_str(), Some(Bytes::from(body.to_string())))
+            .await?;
+        Ok(())
+    }
+
+    async fn tiktok_spend(&self, credential: &TiktokCredential, since: NaiveDate, until: NaiveDate) -> Result<Spend, String> {
+        let (since_date, until_date) = (since.to_string(), until.to_string());
+        let url = Self::url(
+            &self.tiktok_api_url,
+            "report/integrated/get/",
+            &[
+                ("advertiser_id", &credential.advertiser_id),
+                ("report_type", "BASIC"),
+                ("data_level", "AUCTION_ADVERTISER"),
+                ("dimensions", r#"["advertiser_id"]"#),
+                ("metrics", r#"["spend"]"#),
+                ("start_date", &since_date),
+                ("end_date", &until_date),
+            ],
+        )?;
+        let report: Option<TiktokReport> = self.tiktok_call(credential, Method::GET, url.as_str(), None).await?;
+        let mut amount = 0.0;
+        for row in report.map(|report| report.list).unwrap_or_default() {
+            amount += parse_amount(&row.metrics.spend)?;
+        }
+        // Reports are in the advertiser's currency, which the report does not name
+        Ok(Spend { platform: AdPlatform::Tiktok, since, until, amount, currency: None })
+    }
+
+    /// Headers of a Google Ads API call, with an access token for the credential's refresh token.
+    async fn google_headers(&self, credential: &GoogleAdsCredential) -> Result<Vec<(&'static str, String)>, String> {
+        let form = form_urlencoded::Serializer::new(String::new())
+            .append_pair("grant_type", "refresh_token")
+            .append_pair("client_id", &credential.client_id)
+            .append_pair("client_secret", &credential.client_secret)
+            .append_pair("refresh_token", &credential.refresh_token)
+            .finish();
+        let headers = [("content-type", "application/x-www-form-urlencoded".to_string())];
+        let token: GoogleToken = self
+            .call(AdPlatform::GoogleAds, Method::POST, &self.google_oauth_token_url, &headers, Some(Bytes::from(form)))
+            .await?;
+
+        let mut headers = vec![
+            ("authorization", format!("Bearer {}", token.access_token)),
+            ("developer-token", credential.developer_token.clone()),
+        ];
+        if let Some(login_customer_id) = &credential.login_customer_id {
+            let login_customer_id = google_customer_id(login_customer_id).ok_or("invalid login_customer_id")?;
+            headers.push(("login-customer-id", login_customer_id));
+        }
+        Ok(headers)
+    }
+
+    async fn google_conversion(&self, credential: &GoogleAdsCredential, conversion: &Conversion) -> Result<(), String> {
+        let customer_id = google_customer_id(&credential.customer_id).ok_or("invalid customer_id")?;
+        let action_id = require_digits("conversion_action_id", credential.conversion_action_id.as_deref())?;
+        let mut user_identifiers = Vec::new();
+        if let Some(email) = &conversion.email {
+            user_identifiers.push(json!({ "hashedEmail": hashed_email(email) }));
+        }
+        if let Some(phone) = &conversion.phone {
+            user_identifiers.push(json!({ "hashedPhoneNumber": hashed_phone(phone, true) }));
+        }
+        let mut upload = Map::new();
+        upload.insert(
+            "conversionAction".to_string(),
+            format!("customers/{}/conversionActions/{}", customer_id, action_id).into(),
+        );
+        upload.insert(
+            "conversionDateTime".to_string(),
+            conversion.event_time.format("%Y-%m-%d %H:%M:%S+00:00").to_string().into(),
+        );
+        insert_some(&mut upload, "gclid", conversion.click_id.clone());
+        insert_some(&mut upload, "conversionValue", conversion.value);
+        insert_some(&mut upload, "currencyCode", conversion.currency.clone());
+        insert_some(&mut upload, "orderId", conversion.event_id.clone());
+        if !user_identifiers.is_empty() {
+            upload.insert("userIdentifiers".to_string(), user_identifiers.into());
+        }
+
+        let headers = self.google_headers(credential).await?;
+        let url = Self::url(&self.google_ads_api_url, &format!("customers/{}:uploadClickConversions", customer_id), &[])?;
+        let body = json!({ "conversions": [upload], "partialFailure": true });
+        let response: GoogleUploadResponse = self
+            .call(AdPlatform::GoogleAds, Method::POST, url.as_str(), &headers, Some(Bytes::from(body.to_string())))
+            .await?;
+        match response.partial_failure_error {
+            Some(error) => Err(format!("google_ads API error: {}", error.message)),
+            None => Ok(()),
+        }
+    }
+
+    async fn google_spend(&self, credential: &GoogleAdsCredential, since: NaiveDate, until: NaiveDate) -> Result<Spend, String> {
+        let customer_id = google_customer_id(&credential.customer_id).ok_or("invalid customer_id")?;
+        let headers = self.google_headers(credential).await?;
+        let url = Self::url(&self.google_ads_api_url, &format!("customers/{}/googleAds:search", customer_id), &[])?;
+        // Both dates are formatted by chrono, nothing from the caller reaches the query as text
+        let query = format!(
+            "SELECT customer.currency_code, metrics.cost_micros FROM customer WHERE segments.date BETWEEN '{}' AND '{}'",
+            since, until
+        );
+        let response: GoogleSearchResponse = self
+            .call(AdPlatform::GoogleAds, Method::POST, url.as_str(), &headers, Some(Bytes::from(json!({ "query": query }).to_string())))
+            .await?;
+        let mut micros = 0.0;
+        for row in &response.results {
+            micros += row.metrics.cost_micros.as_deref().map(parse_amount).transpose()?.unwrap_or_default();
+        }
+        Ok(Spend {
+            platform: AdPlatform::GoogleAds,
+            since,
+            until,
+            amount: micros / 1_000_000.0,
+            currency: response.results.into_iter().find_map(|row| row.customer?.currency_code),
+        })
+    }
+}
+
+#[async_trait]
+impl AdPlatformClient for HttpAdPlatformClient {
+    async fn send_conversion(&self, credential: &AdPlatformCredential, conversion: &Conversion) -> Result<(), String> {
+        match credential {
+            AdPlatformCredential::Facebook(credential) => self.facebook_conversion(credential, conversion).await,
+            AdPlatformCredential::Tiktok(credential) => self.tiktok_conversion(credential, conversion).await,
+            AdPlatformCredential::GoogleAds(credential) => self.google_conversion(credential, conversion).await,
+        }
+    }
+
+    async fn fetch_spend(&self, credential: &AdPlatformCredential, since: NaiveDate, until: NaiveDate) -> Result<Spend, String> {
+        match credential {
+            AdPlatformCredential::Facebook(credential) => self.facebook_spend(credential, since, until).await,
+            AdPlatformCredential::Tiktok(credential) => self.tiktok_spend(credential, since, until).await,
+            AdPlatformCredential::GoogleAds(credential) => self.google_spend(credential, since, until).await,
+        }
+    }
+}
+
+// Amount and currency reported per account id
+#[cfg(any(test, feature = "test-support"))]
+type AccountSpend = HashMap<String, (f64, Option<String>)>;
+
+/// In-memory `AdPlatformClient` for tests, also built with the
+/// `test-support` feature. It records conversions and reports spend only for
+/// accounts it was told about.
+#[cfg(any(test, feature = "test-support"))]
+#[derive(Debug, Clone, Default)]
+pub struct MockAdPlatformClient {
+    pub conversions: Arc<Mutex<Vec<(AdPlatform, Conversion)>>>,
+    spend: Arc<Mutex<AccountSpend>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl MockAdPlatformClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `account_id` report `amount` for any date range.
+    pub fn with_spend(self, account_id: &str, amount: f64, currency: Option<&str>) -> Self {
+        self.spend
+            .lock()
+            .unwrap()
+            .insert(account_id.to_string(), (amount, currency.map(str::to_string)));
+        self
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[async_trait]
+impl AdPlatformClient for MockAdPlatformClient {
+    async fn send_conversion(&self, credential: &AdPlatformCredential, conversion: &Conversion) -> Result<(), String> {
+        self.conversions.lock().unwrap().push((credential.platform(), conversion.clone()));
+        Ok(())
+    }
+
+    async fn fetch_spend(&self, credential: &AdPlatformCredential, since: NaiveDate, until: NaiveDate) -> Result<Spend, String> {
+        let (amount, currency) = self
+            .spend
+            .lock()
+            .unwrap()
+            .get(credential.account_id())
+            .cloned()
+            .ok_or_else(|| format!("{} API error: unknown account {}", credential.platform(), credential.account_id()))?;
+        Ok(Spend { platform: credential.platform(), since, until, amount, currency })
+    }
+}
@@ -0,0 +1,198 @@
+use axum::{
+    extract::{Path, Query},
+    http::HeaderMap,
+    routing::{get, post},
+    Form, Json, Router,
+};
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    ad_platform_client::{AdPlatformClient, HttpAdPlatformClient, MockAdPlatformClient},
+    config::AdPlatformsConfig,
+    models::{
+        ad_platform::{AdPlatform, AdPlatformCredential, Conversion, GoogleAdsCredential, TiktokCredential},
+        project::FacebookCredential,
+    },
+};
+
+// sha256("jane@example.com")
+const HASHED_EMAIL: &str = "8c87b489ce35cf2e2f39f80e282cb2e804932a56a213983eeeb428407d43b52d";
+
+async fn facebook_events(Path(pixel): Path<String>, Query(query): Query<HashMap<String, String>>, Json(body): Json<Value>) -> Json<Value> {
+    assert_eq!((pixel.as_str(), query["access_token"].as_str()), ("123", "fb-token"));
+    let event = &body["data"][0];
+    assert_eq!(event["event_name"], "Purchase");
+    assert_eq!(event["event_id"], "order-1");
+    assert_eq!(event["user_data"]["em"][0], HASHED_EMAIL);
+    assert_eq!(event["custom_data"]["currency"], "USD");
+    Json(json!({ "events_received": 1 }))
+}
+
+async fn facebook_insights(Path(account): Path<String>, Query(query): Query<HashMap<String, String>>) -> Json<Value> {
+    assert_eq!(account, "act_42");
+    assert_eq!(query["time_range"], r#"{"since":"2024-05-01","until":"2024-05-31"}"#);
+    Json(json!({ "data": [{ "spend": "12.50", "account_currency": "EUR" }] }))
+}
+
+async fn tiktok_track(headers: HeaderMap, Json(body): Json<Value>) -> Json<Value> {
+    assert_eq!(body["event_source_id"], "PIXEL");
+    match headers["access-token"].to_str().unwrap() {
+        "tt-token" => Json(json!({ "code": 0, "message": "OK", "data": {} })),
+        _ => Json(json!({ "code": 40105, "message": "Access token is incorrect or has been revoked." })),
+    }
+}
+
+async fn tiktok_report(Query(query): Query<HashMap<String, String>>) -> Json<Value> {
+    assert_eq!(query["advertiser_id"], "7000000001");
+    Json(json!({ "code": 0, "message": "OK", "data": { "list": [
+        { "metrics": { "spend": "3.25" } },
+        { "metrics": { "spend": "1.75" } },
+    ] } }))
+}
+
+async fn google_token(Form(form): Form<HashMap<String, String>>) -> Json<Value> {
+    assert_eq!(form["grant_type"], "refresh_token");
+    assert_eq!(form["refresh_token"], "refresh");
+    Json(json!({ "access_token": "google-access", "expires_in": 3599 }))
+}
+
+async fn google_ads(Path(path): Path<String>, headers: HeaderMap, Json(body): Json<Value>) -> Json<Value> {
+    assert_eq!(headers["authorization"], "Bearer google-access");
+    assert_eq!(headers["developer-token"], "developer");
+    match path.as_str() {
+        "customers/1234567890/googleAds:search" => {
+            assert!(body["query"].as_str().unwrap().contains("BETWEEN '2024-05-01' AND '2024-05-31'"));
+            Json(json!({ "results": [
+                { "customer": { "currencyCode": "USD" }, "metrics": { "costMicros": "2500000" } },
+                { "metrics": { "costMicros": "500000" } },
+            ] }))
+        }
+        "customers/1234567890:uploadClickConversions" => {
+            assert_eq!(body["conversions"][0]["conversionAction"], "customers/1234567890/conversionActions/42");
+            Json(json!({ "partialFailureError": { "code": 3, "message": "The click is too old to be imported." } }))
+        }
+        _ => panic!("unexpected Google Ads call {}", path),
+    }
+}
+
+async fn spawn_platforms() -> HttpAdPlatformClient {
+    let app = Router::new()
+        .route("/graph/:pixel/events", post(facebook_events))
+        .route("/graph/:account/insights", get(facebook_insights))
+        .route("/tiktok/event/track/", post(tiktok_track))
+        .route("/tiktok/report/integrated/get/", get(tiktok_report))
+        .route("/google/token", post(google_token))
+        .route("/google/ads/*path", post(google_ads));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let config = AdPlatformsConfig {
+        tiktok_api_url: format!("http://{}/tiktok/", addr),
+        google_ads_api_url: format!("http://{}/google/ads", addr),
+        google_oauth_token_url: format!("http://{}/google/token", addr),
+        event_dedup_window: Duration::from_secs(48 * 60 * 60),
+    };
+    HttpAdPlatformClient::new(&format!("http://{}/graph", addr), &config)
+}
+
+fn facebook() -> AdPlatformCredential {
+    AdPlatformCredential::Facebook(FacebookCredential {
+        app_id: "app".to_string(),
+        app_secret: "secret".to_string(),
+        access_token: "fb-token".to_string(),
+        ad_account_id: "act_42".to_string(),
+        account_suffix: "42".to_string(),
+        pixel_id: Some("123".to_string()),
+        link_url: None,
+        page_id: None,
+        watermark: None,
+        token_health: None,
+    })
+}
+
+fn tiktok(access_token: &str) -> AdPlatformCredential {
+    AdPlatformCredential::Tiktok(TiktokCredential {
+        app_id: "app".to_string(),
+        app_secret: "secret".to_string(),
+        access_token: access_token.to_string(),
+        advertiser_id: "7000000001".to_string(),
+        pixel_code: Some("PIXEL".to_string()),
+    })
+}
+
+fn google() -> AdPlatformCredential {
+    AdPlatformCredential::GoogleAds(GoogleAdsCredential {
+        developer_token: "developer".to_string(),
+        client_id: "client".to_string(),
+        client_secret: "client-secret".to_string(),
+        refresh_token: "refresh".to_string(),
+        customer_id: "123-456-7890".to_string(),
+        login_customer_id: None,
+        conversion_action_id: Some("42".to_string()),
+    })
+}
+
+fn purchase() -> Conversion {
+    serde_json::from_value(json!({
+        "event_name": "Purchase",
+        "event_id": "order-1",
+        "value": 10.0,
+        "currency": "USD",
+        "click_id": "click",
+        "email": " Jane@Example.com ",
+    }))
+    .unwrap()
+}
+
+fn may() -> (NaiveDate, NaiveDate) {
+    (NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(), NaiveDate::from_ymd_opt(2024, 5, 31).unwrap())
+}
+
+#[tokio::test]
+async fn test_facebook_conversions_and_spend() {
+    let client = spawn_platforms().await;
+    client.send_conversion(&facebook(), &purchase()).await.unwrap();
+
+    let (since, until) = may();
+    let spend = client.fetch_spend(&facebook(), since, until).await.unwrap();
+    assert_eq!((spend.platform, spend.amount, spend.currency.as_deref()), (AdPlatform::Facebook, 12.5, Some("EUR")));
+}
+
+#[tokio::test]
+async fn test_tiktok_errors_are_read_from_the_response_code() {
+    let client = spawn_platforms().await;
+    client.send_conversion(&tiktok("tt-token"), &purchase()).await.unwrap();
+
+    let error = client.send_conversion(&tiktok("revoked"), &purchase()).await.unwrap_err();
+    assert!(error.starts_with("tiktok API error 40105"), "{}", error);
+
+    let (since, until) = may();
+    let spend = client.fetch_spend(&tiktok("tt-token"), since, until).await.unwrap();
+    assert_eq!((spend.amount, spend.currency), (5.0, None));
+}
+
+#[tokio::test]
+async fn test_google_ads_calls_use_a_refreshed_access_token() {
+    let client = spawn_platforms().await;
+    let (since, until) = may();
+    let spend = client.fetch_spend(&google(), since, until).await.unwrap();
+    assert_eq!((spend.amount, spend.currency.as_deref()), (3.0, Some("USD")));
+
+    let error = client.send_conversion(&google(), &purchase()).await.unwrap_err();
+    assert_eq!(error, "google_ads API error: The click is too old to be imported.");
+}
+
+#[tokio::test]
+async fn test_mock_reports_spend_for_known_accounts() {
+    let client = MockAdPlatformClient::new().with_spend("act_42", 7.5, Some("USD"));
+    let (since, until) = may();
+
+    let spend = client.fetch_spend(&facebook(), since, until).await.unwrap();
+    assert_eq!((spend.since, spend.amount), (since, 7.5));
+    assert!(client.fetch_spend(&google(), since, until).await.is_err());
+
+    client.send_conversion(&google(), &purchase()).await.unwrap();
+    assert_eq!(client.conversions.lock().unwrap()[0].0, AdPlatform::GoogleAds);
+}
@@ -0,0 +1,106 @@
+use axum::{
+    body::Body,
+    http::{header::LINK, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use chrono::{TimeZone, Utc};
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    app::{self, AppState},
+    config::ApiConfig,
+    middleware::deprecation::{DEPRECATION_HEADER, SUNSET_HEADER},
+    test_support::{test_state, unreachable_db, BOOTSTRAP_KEY},
+};
+
+async fn app(deprecated: bool) -> Router {
+    let state = AppState {
+        api: ApiConfig {
+            unversioned_deprecated_at: deprecated.then(|| Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap()),
+            ..ApiConfig::default()
+        },
+        ..test_state(unreachable_db().await)
+    };
+    app::router(state, CorsLayer::permissive())
+}
+
+async fn send(app: &Router, method: &str, uri: &str) -> Response {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("x-api-key", BOOTSTRAP_KEY)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_unversioned_routes_are_deprecated_aliases_of_v1() {
+    let app = app(true).await;
+
+    // Authorization passed in both, so the handler rejected the malformed id
+    let response = send(&app, "GET", "/projects/not-an-id").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()[DEPRECATION_HEADER], "@1798761600");
+    // 180 days after the deprecation by default
+    assert_eq!(response.headers()[SUNSET_HEADER], "Wed, 30 Jun 2027 00:00:00 GMT");
+    assert_eq!(response.headers()[LINK], "</v1/projects/not-an-id>; rel=\"successor-version\"");
+
+    let response = send(&app, "GET", "/v1/projects/not-an-id").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(!response.headers().contains_key(DEPRECATION_HEADER));
+
+    let response = send(&app, "GET", "/v2/projects/not-an-id").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(!response.headers().contains_key(DEPRECATION_HEADER));
+}
+
+#[tokio::test]
+async fn test_unversioned_routes_are_not_deprecated_until_configured() {
+    let response = send(&app(false).await, "GET", "/accounts/not-an-id").await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(!response.headers().contains_key(DEPRECATION_HEADER));
+    assert!(!response.headers().contains_key(SUNSET_HEADER));
+    assert!(!response.headers().contains_key(LINK));
+}
+
+#[tokio::test]
+async fn test_v2_drops_the_facebook_credential_routes() {
+    let app = app(false).await;
+
+    let response = send(&app, "GET", "/v2/projects/650000000000000000000002/facebook-credentials").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = send(&app, "POST", "/v2/projects/650000000000000000000002/facebook-credentials").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_only_manual_project_transitions_are_routed() {
+    let app = app(false).await;
+
+    for version in ["/v1", "/v2"] {
+        // Routed to their handler, which rejected the malformed id
+        for action in ["activate", "suspend", "resume", "cancel"] {
+            let response = send(&app, "POST", &format!("{}/projects/not-an-id/{}", version, action)).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{} {}", version, action);
+        }
+        // Expiring and renewing follow from `expires_at`
+        for action in ["expire", "renew", "archive"] {
+            let response = send(&app, "POST", &format!("{}/projects/not-an-id/{}", version, action)).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{} {}", version, action);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_probes_stay_unversioned() {
+    let app = app(true).await;
+
+    let response = send(&app, "GET", "/healthz").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response.headers().contains_key(DEPRECATION_HEADER));
+    assert_eq!(send(&app, "GET", "/v1/healthz").await.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,259 @@
+use axum::{
+    extract::{FromRef, Path, Request, State},
+    handler::Handler,
+    http::{HeaderValue, Method},
+    middleware::{from_fn, from_fn_with_state},
+    response::{IntoResponse, Response},
+    routing::{any, delete, get, post, put},
+    Router,
+};
+use std::sync::Arc;
+use tower_http::cors::{Any, CorsLayer};
+
+use crate::{
+    config::{ApiConfig, Config, CorsOrigins},
+    error::ApiError,
+    handlers::{
+        account_handler::{
+            create_account, delete_account, export_accounts, get_account, get_all_accounts, import_accounts,
+            update_account,
+        },
+        api_key_handler::{create_api_key, get_all_api_keys, get_api_key, revoke_api_key},
+        ad_platform_handler::{get_dedup_stats, get_spend, issue_event_id, send_conversion},
+        event_handler::stream_events,
+        credential_handler::{
+            add_ad_credential, add_credential, get_ad_credentials, get_credentials, remove_ad_credential,
+            remove_credential, replace_ad_credential, replace_credential,
+        },
+        health_handler::{healthz, readyz, version},
+        lead_handler::{get_leads, receive_notification, verify_subscription},
+        link_handler::{create_link, delete_link, follow_link, get_link_stats, get_links},
+        metrics_handler::get_metrics,
+        organization_handler::{
+            create_organization, get_all_organizations, get_organization, remove_member, set_member_role,
+        },
+        project_handler::{
+            activate_project, batch_get_projects, batch_update_projects, cancel_project, create_project,
+            delete_project, export_projects, get_all_projects, get_project, import_projects, resume_project,
+            suspend_project, update_project,
+        },
+        v2,
+        webhook_handler::{
+            create_webhook, delete_webhook, get_all_webhooks, get_deliveries, get_webhook, redeliver,
+        },
+    },
+    middleware::{
+        self,
+        deprecation::{deprecated, Deprecation},
+        idempotency,
+        rate_limit::{self, RateLimiter},
+    },
+    service::{
+        account_service::AccountService, ad_platform_service::AdPlatformService, api_key_service::ApiKeyService,
+        event_stream_service::EventStreamService, health_service::HealthService,
+        idempotency_service::IdempotencyService,
+        lead_service::LeadService, link_service::LinkService, organization_service::OrganizationService,
+        project_service::ProjectService, webhook_service::WebhookService,
+    },
+};
+
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub project_service: ProjectService,
+    pub account_service: AccountService,
+    pub api_key_service: ApiKeyService,
+    pub organization_service: OrganizationService,
+    pub health_service: HealthService,
+    pub rate_limiter: RateLimiter,
+    pub idempotency_service: IdempotencyService,
+    pub webhook_service: WebhookService,
+    pub lead_service: LeadService,
+    pub ad_platform_service: AdPlatformService,
+    pub link_service: LinkService,
+    pub event_stream_service: EventStreamService,
+    pub api: ApiConfig,
+}
+
+/// Versions the API is served under, as `/v1` and `/v2`. A version only
+/// replaces the handlers whose shape changed and serves the rest as before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ApiVersion {
+    V1,
+    V2,
+}
+
+pub fn cors_layer(config: &Config) -> CorsLayer {
+    match &config.cors.allowed_origins {
+        CorsOrigins::Any => CorsLayer::permissive(),
+        CorsOrigins::List(origins) => {
+            let origins: Vec<HeaderValue> = origins
+                .iter()
+                .filter_map(|origin| origin.parse().ok())
+                .collect();
+            CorsLayer::new()
+                .allow_origin(origins)
+                .allow_methods(Any)
+                .allow_headers(Any)
+        }
+    }
+}
+
+// matchit reads `/projects:import` as a path parameter, so the custom methods
+// of a collection share one `/projects:verb` route and are dispatched here
+async fn project_methods(State(state): State<AppState>, Path(verb): Path<String>, request: Request) -> Response {
+    match (request.method().clone(), verb.trim_start_matches(':')) {
+        (Method::POST, "import") => import_projects.call(request, state).await,
+        (Method::GET, "export") => export_projects.call(request, state).await,
+        (Method::POST, "batchGet") => batch_get_projects.call(request, state).await,
+        (Method::POST, "batchUpdate") => batch_update_projects.call(request, state).await,
+        _ => ApiError::NotFound.into_response(),
+    }
+}
+
+async fn project_methods_v2(State(state): State<AppState>, Path(verb): Path<String>, request: Request) -> Response {
+    match (request.method().clone(), verb.trim_start_matches(':')) {
+        (Method::POST, "batchGet") => v2::project_handler::batch_get_projects.call(request, state).await,
+        (Method::POST, "batchUpdate") => v2::project_handler::batch_update_projects.call(request, state).await,
+        _ => project_methods(State(state), Path(verb.clone()), request).await,
+    }
+}
+
+async fn account_methods(State(state): State<AppState>, Path(verb): Path<String>, request: Request) -> Response {
+    match (request.method().clone(), verb.trim_start_matches(':')) {
+        (Method::POST, "import") => import_accounts.call(request, state).await,
+        (Method::GET, "export") => export_accounts.call(request, state).await,
+        _ => ApiError::NotFound.into_response(),
+    }
+}
+
+fn api_routes(state: &AppState, version: ApiVersion) -> Router<AppState> {
+    // Clients retry creates on flaky networks, an Idempotency-Key makes that safe
+    let idempotent = || from_fn_with_state(state.idempotency_service.clone(), idempotency::idempotent);
+
+    let project_routes = match version {
+        ApiVersion::V1 => Router::new()
+            .route("/projects", post(create_project).route_layer(idempotent()))
+            .route("/projects", get(get_all_projects))
+            .route("/projects:verb", any(project_methods))
+            .route("/projects/:id", get(get_project))
+            .route("/projects/:id", put(update_project))
+            .route("/projects/:id/activate", post(activate_project))
+            .route("/projects/:id/suspend", post(suspend_project))
+            .route("/projects/:id/resume", post(resume_project))
+            .route("/projects/:id/cancel", post(cancel_project))
+            .route("/projects/:id/facebook-credentials", get(get_credentials).post(add_credential))
+            .route("/projects/:id/facebook-credentials/:key", put(replace_credential).delete(remove_credential)),
+        // Projects lose their legacy Facebook views, use ad-credentials instead
+        ApiVersion::V2 => Router::new()
+            .route("/projects", post(v2::project_handler::create_project).route_layer(idempotent()))
+            .route("/projects", get(v2::project_handler::get_all_projects))
+            .route("/projects:verb", any(project_methods_v2))
+            .route("/projects/:id", get(v2::project_handler::get_project))
+            .route("/projects/:id", put(v2::project_handler::update_project))
+            .route("/projects/:id/activate", post(v2::project_handler::activate_project))
+            .route("/projects/:id/suspend", post(v2::project_handler::suspend_project))
+            .route("/projects/:id/resume", post(v2::project_handler::resume_project))
+            .route("/projects/:id/cancel", post(v2::project_handler::cancel_project)),
+    };
+    let project_routes = project_routes
+        .route("/projects/:id", delete(delete_project))
+        .route("/projects/:id/ad-credentials", get(get_ad_credentials).post(add_ad_credential))
+        .route("/projects/:id/ad-credentials/:key", put(replace_ad_credential).delete(remove_ad_credential))
+        .route("/projects/:id/ad-credentials/:key/conversions", post(send_conversion))
+        .route("/projects/:id/ad-credentials/:key/spend", get(get_spend))
+        .route("/projects/:id/event-ids", post(issue_event_id))
+        .route("/projects/:id/event-ids/stats", get(get_dedup_stats))
+        .route("/projects/:id/leads", get(get_leads))
+        .route("/projects/:id/links", get(get_links).post(create_link))
+        .route("/projects/:id/links/:link_id", delete(delete_link))
+        .route("/projects/:id/links/:link_id/stats", get(get_link_stats))
+        .route_layer(from_fn_with_state(state.rate_limiter.group("projects"), rate_limit::limit));
+
+    let account_routes = Router::new()
+        .route("/accounts", post(create_account).route_layer(idempotent()))
+        .route("/accounts", get(get_all_accounts))
+        .route("/accounts:verb", any(account_methods))
+        .route("/accounts/:id", get(get_account))
+        .route("/accounts/:id", put(update_account))
+        .route("/accounts/:id", delete(delete_account))
+        .route_layer(from_fn_with_state(state.rate_limiter.group("accounts"), rate_limit::limit));
+
+    let api_key_routes = Router::new()
+        .route("/api-keys", post(create_api_key))
+        .route("/api-keys", get(get_all_api_keys))
+        .route("/api-keys/:id", get(get_api_key))
+        .route("/api-keys/:id", delete(revoke_api_key))
+        .route_layer(from_fn_with_state(state.rate_limiter.group("api_keys"), rate_limit::limit));
+
+    let organization_routes = Router::new()
+        .route("/organizations", post(create_organization))
+        .route("/organizations", get(get_all_organizations))
+        .route("/organizations/:id", get(get_organization))
+        .route("/organizations/:id/members/:account_id", put(set_member_role))
+        .route("/organizations/:id/members/:account_id", delete(remove_member))
+        .route_layer(from_fn_with_state(state.rate_limiter.group("organizations"), rate_limit::limit));
+
+    let webhook_routes = Router::new()
+        .route("/webhooks", post(create_webhook))
+        .route("/webhooks", get(get_all_webhooks))
+        .route("/webhooks/:id", get(get_webhook))
+        .route("/webhooks/:id", delete(delete_webhook))
+        .route("/webhooks/:id/deliveries", get(get_deliveries))
+        .route("/webhooks/:id/deliveries/:delivery_id/redeliver", post(redeliver))
+        .route_layer(from_fn_with_state(state.rate_limiter.group("webhooks"), rate_limit::limit));
+
+    // Limits how often streams are opened, an open stream is a single request
+    let event_routes = match version {
+        ApiVersion::V1 => Router::new().route("/events/stream", get(stream_events)),
+        ApiVersion::V2 => Router::new().route("/events/stream", get(v2::event_handler::stream_events)),
+    }
+    .route_layer(from_fn_with_state(state.rate_limiter.group("events"), rate_limit::limit));
+
+    project_routes
+        .merge(account_routes)
+        .merge(api_key_routes)
+        .merge(organization_routes)
+        .merge(webhook_routes)
+        .merge(event_routes)
+}
+
+pub fn router(state: AppState, cors: CorsLayer) -> Router {
+    // The unversioned paths predate `/v1` and are served as it until their sunset
+    let mut unversioned_routes = api_routes(&state, ApiVersion::V1);
+    if let (Some(deprecated_at), Some(sunset)) = (state.api.unversioned_deprecated_at, state.api.unversioned_sunset()) {
+        let deprecation = Deprecation {
+            version: "unversioned",
+            deprecated_at,
+            sunset,
+            successor: "/v1",
+        };
+        unversioned_routes = unversioned_routes.route_layer(from_fn_with_state(Arc::new(deprecation), deprecated));
+    }
+
+    // Called by Facebook, which authenticates with a body signature instead of an API key
+    let facebook_routes = Router::new()
+        .route("/facebook/webhook", get(verify_subscription).post(receive_notification));
+
+    // Followed by anyone the link was shared with, limited per IP
+    let link_routes = Router::new()
+        .route("/l/:code", get(follow_link))
+        .route_layer(from_fn_with_state(state.rate_limiter.group("links"), rate_limit::limit));
+
+    // Probes and metrics scrapes are never rate limited
+    let health_routes = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version))
+        .route("/metrics", get(get_metrics));
+
+    let app = unversioned_routes
+        .nest("/v1", api_routes(&state, ApiVersion::V1))
+        .nest("/v2", api_routes(&state, ApiVersion::V2))
+        .merge(facebook_routes)
+        .merge(link_routes)
+        .merge(health_routes)
+        .with_state(state)
+        .layer(from_fn(middleware::metrics::track_http))
+        .layer(cors);
+    middleware::request_tracing::layer(app)
+}
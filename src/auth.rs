@@ -0,0 +1,143 @@
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts, HeaderMap},
+};
+use mongodb::bson::oid::ObjectId;
+use std::{collections::BTreeSet, marker::PhantomData};
+
+use crate::{
+    error::ApiError,
+    models::{api_key::Scope, organization::OrgRole},
+    service::api_key_service::ApiKeyService,
+};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+pub const ORG_ID_HEADER: &str = "x-org-id";
+
+/// The authenticated caller of a request.
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub key_id: Option<ObjectId>,
+    pub name: String,
+    pub scopes: BTreeSet<Scope>,
+    // Organization the key is bound to, platform keys have none
+    pub org_id: Option<ObjectId>,
+    // Role of the member the key acts for
+    pub role: Option<OrgRole>,
+    // Organization the request acts on, chosen by platform keys with X-Org-Id
+    pub tenant_id: Option<ObjectId>,
+    // Requests may name the Telegram user they act for
+    pub bot_frontend: bool,
+}
+
+impl Caller {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    /// The organization tenant-scoped data is read from and written to.
+    pub fn tenant(&self) -> Result<ObjectId, ApiError> {
+        self.tenant_id
+            .ok_or_else(|| ApiError::BadRequest("X-Org-Id header required for platform keys".to_string()))
+    }
+
+    pub fn can_access_org(&self, org_id: &ObjectId) -> bool {
+        self.org_id.is_none_or(|own| own == *org_id)
+    }
+}
+
+/// Ties a marker type to the scope a handler requires.
+pub trait RequiredScope {
+    const SCOPE: Scope;
+}
+
+macro_rules! required_scope {
+    ($name:ident, $scope:expr) => {
+        pub struct $name;
+
+        impl RequiredScope for $name {
+            const SCOPE: Scope = $scope;
+        }
+    };
+}
+
+required_scope!(ProjectsRead, Scope::ProjectsRead);
+required_scope!(ProjectsWrite, Scope::ProjectsWrite);
+required_scope!(AccountsAdmin, Scope::AccountsAdmin);
+required_scope!(KeysAdmin, Scope::KeysAdmin);
+required_scope!(OrgsAdmin, Scope::OrgsAdmin);
+required_scope!(WebhooksAdmin, Scope::WebhooksAdmin);
+
+/// Extractor that authenticates the API key of a request and rejects it
+/// unless the key holds the scope of `R`.
+pub struct Authorized<R: RequiredScope> {
+    pub caller: Caller,
+    _scope: PhantomData<R>,
+}
+
+/// Reads the key from `X-Api-Key` or an `Authorization: Bearer` header.
+pub fn api_key_from_headers(headers: &HeaderMap) -> Option<&str> {
+    if let Some(value) = headers.get(API_KEY_HEADER) {
+        return value.to_str().ok();
+    }
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// Resolves the caller from the API key and `X-Org-Id` headers.
+pub async fn authenticate(headers: &HeaderMap, service: &ApiKeyService) -> Result<Caller, ApiError> {
+    let key = api_key_from_headers(headers).ok_or(ApiError::Unauthorized)?;
+    let requested_org = org_id_from_headers(headers)?;
+    service.authenticate(key.trim(), requested_org).await
+}
+
+fn org_id_from_headers(headers: &HeaderMap) -> Result<Option<ObjectId>, ApiError> {
+    let Some(value) = headers.get(ORG_ID_HEADER) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| ObjectId::parse_str(value.trim()).ok())
+        .map(Some)
+        .ok_or_else(|| ApiError::BadRequest("Invalid X-Org-Id header".to_string()))
+}
+
+#[async_trait]
+impl<R, S> FromRequestParts<S> for Authorized<R>
+where
+    R: RequiredScope,
+    ApiKeyService: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // The rate limiter has authenticated the request already where it runs
+        let caller = match parts.extensions.get::<Caller>() {
+            Some(caller) => caller.clone(),
+            None => authenticate(&parts.headers, &ApiKeyService::from_ref(state)).await?,
+        };
+
+        let span = tracing::Span::current();
+        span.record("api_key", caller.name.as_str());
+        if let Some(id) = caller.key_id {
+            span.record("api_key_id", id.to_hex().as_str());
+        }
+        if let Some(org_id) = caller.tenant_id {
+            span.record("org_id", org_id.to_hex().as_str());
+        }
+
+        if !caller.has_scope(R::SCOPE) {
+            return Err(ApiError::Forbidden(format!("API key lacks the {} scope", R::SCOPE)));
+        }
+
+        Ok(Self {
+            caller,
+            _scope: PhantomData,
+        })
+    }
+}
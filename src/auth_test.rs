@@ -0,0 +1,169 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    app,
+    models::api_key::{CreateApiKey, Scope},
+    test_support::{test_db, test_state, unreachable_db, BOOTSTRAP_KEY},
+};
+
+async fn send(app: &Router, method: &str, uri: &str, key: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(key) = key {
+        request = request.header("x-api-key", key);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, json)
+}
+
+#[tokio::test]
+async fn test_requests_without_valid_key_are_rejected() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+
+    let (status, body) = send(&app, "GET", "/projects", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"], "Missing or invalid API key");
+
+    let (status, _) = send(&app, "GET", "/accounts", Some("not-a-key"), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send(&app, "GET", "/api-keys", Some("Bearer"), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_probes_do_not_require_a_key() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+
+    let (status, _) = send(&app, "GET", "/healthz", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_platform_keys_must_name_a_tenant() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+
+    let (status, body) = send(&app, "GET", "/projects", Some(BOOTSTRAP_KEY), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "X-Org-Id header required for platform keys");
+
+    let request = Request::get("/projects")
+        .header("x-api-key", BOOTSTRAP_KEY)
+        .header("x-org-id", "not-an-id")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_bearer_bootstrap_key_passes_authorization() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+
+    let request = Request::get("/projects/not-an-id")
+        .header("authorization", format!("Bearer {}", BOOTSTRAP_KEY))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+
+    // Authorization passed, so the handler rejected the malformed id
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_scopes_are_enforced_and_revocation_is_immediate() {
+    let state = test_state(test_db("_auth").await);
+    let app = app::router(state.clone(), CorsLayer::permissive());
+
+    let (status, org) = send(&app, "POST", "/organizations", Some(BOOTSTRAP_KEY), Some(json!({ "name": "Auth" }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let org_id = org["_id"]["$oid"].as_str().unwrap().to_string();
+
+    let (status, created) = send(
+        &app,
+        "POST",
+        "/api-keys",
+        Some(BOOTSTRAP_KEY),
+        Some(json!({ "name": "reader", "scopes": ["projects:read"], "org_id": org_id })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let key = created["key"].as_str().unwrap().to_string();
+    let id = created["api_key"]["id"].as_str().unwrap().to_string();
+    assert!(created["api_key"].get("key_hash").is_none());
+    assert_eq!(created["api_key"]["scopes"], json!(["projects:read"]));
+
+    let (status, _) = send(&app, "GET", "/projects", Some(&key), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send(&app, "POST", "/projects", Some(&key), Some(json!({}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "API key lacks the projects:write scope");
+
+    let (status, _) = send(&app, "GET", "/api-keys", Some(&key), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, revoked) = send(&app, "DELETE", &format!("/api-keys/{}", id), Some(BOOTSTRAP_KEY), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(revoked, json!(true));
+
+    let (status, _) = send(&app, "GET", "/projects", Some(&key), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Minting validates its input
+    let bootstrap = state.api_key_service.authenticate(BOOTSTRAP_KEY, None).await.unwrap();
+    let request = |name: &str, scopes: Vec<Scope>| CreateApiKey {
+        name: name.to_string(),
+        scopes,
+        org_id: None,
+        account_id: None,
+        bot_frontend: false,
+    };
+    let result = state.api_key_service.create_api_key(&bootstrap, request("empty", vec![])).await;
+    assert!(result.is_err());
+    let result = state
+        .api_key_service
+        .create_api_key(&bootstrap, request(" ", vec![Scope::ProjectsRead]))
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_only_bootstrap_or_owner_keys_mint_bot_frontend_keys() {
+    let app = app::router(test_state(test_db("_auth_bot_frontend").await), CorsLayer::permissive());
+    let (_, org) = send(&app, "POST", "/organizations", Some(BOOTSTRAP_KEY), Some(json!({ "name": "Bots" }))).await;
+    let org_id = org["_id"]["$oid"].as_str().unwrap().to_string();
+    let key_admin = json!({ "name": "keys", "scopes": ["keys:admin", "projects:read"], "org_id": org_id });
+    let (status, created) = send(&app, "POST", "/api-keys", Some(BOOTSTRAP_KEY), Some(key_admin)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let key_admin = created["key"].as_str().unwrap().to_string();
+
+    let frontend = json!({ "name": "bot", "scopes": ["projects:read"], "bot_frontend": true });
+    let (status, body) = send(&app, "POST", "/api-keys", Some(&key_admin), Some(frontend)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "Only the bootstrap key or an organization owner can mint bot frontend keys");
+
+    // Other keys stay open to it
+    let plain = json!({ "name": "reader", "scopes": ["projects:read"] });
+    let (status, _) = send(&app, "POST", "/api-keys", Some(&key_admin), Some(plain)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let frontend = json!({ "name": "bot", "scopes": ["projects:read"], "org_id": org_id, "bot_frontend": true });
+    let (status, _) = send(&app, "POST", "/api-keys", Some(BOOTSTRAP_KEY), Some(frontend)).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
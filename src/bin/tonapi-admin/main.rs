@@ -0,0 +1,499 @@
+mod output;
+#[cfg(test)]
+mod output_test;
+
+use chrono::{DateTime, Duration, Utc};
+use clap::{Parser, Subcommand};
+use dotenv::dotenv;
+use mongodb::bson::oid::ObjectId;
+use serde_json::json;
+use std::{collections::HashMap, path::PathBuf};
+
+use telegram_ton_api::{
+    config::{CliArgs, Config},
+    crypto::SecretCipher,
+    error::ApiError,
+    migrations::Migrator,
+    models::{
+        account::Account,
+        organization::OrgRole,
+        project::{Project, ProjectAction, ProjectStatus, StatusChange, StatusReason},
+    },
+    repository::{
+        self, account_repository::AccountRepository, organization_repository::OrganizationRepository,
+        outbox_repository::OutboxRepository, project_repository::ProjectRepository,
+        webhook_delivery_repository::WebhookDeliveryRepository, webhook_repository::WebhookRepository,
+    },
+    service::{
+        account_service::AccountService, organization_service::OrganizationService,
+        project_service::ProjectService, webhook_service::WebhookService,
+    },
+};
+
+use crate::output::{print, OutputFormat, Table};
+
+#[derive(Parser, Debug)]
+#[command(name = "tonapi-admin", version, about = "Operate the Telegram TON API database")]
+struct AdminArgs {
+    /// Path to a TOML configuration file (defaults to $CONFIG_FILE)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    #[arg(long, global = true)]
+    mongodb_url: Option<String>,
+    #[arg(long, global = true)]
+    database_name: Option<String>,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, global = true)]
+    output: OutputFormat,
+    #[command(subcommand)]
+    command: AdminCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum AdminCommand {
+    #[command(subcommand)]
+    Projects(ProjectsCommand),
+    #[command(subcommand)]
+    Accounts(AccountsCommand),
+    #[command(subcommand)]
+    Keys(KeysCommand),
+    /// Apply pending database migrations
+    Migrate {
+        /// Report what each pending migration would change without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print projects and accounts as one JSON document
+    Export {
+        #[arg(long, value_parser = parse_id)]
+        org: Option<ObjectId>,
+        /// Include credential secrets instead of the masked placeholder
+        #[arg(long)]
+        reveal_secrets: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ProjectsCommand {
+    List {
+        #[arg(long, value_parser = parse_id)]
+        org: Option<ObjectId>,
+    },
+    Show {
+        #[arg(value_parser = parse_id)]
+        id: ObjectId,
+        /// Include credential secrets instead of the masked placeholder
+        #[arg(long)]
+        reveal_secrets: bool,
+    },
+    Create {
+        #[arg(long, value_parser = parse_id)]
+        org: ObjectId,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        telegram_chat_id: Option<String>,
+        /// RFC 3339 timestamp
+        #[arg(long)]
+        expires_at: Option<DateTime<Utc>>,
+    },
+    /// Activate a draft project
+    Activate {
+        #[arg(value_parser = parse_id)]
+        id: ObjectId,
+    },
+    #[command(alias = "deactivate")]
+    Suspend {
+        #[arg(value_parser = parse_id)]
+        id: ObjectId,
+        /// `non_payment`, `client_request`, `policy_violation`, `fraud` or `other`
+        #[arg(long)]
+        reason: StatusReason,
+        #[arg(long)]
+        note: Option<String>,
+    },
+    Resume {
+        #[arg(value_parser = parse_id)]
+        id: ObjectId,
+    },
+    Cancel {
+        #[arg(value_parser = parse_id)]
+        id: ObjectId,
+        /// `non_payment`, `client_request`, `policy_violation`, `fraud` or `other`
+        #[arg(long)]
+        reason: StatusReason,
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Move the expiry forward, renewing the project if it expired
+    Extend {
+        #[arg(value_parser = parse_id)]
+        id: ObjectId,
+        /// Days to add to the current expiry, or to now when it has passed
+        #[arg(long, required_unless_present = "until", conflicts_with = "until")]
+        days: Option<i64>,
+        /// RFC 3339 timestamp
+        #[arg(long)]
+        until: Option<DateTime<Utc>>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AccountsCommand {
+    List {
+        #[arg(long, value_parser = parse_id)]
+        org: Option<ObjectId>,
+    },
+    /// Make an account a member of its organization
+    Link {
+        #[arg(long, value_parser = parse_id)]
+        org: ObjectId,
+        #[arg(long, value_parser = parse_id)]
+        account: ObjectId,
+        /// `viewer`, `admin` or `owner`
+        #[arg(long, value_parser = parse_role)]
+        role: OrgRole,
+    },
+    Unlink {
+        #[arg(long, value_parser = parse_id)]
+        org: ObjectId,
+        #[arg(long, value_parser = parse_id)]
+        account: ObjectId,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum KeysCommand {
+    /// Re-encrypt stored credential and webhook secrets with the active encryption key
+    Rotate {
+        /// Report how many projects and webhooks would be re-encrypted without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+fn parse_id(value: &str) -> Result<ObjectId, String> {
+    ObjectId::parse_str(value).map_err(|_| "Invalid ID format".to_string())
+}
+
+fn parse_role(value: &str) -> Result<OrgRole, String> {
+    serde_json::from_value(json!(value)).map_err(|_| "expected viewer, admin or owner".to_string())
+}
+
+struct Admin {
+    projects: ProjectService,
+    accounts: AccountService,
+    organizations: OrganizationService,
+    webhooks: WebhookService,
+    migrator: Migrator,
+    output: OutputFormat,
+}
+
+impl Admin {
+    async fn run(&self, command: AdminCommand) -> Result<(), ApiError> {
+        match command {
+            AdminCommand::Projects(command) => self.projects(command).await,
+            AdminCommand::Accounts(command) => self.accounts(command).await,
+            AdminCommand::Keys(KeysCommand::Rotate { dry_run }) => {
+                let projects = self.projects.rotate_encryption_key(dry_run).await?;
+                let webhooks = self.webhooks.rotate_encryption_key(dry_run).await?;
+                let verb = if dry_run { "would be re-encrypted" } else { "re-encrypted" };
+                let report = json!({ "affected": projects, "webhooks": webhooks, "dry_run": dry_run });
+                print(self.output, &report, || {
+                    let mut table = Table::new(vec!["PROJECTS", "WEBHOOKS", "STATUS"]);
+                    table.row(vec![projects.to_string(), webhooks.to_string(), verb.to_string()]);
+                    table
+                });
+                Ok(())
+            }
+            AdminCommand::Migrate { dry_run } => {
+                let outcomes = self.migrator.run(dry_run).await?;
+                print(self.output, &outcomes, || {
+                    let mut table = Table::new(vec!["VERSION", "NAME", "AFFECTED"]);
+                    for outcome in &outcomes {
+                        table.row(vec![
+                            format!("{:04}", outcome.version),
+                            outcome.name.to_string(),
+                            outcome.affected.to_string(),
+                        ]);
+                    }
+                    table
+                });
+                Ok(())
+            }
+            AdminCommand::Export { org, reveal_secrets } => {
+                let (mut projects, accounts) = match &org {
+                    Some(org_id) => (
+                        self.projects.get_all_projects(org_id).await?,
+                        self.accounts.get_all_accounts(org_id).await?,
+                    ),
+                    None => (
+                        self.projects.find_all_projects().await?,
+                        self.accounts.find_all_accounts().await?,
+                    ),
+                };
+                if !reveal_secrets {
+                    projects.iter_mut().for_each(Project::mask_secrets);
+                }
+                let export = json!({
+                    "exported_at": Utc::now(),
+                    "org_id": org.map(|id| id.to_hex()),
+                    "projects": projects,
+                    "accounts": accounts,
+                });
+                // Exports are always JSON, a table would drop most fields
+                print(OutputFormat::Json, &export, Table::default);
+                Ok(())
+            }
+        }
+    }
+
+    async fn projects(&self, command: ProjectsCommand) -> Result<(), ApiError> {
+        match command {
+            ProjectsCommand::List { org } => {
+                let mut projects = match &org {
+                    Some(org_id) => self.projects.get_all_projects(org_id).await?,
+                    None => self.projects.find_all_projects().await?,
+                };
+                projects.iter_mut().for_each(Project::mask_secrets);
+                print(self.output, &projects, || project_table(&projects));
+            }
+            ProjectsCommand::Show { id, reveal_secrets } => {
+                let mut project = self.projects.find_project(&id).await?;
+                if !reveal_secrets {
+                    project.mask_secrets();
+                }
+                print(self.output, &project, || project_detail(&project));
+            }
+            ProjectsCommand::Create {
+                org,
+                name,
+                telegram_chat_id,
+                expires_at,
+            } => {
+                let project = Project {
+                    id: None,
+                    org_id: None,
+                    name,
+                    telegram_chat_id,
+                    ad_credentials: HashMap::new(),
+                    package: None,
+                    expires_at,
+                    status: ProjectStatus::Active,
+                    is_active: true,
+                    status_history: vec![],
+                    is_logging: false,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                };
+                let project = self.projects.create_project(&org, project).await?;
+                print(self.output, &project, || project_table(std::slice::from_ref(&project)));
+            }
+            ProjectsCommand::Activate { id } => {
+                self.transition(id, ProjectAction::Activate, StatusChange::default()).await?;
+            }
+            ProjectsCommand::Suspend { id, reason, note } => {
+                let change = StatusChange { reason: Some(reason), note };
+                self.transition(id, ProjectAction::Suspend, change).await?;
+            }
+            ProjectsCommand::Resume { id } => {
+                self.transition(id, ProjectAction::Resume, StatusChange::default()).await?;
+            }
+            ProjectsCommand::Cancel { id, reason, note } => {
+                let change = StatusChange { reason: Some(reason), note };
+                self.transition(id, ProjectAction::Cancel, change).await?;
+            }
+            ProjectsCommand::Extend { id, days, until } => {
+                let existing = self.projects.find_project(&id).await?;
+                let org_id = owning_org(&existing)?;
+                let expires_at = match (until, days) {
+                    (Some(until), _) => until,
+                    (None, Some(days)) => {
+                        let from = existing.expires_at.filter(|at| *at > Utc::now()).unwrap_or_else(Utc::now);
+                        from + Duration::days(days)
+                    }
+                    (None, None) => return Err(ApiError::BadRequest("Pass --days or --until".to_string())),
+                };
+                let mut project = self.projects.extend_project(&org_id, &id, expires_at).await?;
+                project.mask_secrets();
+                print(self.output, &project, || project_table(std::slice::from_ref(&project)));
+            }
+        }
+        Ok(())
+    }
+
+    async fn transition(&self, id: ObjectId, action: ProjectAction, change: StatusChange) -> Result<(), ApiError> {
+        let org_id = owning_org(&self.projects.find_project(&id).await?)?;
+        let mut project = self.projects.transition_project(&org_id, &id, action, change).await?;
+        project.mask_secrets();
+        print(self.output, &project, || project_table(std::slice::from_ref(&project)));
+        Ok(())
+    }
+
+    async fn accounts(&self, command: AccountsCommand) -> Result<(), ApiError> {
+        match command {
+            AccountsCommand::List { org } => {
+                let accounts = match &org {
+                    Some(org_id) => self.accounts.get_all_accounts(org_id).await?,
+                    None => self.accounts.find_all_accounts().await?,
+                };
+                print(self.output, &accounts, || account_table(&accounts));
+            }
+            AccountsCommand::Link { org, account, role } => {
+                // Operators act like a platform key, not as a member
+                let organization = self.organizations.set_member_role(&org, account, role, None).await?;
+                print(self.output, &organization, || {
+                    let mut table = Table::new(vec!["ACCOUNT", "ROLE"]);
+                    for member in &organization.members {
+                        table.row(vec![member.account_id.to_hex(), json!(member.role).as_str().unwrap_or_default().to_string()]);
+                    }
+                    table
+                });
+            }
+            AccountsCommand::Unlink { org, account } => {
+                let removed = self.organizations.remove_member(&org, &account, None).await?;
+                if !removed {
+                    return Err(ApiError::NotFound);
+                }
+                print(self.output, &json!({ "removed": removed }), || {
+                    let mut table = Table::new(vec!["ACCOUNT", "REMOVED"]);
+                    table.row(vec![account.to_hex(), "yes".to_string()]);
+                    table
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn owning_org(project: &Project) -> Result<ObjectId, ApiError> {
+    project
+        .org_id
+        .ok_or_else(|| ApiError::Conflict("Project belongs to no organization".to_string()))
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+fn format_date(date: Option<DateTime<Utc>>) -> String {
+    date.map(|date| date.to_rfc3339()).unwrap_or_else(|| "-".to_string())
+}
+
+fn project_table(projects: &[Project]) -> Table {
+    let mut table = Table::new(vec!["ID", "ORG", "NAME", "STATUS", "EXPIRES AT", "CREDENTIALS"]);
+    for project in projects {
+        table.row(vec![
+            project.id.map(|id| id.to_hex()).unwrap_or_default(),
+            project.org_id.map(|id| id.to_hex()).unwrap_or_else(|| "-".to_string()),
+            project.name.clone(),
+            project.status.to_string(),
+            format_date(project.expires_at),
+            project.ad_credentials.len().to_string(),
+        ]);
+    }
+    table
+}
+
+fn project_detail(project: &Project) -> Table {
+    let mut table = Table::new(vec!["FIELD", "VALUE"]);
+    let mut field = |name: &str, value: String| table.row(vec![name.to_string(), value]);
+    field("id", project.id.map(|id| id.to_hex()).unwrap_or_default());
+    field("org_id", project.org_id.map(|id| id.to_hex()).unwrap_or_else(|| "-".to_string()));
+    field("name", project.name.clone());
+    field("telegram_chat_id", project.telegram_chat_id.clone().unwrap_or_else(|| "-".to_string()));
+    field("status", project.status.to_string());
+    field("is_logging", yes_no(project.is_logging));
+    field("expires_at", format_date(project.expires_at));
+    field("created_at", project.created_at.to_rfc3339());
+    field("updated_at", project.updated_at.to_rfc3339());
+
+    let mut keys: Vec<&String> = project.ad_credentials.keys().collect();
+    keys.sort();
+    for key in keys {
+        let credential = &project.ad_credentials[key];
+        field(
+            &format!("ad_credentials.{}", key),
+            format!(
+                "platform={} account_id={} token={}",
+                credential.platform(),
+                credential.account_id(),
+                credential.token()
+            ),
+        );
+    }
+    table
+}
+
+fn account_table(accounts: &[Account]) -> Table {
+    let mut table = Table::new(vec!["ID", "ORG", "NAME", "EMAIL", "WALLET", "PROJECTS"]);
+    for account in accounts {
+        table.row(vec![
+            account.id.map(|id| id.to_hex()).unwrap_or_default(),
+            account.org_id.map(|id| id.to_hex()).unwrap_or_else(|| "-".to_string()),
+            account.account_name.clone(),
+            account.email.clone(),
+            account.wallet_address.clone(),
+            account.project_ids.len().to_string(),
+        ]);
+    }
+    table
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+    let args = AdminArgs::parse();
+    let cli = CliArgs {
+        config: args.config,
+        mongodb_url: args.mongodb_url,
+        database_name: args.database_name,
+        ..CliArgs::default()
+    };
+    let config = match Config::load(&cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let client = match repository::connect(&config.mongo).await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("error: failed to create MongoDB client: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let db = client.database(&config.mongo.database);
+
+    let outbox = OutboxRepository::new(db.clone());
+    let cipher = SecretCipher::new(&config.encryption);
+    let project_repository = ProjectRepository::new(db.clone()).with_cipher(cipher.clone());
+    let account_repository = AccountRepository::new(db.clone());
+    let organization_repository = OrganizationRepository::new(db.clone());
+    let admin = Admin {
+        projects: ProjectService::new(project_repository.clone(), outbox.clone()),
+        accounts: AccountService::new(
+            account_repository.clone(),
+            project_repository.clone(),
+            organization_repository.clone(),
+            outbox.clone(),
+        ),
+        organizations: OrganizationService::new(organization_repository, account_repository, outbox),
+        webhooks: WebhookService::new(
+            WebhookRepository::new(db.clone()).with_cipher(cipher),
+            WebhookDeliveryRepository::new(db.clone()),
+            project_repository,
+            &config.webhooks,
+        ),
+        migrator: Migrator::new(db),
+        output: args.output,
+    };
+
+    let result = admin.run(args.command).await;
+    client.shutdown().await;
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,18 @@
+use crate::output::Table;
+
+#[test]
+fn test_table_pads_columns_to_the_widest_cell() {
+    let mut table = Table::new(vec!["ID", "NAME", "ACTIVE"]);
+    table.row(vec!["1".to_string(), "Longer name".to_string(), "yes".to_string()]);
+    table.row(vec!["22".to_string(), "Short".to_string(), "no".to_string()]);
+
+    assert_eq!(
+        table.to_string(),
+        "ID  NAME         ACTIVE\n1   Longer name  yes\n22  Short        no\n"
+    );
+}
+
+#[test]
+fn test_empty_table_prints_headers_only() {
+    assert_eq!(Table::new(vec!["VERSION", "NAME"]).to_string(), "VERSION  NAME\n");
+}
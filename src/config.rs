@@ -0,0 +1,1050 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fmt,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tracing::level_filters::LevelFilter;
+
+const DEFAULT_BIND_ADDR: &str = "0.0.0.0:3000";
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_EXPIRY_CHECK_INTERVAL_SECS: u64 = 60;
+const ENCRYPTION_KEY_LEN: usize = 32;
+const MIN_BOOTSTRAP_KEY_LEN: usize = 32;
+const DEFAULT_RATE_LIMIT_PER_MINUTE: u32 = 300;
+const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 24 * 60 * 60;
+const DEFAULT_WEBHOOK_MAX_ATTEMPTS: u32 = 8;
+const DEFAULT_WEBHOOK_RETRY_BASE_SECS: u64 = 10;
+const DEFAULT_TOKEN_CHECK_INTERVAL_SECS: u64 = 6 * 60 * 60;
+const DEFAULT_GRAPH_API_URL: &str = "https://graph.facebook.com/v19.0";
+const DEFAULT_TOKEN_WARNING_DAYS: u64 = 7;
+const DEFAULT_TIKTOK_API_URL: &str = "https://business-api.tiktok.com/open_api/v1.3";
+const DEFAULT_GOOGLE_ADS_API_URL: &str = "https://googleads.googleapis.com/v17";
+const DEFAULT_GOOGLE_OAUTH_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+// Meta deduplicates browser and server events sharing an id for 48 hours
+const DEFAULT_EVENT_DEDUP_WINDOW_HOURS: u64 = 48;
+const DEFAULT_UNVERSIONED_SUNSET_DAYS: u64 = 180;
+// Keeps the sunset a representable date
+const MAX_UNVERSIONED_SUNSET_DAYS: u64 = 100 * 365;
+
+/// Route groups that can be given their own rate limit.
+pub const RATE_LIMIT_GROUPS: [&str; 7] = [
+    "projects", "accounts", "api_keys", "organizations", "webhooks", "links", "events",
+];
+
+#[derive(Parser, Debug, Default)]
+#[command(name = "telegram-ton-api", version, about = "Telegram TON API server")]
+pub struct CliArgs {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Path to a TOML configuration file (defaults to $CONFIG_FILE)
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    #[arg(long)]
+    pub bind_addr: Option<String>,
+    #[arg(long)]
+    pub mongodb_url: Option<String>,
+    #[arg(long)]
+    pub database_name: Option<String>,
+    #[arg(long)]
+    pub log_level: Option<String>,
+    /// `text` or `json`
+    #[arg(long)]
+    pub log_format: Option<String>,
+    /// Comma separated list of allowed CORS origins, `*` allows any origin
+    #[arg(long)]
+    pub cors_allowed_origins: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Apply pending database migrations and exit
+    Migrate {
+        /// Report what each pending migration would change without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Wraps a secret value so it never shows up in `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub mongo: MongoConfig,
+    pub cors: CorsConfig,
+    pub telegram: TelegramConfig,
+    pub facebook: FacebookConfig,
+    pub ad_platforms: AdPlatformsConfig,
+    pub links: LinksConfig,
+    pub api: ApiConfig,
+    pub encryption: EncryptionConfig,
+    pub log: LogConfig,
+    pub workers: WorkersConfig,
+    pub auth: AuthConfig,
+    pub rate_limit: RateLimitConfig,
+    pub idempotency: IdempotencyConfig,
+    pub webhooks: WebhooksConfig,
+    pub migrations: MigrationsConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub bind_addr: SocketAddr,
+    pub shutdown_timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct MongoConfig {
+    pub uri: Secret,
+    pub database: String,
+    pub max_pool_size: Option<u32>,
+    pub min_pool_size: Option<u32>,
+    pub connect_timeout: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorsOrigins {
+    Any,
+    List(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    pub allowed_origins: CorsOrigins,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TelegramConfig {
+    pub bot_token: Option<Secret>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FacebookConfig {
+    /// Graph API base URL including the version, e.g. `https://graph.facebook.com/v19.0`
+    pub graph_api_url: String,
+    /// Project chats are warned this long before an access token expires
+    pub token_warning: Duration,
+    /// Token Facebook echoes when subscribing `/facebook/webhook`, unset refuses subscriptions
+    pub webhook_verify_token: Option<Secret>,
+}
+
+/// API endpoints of the ad platforms other than Facebook, which uses `facebook.graph_api_url`.
+#[derive(Debug, Clone)]
+pub struct AdPlatformsConfig {
+    /// TikTok Business API base URL including the version
+    pub tiktok_api_url: String,
+    /// Google Ads API base URL including the version
+    pub google_ads_api_url: String,
+    /// Where Google Ads refresh tokens are exchanged for access tokens
+    pub google_oauth_token_url: String,
+    /// How long event ids are kept, and repeated server events suppressed
+    pub event_dedup_window: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LinksConfig {
+    /// Public URL `/l/:code` is served under, e.g. `https://go.example.com`; unset omits `short_url`
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiConfig {
+    /// When the unversioned routes were deprecated in favour of `/v1`, unset while they are not
+    pub unversioned_deprecated_at: Option<DateTime<Utc>>,
+    /// How long after their deprecation the unversioned routes stop being served
+    pub unversioned_sunset_after: chrono::Duration,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            unversioned_deprecated_at: None,
+            unversioned_sunset_after: chrono::Duration::days(DEFAULT_UNVERSIONED_SUNSET_DAYS as i64),
+        }
+    }
+}
+
+impl ApiConfig {
+    /// When the unversioned routes stop being served, once they are deprecated.
+    pub fn unversioned_sunset(&self) -> Option<DateTime<Utc>> {
+        self.unversioned_deprecated_at.map(|at| at + self.unversioned_sunset_after)
+    }
+}
+
+#[derive(Clone)]
+pub struct EncryptionKey {
+    pub id: String,
+    pub key: [u8; ENCRYPTION_KEY_LEN],
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("id", &self.id)
+            .field("key", &"***")
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EncryptionConfig {
+    pub keys: Vec<EncryptionKey>,
+    pub active_key_id: Option<String>,
+}
+
+impl EncryptionConfig {
+    pub fn active_key(&self) -> Option<&EncryptionKey> {
+        let id = self.active_key_id.as_ref()?;
+        self.keys.iter().find(|k| &k.id == id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    pub level: LevelFilter,
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    /// Key holding every scope, used to mint the first API keys
+    pub bootstrap_api_key: Option<Secret>,
+}
+
+/// Token bucket refilling at `requests_per_minute` and holding at most `burst` tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitRule {
+    pub requests_per_minute: u32,
+    pub burst: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub default: RateLimitRule,
+    pub groups: BTreeMap<String, RateLimitRule>,
+}
+
+impl RateLimitConfig {
+    pub fn rule(&self, group: &str) -> RateLimitRule {
+        self.groups.get(group).copied().unwrap_or(self.default)
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default: RateLimitRule {
+                requests_per_minute: DEFAULT_RATE_LIMIT_PER_MINUTE,
+                burst: DEFAULT_RATE_LIMIT_PER_MINUTE,
+            },
+            groups: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyConfig {
+    /// How long stored responses are replayed for
+    pub ttl: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhooksConfig {
+    /// Deliveries failing this many times are dead-lettered
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every further attempt
+    pub retry_base: Duration,
+    /// Allows targets on loopback and private networks, off to prevent SSRF
+    pub allow_private_targets: bool,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_WEBHOOK_MAX_ATTEMPTS,
+            retry_base: Duration::from_secs(DEFAULT_WEBHOOK_RETRY_BASE_SECS),
+            allow_private_targets: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationsConfig {
+    pub run_on_startup: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkersConfig {
+    pub expiry_check_interval: Duration,
+    pub token_check_interval: Duration,
+}
+
+/// Every problem found while loading the configuration, reported together.
+#[derive(Debug)]
+pub struct ConfigError {
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "invalid configuration ({} error(s)):", self.errors.len())?;
+        for error in &self.errors {
+            writeln!(f, "  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// Raw, partially specified configuration as read from a single source.
+// Sources are merged with `merge` (later sources win) and then validated.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawConfig {
+    pub server: RawServer,
+    pub mongo: RawMongo,
+    pub cors: RawCors,
+    pub telegram: RawTelegram,
+    pub facebook: RawFacebook,
+    pub ad_platforms: RawAdPlatforms,
+    pub links: RawLinks,
+    pub api: RawApi,
+    pub encryption: RawEncryption,
+    pub log: RawLog,
+    pub workers: RawWorkers,
+    pub auth: RawAuth,
+    pub rate_limit: RawRateLimit,
+    pub idempotency: RawIdempotency,
+    pub webhooks: RawWebhooks,
+    pub migrations: RawMigrations,
+    #[serde(skip)]
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawServer {
+    pub bind_addr: Option<String>,
+    pub shutdown_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawMongo {
+    pub uri: Option<String>,
+    pub database: Option<String>,
+    pub max_pool_size: Option<u32>,
+    pub min_pool_size: Option<u32>,
+    pub connect_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawCors {
+    pub allowed_origins: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawTelegram {
+    pub bot_token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawFacebook {
+    pub graph_api_url: Option<String>,
+    pub token_warning_days: Option<u64>,
+    pub webhook_verify_token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawAdPlatforms {
+    pub tiktok_api_url: Option<String>,
+    pub google_ads_api_url: Option<String>,
+    pub google_oauth_token_url: Option<String>,
+    pub event_dedup_window_hours: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawLinks {
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawApi {
+    pub unversioned_deprecated_at: Option<String>,
+    pub unversioned_sunset_days: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawEncryption {
+    pub keys: Option<Vec<RawEncryptionKey>>,
+    pub active_key_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawEncryptionKey {
+    pub id: String,
+    pub key: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawLog {
+    pub level: Option<String>,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawAuth {
+    pub bootstrap_api_key: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawRateLimit {
+    pub enabled: Option<bool>,
+    pub default: Option<RawRateLimitRule>,
+    pub groups: BTreeMap<String, RawRateLimitRule>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawRateLimitRule {
+    pub requests_per_minute: u32,
+    pub burst: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawIdempotency {
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawWebhooks {
+    pub max_attempts: Option<u32>,
+    pub retry_base_secs: Option<u64>,
+    pub allow_private_targets: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawMigrations {
+    pub run_on_startup: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RawWorkers {
+    pub expiry_check_interval_secs: Option<u64>,
+    pub token_check_interval_secs: Option<u64>,
+}
+
+macro_rules! merge_field {
+    ($target:expr, $source:expr) => {
+        if $source.is_some() {
+            $target = $source;
+        }
+    };
+}
+
+impl RawConfig {
+    pub fn from_toml_str(content: &str) -> Self {
+        match toml::from_str(content) {
+            Ok(config) => config,
+            Err(e) => Self {
+                errors: vec![format!("config file: {}", e.message())],
+                ..Self::default()
+            },
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                let mut config = Self::from_toml_str(&content);
+                for error in config.errors.iter_mut() {
+                    *error = format!("{} ({})", error, path.display());
+                }
+                config
+            }
+            Err(e) => Self {
+                errors: vec![format!("config file {}: {}", path.display(), e)],
+                ..Self::default()
+            },
+        }
+    }
+
+    /// Reads configuration from environment variables through `lookup`,
+    /// which makes it possible to test without touching the process env.
+    pub fn from_env<F>(lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+
+        let mut parse_number = |name: &str| -> Option<u64> {
+            let value = lookup(name)?;
+            match value.trim().parse() {
+                Ok(n) => Some(n),
+                Err(_) => {
+                    errors.push(format!("{} must be a non-negative integer, got {:?}", name, value));
+                    None
+                }
+            }
+        };
+        let max_pool_size = parse_number("MONGODB_MAX_POOL_SIZE");
+        let min_pool_size = parse_number("MONGODB_MIN_POOL_SIZE");
+        let connect_timeout_secs = parse_number("MONGODB_CONNECT_TIMEOUT_SECS");
+        let shutdown_timeout_secs = parse_number("SHUTDOWN_TIMEOUT_SECS");
+        let expiry_check_interval_secs = parse_number("EXPIRY_CHECK_INTERVAL_SECS");
+        let token_check_interval_secs = parse_number("TOKEN_CHECK_INTERVAL_SECS");
+        let token_warning_days = parse_number("FACEBOOK_TOKEN_WARNING_DAYS");
+        let event_dedup_window_hours = parse_number("EVENT_DEDUP_WINDOW_HOURS");
+        let unversioned_sunset_days = parse_number("API_UNVERSIONED_SUNSET_DAYS");
+        let idempotency_ttl_secs = parse_number("IDEMPOTENCY_TTL_SECS");
+        let webhook_max_attempts = parse_number("WEBHOOK_MAX_ATTEMPTS");
+        let webhook_retry_base_secs = parse_number("WEBHOOK_RETRY_BASE_SECS");
+
+        let mut narrow = |name: &str, value: Option<u64>| -> Option<u32> {
+            let value = value?;
+            match u32::try_from(value) {
+                Ok(n) => Some(n),
+                Err(_) => {
+                    errors.push(format!("{} must be at most {}, got {}", name, u32::MAX, value));
+                    None
+                }
+            }
+        };
+        let webhook_max_attempts = narrow("WEBHOOK_MAX_ATTEMPTS", webhook_max_attempts);
+        let max_pool_size = narrow("MONGODB_MAX_POOL_SIZE", max_pool_size);
+        let min_pool_size = narrow("MONGODB_MIN_POOL_SIZE", min_pool_size);
+
+        config.server.bind_addr = lookup("BIND_ADDR");
+        config.server.shutdown_timeout_secs = shutdown_timeout_secs;
+        config.workers.expiry_check_interval_secs = expiry_check_interval_secs;
+        config.workers.token_check_interval_secs = token_check_interval_secs;
+        config.idempotency.ttl_secs = idempotency_ttl_secs;
+        config.webhooks.max_attempts = webhook_max_attempts;
+        config.webhooks.retry_base_secs = webhook_retry_base_secs;
+        config.mongo.uri = lookup("MONGODB_URL");
+        config.mongo.database = lookup("DATABASE_NAME");
+        config.mongo.max_pool_size = max_pool_size;
+        config.mongo.min_pool_size = min_pool_size;
+        config.mongo.connect_timeout_secs = connect_timeout_secs;
+        config.cors.allowed_origins = lookup("CORS_ALLOWED_ORIGINS").map(|v| split_list(&v));
+        config.telegram.bot_token = lookup("TELEGRAM_BOT_TOKEN");
+        config.facebook.graph_api_url = lookup("FACEBOOK_GRAPH_API_URL");
+        config.facebook.token_warning_days = token_warning_days;
+        config.facebook.webhook_verify_token = lookup("FACEBOOK_WEBHOOK_VERIFY_TOKEN");
+        config.ad_platforms.tiktok_api_url = lookup("TIKTOK_API_URL");
+        config.ad_platforms.google_ads_api_url = lookup("GOOGLE_ADS_API_URL");
+        config.ad_platforms.google_oauth_token_url = lookup("GOOGLE_OAUTH_TOKEN_URL");
+        config.ad_platforms.event_dedup_window_hours = event_dedup_window_hours;
+        config.links.base_url = lookup("LINK_BASE_URL");
+        config.api.unversioned_deprecated_at = lookup("API_UNVERSIONED_DEPRECATED_AT");
+        config.api.unversioned_sunset_days = unversioned_sunset_days;
+        config.auth.bootstrap_api_key = lookup("BOOTSTRAP_API_KEY");
+        config.log.level = lookup("LOG_LEVEL");
+        config.log.format = lookup("LOG_FORMAT");
+        config.encryption.active_key_id = lookup("ENCRYPTION_ACTIVE_KEY_ID");
+
+        // ENCRYPTION_KEYS=key_id:base64key,other_id:base64key
+        if let Some(value) = lookup("ENCRYPTION_KEYS") {
+            let mut keys = Vec::new();
+            for entry in split_list(&value) {
+                match entry.split_once(':') {
+                    Some((id, key)) => keys.push(RawEncryptionKey {
+                        id: id.trim().to_string(),
+                        key: key.trim().to_string(),
+                    }),
+                    None => errors.push("ENCRYPTION_KEYS entries must look like `id:base64key`".to_string()),
+                }
+            }
+            config.encryption.keys = Some(keys);
+        }
+
+        let mut parse_bool = |name: &str| -> Option<bool> {
+            let value = lookup(name)?;
+            match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" => Some(true),
+                "false" | "0" => Some(false),
+                _ => {
+                    errors.push(format!("{} must be true or false, got {:?}", name, value));
+                    None
+                }
+            }
+        };
+        config.rate_limit.enabled = parse_bool("RATE_LIMIT_ENABLED");
+        config.webhooks.allow_private_targets = parse_bool("WEBHOOK_ALLOW_PRIVATE_TARGETS");
+        config.migrations.run_on_startup = parse_bool("MIGRATE_ON_STARTUP");
+
+        // RATE_LIMIT_DEFAULT=requests_per_minute[/burst], RATE_LIMIT_<GROUP> likewise
+        let mut parse_rule = |name: &str| -> Option<RawRateLimitRule> {
+            let value = lookup(name)?;
+            let (rate, burst) = match value.split_once('/') {
+                Some((rate, burst)) => (rate, Some(burst)),
+                None => (value.as_str(), None),
+            };
+            let rate = rate.trim().parse().ok();
+            let burst = burst.map(|b| b.trim().parse().ok());
+            match (rate, burst) {
+                (Some(requests_per_minute), None) => Some(RawRateLimitRule { requests_per_minute, burst: None }),
+                (Some(requests_per_minute), Some(Some(burst))) => {
+                    Some(RawRateLimitRule { requests_per_minute, burst: Some(burst) })
+                }
+                _ => {
+                    errors.push(format!("{} must look like `requests_per_minute[/burst]`, got {:?}", name, value));
+                    None
+                }
+            }
+        };
+        config.rate_limit.default = parse_rule("RATE_LIMIT_DEFAULT");
+        for group in RATE_LIMIT_GROUPS {
+            if let Some(rule) = parse_rule(&format!("RATE_LIMIT_{}", group.to_ascii_uppercase())) {
+                config.rate_limit.groups.insert(group.to_string(), rule);
+            }
+        }
+
+        config.errors = errors;
+        config
+    }
+
+    pub fn from_cli(args: &CliArgs) -> Self {
+        let mut config = Self::default();
+        config.server.bind_addr = args.bind_addr.clone();
+        config.mongo.uri = args.mongodb_url.clone();
+        config.mongo.database = args.database_name.clone();
+        config.log.level = args.log_level.clone();
+        config.log.format = args.log_format.clone();
+        config.cors.allowed_origins = args.cors_allowed_origins.as_deref().map(split_list);
+        config
+    }
+
+    pub fn merge(mut self, other: RawConfig) -> Self {
+        merge_field!(self.server.bind_addr, other.server.bind_addr);
+        merge_field!(self.server.shutdown_timeout_secs, other.server.shutdown_timeout_secs);
+        merge_field!(
+            self.workers.expiry_check_interval_secs,
+            other.workers.expiry_check_interval_secs
+        );
+        merge_field!(
+            self.workers.token_check_interval_secs,
+            other.workers.token_check_interval_secs
+        );
+        merge_field!(self.mongo.uri, other.mongo.uri);
+        merge_field!(self.mongo.database, other.mongo.database);
+        merge_field!(self.mongo.max_pool_size, other.mongo.max_pool_size);
+        merge_field!(self.mongo.min_pool_size, other.mongo.min_pool_size);
+        merge_field!(self.mongo.connect_timeout_secs, other.mongo.connect_timeout_secs);
+        merge_field!(self.cors.allowed_origins, other.cors.allowed_origins);
+        merge_field!(self.telegram.bot_token, other.telegram.bot_token);
+        merge_field!(self.facebook.graph_api_url, other.facebook.graph_api_url);
+        merge_field!(self.facebook.token_warning_days, other.facebook.token_warning_days);
+        merge_field!(self.facebook.webhook_verify_token, other.facebook.webhook_verify_token);
+        merge_field!(self.ad_platforms.tiktok_api_url, other.ad_platforms.tiktok_api_url);
+        merge_field!(self.ad_platforms.google_ads_api_url, other.ad_platforms.google_ads_api_url);
+        merge_field!(self.ad_platforms.google_oauth_token_url, other.ad_platforms.google_oauth_token_url);
+        merge_field!(self.ad_platforms.event_dedup_window_hours, other.ad_platforms.event_dedup_window_hours);
+        merge_field!(self.links.base_url, other.links.base_url);
+        merge_field!(self.api.unversioned_deprecated_at, other.api.unversioned_deprecated_at);
+        merge_field!(self.api.unversioned_sunset_days, other.api.unversioned_sunset_days);
+        merge_field!(self.auth.bootstrap_api_key, other.auth.bootstrap_api_key);
+        merge_field!(self.encryption.keys, other.encryption.keys);
+        merge_field!(self.encryption.active_key_id, other.encryption.active_key_id);
+        merge_field!(self.log.level, other.log.level);
+        merge_field!(self.log.format, other.log.format);
+        merge_field!(self.idempotency.ttl_secs, other.idempotency.ttl_secs);
+        merge_field!(self.webhooks.max_attempts, other.webhooks.max_attempts);
+        merge_field!(self.webhooks.retry_base_secs, other.webhooks.retry_base_secs);
+        merge_field!(self.webhooks.allow_private_targets, other.webhooks.allow_private_targets);
+        merge_field!(self.migrations.run_on_startup, other.migrations.run_on_startup);
+        merge_field!(self.rate_limit.enabled, other.rate_limit.enabled);
+        merge_field!(self.rate_limit.default, other.rate_limit.default);
+        self.rate_limit.groups.extend(other.rate_limit.groups);
+        self.errors.extend(other.errors);
+        self
+    }
+
+    pub fn validate(self) -> Result<Config, ConfigError> {
+        let mut errors = self.errors;
+
+        let bind_addr = self.server.bind_addr.as_deref().unwrap_or(DEFAULT_BIND_ADDR);
+        let bind_addr = match bind_addr.parse::<SocketAddr>() {
+            Ok(addr) => Some(addr),
+            Err(_) => {
+                errors.push(format!("server.bind_addr {:?} is not a valid socket address", bind_addr));
+                None
+            }
+        };
+
+        if self.workers.expiry_check_interval_secs == Some(0) {
+            errors.push("workers.expiry_check_interval_secs must be greater than zero".to_string());
+        }
+        if self.workers.token_check_interval_secs == Some(0) {
+            errors.push("workers.token_check_interval_secs must be greater than zero".to_string());
+        }
+        let mut api_url = |field: &str, value: Option<String>, default: &str| {
+            let url = non_empty(value).unwrap_or_else(|| default.to_string()).trim_end_matches('/').to_string();
+            if let Err(e) = crate::http_client::HttpClient::validate_url(&url) {
+                errors.push(format!("{} {:?}: {}", field, url, e));
+            }
+            url
+        };
+        let graph_api_url = api_url("facebook.graph_api_url", self.facebook.graph_api_url, DEFAULT_GRAPH_API_URL);
+        let ad_platforms = AdPlatformsConfig {
+            tiktok_api_url: api_url(
+                "ad_platforms.tiktok_api_url",
+                self.ad_platforms.tiktok_api_url,
+                DEFAULT_TIKTOK_API_URL,
+            ),
+            google_ads_api_url: api_url(
+                "ad_platforms.google_ads_api_url",
+                self.ad_platforms.google_ads_api_url,
+                DEFAULT_GOOGLE_ADS_API_URL,
+            ),
+            google_oauth_token_url: api_url(
+                "ad_platforms.google_oauth_token_url",
+                self.ad_platforms.google_oauth_token_url,
+                DEFAULT_GOOGLE_OAUTH_TOKEN_URL,
+            ),
+            event_dedup_window: Duration::from_secs(
+                self.ad_platforms
+                    .event_dedup_window_hours
+                    .unwrap_or(DEFAULT_EVENT_DEDUP_WINDOW_HOURS)
+                    .saturating_mul(60 * 60),
+            ),
+        };
+        if self.ad_platforms.event_dedup_window_hours == Some(0) {
+            errors.push("ad_platforms.event_dedup_window_hours must be greater than zero".to_string());
+        }
+        let link_base_url = non_empty(self.links.base_url).map(|url| url.trim_end_matches('/').to_string());
+        if let Some(url) = &link_base_url {
+            if let Err(e) = crate::http_client::HttpClient::validate_url(url) {
+                errors.push(format!("links.base_url {:?}: {}", url, e));
+            }
+        }
+        let unversioned_deprecated_at = non_empty(self.api.unversioned_deprecated_at).and_then(|value| {
+            match DateTime::parse_from_rfc3339(&value) {
+                Ok(date) => Some(date.with_timezone(&Utc)),
+                Err(e) => {
+                    errors.push(format!("api.unversioned_deprecated_at {:?}: {}", value, e));
+                    None
+                }
+            }
+        });
+        let unversioned_sunset_days = self.api.unversioned_sunset_days.unwrap_or(DEFAULT_UNVERSIONED_SUNSET_DAYS);
+        if !(1..=MAX_UNVERSIONED_SUNSET_DAYS).contains(&unversioned_sunset_days) {
+            errors.push(format!(
+                "api.unversioned_sunset_days must be between 1 and {}",
+                MAX_UNVERSIONED_SUNSET_DAYS
+            ));
+        }
+        if self.idempotency.ttl_secs == Some(0) {
+            errors.push("idempotency.ttl_secs must be greater than zero".to_string());
+        }
+        if self.webhooks.max_attempts == Some(0) {
+            errors.push("webhooks.max_attempts must be greater than zero".to_string());
+        }
+
+        let bootstrap_api_key = non_empty(self.auth.bootstrap_api_key);
+        if bootstrap_api_key.as_ref().is_some_and(|key| key.len() < MIN_BOOTSTRAP_KEY_LEN) {
+            errors.push(format!(
+                "auth.bootstrap_api_key must be at least {} characters",
+                MIN_BOOTSTRAP_KEY_LEN
+            ));
+        }
+
+        let uri = non_empty(self.mongo.uri);
+        match uri.as_deref() {
+            None => errors.push("mongo.uri (MONGODB_URL) must be set".to_string()),
+            Some(uri) if !uri.starts_with("mongodb://") && !uri.starts_with("mongodb+srv://") => {
+                errors.push("mongo.uri must start with mongodb:// or mongodb+srv://".to_string())
+            }
+            _ => {}
+        }
+        let database = non_empty(self.mongo.database);
+        if database.is_none() {
+            errors.push("mongo.database (DATABASE_NAME) must be set".to_string());
+        }
+        if let (Some(min), Some(max)) = (self.mongo.min_pool_size, self.mongo.max_pool_size) {
+            if min > max {
+                errors.push(format!("mongo.min_pool_size ({}) exceeds mongo.max_pool_size ({})", min, max));
+            }
+        }
+        if self.mongo.max_pool_size == Some(0) {
+            errors.push("mongo.max_pool_size must be greater than zero".to_string());
+        }
+
+        let allowed_origins = match self.cors.allowed_origins {
+            None => CorsOrigins::Any,
+            Some(origins) if origins.iter().any(|o| o == "*") => CorsOrigins::Any,
+            Some(origins) => {
+                for origin in &origins {
+                    if !origin.starts_with("http://") && !origin.starts_with("https://") {
+                        errors.push(format!("cors origin {:?} must start with http:// or https://", origin));
+                    }
+                }
+                CorsOrigins::List(origins)
+            }
+        };
+
+        let level_str = self.log.level.as_deref().unwrap_or("info");
+        let level = match level_str.parse::<LevelFilter>() {
+            Ok(level) => level,
+            Err(_) => {
+                errors.push(format!("log.level {:?} is not a valid level", level_str));
+                LevelFilter::INFO
+            }
+        };
+        let format = match self.log.format.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            None | Some("text") => LogFormat::Text,
+            Some("json") => LogFormat::Json,
+            Some(other) => {
+                errors.push(format!("log.format {:?} must be `text` or `json`", other));
+                LogFormat::Text
+            }
+        };
+
+        let mut keys = Vec::new();
+        for raw in self.encryption.keys.unwrap_or_default() {
+            if raw.id.is_empty() {
+                errors.push("encryption key id cannot be empty".to_string());
+                continue;
+            }
+            if keys.iter().any(|k: &EncryptionKey| k.id == raw.id) {
+                errors.push(format!("encryption key id {:?} is defined twice", raw.id));
+                continue;
+            }
+            match BASE64.decode(raw.key.as_bytes()) {
+                Ok(bytes) if bytes.len() == ENCRYPTION_KEY_LEN => {
+                    let mut key = [0u8; ENCRYPTION_KEY_LEN];
+                    key.copy_from_slice(&bytes);
+                    keys.push(EncryptionKey { id: raw.id, key });
+                }
+                Ok(bytes) => errors.push(format!(
+                    "encryption key {:?} must be {} bytes, got {}",
+                    raw.id,
+                    ENCRYPTION_KEY_LEN,
+                    bytes.len()
+                )),
+                Err(_) => errors.push(format!("encryption key {:?} is not valid base64", raw.id)),
+            }
+        }
+        let active_key_id = non_empty(self.encryption.active_key_id);
+        match &active_key_id {
+            Some(id) if !keys.iter().any(|k| &k.id == id) => {
+                errors.push(format!("encryption.active_key_id {:?} does not match any key", id))
+            }
+            None if !keys.is_empty() => {
+                errors.push("encryption.active_key_id must be set when keys are configured".to_string())
+            }
+            _ => {}
+        }
+
+        let mut rate_limit = RateLimitConfig {
+            enabled: self.rate_limit.enabled.unwrap_or(true),
+            ..RateLimitConfig::default()
+        };
+        if let Some(raw) = self.rate_limit.default {
+            match validate_rate_limit_rule("rate_limit.default", raw) {
+                Ok(rule) => rate_limit.default = rule,
+                Err(e) => errors.push(e),
+            }
+        }
+        for (group, raw) in self.rate_limit.groups {
+            if !RATE_LIMIT_GROUPS.contains(&group.as_str()) {
+                errors.push(format!(
+                    "rate_limit.groups.{} is not a route group, expected one of {}",
+                    group,
+                    RATE_LIMIT_GROUPS.join(", ")
+                ));
+                continue;
+            }
+            match validate_rate_limit_rule(&format!("rate_limit.groups.{}", group), raw) {
+                Ok(rule) => {
+                    rate_limit.groups.insert(group, rule);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(ConfigError { errors });
+        }
+
+        Ok(Config {
+            server: ServerConfig {
+                bind_addr: bind_addr.expect("validated above"),
+                shutdown_timeout: Duration::from_secs(
+                    self.server.shutdown_timeout_secs.unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+                ),
+            },
+            mongo: MongoConfig {
+                uri: Secret::new(uri.expect("validated above")),
+                database: database.expect("validated above"),
+                max_pool_size: self.mongo.max_pool_size,
+                min_pool_size: self.mongo.min_pool_size,
+                connect_timeout: Duration::from_secs(
+                    self.mongo.connect_timeout_secs.unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+                ),
+            },
+            cors: CorsConfig { allowed_origins },
+            telegram: TelegramConfig {
+                bot_token: non_empty(self.telegram.bot_token).map(Secret::new),
+            },
+            facebook: FacebookConfig {
+                graph_api_url,
+                token_warning: Duration::from_secs(
+                    self.facebook
+                        .token_warning_days
+                        .unwrap_or(DEFAULT_TOKEN_WARNING_DAYS)
+                        .saturating_mul(24 * 60 * 60),
+                ),
+                webhook_verify_token: non_empty(self.facebook.webhook_verify_token).map(Secret::new),
+            },
+            ad_platforms,
+            links: LinksConfig { base_url: link_base_url },
+            api: ApiConfig {
+                unversioned_deprecated_at,
+                unversioned_sunset_after: chrono::Duration::days(unversioned_sunset_days as i64),
+            },
+            encryption: EncryptionConfig { keys, active_key_id },
+            log: LogConfig { level, format },
+            auth: AuthConfig {
+                bootstrap_api_key: bootstrap_api_key.map(Secret::new),
+            },
+            workers: WorkersConfig {
+                expiry_check_interval: Duration::from_secs(
+                    self.workers
+                        .expiry_check_interval_secs
+                        .unwrap_or(DEFAULT_EXPIRY_CHECK_INTERVAL_SECS),
+                ),
+                token_check_interval: Duration::from_secs(
+                    self.workers
+                        .token_check_interval_secs
+                        .unwrap_or(DEFAULT_TOKEN_CHECK_INTERVAL_SECS),
+                ),
+            },
+            rate_limit,
+            idempotency: IdempotencyConfig {
+                ttl: Duration::from_secs(self.idempotency.ttl_secs.unwrap_or(DEFAULT_IDEMPOTENCY_TTL_SECS)),
+            },
+            webhooks: WebhooksConfig {
+                max_attempts: self.webhooks.max_attempts.unwrap_or(DEFAULT_WEBHOOK_MAX_ATTEMPTS),
+                retry_base: Duration::from_secs(
+                    self.webhooks.retry_base_secs.unwrap_or(DEFAULT_WEBHOOK_RETRY_BASE_SECS),
+                ),
+                allow_private_targets: self.webhooks.allow_private_targets.unwrap_or(false),
+            },
+            migrations: MigrationsConfig {
+                run_on_startup: self.migrations.run_on_startup.unwrap_or(true),
+            },
+        })
+    }
+}
+
+impl Config {
+    /// Secret values that must never appear in log output.
+    pub fn secrets(&self) -> Vec<String> {
+        let mut secrets = Vec::new();
+        if let Some(token) = &self.telegram.bot_token {
+            secrets.push(token.expose().to_string());
+        }
+        if let Some(key) = &self.auth.bootstrap_api_key {
+            secrets.push(key.expose().to_string());
+        }
+        if let Some(token) = &self.facebook.webhook_verify_token {
+            secrets.push(token.expose().to_string());
+        }
+        for key in &self.encryption.keys {
+            secrets.push(BASE64.encode(key.key));
+        }
+        secrets
+    }
+
+    /// Loads configuration with precedence CLI flags > environment > TOML file > defaults.
+    pub fn load(args: &CliArgs) -> Result<Config, ConfigError> {
+        let config_file = args
+            .config
+            .clone()
+            .or_else(|| env::var("CONFIG_FILE").ok().map(PathBuf::from));
+
+        let file = match config_file {
+            Some(path) => RawConfig::from_file(&path),
+            None => RawConfig::default(),
+        };
+
+        file.merge(RawConfig::from_env(|name| env::var(name).ok()))
+            .merge(RawConfig::from_cli(args))
+            .validate()
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn validate_rate_limit_rule(name: &str, raw: RawRateLimitRule) -> Result<RateLimitRule, String> {
+    // A burst defaults to a full minute of requests
+    let burst = raw.burst.unwrap_or(raw.requests_per_minute);
+    if raw.requests_per_minute == 0 || burst == 0 {
+        return Err(format!("{} requests_per_minute and burst must be greater than zero", name));
+    }
+    Ok(RateLimitRule {
+        requests_per_minute: raw.requests_per_minute,
+        burst,
+    })
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use crate::config::{CliArgs, CorsOrigins, RawConfig};
+
+const TEST_KEY: &str = "MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTIzNDU2Nzg5MDE=";
+
+fn env_from(vars: &[(&str, &str)]) -> RawConfig {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    RawConfig::from_env(|name| vars.get(name).cloned())
+}
+
+#[test]
+fn test_minimal_env_uses_defaults() {
+    let config = env_from(&[
+        ("MONGODB_URL", "mongodb://localhost:27017"),
+        ("DATABASE_NAME", "test_database"),
+    ])
+    .validate()
+    .expect("minimal config should be valid");
+
+    assert_eq!(config.server.bind_addr.to_string(), "0.0.0.0:3000");
+    assert_eq!(config.mongo.database, "test_database");
+    assert_eq!(config.cors.allowed_origins, CorsOrigins::Any);
+    assert_eq!(config.log.level, tracing::level_filters::LevelFilter::INFO);
+    assert_eq!(config.log.format, crate::config::LogFormat::Text);
+    assert!(config.telegram.bot_token.is_none());
+    assert_eq!(config.facebook.graph_api_url, "https://graph.facebook.com/v19.0");
+    assert_eq!(config.facebook.token_warning, std::time::Duration::from_secs(7 * 24 * 60 * 60));
+    assert_eq!(config.ad_platforms.tiktok_api_url, "https://business-api.tiktok.com/open_api/v1.3");
+    assert_eq!(config.ad_platforms.google_ads_api_url, "https://googleads.googleapis.com/v17");
+    assert_eq!(config.ad_platforms.google_oauth_token_url, "https://oauth2.googleapis.com/token");
+    assert_eq!(config.ad_platforms.event_dedup_window, std::time::Duration::from_secs(48 * 60 * 60));
+}
+
+#[test]
+fn test_event_dedup_window_from_env() {
+    let env = |hours: &str| {
+        env_from(&[
+            ("MONGODB_URL", "mongodb://localhost:27017"),
+            ("DATABASE_NAME", "test_database"),
+            ("EVENT_DEDUP_WINDOW_HOURS", hours),
+        ])
+        .validate()
+    };
+    assert_eq!(env("72").unwrap().ad_platforms.event_dedup_window, std::time::Duration::from_secs(72 * 60 * 60));
+    assert!(env("0").is_err());
+    assert!(env("two days").is_err());
+}
+
+#[test]
+fn test_link_base_url_from_env() {
+    let env = |url: &str| {
+        env_from(&[
+            ("MONGODB_URL", "mongodb://localhost:27017"),
+            ("DATABASE_NAME", "test_database"),
+            ("LINK_BASE_URL", url),
+        ])
+        .validate()
+    };
+    assert_eq!(env("https://go.example.com/").unwrap().links.base_url.as_deref(), Some("https://go.example.com"));
+    assert_eq!(env("").unwrap().links.base_url, None);
+    assert!(env("go.example.com").is_err());
+}
+
+#[test]
+fn test_unversioned_deprecation_from_env() {
+    let env = |deprecated_at: &str, sunset_days: &str| {
+        let mut vars = vec![
+            ("MONGODB_URL", "mongodb://localhost:27017"),
+            ("DATABASE_NAME", "test_database"),
+            ("API_UNVERSIONED_DEPRECATED_AT", deprecated_at),
+        ];
+        if !sunset_days.is_empty() {
+            vars.push(("API_UNVERSIONED_SUNSET_DAYS", sunset_days));
+        }
+        env_from(&vars).validate()
+    };
+    let api = env("2027-01-01T00:00:00+02:00", "").unwrap().api;
+    assert_eq!(api.unversioned_deprecated_at.unwrap().to_rfc3339(), "2026-12-31T22:00:00+00:00");
+    assert_eq!(api.unversioned_sunset().unwrap().to_rfc3339(), "2027-06-29T22:00:00+00:00");
+    let api = env("2027-01-01T00:00:00Z", "30").unwrap().api;
+    assert_eq!(api.unversioned_sunset().unwrap().to_rfc3339(), "2027-01-31T00:00:00+00:00");
+
+    let api = env("", "").unwrap().api;
+    assert_eq!((api.unversioned_deprecated_at, api.unversioned_sunset()), (None, None));
+    assert!(env("January 2027", "").is_err());
+    assert!(env("2027-01-01T00:00:00Z", "0").is_err());
+}
+
+#[test]
+fn test_ad_platform_urls_from_env() {
+    let config = env_from(&[
+        ("MONGODB_URL", "mongodb://localhost:27017"),
+        ("DATABASE_NAME", "test_database"),
+        ("TIKTOK_API_URL", "http://localhost:9001/tiktok/"),
+        ("GOOGLE_ADS_API_URL", "http://localhost:9002/v17"),
+        ("GOOGLE_OAUTH_TOKEN_URL", "http://localhost:9002/token"),
+    ])
+    .validate()
+    .unwrap();
+    assert_eq!(config.ad_platforms.tiktok_api_url, "http://localhost:9001/tiktok");
+    assert_eq!(config.ad_platforms.google_oauth_token_url, "http://localhost:9002/token");
+
+    let err = env_from(&[
+        ("MONGODB_URL", "mongodb://localhost:27017"),
+        ("DATABASE_NAME", "test_database"),
+        ("GOOGLE_ADS_API_URL", "googleads.googleapis.com"),
+    ])
+    .validate()
+    .unwrap_err();
+    assert_eq!(err.errors.len(), 1);
+}
+
+#[test]
+fn test_facebook_settings_from_env() {
+    let config = env_from(&[
+        ("MONGODB_URL", "mongodb://localhost:27017"),
+        ("DATABASE_NAME", "test_database"),
+        ("FACEBOOK_GRAPH_API_URL", "http://localhost:9000/v20.0/"),
+        ("FACEBOOK_TOKEN_WARNING_DAYS", "3"),
+        ("TOKEN_CHECK_INTERVAL_SECS", "600"),
+    ])
+    .validate()
+    .unwrap();
+
+    assert_eq!(config.facebook.graph_api_url, "http://localhost:9000/v20.0");
+    assert_eq!(config.facebook.token_warning, std::time::Duration::from_secs(3 * 24 * 60 * 60));
+    assert_eq!(config.workers.token_check_interval, std::time::Duration::from_secs(600));
+
+    let err = env_from(&[
+        ("MONGODB_URL", "mongodb://localhost:27017"),
+        ("DATABASE_NAME", "test_database"),
+        ("FACEBOOK_GRAPH_API_URL", "graph.facebook.com"),
+        ("TOKEN_CHECK_INTERVAL_SECS", "0"),
+    ])
+    .validate()
+    .unwrap_err();
+    assert_eq!(err.errors.len(), 2);
+}
+
+#[test]
+fn test_all_errors_are_reported_together() {
+    let err = env_from(&[
+        ("BIND_ADDR", "not an address"),
+        ("MONGODB_MAX_POOL_SIZE", "lots"),
+        ("LOG_LEVEL", "loud"),
+        ("LOG_FORMAT", "xml"),
+        ("CORS_ALLOWED_ORIGINS", "example.com"),
+        ("ENCRYPTION_KEYS", "k1:c2hvcnQ="),
+        ("ENCRYPTION_ACTIVE_KEY_ID", "k2"),
+    ])
+    .validate()
+    .expect_err("config should be invalid");
+
+    let message = err.to_string();
+    assert!(message.contains("MONGODB_MAX_POOL_SIZE"));
+    assert!(message.contains("server.bind_addr"));
+    assert!(message.contains("MONGODB_URL"));
+    assert!(message.contains("DATABASE_NAME"));
+    assert!(message.contains("log.level"));
+    assert!(message.contains("log.format"));
+    assert!(message.contains("example.com"));
+    assert!(message.contains("must be 32 bytes"));
+    assert!(message.contains("active_key_id \"k2\""));
+    assert_eq!(err.errors.len(), 9);
+}
+
+#[test]
+fn test_numbers_too_large_for_u32_are_rejected() {
+    let err = env_from(&[
+        ("MONGODB_URL", "mongodb://localhost:27017"),
+        ("DATABASE_NAME", "test_database"),
+        ("MONGODB_MAX_POOL_SIZE", "4294967297"),
+        ("WEBHOOK_MAX_ATTEMPTS", "4294967296"),
+    ])
+    .validate()
+    .unwrap_err();
+
+    assert_eq!(err.errors.len(), 2);
+    assert!(err.errors.iter().any(|e| e.contains("MONGODB_MAX_POOL_SIZE")));
+}
+
+#[test]
+fn test_precedence_cli_over_env_over_file() {
+    let file = RawConfig::from_toml_str(
+        r#"
+        [server]
+        bind_addr = "127.0.0.1:4000"
+
+        [mongo]
+        uri = "mongodb://file:27017"
+        database = "from_file"
+        max_pool_size = 20
+
+        [cors]
+        allowed_origins = ["https://app.example.com"]
+
+        [[encryption.keys]]
+        id = "k1"
+        key = "MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTIzNDU2Nzg5MDE="
+
+        [encryption]
+        active_key_id = "k1"
+        "#,
+    );
+    let env = env_from(&[("DATABASE_NAME", "from_env"), ("BIND_ADDR", "127.0.0.1:5000")]);
+    let cli = RawConfig::from_cli(&CliArgs {
+        bind_addr: Some("127.0.0.1:6000".to_string()),
+        ..CliArgs::default()
+    });
+
+    let config = file.merge(env).merge(cli).validate().expect("config should be valid");
+
+    assert_eq!(config.server.bind_addr.to_string(), "127.0.0.1:6000");
+    assert_eq!(config.mongo.uri.expose(), "mongodb://file:27017");
+    assert_eq!(config.mongo.database, "from_env");
+    assert_eq!(config.mongo.max_pool_size, Some(20));
+    assert_eq!(
+        config.cors.allowed_origins,
+        CorsOrigins::List(vec!["https://app.example.com".to_string()])
+    );
+    assert_eq!(config.encryption.active_key().map(|k| k.id.as_str()), Some("k1"));
+}
+
+#[test]
+fn test_encryption_keys_from_env() {
+    let keys = format!("k1:{},k2:{}", TEST_KEY, TEST_KEY);
+    let config = env_from(&[
+        ("MONGODB_URL", "mongodb://localhost:27017"),
+        ("DATABASE_NAME", "test_database"),
+        ("ENCRYPTION_KEYS", keys.as_str()),
+        ("ENCRYPTION_ACTIVE_KEY_ID", "k2"),
+        ("TELEGRAM_BOT_TOKEN", "123:abc"),
+    ])
+    .validate()
+    .expect("config should be valid");
+
+    assert_eq!(config.encryption.keys.len(), 2);
+    assert_eq!(config.encryption.active_key().unwrap().id, "k2");
+    let debug = format!("{:?}", config);
+    assert!(!debug.contains("123:abc"));
+    assert!(config.secrets().contains(&"123:abc".to_string()));
+    assert!(config.secrets().contains(&TEST_KEY.to_string()));
+    assert!(!debug.contains("mongodb://localhost"));
+}
+
+#[test]
+fn test_unknown_file_keys_are_rejected() {
+    let err = RawConfig::from_toml_str("[server]\nport = 3000\n")
+        .validate()
+        .expect_err("unknown key should be rejected");
+
+    assert!(err.errors.iter().any(|e| e.contains("unknown field")));
+}
+
+#[test]
+fn test_rate_limit_groups() {
+    let file = RawConfig::from_toml_str(
+        r#"
+        [mongo]
+        uri = "mongodb://localhost:27017"
+        database = "test_database"
+
+        [rate_limit.default]
+        requests_per_minute = 100
+
+        [rate_limit.groups.projects]
+        requests_per_minute = 60
+        burst = 10
+        "#,
+    );
+    let env = env_from(&[("RATE_LIMIT_ACCOUNTS", "30/5")]);
+    let config = file.merge(env).validate().expect("config should be valid");
+
+    assert!(config.rate_limit.enabled);
+    let projects = config.rate_limit.rule("projects");
+    assert_eq!((projects.requests_per_minute, projects.burst), (60, 10));
+    let accounts = config.rate_limit.rule("accounts");
+    assert_eq!((accounts.requests_per_minute, accounts.burst), (30, 5));
+    let api_keys = config.rate_limit.rule("api_keys");
+    assert_eq!((api_keys.requests_per_minute, api_keys.burst), (100, 100));
+
+    let err = RawConfig::from_toml_str(
+        r#"
+        [mongo]
+        uri = "mongodb://localhost:27017"
+        database = "test_database"
+
+        [rate_limit.groups.unknown]
+        requests_per_minute = 60
+        "#,
+    )
+    .merge(env_from(&[("RATE_LIMIT_PROJECTS", "0"), ("RATE_LIMIT_ENABLED", "maybe")]))
+    .validate()
+    .expect_err("config should be invalid");
+    assert_eq!(err.errors.len(), 3);
+}
@@ -0,0 +1,98 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rand::{rngs::OsRng, RngCore};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use std::sync::Arc;
+
+use crate::{
+    config::{EncryptionConfig, EncryptionKey},
+    error::ApiError,
+};
+
+const PREFIX: &str = "enc:v1:";
+
+/// Encrypts stored secrets with AES-256-GCM under the active key. Values are
+/// written as `enc:v1:<key_id>:<base64 nonce+ciphertext>` so they can still be
+/// read after the active key changes. Without an active key values pass
+/// through unchanged, and values without the prefix are read as plaintext,
+/// so secrets from clients must not look encrypted (see [`Self::is_encrypted`]).
+#[derive(Clone, Default)]
+pub struct SecretCipher {
+    keys: Arc<Vec<EncryptionKey>>,
+    active_key_id: Option<String>,
+}
+
+impl SecretCipher {
+    pub fn new(config: &EncryptionConfig) -> Self {
+        Self {
+            keys: Arc::new(config.keys.clone()),
+            active_key_id: config.active_key().map(|key| key.id.clone()),
+        }
+    }
+
+    pub fn active_key_id(&self) -> Option<&str> {
+        self.active_key_id.as_deref()
+    }
+
+    /// Id of the key `value` was encrypted with, `None` for plaintext.
+    pub fn key_id(value: &str) -> Option<&str> {
+        let rest = value.strip_prefix(PREFIX)?;
+        rest.rsplit_once(':').map(|(key_id, _)| key_id)
+    }
+
+    /// Whether `value` would be read as ciphertext rather than plaintext.
+    pub fn is_encrypted(value: &str) -> bool {
+        Self::key_id(value).is_some()
+    }
+
+    /// Whether `value` is plaintext or encrypted with a key other than the active one.
+    pub fn needs_rotation(&self, value: &str) -> bool {
+        self.active_key_id.is_some() && Self::key_id(value) != self.active_key_id()
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, ApiError> {
+        let Some(key_id) = &self.active_key_id else {
+            return Ok(plaintext.to_string());
+        };
+        let key = self.sealing_key(key_id)?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let mut sealed = plaintext.as_bytes().to_vec();
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(key_id.as_bytes()), &mut sealed)
+            .map_err(|_| ApiError::InternalServerError("Failed to encrypt secret".into()))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&sealed);
+        Ok(format!("{}{}:{}", PREFIX, key_id, BASE64.encode(payload)))
+    }
+
+    pub fn decrypt(&self, value: &str) -> Result<String, ApiError> {
+        let Some((key_id, payload)) = value.strip_prefix(PREFIX).and_then(|rest| rest.rsplit_once(':')) else {
+            return Ok(value.to_string());
+        };
+        let key = self.sealing_key(key_id)?;
+        let invalid = || ApiError::InternalServerError(format!("Secret encrypted with key {:?} is corrupt", key_id));
+
+        let mut payload = BASE64.decode(payload).map_err(|_| invalid())?;
+        if payload.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let mut sealed = payload.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&payload).map_err(|_| invalid())?;
+        let plaintext = key
+            .open_in_place(nonce, Aad::from(key_id.as_bytes()), &mut sealed)
+            .map_err(|_| invalid())?;
+        String::from_utf8(plaintext.to_vec()).map_err(|_| invalid())
+    }
+
+    fn sealing_key(&self, key_id: &str) -> Result<LessSafeKey, ApiError> {
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.id == key_id)
+            .ok_or_else(|| ApiError::InternalServerError(format!("Encryption key {:?} is not configured", key_id)))?;
+        let key = UnboundKey::new(&AES_256_GCM, &key.key)
+            .map_err(|_| ApiError::InternalServerError("Invalid encryption key".into()))?;
+        Ok(LessSafeKey::new(key))
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{
+    config::{EncryptionConfig, EncryptionKey},
+    crypto::SecretCipher,
+};
+
+fn cipher(keys: &[(&str, u8)], active: Option<&str>) -> SecretCipher {
+    SecretCipher::new(&EncryptionConfig {
+        keys: keys
+            .iter()
+            .map(|(id, byte)| EncryptionKey { id: id.to_string(), key: [*byte; 32] })
+            .collect(),
+        active_key_id: active.map(str::to_string),
+    })
+}
+
+#[test]
+fn test_encrypts_with_the_active_key_and_round_trips() {
+    let cipher = cipher(&[("k1", 1)], Some("k1"));
+    let sealed = cipher.encrypt("EAAB-token").unwrap();
+
+    assert!(sealed.starts_with("enc:v1:k1:"));
+    assert!(!sealed.contains("EAAB-token"));
+    assert_ne!(sealed, cipher.encrypt("EAAB-token").unwrap());
+    assert_eq!(cipher.decrypt(&sealed).unwrap(), "EAAB-token");
+    // Values that look encrypted are still encrypted, and read back as they were
+    let wrapped = cipher.encrypt(&sealed).unwrap();
+    assert_ne!(wrapped, sealed);
+    assert_eq!(cipher.decrypt(&wrapped).unwrap(), sealed);
+    assert!(SecretCipher::is_encrypted(&sealed));
+    assert!(!SecretCipher::is_encrypted("EAAB-token"));
+}
+
+#[test]
+fn test_plaintext_passes_through_without_an_active_key() {
+    let cipher = cipher(&[], None);
+    assert_eq!(cipher.encrypt("secret").unwrap(), "secret");
+    assert_eq!(cipher.decrypt("secret").unwrap(), "secret");
+    assert!(!cipher.needs_rotation("secret"));
+}
+
+#[test]
+fn test_old_keys_still_decrypt_after_rotation() {
+    let old = cipher(&[("k1", 1)], Some("k1"));
+    let sealed = old.encrypt("secret").unwrap();
+    let rotated = cipher(&[("k1", 1), ("k2", 2)], Some("k2"));
+
+    assert_eq!(SecretCipher::key_id(&sealed), Some("k1"));
+    assert!(rotated.needs_rotation(&sealed));
+    assert!(rotated.needs_rotation("plaintext"));
+    assert_eq!(rotated.decrypt(&sealed).unwrap(), "secret");
+
+    let resealed = rotated.encrypt(&rotated.decrypt(&sealed).unwrap()).unwrap();
+    assert!(!rotated.needs_rotation(&resealed));
+}
+
+#[test]
+fn test_tampered_or_unknown_key_values_are_rejected() {
+    let cipher = cipher(&[("k1", 1)], Some("k1"));
+    let sealed = cipher.encrypt("secret").unwrap();
+    let mut tampered = sealed.clone();
+    tampered.replace_range(sealed.len() - 4.., "AAAA");
+
+    assert!(cipher.decrypt(&tampered).is_err());
+    assert!(cipher.decrypt(&sealed.replace(":k1:", ":k9:")).is_err());
+    // The key id is authenticated, relabelling a value breaks it
+    let relabelled = self::cipher(&[("k1", 1), ("k2", 1)], Some("k1"));
+    assert!(relabelled.decrypt(&sealed.replace(":k1:", ":k2:")).is_err());
+}
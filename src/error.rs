@@ -14,8 +14,19 @@ pub enum ApiError {
     NotFound,
     #[error("Bad request: {0}")]
     BadRequest(String),
+    #[error("Unauthorized")]
+    Unauthorized,
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Unprocessable entity: {0}")]
+    UnprocessableEntity(String),
     #[error("Internal server error: {0}")]
     InternalServerError(String),
+    // An upstream API such as an ad platform failed the request
+    #[error("Bad gateway: {0}")]
+    BadGateway(String),
     #[error("Serialization error: {0}")]
     Serialization(#[from] mongodb::bson::ser::Error),
     #[error("Deserialization error: {0}")]
@@ -28,7 +39,12 @@ impl IntoResponse for ApiError {
             ApiError::MongoDB(ref e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             ApiError::NotFound => (StatusCode::NOT_FOUND, "Resource not found".to_string()),
             ApiError::BadRequest(ref message) => (StatusCode::BAD_REQUEST, message.clone()),
+            ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, "Missing or invalid API key".to_string()),
+            ApiError::Forbidden(ref message) => (StatusCode::FORBIDDEN, message.clone()),
+            ApiError::Conflict(ref message) => (StatusCode::CONFLICT, message.clone()),
+            ApiError::UnprocessableEntity(ref message) => (StatusCode::UNPROCESSABLE_ENTITY, message.clone()),
             ApiError::InternalServerError(ref message) => (StatusCode::INTERNAL_SERVER_ERROR, message.clone()),
+            ApiError::BadGateway(ref message) => (StatusCode::BAD_GATEWAY, message.clone()),
             ApiError::Serialization(ref e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             ApiError::Deserialization(ref e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
         };
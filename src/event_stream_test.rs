@@ -0,0 +1,145 @@
+use axum::{
+    body::{to_bytes, Body, BodyDataStream},
+    http::{header, Request, StatusCode},
+    Router,
+};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::{sync::Arc, time::Duration};
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    app,
+    repository::outbox_repository::OutboxRepository,
+    service::event_bus::EventBus,
+    test_support::{test_db, test_state, unreachable_db, BOOTSTRAP_KEY},
+};
+
+async fn send(app: &Router, method: &str, uri: &str, org_id: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri).header("x-api-key", BOOTSTRAP_KEY);
+    if let Some(org_id) = org_id {
+        request = request.header("x-org-id", org_id);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+async fn open(app: &Router, uri: &str, org_id: &str, last_event_id: Option<&str>) -> BodyDataStream {
+    let mut request = Request::get(uri).header("x-api-key", BOOTSTRAP_KEY).header("x-org-id", org_id);
+    if let Some(id) = last_event_id {
+        request = request.header("last-event-id", id);
+    }
+    let response = app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+    response.into_body().into_data_stream()
+}
+
+/// Reads the next event of a stream as its `id` and JSON data, skipping keep-alives.
+async fn next_event(stream: &mut BodyDataStream) -> (String, Value) {
+    let mut buffer = String::new();
+    loop {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("no event in time")
+            .expect("stream ended")
+            .unwrap();
+        buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        while let Some(end) = buffer.find("\n\n") {
+            let frame: String = buffer.drain(..end + 2).collect();
+            let field = |name: &str| {
+                frame
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name))
+                    .map(|value| value.trim_start().to_string())
+            };
+            if let (Some(id), Some(data)) = (field("id:"), field("data:")) {
+                return (id, serde_json::from_str(&data).unwrap());
+            }
+        }
+    }
+}
+
+fn project(name: &str) -> Value {
+    json!({
+        "name": name,
+        "is_logging": false,
+        "created_at": { "$date": { "$numberLong": "0" } },
+        "updated_at": { "$date": { "$numberLong": "0" } },
+    })
+}
+
+#[tokio::test]
+async fn test_event_stream_validates_its_request() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+
+    let request = Request::get("/v1/events/stream").body(Body::empty()).unwrap();
+    assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+    for uri in ["/v1/events/stream?entity=webhook", "/v1/events/stream?id=42", "/v2/events/stream?id=42"] {
+        let (status, _) = send(&app, "GET", uri, None, None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+    }
+
+    let request = Request::get("/v1/events/stream")
+        .header("x-api-key", BOOTSTRAP_KEY)
+        .header("last-event-id", "not-an-id")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_event_stream_follows_and_resumes() {
+    let db = test_db("_event_stream").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let state = test_state(db.clone());
+    // Without a replica set, streams are fed by the event bus
+    let bus = EventBus::new(OutboxRepository::new(db)).subscribe(Arc::new(state.event_stream_service.clone()));
+    let app = app::router(state, CorsLayer::permissive());
+    let (_, org) = send(&app, "POST", "/v1/organizations", None, Some(json!({ "name": "Stream" }))).await;
+    let org = org["_id"]["$oid"].as_str().unwrap().to_string();
+    let org_id = Some(org.as_str());
+
+    let mut all = open(&app, "/v1/events/stream", &org, None).await;
+    let (status, first) = send(&app, "POST", "/v1/projects", org_id, Some(project("First"))).await;
+    assert_eq!(status, StatusCode::OK, "{}", first);
+    let first_id = first["_id"]["$oid"].as_str().unwrap().to_string();
+    let mut first_only = open(&app, &format!("/v2/events/stream?entity=project&id={}", first_id), &org, None).await;
+    let (_, second) = send(&app, "POST", "/v1/projects", org_id, Some(project("Second"))).await;
+    let (status, _) = send(&app, "DELETE", &format!("/v1/projects/{}", first_id), org_id, None).await;
+    assert_eq!(status, StatusCode::OK);
+    bus.dispatch_due().await.unwrap();
+
+    let (created_id, created) = next_event(&mut all).await;
+    assert_eq!(created["event"], "project.created");
+    // Without a replica set the event id is the outbox entry id
+    assert_eq!(created["id"], created_id.as_str());
+    assert_eq!(created["org_id"], org.as_str());
+    assert_eq!(created["data"]["name"], "First");
+    assert!(created["data"]["is_active"].is_boolean());
+    let (_, next) = next_event(&mut all).await;
+    assert_eq!(next["data"]["_id"], second["_id"]);
+
+    // Only events about the first project
+    let (_, deleted) = next_event(&mut first_only).await;
+    assert_eq!(deleted["event"], "project.deleted");
+    assert_eq!(deleted["data"]["id"], first_id.as_str());
+
+    let mut resumed = open(&app, "/v1/events/stream", &org, Some(&created_id)).await;
+    let (_, next) = next_event(&mut resumed).await;
+    assert_eq!(next["data"]["_id"], second["_id"]);
+    let (_, next) = next_event(&mut resumed).await;
+    assert_eq!(next["event"], "project.deleted");
+}
@@ -0,0 +1,107 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    app,
+    test_support::{test_db, test_state, unreachable_db, BOOTSTRAP_KEY},
+};
+
+async fn send(app: &Router, method: &str, uri: &str, org_id: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri).header("x-api-key", BOOTSTRAP_KEY);
+    if let Some(org_id) = org_id {
+        request = request.header("x-org-id", org_id);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+fn credential(access_token: &str) -> Value {
+    json!({
+        "app_id": "app",
+        "app_secret": "secret",
+        "access_token": access_token,
+        "ad_account_id": "act_1",
+        "account_suffix": "",
+    })
+}
+
+#[tokio::test]
+async fn test_credential_routes_require_a_key() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+    let collection = "/projects/65f000000000000000000000/facebook-credentials";
+    let key = format!("{}/main", collection);
+
+    for (method, uri) in [("GET", collection), ("POST", collection), ("PUT", &key), ("DELETE", &key)] {
+        let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::UNAUTHORIZED, "{} {}", method, uri);
+    }
+}
+
+#[tokio::test]
+async fn test_credentials_are_managed_one_key_at_a_time() {
+    let db = test_db("_facebook_credentials").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let app = app::router(test_state(db), CorsLayer::permissive());
+    let (_, org) = send(&app, "POST", "/organizations", None, Some(json!({ "name": "Credentials" }))).await;
+    let org = org["_id"]["$oid"].as_str().unwrap().to_string();
+    let org_id = Some(org.as_str());
+
+    let project = json!({
+        "name": "Ads",
+        "facebook_credentials": { "main": credential("main-token") },
+        "is_active": true,
+        "is_logging": false,
+        "created_at": { "$date": { "$numberLong": "0" } },
+        "updated_at": { "$date": { "$numberLong": "0" } },
+    });
+    let (status, created) = send(&app, "POST", "/projects", org_id, Some(project)).await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+    let uri = format!("/projects/{}/facebook-credentials", created["_id"]["$oid"].as_str().unwrap());
+
+    let mut entry = credential("second-token");
+    entry["key"] = json!("second");
+    let (status, added) = send(&app, "POST", &uri, org_id, Some(entry.clone())).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", added);
+    assert_eq!(added["key"], "second");
+    let (status, _) = send(&app, "POST", &uri, org_id, Some(entry.clone())).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    entry["key"] = json!("bad.key");
+    let (status, _) = send(&app, "POST", &uri, org_id, Some(entry)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // The masked placeholder keeps the stored secret
+    let mut replacement = credential("********");
+    replacement["app_id"] = json!("new-app");
+    let (status, replaced) = send(&app, "PUT", &format!("{}/main", uri), org_id, Some(replacement.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(replaced["app_id"], "new-app");
+    let (status, _) = send(&app, "PUT", &format!("{}/missing", uri), org_id, Some(replacement)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, listed) = send(&app, "GET", &uri, org_id, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed["main"]["access_token"], "main-token");
+    assert_eq!(listed["second"]["access_token"], "second-token");
+
+    let (_, removed) = send(&app, "DELETE", &format!("{}/second", uri), org_id, None).await;
+    assert_eq!(removed, json!(true));
+    let (_, removed) = send(&app, "DELETE", &format!("{}/second", uri), org_id, None).await;
+    assert_eq!(removed, json!(false));
+    let (_, listed) = send(&app, "GET", &uri, org_id, None).await;
+    assert_eq!(listed.as_object().unwrap().keys().collect::<Vec<_>>(), vec!["main"]);
+}
@@ -0,0 +1,239 @@
+use axum::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::time::Duration;
+use url::Url;
+#[cfg(any(test, feature = "test-support"))]
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{http_client::HttpClient, models::lead::LeadField};
+
+const GRAPH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// What `debug_token` reports about an access token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenInfo {
+    pub valid: bool,
+    // `None` for tokens that never expire
+    pub expires_at: Option<DateTime<Utc>>,
+    pub scopes: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// A long-lived token handed out in exchange for a short-lived one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangedToken {
+    pub access_token: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// The Facebook Graph API calls the service makes. Errors are transport
+/// failures or Graph API errors, an invalid token is not an error for
+/// `debug_token`.
+#[async_trait]
+pub trait GraphClient: Send + Sync {
+    async fn debug_token(&self, app_id: &str, app_secret: &str, access_token: &str) -> Result<TokenInfo, String>;
+
+    async fn exchange_token(&self, app_id: &str, app_secret: &str, access_token: &str) -> Result<ExchangedToken, String>;
+
+    /// The form answers of a Lead Ads lead.
+    async fn get_lead(&self, access_token: &str, leadgen_id: &str) -> Result<Vec<LeadField>, String>;
+}
+
+#[derive(Deserialize)]
+struct GraphErrorBody {
+    error: GraphError,
+}
+
+#[derive(Deserialize)]
+struct GraphError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct DebugTokenBody {
+    data: DebugTokenData,
+}
+
+#[derive(Deserialize)]
+struct DebugTokenData {
+    #[serde(default)]
+    is_valid: bool,
+    // Unix seconds, 0 for tokens that never expire
+    #[serde(default)]
+    expires_at: i64,
+    #[serde(default)]
+    scopes: Vec<String>,
+    error: Option<GraphError>,
+}
+
+#[derive(Deserialize)]
+struct ExchangeBody {
+    access_token: String,
+    expires_in: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct LeadBody {
+    #[serde(default)]
+    field_data: Vec<LeadField>,
+}
+
+/// `GraphClient` talking to the real Graph API, or whatever `base_url` points at.
+#[derive(Debug, Clone)]
+pub struct HttpGraphClient {
+    http: HttpClient,
+    base_url: String,
+}
+
+impl HttpGraphClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            // The URL comes from the operator, not from API callers
+            http: HttpClient::new(GRAPH_TIMEOUT, true),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn get<T: for<'de> Deserialize<'de>>(&self, path: &str, query: &[(&str, &str)]) -> Result<T, String> {
+        let mut url = Url::parse(&format!("{}/{}", self.base_url, path)).map_err(|e| e.to_string())?;
+        url.query_pairs_mut().extend_pairs(query);
+        let (status, body) = self.http.get(url.as_str()).await?;
+        if !status.is_success() {
+            return Err(match serde_json::from_slice::<GraphErrorBody>(&body) {
+                Ok(body) => format!("Graph API error: {}", body.error.message),
+                Err(_) => format!("Graph API responded with {}", status),
+            });
+        }
+        serde_json::from_slice(&body).map_err(|e| format!("unexpected Graph API response: {}", e))
+    }
+}
+
+#[async_trait]
+impl GraphClient for HttpGraphClient {
+    async fn debug_token(&self, app_id: &str, app_secret: &str, access_token: &str) -> Result<TokenInfo, String> {
+        let app_token = format!("{}|{}", app_id, app_secret);
+        let body: DebugTokenBody = self
+            .get("debug_token", &[("input_token", access_token), ("access_token", &app_token)])
+            .await?;
+        let data = body.data;
+        Ok(TokenInfo {
+            valid: data.is_valid,
+            expires_at: match data.expires_at {
+                0 => None,
+                at => Utc.timestamp_opt(at, 0).single(),
+            },
+            scopes: data.scopes,
+            error: data.error.map(|error| error.message),
+        })
+    }
+
+    async fn exchange_token(&self, app_id: &str, app_secret: &str, access_token: &str) -> Result<ExchangedToken, String> {
+        let body: ExchangeBody = self
+            .get(
+                "oauth/access_token",
+                &[
+                    ("grant_type", "fb_exchange_token"),
+                    ("client_id", app_id),
+                    ("client_secret", app_secret),
+                    ("fb_exchange_token", access_token),
+                ],
+            )
+            .await?;
+        Ok(ExchangedToken {
+            access_token: body.access_token,
+            expires_at: body.expires_in.map(|secs| Utc::now() + chrono::Duration::seconds(secs)),
+        })
+    }
+
+    async fn get_lead(&self, access_token: &str, leadgen_id: &str) -> Result<Vec<LeadField>, String> {
+        // Lead ids are numeric, anything else must not reach the URL path
+        if leadgen_id.is_empty() || !leadgen_id.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("invalid leadgen id {:?}", leadgen_id));
+        }
+        let body: LeadBody = self
+            .get(leadgen_id, &[("fields", "field_data"), ("access_token", access_token)])
+            .await?;
+        Ok(body.field_data)
+    }
+}
+
+/// In-memory `GraphClient` for tests, also built with the `test-support`
+/// feature. Tokens it was not told about are reported as invalid.
+#[cfg(any(test, feature = "test-support"))]
+#[derive(Debug, Clone, Default)]
+pub struct MockGraphClient {
+    tokens: Arc<Mutex<HashMap<String, TokenInfo>>>,
+    exchanges: Arc<Mutex<HashMap<String, ExchangedToken>>>,
+    leads: Arc<Mutex<HashMap<String, Vec<LeadField>>>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl MockGraphClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_token(self, access_token: &str, info: TokenInfo) -> Self {
+        self.tokens.lock().unwrap().insert(access_token.to_string(), info);
+        self
+    }
+
+    /// Makes `access_token` exchangeable for `exchanged`, which is then
+    /// reported valid with the scopes of the original token.
+    pub fn with_exchange(self, access_token: &str, exchanged: ExchangedToken) -> Self {
+        self.exchanges.lock().unwrap().insert(access_token.to_string(), exchanged);
+        self
+    }
+
+    /// Makes `leadgen_id` readable with any valid token.
+    pub fn with_lead(self, leadgen_id: &str, fields: Vec<LeadField>) -> Self {
+        self.leads.lock().unwrap().insert(leadgen_id.to_string(), fields);
+        self
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[async_trait]
+impl GraphClient for MockGraphClient {
+    async fn debug_token(&self, _app_id: &str, _app_secret: &str, access_token: &str) -> Result<TokenInfo, String> {
+        Ok(self.tokens.lock().unwrap().get(access_token).cloned().unwrap_or(TokenInfo {
+            valid: false,
+            expires_at: None,
+            scopes: vec![],
+            error: Some("Invalid OAuth access token".to_string()),
+        }))
+    }
+
+    async fn exchange_token(&self, _app_id: &str, _app_secret: &str, access_token: &str) -> Result<ExchangedToken, String> {
+        let exchanged = self
+            .exchanges
+            .lock()
+            .unwrap()
+            .get(access_token)
+            .cloned()
+            .ok_or_else(|| "Graph API error: Invalid OAuth access token".to_string())?;
+        let mut tokens = self.tokens.lock().unwrap();
+        let scopes = tokens.get(access_token).map(|info| info.scopes.clone()).unwrap_or_default();
+        tokens.insert(
+            exchanged.access_token.clone(),
+            TokenInfo { valid: true, expires_at: exchanged.expires_at, scopes, error: None },
+        );
+        Ok(exchanged)
+    }
+
+    async fn get_lead(&self, access_token: &str, leadgen_id: &str) -> Result<Vec<LeadField>, String> {
+        if !self.tokens.lock().unwrap().get(access_token).is_some_and(|info| info.valid) {
+            return Err("Graph API error: Invalid OAuth access token".to_string());
+        }
+        self.leads
+            .lock()
+            .unwrap()
+            .get(leadgen_id)
+            .cloned()
+            .ok_or_else(|| format!("Graph API error: Unsupported get request, lead {} does not exist", leadgen_id))
+    }
+}
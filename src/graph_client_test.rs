@@ -0,0 +1,76 @@
+use axum::{extract::Query, http::StatusCode, routing::get, Json, Router};
+use chrono::{TimeZone, Utc};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::graph_client::{ExchangedToken, GraphClient, HttpGraphClient, MockGraphClient, TokenInfo};
+
+async fn debug_token(Query(query): Query<HashMap<String, String>>) -> Json<Value> {
+    assert_eq!(query["access_token"], "app|secret");
+    Json(match query["input_token"].as_str() {
+        "good" => json!({ "data": { "is_valid": true, "expires_at": 1_900_000_000, "scopes": ["ads_read"] } }),
+        "forever" => json!({ "data": { "is_valid": true, "expires_at": 0, "scopes": [] } }),
+        _ => json!({ "data": { "is_valid": false, "error": { "message": "Session has expired" } } }),
+    })
+}
+
+async fn exchange(Query(query): Query<HashMap<String, String>>) -> (StatusCode, Json<Value>) {
+    assert_eq!(query["grant_type"], "fb_exchange_token");
+    assert_eq!((query["client_id"].as_str(), query["client_secret"].as_str()), ("app", "secret"));
+    match query["fb_exchange_token"].as_str() {
+        "good" => (StatusCode::OK, Json(json!({ "access_token": "long", "token_type": "bearer", "expires_in": 5_184_000 }))),
+        _ => (StatusCode::BAD_REQUEST, Json(json!({ "error": { "message": "Invalid OAuth access token" } }))),
+    }
+}
+
+async fn spawn_graph() -> HttpGraphClient {
+    let app = Router::new()
+        .route("/v19.0/debug_token", get(debug_token))
+        .route("/v19.0/oauth/access_token", get(exchange));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    HttpGraphClient::new(&format!("http://{}/v19.0/", addr))
+}
+
+#[tokio::test]
+async fn test_debug_token_reports_validity_expiry_and_scopes() {
+    let graph = spawn_graph().await;
+
+    let info = graph.debug_token("app", "secret", "good").await.unwrap();
+    assert!(info.valid);
+    assert_eq!(info.expires_at, Utc.timestamp_opt(1_900_000_000, 0).single());
+    assert_eq!(info.scopes, vec!["ads_read"]);
+
+    assert_eq!(graph.debug_token("app", "secret", "forever").await.unwrap().expires_at, None);
+
+    let info = graph.debug_token("app", "secret", "stale").await.unwrap();
+    assert!(!info.valid);
+    assert_eq!(info.error.as_deref(), Some("Session has expired"));
+}
+
+#[tokio::test]
+async fn test_exchange_token_returns_the_long_lived_token() {
+    let graph = spawn_graph().await;
+
+    let exchanged = graph.exchange_token("app", "secret", "good").await.unwrap();
+    assert_eq!(exchanged.access_token, "long");
+    assert!(exchanged.expires_at.unwrap() > Utc::now() + chrono::Duration::days(59));
+
+    let err = graph.exchange_token("app", "secret", "stale").await.unwrap_err();
+    assert_eq!(err, "Graph API error: Invalid OAuth access token");
+}
+
+#[tokio::test]
+async fn test_mock_reports_exchanged_tokens_as_valid() {
+    let graph = MockGraphClient::new()
+        .with_token("short", TokenInfo { valid: true, expires_at: None, scopes: vec!["ads_read".to_string()], error: None })
+        .with_exchange("short", ExchangedToken { access_token: "long".to_string(), expires_at: None });
+
+    assert!(!graph.debug_token("app", "secret", "long").await.unwrap().valid);
+    graph.exchange_token("app", "secret", "short").await.unwrap();
+    let info = graph.debug_token("app", "secret", "long").await.unwrap();
+    assert!(info.valid);
+    assert_eq!(info.scopes, vec!["ads_read"]);
+    assert!(graph.exchange_token("app", "secret", "unknown").await.is_err());
+}
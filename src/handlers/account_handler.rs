@@ -1,57 +1,95 @@
 use axum::{
-    extract::{Path, State},
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
     Json,
 };
+use futures_util::StreamExt;
 use mongodb::bson::oid::ObjectId;
 
 use crate::{
-    models::account::Account,
+    auth::{AccountsAdmin, Authorized},
+    models::{
+        account::{Account, AccountRecord},
+        import::{ExportParams, ImportParams, ImportReport},
+    },
+    records::{self, RecordFormat},
     service::account_service::AccountService,
     error::ApiError,
 };
 
 pub async fn create_account(
+    auth: Authorized<AccountsAdmin>,
     State(service): State<AccountService>,
     Json(account): Json<Account>,
 ) -> Result<Json<Account>, ApiError> {
-    let account = service.create_account(account).await?;
+    let account = service.create_account(&auth.caller.tenant()?, account).await?;
     Ok(Json(account))
 }
 
 pub async fn update_account(
+    auth: Authorized<AccountsAdmin>,
     State(service): State<AccountService>,
     Path(id): Path<String>,
     Json(account): Json<Account>,
 ) -> Result<Json<Account>, ApiError> {
     let object_id = ObjectId::parse_str(&id)
         .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
-    let account = service.update_account(&object_id, account).await?;
+    let account = service.update_account(&auth.caller.tenant()?, &object_id, account).await?;
     Ok(Json(account))
 }
 
 pub async fn delete_account(
+    auth: Authorized<AccountsAdmin>,
     State(service): State<AccountService>,
     Path(id): Path<String>,
 ) -> Result<Json<bool>, ApiError> {
     let object_id = ObjectId::parse_str(&id)
         .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
-    let result = service.delete_account(&object_id).await?;
+    let result = service.delete_account(&auth.caller.tenant()?, &object_id, auth.caller.role).await?;
     Ok(Json(result))
 }
 
 pub async fn get_account(
+    auth: Authorized<AccountsAdmin>,
     State(service): State<AccountService>,
     Path(id): Path<String>,
 ) -> Result<Json<Account>, ApiError> {
     let object_id = ObjectId::parse_str(&id)
         .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
-    let account = service.get_account(&object_id).await?;
+    let account = service.get_account(&auth.caller.tenant()?, &object_id).await?;
     Ok(Json(account))
 }
 
 pub async fn get_all_accounts(
+    auth: Authorized<AccountsAdmin>,
     State(service): State<AccountService>,
 ) -> Result<Json<Vec<Account>>, ApiError> {
-    let accounts = service.get_all_accounts().await?;
+    let accounts = service.get_all_accounts(&auth.caller.tenant()?).await?;
     Ok(Json(accounts))
-}
\ No newline at end of file
+}
+pub async fn import_accounts(
+    auth: Authorized<AccountsAdmin>,
+    State(service): State<AccountService>,
+    Query(params): Query<ImportParams>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<(StatusCode, Json<ImportReport>), ApiError> {
+    let format = RecordFormat::of_request(&headers)?;
+    let rows = records::decode_body::<AccountRecord>(format, body);
+    let report = service.import_accounts(&auth.caller.tenant()?, rows, params.mode).await?;
+    Ok(records::import_response(report))
+}
+
+pub async fn export_accounts(
+    auth: Authorized<AccountsAdmin>,
+    State(service): State<AccountService>,
+    Query(params): Query<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let format = RecordFormat::of_export(params.format.as_deref(), &headers)?;
+    let accounts = service.export_accounts(&auth.caller.tenant()?).await?;
+    let records = accounts.map(|account| account.map(AccountRecord::from));
+    Ok(records::export_response(format, "accounts", records))
+}
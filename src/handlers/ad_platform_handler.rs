@@ -0,0 +1,59 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use mongodb::bson::oid::ObjectId;
+
+use crate::{
+    auth::{Authorized, ProjectsRead, ProjectsWrite},
+    models::{
+        ad_platform::{Conversion, Spend, SpendQuery},
+        event_id::{ConversionReceipt, DedupStats, EventIdRequest, IssuedEventId},
+    },
+    service::ad_platform_service::AdPlatformService,
+    error::ApiError,
+};
+
+fn parse_id(id: &str) -> Result<ObjectId, ApiError> {
+    ObjectId::parse_str(id).map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))
+}
+
+pub async fn send_conversion(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<AdPlatformService>,
+    Path((id, key)): Path<(String, String)>,
+    Json(conversion): Json<Conversion>,
+) -> Result<Json<ConversionReceipt>, ApiError> {
+    let receipt = service.send_conversion(&auth.caller.tenant()?, &parse_id(&id)?, &key, conversion).await?;
+    Ok(Json(receipt))
+}
+
+pub async fn issue_event_id(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<AdPlatformService>,
+    Path(id): Path<String>,
+    Json(request): Json<EventIdRequest>,
+) -> Result<(StatusCode, Json<IssuedEventId>), ApiError> {
+    let issued = service.issue_event_id(&auth.caller.tenant()?, &parse_id(&id)?, request).await?;
+    Ok((StatusCode::CREATED, Json(issued)))
+}
+
+pub async fn get_dedup_stats(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<AdPlatformService>,
+    Path(id): Path<String>,
+) -> Result<Json<DedupStats>, ApiError> {
+    let stats = service.dedup_stats(&auth.caller.tenant()?, &parse_id(&id)?).await?;
+    Ok(Json(stats))
+}
+
+pub async fn get_spend(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<AdPlatformService>,
+    Path((id, key)): Path<(String, String)>,
+    Query(query): Query<SpendQuery>,
+) -> Result<Json<Spend>, ApiError> {
+    let spend = service.fetch_spend(&auth.caller.tenant()?, &parse_id(&id)?, &key, query).await?;
+    Ok(Json(spend))
+}
@@ -0,0 +1,52 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use mongodb::bson::oid::ObjectId;
+
+use crate::{
+    auth::{Authorized, KeysAdmin},
+    models::api_key::{ApiKeySummary, CreateApiKey, CreatedApiKey},
+    service::api_key_service::ApiKeyService,
+    error::ApiError,
+};
+
+pub async fn create_api_key(
+    auth: Authorized<KeysAdmin>,
+    State(service): State<ApiKeyService>,
+    Json(request): Json<CreateApiKey>,
+) -> Result<(StatusCode, Json<CreatedApiKey>), ApiError> {
+    let created = service.create_api_key(&auth.caller, request).await?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+pub async fn revoke_api_key(
+    auth: Authorized<KeysAdmin>,
+    State(service): State<ApiKeyService>,
+    Path(id): Path<String>,
+) -> Result<Json<bool>, ApiError> {
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    let result = service.revoke_api_key(&auth.caller, &object_id).await?;
+    Ok(Json(result))
+}
+
+pub async fn get_api_key(
+    auth: Authorized<KeysAdmin>,
+    State(service): State<ApiKeyService>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiKeySummary>, ApiError> {
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    let api_key = service.get_api_key(&auth.caller, &object_id).await?;
+    Ok(Json(api_key.into()))
+}
+
+pub async fn get_all_api_keys(
+    auth: Authorized<KeysAdmin>,
+    State(service): State<ApiKeyService>,
+) -> Result<Json<Vec<ApiKeySummary>>, ApiError> {
+    let api_keys = service.get_all_api_keys(&auth.caller).await?;
+    Ok(Json(api_keys.into_iter().map(ApiKeySummary::from).collect()))
+}
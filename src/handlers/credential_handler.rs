@@ -0,0 +1,129 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use mongodb::bson::oid::ObjectId;
+use std::collections::HashMap;
+
+use crate::{
+    auth::{Authorized, Caller, ProjectsRead, ProjectsWrite},
+    models::{
+        ad_platform::{AdCredentialEntry, AdPlatform, AdPlatformCredential},
+        api_key::Scope,
+        project::{FacebookCredential, FacebookCredentialEntry},
+    },
+    service::project_service::ProjectService,
+    error::ApiError
+};
+
+fn present(mut credential: AdPlatformCredential, caller: &Caller) -> AdPlatformCredential {
+    if !caller.has_scope(Scope::SecretsReveal) {
+        credential.mask_secrets();
+    }
+    credential
+}
+
+fn present_entry(entry: AdCredentialEntry, caller: &Caller) -> AdCredentialEntry {
+    AdCredentialEntry { key: entry.key, credential: present(entry.credential, caller) }
+}
+
+fn present_facebook(entry: AdCredentialEntry, caller: &Caller) -> Result<FacebookCredentialEntry, ApiError> {
+    let credential = present(entry.credential, caller)
+        .into_facebook()
+        .ok_or_else(|| ApiError::InternalServerError(format!("Credential {} is not a Facebook credential", entry.key)))?;
+    Ok(FacebookCredentialEntry { key: entry.key, credential })
+}
+
+fn parse_id(id: &str) -> Result<ObjectId, ApiError> {
+    ObjectId::parse_str(id).map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))
+}
+
+pub async fn get_ad_credentials(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<ProjectService>,
+    Path(id): Path<String>,
+) -> Result<Json<HashMap<String, AdPlatformCredential>>, ApiError> {
+    let credentials = service.get_credentials(&auth.caller.tenant()?, &parse_id(&id)?).await?;
+    let credentials = credentials
+        .into_iter()
+        .map(|(key, credential)| (key, present(credential, &auth.caller)))
+        .collect();
+    Ok(Json(credentials))
+}
+
+pub async fn add_ad_credential(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<ProjectService>,
+    Path(id): Path<String>,
+    Json(entry): Json<AdCredentialEntry>,
+) -> Result<(StatusCode, Json<AdCredentialEntry>), ApiError> {
+    let entry = service.add_credential(&auth.caller.tenant()?, &parse_id(&id)?, entry).await?;
+    Ok((StatusCode::CREATED, Json(present_entry(entry, &auth.caller))))
+}
+
+pub async fn replace_ad_credential(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<ProjectService>,
+    Path((id, key)): Path<(String, String)>,
+    Json(credential): Json<AdPlatformCredential>,
+) -> Result<Json<AdCredentialEntry>, ApiError> {
+    let entry = service.replace_credential(&auth.caller.tenant()?, &parse_id(&id)?, &key, credential).await?;
+    Ok(Json(present_entry(entry, &auth.caller)))
+}
+
+pub async fn remove_ad_credential(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<ProjectService>,
+    Path((id, key)): Path<(String, String)>,
+) -> Result<Json<bool>, ApiError> {
+    let removed = service.remove_credential(&auth.caller.tenant()?, &parse_id(&id)?, &key, None).await?;
+    Ok(Json(removed))
+}
+
+// The `facebook-credentials` routes predate `ad-credentials` and only see Facebook entries
+
+pub async fn get_credentials(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<ProjectService>,
+    Path(id): Path<String>,
+) -> Result<Json<HashMap<String, FacebookCredential>>, ApiError> {
+    let credentials = service.get_credentials(&auth.caller.tenant()?, &parse_id(&id)?).await?;
+    let credentials = credentials
+        .into_iter()
+        .filter_map(|(key, credential)| Some((key, present(credential, &auth.caller).into_facebook()?)))
+        .collect();
+    Ok(Json(credentials))
+}
+
+pub async fn add_credential(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<ProjectService>,
+    Path(id): Path<String>,
+    Json(entry): Json<FacebookCredentialEntry>,
+) -> Result<(StatusCode, Json<FacebookCredentialEntry>), ApiError> {
+    let entry = AdCredentialEntry { key: entry.key, credential: AdPlatformCredential::Facebook(entry.credential) };
+    let entry = service.add_credential(&auth.caller.tenant()?, &parse_id(&id)?, entry).await?;
+    Ok((StatusCode::CREATED, Json(present_facebook(entry, &auth.caller)?)))
+}
+
+pub async fn replace_credential(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<ProjectService>,
+    Path((id, key)): Path<(String, String)>,
+    Json(credential): Json<FacebookCredential>,
+) -> Result<Json<FacebookCredentialEntry>, ApiError> {
+    let credential = AdPlatformCredential::Facebook(credential);
+    let entry = service.replace_credential(&auth.caller.tenant()?, &parse_id(&id)?, &key, credential).await?;
+    Ok(Json(present_facebook(entry, &auth.caller)?))
+}
+
+pub async fn remove_credential(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<ProjectService>,
+    Path((id, key)): Path<(String, String)>,
+) -> Result<Json<bool>, ApiError> {
+    let platform = Some(AdPlatform::Facebook);
+    let removed = service.remove_credential(&auth.caller.tenant()?, &parse_id(&id)?, &key, platform).await?;
+    Ok(Json(removed))
+}
@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, HeaderName},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{Stream, StreamExt};
+use mongodb::bson::oid::ObjectId;
+use serde::Serialize;
+use serde_json::json;
+use std::convert::Infallible;
+
+use crate::{
+    auth::{Authorized, Caller, ProjectsRead},
+    error::ApiError,
+    models::{
+        api_key::Scope,
+        event::{EventEntity, EventFilter, EventStreamQuery, OutboxEntry},
+        project::Project,
+    },
+    service::event_stream_service::EventStreamService,
+};
+
+pub const LAST_EVENT_ID_HEADER: HeaderName = HeaderName::from_static("last-event-id");
+
+fn parse_id(id: &str, what: &str) -> Result<ObjectId, ApiError> {
+    ObjectId::parse_str(id.trim()).map_err(|_| ApiError::BadRequest(format!("Invalid {}", what)))
+}
+
+// Account events are only streamed to callers holding accounts:admin
+fn filter(query: EventStreamQuery, caller: &Caller) -> Result<EventFilter, ApiError> {
+    let accounts = caller.has_scope(Scope::AccountsAdmin);
+    let entities = match query.entity {
+        Some(EventEntity::Account) if !accounts => {
+            return Err(ApiError::Forbidden("Account events require the accounts:admin scope".to_string()))
+        }
+        Some(entity) => vec![entity],
+        None if accounts => vec![EventEntity::Project, EventEntity::Account],
+        None => vec![EventEntity::Project],
+    };
+    let id = query.id.as_deref().map(|id| parse_id(id, "ID format")).transpose()?;
+    Ok(EventFilter { entities, id })
+}
+
+/// The entry as an SSE event, its data shaped like a webhook payload with
+/// projects sent as `P`.
+fn sse_event<P: From<Project> + Serialize>(event_id: String, entry: OutboxEntry) -> Option<Event> {
+    let id = entry.id?.to_hex();
+    let (event, data) = entry.event.published::<P>()?;
+    let payload = json!({
+        "id": id,
+        "event": event,
+        "org_id": entry.org_id.to_hex(),
+        "created_at": entry.created_at,
+        "data": data,
+    });
+    Some(Event::default().id(event_id).event(event.as_str()).data(payload.to_string()))
+}
+
+/// `GET /events/stream` for an API version sending projects as `P`.
+pub async fn stream_events_as<P: From<Project> + Serialize>(
+    auth: Authorized<ProjectsRead>,
+    service: EventStreamService,
+    query: EventStreamQuery,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let filter = filter(query, &auth.caller)?;
+    let last_event_id = match headers.get(LAST_EVENT_ID_HEADER) {
+        Some(value) => Some(value.to_str().map_err(|_| ApiError::BadRequest("Invalid Last-Event-ID header".to_string()))?),
+        None => None,
+    };
+    let entries = service.subscribe(auth.caller.tenant()?, filter, last_event_id).await?;
+    let events = entries.filter_map(|(id, entry)| async move { sse_event::<P>(id, entry).map(Ok) });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+pub async fn stream_events(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<EventStreamService>,
+    Query(query): Query<EventStreamQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    stream_events_as::<Project>(auth, service, query, headers).await
+}
@@ -0,0 +1,27 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde_json::{json, Value};
+
+use crate::{
+    models::health::{ReadinessReport, VersionInfo},
+    service::health_service::HealthService,
+};
+
+pub async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "up" }))
+}
+
+pub async fn readyz(
+    State(service): State<HealthService>,
+) -> (StatusCode, Json<ReadinessReport>) {
+    let report = service.readiness().await;
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+pub async fn version(State(service): State<HealthService>) -> Json<VersionInfo> {
+    Json(service.version())
+}
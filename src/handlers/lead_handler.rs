@@ -0,0 +1,43 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use mongodb::bson::oid::ObjectId;
+
+use crate::{
+    auth::{Authorized, ProjectsRead},
+    models::lead::{Lead, SubscriptionVerification},
+    service::lead_service::{LeadService, SIGNATURE_HEADER},
+    error::ApiError,
+};
+
+pub async fn verify_subscription(
+    State(service): State<LeadService>,
+    Query(query): Query<SubscriptionVerification>,
+) -> Result<String, ApiError> {
+    service.verify_subscription(&query)
+}
+
+pub async fn receive_notification(
+    State(service): State<LeadService>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, ApiError> {
+    let signature = headers.get(SIGNATURE_HEADER).and_then(|value| value.to_str().ok());
+    service.receive(signature, &body).await?;
+    Ok(StatusCode::OK)
+}
+
+pub async fn get_leads(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<LeadService>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Lead>>, ApiError> {
+    let org_id = auth.caller.tenant()?;
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    let leads = service.get_leads(&org_id, &object_id).await?;
+    Ok(Json(leads))
+}
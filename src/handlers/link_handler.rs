@@ -0,0 +1,76 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::bson::oid::ObjectId;
+use std::collections::HashMap;
+
+use crate::{
+    auth::{Authorized, ProjectsRead, ProjectsWrite},
+    models::link::{LinkStats, LinkView, NewLink},
+    service::link_service::{ClickContext, LinkService},
+    error::ApiError,
+};
+
+fn parse_id(id: &str) -> Result<ObjectId, ApiError> {
+    ObjectId::parse_str(id).map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))
+}
+
+fn header_value(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
+
+pub async fn create_link(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<LinkService>,
+    Path(id): Path<String>,
+    Json(new_link): Json<NewLink>,
+) -> Result<(StatusCode, Json<LinkView>), ApiError> {
+    let link = service.create_link(&auth.caller.tenant()?, &parse_id(&id)?, new_link).await?;
+    Ok((StatusCode::CREATED, Json(link)))
+}
+
+pub async fn get_links(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<LinkService>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<LinkView>>, ApiError> {
+    let links = service.get_links(&auth.caller.tenant()?, &parse_id(&id)?).await?;
+    Ok(Json(links))
+}
+
+pub async fn delete_link(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<LinkService>,
+    Path((id, link_id)): Path<(String, String)>,
+) -> Result<Json<bool>, ApiError> {
+    let deleted = service.delete_link(&auth.caller.tenant()?, &parse_id(&id)?, &parse_id(&link_id)?).await?;
+    Ok(Json(deleted))
+}
+
+pub async fn get_link_stats(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<LinkService>,
+    Path((id, link_id)): Path<(String, String)>,
+) -> Result<Json<LinkStats>, ApiError> {
+    let stats = service.get_stats(&auth.caller.tenant()?, &parse_id(&id)?, &parse_id(&link_id)?).await?;
+    Ok(Json(stats))
+}
+
+pub async fn follow_link(
+    State(service): State<LinkService>,
+    Path(code): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let context = ClickContext {
+        referrer: header_value(&headers, header::REFERER),
+        user_agent: header_value(&headers, header::USER_AGENT),
+        fbclid: query.get("fbclid").cloned(),
+    };
+    let location = service.follow(&code, context).await?;
+    // Not cached, so that every click reaches the server and is counted
+    Ok((StatusCode::FOUND, [(header::LOCATION, location), (header::CACHE_CONTROL, "no-store".to_string())]).into_response())
+}
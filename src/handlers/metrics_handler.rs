@@ -0,0 +1,20 @@
+use axum::{extract::State, http::header, response::IntoResponse};
+use std::time::Duration;
+use tracing::error;
+
+use crate::{metrics, service::project_service::ProjectService};
+
+const COUNT_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub async fn get_metrics(State(service): State<ProjectService>) -> impl IntoResponse {
+    // Project gauges are counted on each scrape; a failed count keeps the last values
+    match tokio::time::timeout(COUNT_TIMEOUT, service.count_projects_by_state()).await {
+        Ok(Ok(counts)) => metrics::global().set_project_counts(counts.active, counts.expired),
+        Ok(Err(e)) => error!(error = %e, "Failed to count projects"),
+        Err(_) => error!(timeout = ?COUNT_TIMEOUT, "Counting projects timed out"),
+    }
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::global().render(),
+    )
+}
@@ -1,2 +1,13 @@
 pub mod project_handler;
-pub mod account_handler;
\ No newline at end of file
+pub mod credential_handler;
+pub mod account_handler;
+pub mod health_handler;
+pub mod metrics_handler;
+pub mod api_key_handler;
+pub mod organization_handler;
+pub mod webhook_handler;
+pub mod lead_handler;
+pub mod ad_platform_handler;
+pub mod link_handler;
+pub mod event_handler;
+pub mod v2;
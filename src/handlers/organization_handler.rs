@@ -0,0 +1,80 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use mongodb::bson::oid::ObjectId;
+
+use crate::{
+    auth::{AccountsAdmin, Authorized, OrgsAdmin, ProjectsRead},
+    models::organization::{CreateOrganization, Organization, SetMemberRole},
+    service::organization_service::OrganizationService,
+    error::ApiError,
+};
+
+pub async fn create_organization(
+    _auth: Authorized<OrgsAdmin>,
+    State(service): State<OrganizationService>,
+    Json(request): Json<CreateOrganization>,
+) -> Result<(StatusCode, Json<Organization>), ApiError> {
+    let organization = service.create_organization(request).await?;
+    Ok((StatusCode::CREATED, Json(organization)))
+}
+
+pub async fn get_all_organizations(
+    _auth: Authorized<OrgsAdmin>,
+    State(service): State<OrganizationService>,
+) -> Result<Json<Vec<Organization>>, ApiError> {
+    let organizations = service.get_all_organizations().await?;
+    Ok(Json(organizations))
+}
+
+pub async fn get_organization(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<OrganizationService>,
+    Path(id): Path<String>,
+) -> Result<Json<Organization>, ApiError> {
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    // Other tenants' organizations are reported as missing
+    if !auth.caller.can_access_org(&object_id) {
+        return Err(ApiError::NotFound);
+    }
+    let organization = service.get_organization(&object_id).await?;
+    Ok(Json(organization))
+}
+
+pub async fn set_member_role(
+    auth: Authorized<AccountsAdmin>,
+    State(service): State<OrganizationService>,
+    Path((id, account_id)): Path<(String, String)>,
+    Json(request): Json<SetMemberRole>,
+) -> Result<Json<Organization>, ApiError> {
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    let account_id = ObjectId::parse_str(&account_id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    if !auth.caller.can_access_org(&object_id) {
+        return Err(ApiError::NotFound);
+    }
+    let organization = service
+        .set_member_role(&object_id, account_id, request.role, auth.caller.role)
+        .await?;
+    Ok(Json(organization))
+}
+
+pub async fn remove_member(
+    auth: Authorized<AccountsAdmin>,
+    State(service): State<OrganizationService>,
+    Path((id, account_id)): Path<(String, String)>,
+) -> Result<Json<bool>, ApiError> {
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    let account_id = ObjectId::parse_str(&account_id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    if !auth.caller.can_access_org(&object_id) {
+        return Err(ApiError::NotFound);
+    }
+    let result = service.remove_member(&object_id, &account_id, auth.caller.role).await?;
+    Ok(Json(result))
+}
@@ -1,57 +1,191 @@
 use axum::{
-    extract::{Path, State},
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
     Json,
 };
+use futures_util::StreamExt;
 use mongodb::bson::oid::ObjectId;
 
 use crate::{
-    models::project::Project, 
+    auth::{Authorized, Caller, ProjectsRead, ProjectsWrite},
+    models::{
+        api_key::Scope,
+        batch::{BatchGetProjects, BatchResponse, BatchResult, BatchUpdateProjects},
+        import::{ExportParams, ImportParams, ImportReport},
+        project::{Project, ProjectAction, ProjectRecord, StatusChange},
+    },
+    records::{self, RecordFormat},
     service::project_service::ProjectService,
     error::ApiError
 };
 
+// Secrets are only returned to callers holding secrets:reveal
+fn present(mut project: Project, caller: &Caller) -> Project {
+    if !caller.has_scope(Scope::SecretsReveal) {
+        project.mask_secrets();
+    }
+    project
+}
+
 pub async fn create_project(
+    auth: Authorized<ProjectsWrite>,
     State(service): State<ProjectService>,
     Json(project): Json<Project>,
 ) -> Result<Json<Project>, ApiError> {
-    let project = service.create_project(project).await?;
-    Ok(Json(project))
+    let project = service.create_project(&auth.caller.tenant()?, project).await?;
+    Ok(Json(present(project, &auth.caller)))
 }
 
 pub async fn update_project(
+    auth: Authorized<ProjectsWrite>,
     State(service): State<ProjectService>,
     Path(id): Path<String>,
     Json(project): Json<Project>,
 ) -> Result<Json<Project>, ApiError> {
     let object_id = ObjectId::parse_str(&id)
         .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
-    let project = service.update_project(&object_id, project).await?;
-    Ok(Json(project))
+    let project = service.update_project(&auth.caller.tenant()?, &object_id, project).await?;
+    Ok(Json(present(project, &auth.caller)))
 }
 
 pub async fn delete_project(
+    auth: Authorized<ProjectsWrite>,
     State(service): State<ProjectService>,
     Path(id): Path<String>,
 ) -> Result<Json<bool>, ApiError> {
     let object_id = ObjectId::parse_str(&id)
         .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
-    let result = service.delete_project(&object_id).await?;
+    let result = service.delete_project(&auth.caller.tenant()?, &object_id).await?;
     Ok(Json(result))
 }
 
 pub async fn get_project(
+    auth: Authorized<ProjectsRead>,
     State(service): State<ProjectService>,
     Path(id): Path<String>,
 ) -> Result<Json<Project>, ApiError> {
     let object_id = ObjectId::parse_str(&id)
         .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
-    let project = service.get_project(&object_id).await?;
-    Ok(Json(project))
+    let project = service.get_project(&auth.caller.tenant()?, &object_id).await?;
+    Ok(Json(present(project, &auth.caller)))
 }
 
 pub async fn get_all_projects(
+    auth: Authorized<ProjectsRead>,
     State(service): State<ProjectService>,
 ) -> Result<Json<Vec<Project>>, ApiError> {
-    let projects = service.get_all_projects().await?;
+    let projects = service.get_all_projects(&auth.caller.tenant()?).await?;
+    let projects = projects
+        .into_iter()
+        .map(|project| present(project, &auth.caller))
+        .collect();
     Ok(Json(projects))
-} 
\ No newline at end of file
+}
+
+pub async fn activate_project(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    id: Path<String>,
+    change: Option<Json<StatusChange>>,
+) -> Result<Json<Project>, ApiError> {
+    transition_project(auth, service, id, ProjectAction::Activate, change).await
+}
+
+pub async fn suspend_project(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    id: Path<String>,
+    change: Option<Json<StatusChange>>,
+) -> Result<Json<Project>, ApiError> {
+    transition_project(auth, service, id, ProjectAction::Suspend, change).await
+}
+
+pub async fn resume_project(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    id: Path<String>,
+    change: Option<Json<StatusChange>>,
+) -> Result<Json<Project>, ApiError> {
+    transition_project(auth, service, id, ProjectAction::Resume, change).await
+}
+
+pub async fn cancel_project(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    id: Path<String>,
+    change: Option<Json<StatusChange>>,
+) -> Result<Json<Project>, ApiError> {
+    transition_project(auth, service, id, ProjectAction::Cancel, change).await
+}
+
+// Expiring and renewing follow from `expires_at`, they have no endpoint
+async fn transition_project(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<ProjectService>,
+    Path(id): Path<String>,
+    action: ProjectAction,
+    change: Option<Json<StatusChange>>,
+) -> Result<Json<Project>, ApiError> {
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    let change = change.map(|Json(change)| change).unwrap_or_default();
+    let project = service.transition_project(&auth.caller.tenant()?, &object_id, action, change).await?;
+    Ok(Json(present(project, &auth.caller)))
+}
+
+pub async fn import_projects(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<ProjectService>,
+    Query(params): Query<ImportParams>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<(StatusCode, Json<ImportReport>), ApiError> {
+    let format = RecordFormat::of_request(&headers)?;
+    let rows = records::decode_body::<ProjectRecord>(format, body);
+    let report = service.import_projects(&auth.caller.tenant()?, rows, params.mode).await?;
+    Ok(records::import_response(report))
+}
+
+pub async fn export_projects(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<ProjectService>,
+    Query(params): Query<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let format = RecordFormat::of_export(params.format.as_deref(), &headers)?;
+    let projects = service.export_projects(&auth.caller.tenant()?).await?;
+    let caller = auth.caller;
+    let records = projects.map(move |project| project.map(|project| ProjectRecord::from(present(project, &caller))));
+    Ok(records::export_response(format, "projects", records))
+}
+
+fn present_results(results: Vec<BatchResult>, caller: &Caller) -> BatchResponse {
+    let results = results
+        .into_iter()
+        .map(|mut result| {
+            result.project = result.project.map(|project| present(project, caller));
+            result
+        })
+        .collect();
+    BatchResponse { results }
+}
+
+pub async fn batch_get_projects(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<ProjectService>,
+    Json(request): Json<BatchGetProjects>,
+) -> Result<Json<BatchResponse>, ApiError> {
+    let results = service.batch_get_projects(&auth.caller.tenant()?, request.ids).await?;
+    Ok(Json(present_results(results, &auth.caller)))
+}
+
+pub async fn batch_update_projects(
+    auth: Authorized<ProjectsWrite>,
+    State(service): State<ProjectService>,
+    Json(request): Json<BatchUpdateProjects>,
+) -> Result<Json<BatchResponse>, ApiError> {
+    let results = service.batch_update_projects(&auth.caller.tenant()?, request).await?;
+    Ok(Json(present_results(results, &auth.caller)))
+}
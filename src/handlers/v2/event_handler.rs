@@ -0,0 +1,24 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::sse::{Event, Sse},
+};
+use futures_util::Stream;
+use std::convert::Infallible;
+
+use crate::{
+    auth::{Authorized, ProjectsRead},
+    error::ApiError,
+    handlers::event_handler as v1,
+    models::{event::EventStreamQuery, project::ProjectV2},
+    service::event_stream_service::EventStreamService,
+};
+
+pub async fn stream_events(
+    auth: Authorized<ProjectsRead>,
+    State(service): State<EventStreamService>,
+    Query(query): Query<EventStreamQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    v1::stream_events_as::<ProjectV2>(auth, service, query, headers).await
+}
@@ -0,0 +1,2 @@
+pub mod event_handler;
+pub mod project_handler;
@@ -0,0 +1,125 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::{
+    auth::{Authorized, ProjectsRead, ProjectsWrite},
+    error::ApiError,
+    handlers::project_handler as v1,
+    models::{
+        batch::{BatchGetProjects, BatchResponse, BatchResult, BatchUpdateProjects},
+        project::{ProjectV2, StatusChange},
+    },
+    service::project_service::ProjectService,
+};
+
+// The v2 handlers run their v1 counterparts and only change the project's shape
+
+pub async fn create_project(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    Json(project): Json<ProjectV2>,
+) -> Result<Json<ProjectV2>, ApiError> {
+    let Json(project) = v1::create_project(auth, service, Json(project.into())).await?;
+    Ok(Json(project.into()))
+}
+
+pub async fn update_project(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    id: Path<String>,
+    Json(project): Json<ProjectV2>,
+) -> Result<Json<ProjectV2>, ApiError> {
+    let Json(project) = v1::update_project(auth, service, id, Json(project.into())).await?;
+    Ok(Json(project.into()))
+}
+
+pub async fn get_project(
+    auth: Authorized<ProjectsRead>,
+    service: State<ProjectService>,
+    id: Path<String>,
+) -> Result<Json<ProjectV2>, ApiError> {
+    let Json(project) = v1::get_project(auth, service, id).await?;
+    Ok(Json(project.into()))
+}
+
+pub async fn get_all_projects(
+    auth: Authorized<ProjectsRead>,
+    service: State<ProjectService>,
+) -> Result<Json<Vec<ProjectV2>>, ApiError> {
+    let Json(projects) = v1::get_all_projects(auth, service).await?;
+    Ok(Json(projects.into_iter().map(ProjectV2::from).collect()))
+}
+
+pub async fn activate_project(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    id: Path<String>,
+    change: Option<Json<StatusChange>>,
+) -> Result<Json<ProjectV2>, ApiError> {
+    let Json(project) = v1::activate_project(auth, service, id, change).await?;
+    Ok(Json(project.into()))
+}
+
+pub async fn suspend_project(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    id: Path<String>,
+    change: Option<Json<StatusChange>>,
+) -> Result<Json<ProjectV2>, ApiError> {
+    let Json(project) = v1::suspend_project(auth, service, id, change).await?;
+    Ok(Json(project.into()))
+}
+
+pub async fn resume_project(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    id: Path<String>,
+    change: Option<Json<StatusChange>>,
+) -> Result<Json<ProjectV2>, ApiError> {
+    let Json(project) = v1::resume_project(auth, service, id, change).await?;
+    Ok(Json(project.into()))
+}
+
+pub async fn cancel_project(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    id: Path<String>,
+    change: Option<Json<StatusChange>>,
+) -> Result<Json<ProjectV2>, ApiError> {
+    let Json(project) = v1::cancel_project(auth, service, id, change).await?;
+    Ok(Json(project.into()))
+}
+
+fn v2_results(response: BatchResponse) -> BatchResponse<ProjectV2> {
+    let results = response
+        .results
+        .into_iter()
+        .map(|result| BatchResult {
+            id: result.id,
+            status: result.status,
+            project: result.project.map(ProjectV2::from),
+            error: result.error,
+        })
+        .collect();
+    BatchResponse { results }
+}
+
+pub async fn batch_get_projects(
+    auth: Authorized<ProjectsRead>,
+    service: State<ProjectService>,
+    request: Json<BatchGetProjects>,
+) -> Result<Json<BatchResponse<ProjectV2>>, ApiError> {
+    let Json(response) = v1::batch_get_projects(auth, service, request).await?;
+    Ok(Json(v2_results(response)))
+}
+
+pub async fn batch_update_projects(
+    auth: Authorized<ProjectsWrite>,
+    service: State<ProjectService>,
+    request: Json<BatchUpdateProjects>,
+) -> Result<Json<BatchResponse<ProjectV2>>, ApiError> {
+    let Json(response) = v1::batch_update_projects(auth, service, request).await?;
+    Ok(Json(v2_results(response)))
+}
@@ -0,0 +1,82 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use mongodb::bson::oid::ObjectId;
+
+use crate::{
+    auth::{Authorized, WebhooksAdmin},
+    models::webhook::{CreateWebhook, CreatedWebhook, WebhookDelivery, WebhookSummary},
+    service::webhook_service::WebhookService,
+    error::ApiError,
+};
+
+pub async fn create_webhook(
+    auth: Authorized<WebhooksAdmin>,
+    State(service): State<WebhookService>,
+    Json(request): Json<CreateWebhook>,
+) -> Result<(StatusCode, Json<CreatedWebhook>), ApiError> {
+    let org_id = auth.caller.tenant()?;
+    let webhook = service.create_webhook(&org_id, request).await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+pub async fn get_all_webhooks(
+    auth: Authorized<WebhooksAdmin>,
+    State(service): State<WebhookService>,
+) -> Result<Json<Vec<WebhookSummary>>, ApiError> {
+    let org_id = auth.caller.tenant()?;
+    let webhooks = service.get_all_webhooks(&org_id).await?;
+    Ok(Json(webhooks))
+}
+
+pub async fn get_webhook(
+    auth: Authorized<WebhooksAdmin>,
+    State(service): State<WebhookService>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookSummary>, ApiError> {
+    let org_id = auth.caller.tenant()?;
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    let webhook = service.get_webhook(&org_id, &object_id).await?;
+    Ok(Json(webhook))
+}
+
+pub async fn delete_webhook(
+    auth: Authorized<WebhooksAdmin>,
+    State(service): State<WebhookService>,
+    Path(id): Path<String>,
+) -> Result<Json<bool>, ApiError> {
+    let org_id = auth.caller.tenant()?;
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    let result = service.delete_webhook(&org_id, &object_id).await?;
+    Ok(Json(result))
+}
+
+pub async fn get_deliveries(
+    auth: Authorized<WebhooksAdmin>,
+    State(service): State<WebhookService>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<WebhookDelivery>>, ApiError> {
+    let org_id = auth.caller.tenant()?;
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    let deliveries = service.get_deliveries(&org_id, &object_id).await?;
+    Ok(Json(deliveries))
+}
+
+pub async fn redeliver(
+    auth: Authorized<WebhooksAdmin>,
+    State(service): State<WebhookService>,
+    Path((id, delivery_id)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let org_id = auth.caller.tenant()?;
+    let object_id = ObjectId::parse_str(&id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    let delivery_id = ObjectId::parse_str(&delivery_id)
+        .map_err(|_| ApiError::BadRequest("Invalid ID format".to_string()))?;
+    service.redeliver(&org_id, &object_id, &delivery_id).await?;
+    Ok(StatusCode::ACCEPTED)
+}
@@ -0,0 +1,219 @@
+use axum::body::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    client::conn::http1,
+    header::{CONTENT_LENGTH, CONTENT_TYPE, HOST, USER_AGENT},
+    Method, Request, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName},
+    TlsConnector,
+};
+use url::Url;
+
+const CLIENT_USER_AGENT: &str = concat!("telegram-ton-api/", env!("CARGO_PKG_VERSION"));
+
+static TLS: LazyLock<TlsConnector> = LazyLock::new(|| {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+});
+
+// Responses larger than this are cut off instead of buffered
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+
+/// Minimal HTTP/1.1 client for outbound calls such as webhook deliveries.
+/// Every request uses a fresh connection.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    timeout: Duration,
+    allow_private_targets: bool,
+}
+
+impl HttpClient {
+    pub fn new(timeout: Duration, allow_private_targets: bool) -> Self {
+        Self {
+            timeout,
+            allow_private_targets,
+        }
+    }
+
+    /// Checks that `url` is something this client is willing to call.
+    pub fn validate_url(url: &str) -> Result<Url, String> {
+        let url = Url::parse(url).map_err(|e| format!("invalid URL: {}", e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("URL must use http or https".to_string());
+        }
+        if url.host_str().is_none() {
+            return Err("URL must have a host".to_string());
+        }
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err("URL must not contain credentials".to_string());
+        }
+        Ok(url)
+    }
+
+    /// POSTs a JSON body and returns the response status.
+    pub async fn post_json(
+        &self,
+        url: &str,
+        headers: &[(&str, String)],
+        body: Bytes,
+    ) -> Result<StatusCode, String> {
+        let (status, _) = self.request(Method::POST, url, headers, Some(body)).await?;
+        Ok(status)
+    }
+
+    /// GETs `url` and returns the response status and body.
+    pub async fn get(&self, url: &str) -> Result<(StatusCode, Bytes), String> {
+        self.request(Method::GET, url, &[], None).await
+    }
+
+    /// Sends a request with `body`, as JSON unless `headers` set a
+    /// `content-type`, and returns the response status and body.
+    pub async fn request(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(&str, String)],
+        body: Option<Bytes>,
+    ) -> Result<(StatusCode, Bytes), String> {
+        tokio::time::timeout(self.timeout, self.request_inner(method, url, headers, body))
+            .await
+            .map_err(|_| format!("timed out after {:?}", self.timeout))?
+    }
+
+    async fn request_inner(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(&str, String)],
+        body: Option<Bytes>,
+    ) -> Result<(StatusCode, Bytes), String> {
+        let url = Self::validate_url(url)?;
+        let host = url.host_str().expect("validated above").to_string();
+        let port = url.port_or_known_default().ok_or("URL has no port")?;
+        let addr = self.resolve(&host, port).await?;
+
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let host_header = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.clone(),
+        };
+        let mut request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(HOST, host_header)
+            .header(USER_AGENT, CLIENT_USER_AGENT);
+        if let Some(body) = &body {
+            request = request.header(CONTENT_LENGTH, body.len());
+            if !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str())) {
+                request = request.header(CONTENT_TYPE, "application/json");
+            }
+        }
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        let request = request
+            .body(Full::new(body.unwrap_or_default()))
+            .map_err(|e| e.to_string())?;
+
+        let tcp = TcpStream::connect(addr).await.map_err(|e| format!("connect failed: {}", e))?;
+        if url.scheme() == "https" {
+            let name = ServerName::try_from(host.trim_start_matches('[').trim_end_matches(']'))
+                .map_err(|_| format!("invalid TLS server name {:?}", host))?;
+            let tls = TLS
+                .connect(name, tcp)
+                .await
+                .map_err(|e| format!("TLS handshake failed: {}", e))?;
+            send(tls, request).await
+        } else {
+            send(tcp, request).await
+        }
+    }
+
+    async fn resolve(&self, host: &str, port: u16) -> Result<SocketAddr, String> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| format!("failed to resolve {}: {}", host, e))?
+            .collect();
+        // Check every address so DNS cannot point a public name at an internal one
+        if !self.allow_private_targets && addrs.iter().any(|addr| is_private(addr.ip())) {
+            return Err(format!("{} resolves to a private address", host));
+        }
+        addrs
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("{} did not resolve", host))
+    }
+}
+
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64)
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            ip.is_loopback()
+                || ip.is_unspecified()
+                // Unique local fc00::/7 and link local fe80::/10
+                || (segments[0] & 0xfe00) == 0xfc00
+                || (segments[0] & 0xffc0) == 0xfe80
+                || ip.to_ipv4_mapped().is_some_and(|v4| is_private(IpAddr::V4(v4)))
+        }
+    }
+}
+
+async fn send<S>(stream: S, request: Request<Full<Bytes>>) -> Result<(StatusCode, Bytes), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(|e| format!("HTTP handshake failed: {}", e))?;
+    let connection = tokio::spawn(connection);
+
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|e| format!("request failed: {}", e))?;
+    let status = response.status();
+    let body = http_body_util::Limited::new(response.into_body(), MAX_RESPONSE_BYTES)
+        .collect()
+        .await
+        .map(|body| body.to_bytes())
+        .map_err(|e| format!("failed to read response: {}", e))?;
+    connection.abort();
+    Ok((status, body))
+}
@@ -0,0 +1,185 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    app,
+    repository::outbox_repository::OutboxRepository,
+    test_support::{test_db, test_state, unreachable_db, BOOTSTRAP_KEY},
+};
+
+async fn send(app: &Router, method: &str, uri: &str, key: &str, content_type: Option<&str>, body: String) -> (StatusCode, String) {
+    let mut request = Request::builder().method(method).uri(uri).header("x-api-key", key);
+    if let Some(content_type) = content_type {
+        request = request.header("content-type", content_type);
+    }
+    let response = app.clone().oneshot(request.body(Body::from(body)).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+async fn org_key(app: &Router) -> String {
+    let (_, org) = send(app, "POST", "/organizations", BOOTSTRAP_KEY, Some("application/json"), json!({ "name": "Importers" }).to_string()).await;
+    let org: Value = serde_json::from_str(&org).unwrap();
+    let body = json!({
+        "name": "bulk",
+        "scopes": ["projects:read", "projects:write", "accounts:admin"],
+        "org_id": org["_id"]["$oid"],
+    });
+    let (status, created) = send(app, "POST", "/api-keys", BOOTSTRAP_KEY, Some("application/json"), body.to_string()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    serde_json::from_str::<Value>(&created).unwrap()["key"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_custom_methods_are_dispatched_on_their_verb() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+
+    // Known verbs reach their handler, which rejects the missing key first
+    for (method, uri) in [("POST", "/projects:import"), ("GET", "/projects:export"), ("GET", "/accounts:export"), ("POST", "/projects:batchGet"), ("POST", "/projects:batchUpdate")] {
+        let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::UNAUTHORIZED, "{} {}", method, uri);
+    }
+    for (method, uri) in [("GET", "/projects:import"), ("POST", "/accounts:frobnicate"), ("GET", "/projectsx"), ("GET", "/projects:batchGet"), ("POST", "/accounts:batchGet")] {
+        let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::NOT_FOUND, "{} {}", method, uri);
+    }
+}
+
+#[tokio::test]
+async fn test_import_export_round_trip() {
+    let db = test_db("_import_export").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let replica_set = OutboxRepository::new(db.clone()).is_replica_set().await.unwrap();
+    let app = app::router(test_state(db), CorsLayer::permissive());
+    let key = org_key(&app).await;
+    let csv = "name,expires_at,is_active\nFirst,2030-01-01T00:00:00Z,true\n,2030-01-01T00:00:00Z,true\n\"Third, quoted\",,false\n";
+
+    // One bad row rejects an atomic import as a whole
+    let (status, report) = send(&app, "POST", "/projects:import", &key, Some("text/csv"), csv.to_string()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let report: Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["total"], 3);
+    assert_eq!(report["imported"], 0);
+    assert_eq!(report["errors"], json!([{ "row": 2, "error": "Project name cannot be empty" }]));
+    let (_, listed) = send(&app, "GET", "/projects", &key, None, String::new()).await;
+    assert_eq!(listed, "[]");
+
+    let (status, report) = send(&app, "POST", "/projects:import?mode=best_effort", &key, Some("text/csv"), csv.to_string()).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: Value = serde_json::from_str(&report).unwrap();
+    assert_eq!((report["imported"].as_u64(), report["failed"].as_u64()), (Some(2), Some(1)));
+
+    let (status, exported) = send(&app, "GET", "/projects:export", &key, None, String::new()).await;
+    assert_eq!(status, StatusCode::OK);
+    let names: Vec<String> = exported
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap()["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, vec!["First", "Third, quoted"]);
+
+    let (_, exported) = send(&app, "GET", "/projects:export?format=csv", &key, None, String::new()).await;
+    let mut lines = exported.lines();
+    assert_eq!(lines.next(), Some("id,name,telegram_chat_id,expires_at,status,is_active,is_logging,package_name,package_description"));
+    assert!(lines.nth(1).unwrap().contains("\"Third, quoted\",,,draft,false,false,,"));
+
+    // Emails stay unique across existing accounts and within the file
+    let accounts = "{\"wallet_address\":\"EQ1\",\"email\":\"a@example.com\",\"account_name\":\"A\"}\n\
+                    {\"wallet_address\":\"EQ2\",\"email\":\"a@example.com\",\"account_name\":\"B\"}\n";
+    let (status, report) = send(&app, "POST", "/accounts:import?mode=best_effort", &key, Some("application/x-ndjson"), accounts.to_string()).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["errors"], json!([{ "row": 2, "error": "Email already exists" }]));
+    let (_, exported) = send(&app, "GET", "/accounts:export", &key, None, String::new()).await;
+    assert_eq!(exported.lines().count(), 1);
+
+    // A standalone server cannot write the rows of an atomic import together
+    let (status, report) = send(&app, "POST", "/projects:import", &key, Some("text/csv"), "name
+Fourth
+".to_string()).await;
+    match replica_set {
+        true => assert_eq!(status, StatusCode::OK, "{}", report),
+        false => assert_eq!(status, StatusCode::BAD_REQUEST, "{}", report),
+    }
+
+    let (status, _) = send(&app, "POST", "/projects:import", &key, Some("application/json"), "{}".to_string()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, "POST", "/projects:frobnicate", &key, None, String::new()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_batch_get_and_update_report_each_id() {
+    let db = test_db("_batch").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let app = app::router(test_state(db), CorsLayer::permissive());
+    let key = org_key(&app).await;
+
+    let mut ids = vec![];
+    for (name, is_active) in [("Live", true), ("Paused", false)] {
+        let body = json!({
+            "name": name,
+            "facebook_credentials": {},
+            "is_active": is_active,
+            "is_logging": false,
+            "created_at": { "$date": { "$numberLong": "0" } },
+            "updated_at": { "$date": { "$numberLong": "0" } },
+        });
+        let (status, created) = send(&app, "POST", "/projects", &key, Some("application/json"), body.to_string()).await;
+        assert_eq!(status, StatusCode::OK, "{}", created);
+        ids.push(serde_json::from_str::<Value>(&created).unwrap()["_id"]["$oid"].as_str().unwrap().to_string());
+    }
+    let missing = "65f000000000000000000000";
+
+    let body = json!({ "ids": [ids[1], "nope", missing, ids[0]] });
+    let (status, response) = send(&app, "POST", "/projects:batchGet", &key, Some("application/json"), body.to_string()).await;
+    assert_eq!(status, StatusCode::OK);
+    let results = serde_json::from_str::<Value>(&response).unwrap()["results"].clone();
+    let statuses: Vec<&str> = results.as_array().unwrap().iter().map(|r| r["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, vec!["found", "invalid_id", "not_found", "found"]);
+    assert_eq!(results[3]["project"]["name"], "Live");
+
+    let body = json!({ "ids": ids, "operation": { "type": "activate" } });
+    let (status, response) = send(&app, "POST", "/projects:batchUpdate", &key, Some("application/json"), body.to_string()).await;
+    assert_eq!(status, StatusCode::OK);
+    let results = serde_json::from_str::<Value>(&response).unwrap()["results"].clone();
+    assert_eq!((results[0]["status"].as_str(), results[1]["status"].as_str()), (Some("unchanged"), Some("updated")));
+    assert_eq!(results[1]["project"]["status"], "active");
+    assert_eq!(results[1]["project"]["is_active"], true);
+
+    let body = json!({ "ids": ids, "operation": { "type": "suspend", "reason": "non_payment" } });
+    let (_, response) = send(&app, "POST", "/projects:batchUpdate", &key, Some("application/json"), body.to_string()).await;
+    let results = serde_json::from_str::<Value>(&response).unwrap()["results"].clone();
+    assert_eq!(results[0]["project"]["status_history"][0]["reason"], "non_payment");
+
+    // Suspended projects are resumed, not activated
+    let body = json!({ "ids": ids, "operation": { "type": "activate" } });
+    let (_, response) = send(&app, "POST", "/projects:batchUpdate", &key, Some("application/json"), body.to_string()).await;
+    let results = serde_json::from_str::<Value>(&response).unwrap()["results"].clone();
+    assert_eq!(results[0]["status"], "conflict");
+    assert_eq!(results[0]["error"], "Cannot activate a suspended project");
+    let body = json!({ "ids": ids, "operation": { "type": "resume" } });
+    let (_, response) = send(&app, "POST", "/projects:batchUpdate", &key, Some("application/json"), body.to_string()).await;
+    let results = serde_json::from_str::<Value>(&response).unwrap()["results"].clone();
+    assert_eq!((results[0]["status"].as_str(), results[1]["status"].as_str()), (Some("updated"), Some("updated")));
+
+    let body = json!({ "ids": [ids[0]], "operation": { "type": "extend", "until": "2040-01-01T00:00:00Z" } });
+    let (_, response) = send(&app, "POST", "/projects:batchUpdate", &key, Some("application/json"), body.to_string()).await;
+    let results = serde_json::from_str::<Value>(&response).unwrap()["results"].clone();
+    assert_eq!(results[0]["status"], "updated");
+    assert!(results[0]["project"]["expires_at"].to_string().contains("2040"));
+
+    for operation in [json!({ "type": "cancel" }), json!({ "type": "extend" }), json!({ "type": "extend", "days": 0 }), json!({ "type": "extend", "until": "2000-01-01T00:00:00Z" })] {
+        let body = json!({ "ids": [ids[0]], "operation": operation });
+        let (status, _) = send(&app, "POST", "/projects:batchUpdate", &key, Some("application/json"), body.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", operation);
+    }
+    let (status, _) = send(&app, "POST", "/projects:batchGet", &key, Some("application/json"), json!({ "ids": [] }).to_string()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,137 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::sync::Arc;
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    app,
+    graph_client::{MockGraphClient, TokenInfo},
+    models::lead::LeadField,
+    test_support::{
+        test_db, test_lead_service, test_state, unreachable_db, RecordingNotifier, BOOTSTRAP_KEY, FACEBOOK_VERIFY_TOKEN,
+    },
+};
+
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+async fn notify(app: &Router, body: &str, signature: Option<String>) -> StatusCode {
+    let mut request = Request::builder()
+        .method("POST")
+        .uri("/facebook/webhook")
+        .header("content-type", "application/json");
+    if let Some(signature) = signature {
+        request = request.header("x-hub-signature-256", signature);
+    }
+    app.clone().oneshot(request.body(Body::from(body.to_string())).unwrap()).await.unwrap().status()
+}
+
+fn leadgen(page_id: &str, leadgen_ids: &[&str]) -> String {
+    let changes: Vec<Value> = leadgen_ids
+        .iter()
+        .map(|id| json!({ "field": "leadgen", "value": { "leadgen_id": id, "page_id": page_id, "form_id": "77" } }))
+        .collect();
+    json!({ "object": "page", "entry": [{ "id": page_id, "time": 0, "changes": changes }] }).to_string()
+}
+
+#[tokio::test]
+async fn test_webhook_subscription_is_verified() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+    let verify = |token: &str| {
+        let uri = format!("/facebook/webhook?hub.mode=subscribe&hub.verify_token={}&hub.challenge=1158201444", token);
+        app.clone().oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+    };
+
+    let response = verify(FACEBOOK_VERIFY_TOKEN).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(to_bytes(response.into_body(), usize::MAX).await.unwrap().as_ref(), b"1158201444");
+    assert_eq!(verify("guess").await.unwrap().status(), StatusCode::FORBIDDEN);
+
+    assert_eq!(notify(&app, "not json", None).await, StatusCode::BAD_REQUEST);
+    assert_eq!(notify(&app, r#"{"object":"user","entry":[]}"#, None).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_leads_are_forwarded_once_per_project() {
+    let db = test_db("_lead_ads").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let graph = MockGraphClient::new()
+        .with_token("page-token", TokenInfo { valid: true, expires_at: None, scopes: vec![], error: None })
+        .with_lead("1001", vec![LeadField { name: "email".to_string(), values: vec!["ann@example.com".to_string()] }]);
+    let notifier = RecordingNotifier::default();
+    let mut state = test_state(db.clone());
+    state.lead_service = test_lead_service(db, graph, Some(Arc::new(notifier.clone())));
+    let app = app::router(state, CorsLayer::permissive());
+
+    let send = |method: &str, uri: &str, org_id: Option<&str>, body: Option<Value>| {
+        let mut request = Request::builder().method(method).uri(uri).header("x-api-key", BOOTSTRAP_KEY);
+        if let Some(org_id) = org_id {
+            request = request.header("x-org-id", org_id);
+        }
+        let request = match body {
+            Some(body) => request.header("content-type", "application/json").body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        };
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request.unwrap()).await.unwrap();
+            let status = response.status();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&bytes).unwrap_or(Value::Null))
+        }
+    };
+    let (_, org) = send("POST", "/organizations", None, Some(json!({ "name": "Leads" }))).await;
+    let org = org["_id"]["$oid"].as_str().unwrap().to_string();
+    let project = json!({
+        "name": "Shop",
+        "telegram_chat_id": "-100",
+        "facebook_credentials": { "main": {
+            "app_id": "app", "app_secret": "app-secret", "access_token": "page-token",
+            "ad_account_id": "act_1", "account_suffix": "", "page_id": "555",
+        } },
+        "is_active": true,
+        "is_logging": false,
+        "created_at": { "$date": { "$numberLong": "0" } },
+        "updated_at": { "$date": { "$numberLong": "0" } },
+    });
+    let (status, created) = send("POST", "/projects", Some(&org), Some(project)).await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+    let leads_uri = format!("/projects/{}/leads", created["_id"]["$oid"].as_str().unwrap());
+
+    let body = leadgen("555", &["1001"]);
+    assert_eq!(notify(&app, &body, None).await, StatusCode::FORBIDDEN);
+    assert_eq!(notify(&app, &body, Some(sign("other-secret", &body))).await, StatusCode::FORBIDDEN);
+    assert_eq!(notify(&app, &body, Some(sign("app-secret", &body))).await, StatusCode::OK);
+    // Redeliveries are deduplicated by leadgen id
+    assert_eq!(notify(&app, &body, Some(sign("app-secret", &body))).await, StatusCode::OK);
+
+    let sent = notifier.sent.lock().unwrap().clone();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].0, "-100");
+    assert!(sent[0].1.contains("New lead for Shop") && sent[0].1.contains("email: ann@example.com"));
+
+    // A lead that cannot be fetched fails the delivery so Facebook retries it
+    let body = leadgen("555", &["1002"]);
+    assert_eq!(notify(&app, &body, Some(sign("app-secret", &body))).await, StatusCode::INTERNAL_SERVER_ERROR);
+    // Pages no project knows are acknowledged
+    let body = leadgen("999", &["1003"]);
+    assert_eq!(notify(&app, &body, Some(sign("app-secret", &body))).await, StatusCode::OK);
+
+    let (status, leads) = send("GET", &leads_uri, Some(&org), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let leads = leads.as_array().unwrap();
+    assert_eq!(leads.len(), 2);
+    let forwarded = leads.iter().find(|lead| lead["leadgen_id"] == "1001").unwrap();
+    assert_eq!(forwarded["fields"][0]["values"][0], "ann@example.com");
+    assert!(!forwarded["processed_at"].is_null());
+}
@@ -0,0 +1,59 @@
+pub mod ad_platform_client;
+pub mod app;
+pub mod auth;
+pub mod config;
+pub mod crypto;
+pub mod error;
+pub mod handlers;
+pub mod graph_client;
+pub mod http_client;
+pub mod models;
+pub mod records;
+pub mod repository;
+pub mod logger;
+pub mod metrics;
+pub mod middleware;
+pub mod migrations;
+pub mod service;
+pub mod shutdown;
+pub mod telegram_client;
+pub mod worker;
+// Router fixtures, also used by the client crate's tests
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+#[cfg(test)]
+mod api_versions_test;
+#[cfg(test)]
+mod auth_test;
+#[cfg(test)]
+mod config_test;
+#[cfg(test)]
+mod ad_credentials_test;
+#[cfg(test)]
+mod ad_platform_client_test;
+#[cfg(test)]
+mod crypto_test;
+#[cfg(test)]
+mod records_test;
+#[cfg(test)]
+mod event_stream_test;
+#[cfg(test)]
+mod facebook_credentials_test;
+#[cfg(test)]
+mod graph_client_test;
+#[cfg(test)]
+mod http_client_test;
+#[cfg(test)]
+mod import_export_test;
+#[cfg(test)]
+mod lead_ads_test;
+#[cfg(test)]
+mod links_test;
+#[cfg(test)]
+mod logger_test;
+#[cfg(test)]
+mod project_status_test;
+#[cfg(test)]
+mod shutdown_test;
+#[cfg(test)]
+mod tenant_isolation_test;
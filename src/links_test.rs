@@ -0,0 +1,133 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    app,
+    test_support::{test_db, test_state, unreachable_db, BOOTSTRAP_KEY, LINK_BASE_URL},
+};
+
+async fn send(app: &Router, method: &str, uri: &str, org_id: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri).header("x-api-key", BOOTSTRAP_KEY);
+    if let Some(org_id) = org_id {
+        request = request.header("x-org-id", org_id);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+async fn follow(app: &Router, uri: &str, referrer: Option<&str>) -> (StatusCode, Option<String>) {
+    let mut request = Request::builder().uri(uri).header(header::USER_AGENT, "test-agent");
+    if let Some(referrer) = referrer {
+        request = request.header(header::REFERER, referrer);
+    }
+    let response = app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    let location = response.headers().get(header::LOCATION).map(|value| value.to_str().unwrap().to_string());
+    (response.status(), location)
+}
+
+#[tokio::test]
+async fn test_link_routes_require_a_key() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+    let links = "/projects/65f000000000000000000000/links";
+    let link = format!("{}/65f000000000000000000001", links);
+    let stats = format!("{}/stats", link);
+
+    for (method, uri) in [("GET", links), ("POST", links), ("DELETE", &link), ("GET", &stats)] {
+        let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::UNAUTHORIZED, "{} {}", method, uri);
+    }
+
+    // Anything that cannot be a code is not looked up
+    assert_eq!(follow(&app, "/l/not-a-code", None).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_links_redirect_and_count_clicks() {
+    let db = test_db("_links").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let app = app::router(test_state(db), CorsLayer::permissive());
+    let (_, org) = send(&app, "POST", "/organizations", None, Some(json!({ "name": "Links" }))).await;
+    let org = org["_id"]["$oid"].as_str().unwrap().to_string();
+    let org_id = Some(org.as_str());
+
+    let project = json!({
+        "name": "Shop",
+        "ad_credentials": {
+            "main": {
+                "platform": "facebook",
+                "app_id": "app",
+                "app_secret": "secret",
+                "access_token": "token",
+                "ad_account_id": "act_1",
+                "account_suffix": "eu-1",
+                "link_url": "https://shop.example.com/landing?ref=ads",
+            },
+            "bare": {
+                "platform": "facebook",
+                "app_id": "app",
+                "app_secret": "secret",
+                "access_token": "token",
+                "ad_account_id": "act_2",
+                "account_suffix": "",
+            },
+        },
+        "is_active": true,
+        "is_logging": false,
+        "created_at": { "$date": { "$numberLong": "0" } },
+        "updated_at": { "$date": { "$numberLong": "0" } },
+    });
+    let (status, created) = send(&app, "POST", "/projects", org_id, Some(project)).await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+    let uri = format!("/projects/{}/links", created["_id"]["$oid"].as_str().unwrap());
+
+    let (status, link) = send(&app, "POST", &uri, org_id, Some(json!({ "credential_key": "main", "campaign": "spring" }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", link);
+    let code = link["code"].as_str().unwrap().to_string();
+    assert_eq!(link["short_url"], format!("{}/l/{}", LINK_BASE_URL, code));
+    assert_eq!(
+        link["destination"],
+        "https://shop.example.com/landing?ref=ads&utm_source=facebook&utm_medium=paid_social&utm_campaign=spring&utm_content=eu-1"
+    );
+    for credential_key in ["bare", "missing"] {
+        let body = json!({ "credential_key": credential_key, "campaign": "spring" });
+        let (status, _) = send(&app, "POST", &uri, org_id, Some(body)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", credential_key);
+    }
+
+    let (status, location) = follow(&app, &format!("/l/{}?fbclid=IwAR0abc", code), Some("https://m.facebook.com/")).await;
+    assert_eq!(status, StatusCode::FOUND);
+    assert!(location.unwrap().ends_with("&utm_content=eu-1&fbclid=IwAR0abc"));
+    follow(&app, &format!("/l/{}", code), Some("https://m.facebook.com/")).await;
+    follow(&app, &format!("/l/{}", code), None).await;
+    let missing = if code == "AAAAAAAA" { "BBBBBBBB" } else { "AAAAAAAA" };
+    assert_eq!(follow(&app, &format!("/l/{}", missing), None).await.0, StatusCode::NOT_FOUND);
+
+    let link_uri = format!("{}/{}", uri, link["_id"]["$oid"].as_str().unwrap());
+    let (status, stats) = send(&app, "GET", &format!("{}/stats", link_uri), org_id, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", stats);
+    assert_eq!((stats["clicks"].as_u64(), stats["fbclid_clicks"].as_u64()), (Some(3), Some(1)));
+    assert_eq!(stats["by_day"].as_array().unwrap().len(), 1);
+    assert_eq!(stats["by_day"][0]["clicks"], 3);
+    assert_eq!(stats["top_referrers"], json!([{ "referrer": "https://m.facebook.com/", "clicks": 2 }]));
+
+    let (_, listed) = send(&app, "GET", &uri, org_id, None).await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    let (_, deleted) = send(&app, "DELETE", &link_uri, org_id, None).await;
+    assert_eq!(deleted, json!(true));
+    assert_eq!(follow(&app, &format!("/l/{}", code), None).await.0, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,70 @@
+use crate::logger::Redactor;
+
+#[test]
+fn test_known_secrets_are_redacted() {
+    let redactor = Redactor::new(vec!["super-secret-value".to_string(), "abc".to_string()]);
+
+    let line = redactor.redact("connecting with super-secret-value to abc");
+
+    assert_eq!(line, "connecting with [REDACTED] to abc");
+}
+
+#[test]
+fn test_secret_fields_are_redacted_in_every_format() {
+    let redactor = Redactor::new(Vec::new());
+
+    let debug = redactor.redact(r#"FacebookCredential { app_id: "1", app_secret: "s3cr3t", access_token: "EAAB123" }"#);
+    assert!(!debug.contains("s3cr3t"));
+    assert!(!debug.contains("EAAB123"));
+    assert!(debug.contains(r#"app_id: "1""#));
+
+    let json = redactor.redact(r#"{"fields":{"message":"sent","access_token":"EAAB123","app_id":"1"}}"#);
+    assert_eq!(json, r#"{"fields":{"message":"sent","access_token":"[REDACTED]","app_id":"1"}}"#);
+
+    let query = redactor.redact("GET /debug_token?input_token=abc&access_token=EAAB123&fields=id");
+    assert!(!query.contains("EAAB123"));
+    assert!(query.contains("fields=id"));
+
+    let header = redactor.redact("authorization: Bearer eyJhbGciOi");
+    assert!(!header.contains("eyJhbGciOi"));
+}
+
+#[test]
+fn test_fields_only_named_like_secrets_are_kept() {
+    let redactor = Redactor::new(Vec::new());
+
+    let line = redactor.redact(
+        r#"checked token_health="expiring" next_page_token=CAoQAA refresh_token=1//0g developer_token: "dev-123""#,
+    );
+
+    assert!(line.contains(r#"token_health="expiring""#));
+    assert!(line.contains("next_page_token=CAoQAA"));
+    assert!(!line.contains("1//0g"));
+    assert!(!line.contains("dev-123"));
+
+    let json = redactor.redact(r#"{"token_health":{"status":"valid"},"nextPageToken":"CAoQAA","token":"abc"}"#);
+    assert_eq!(json, r#"{"token_health":{"status":"valid"},"nextPageToken":"CAoQAA","token":"[REDACTED]"}"#);
+}
+
+#[test]
+fn test_ansi_colored_fields_are_redacted() {
+    let redactor = Redactor::new(Vec::new());
+
+    let line = redactor.redact("\x1b[3mapp_secret\x1b[0m\x1b[2m=\x1b[0m\"s3cr3t\" \x1b[3mproject\x1b[0m=1");
+
+    assert!(!line.contains("s3cr3t"));
+    assert!(line.contains("project"));
+}
+
+#[test]
+fn test_uri_credentials_and_bot_tokens_are_redacted() {
+    let redactor = Redactor::new(Vec::new());
+
+    let line = redactor.redact(
+        "mongodb://admin:hunter2@db:27017 bot 123456789:AAHdqTcvCH1vGWJxfSeofSAs0K5PALDsaw1",
+    );
+
+    assert!(!line.contains("hunter2"));
+    assert!(!line.contains("AAHdqTcvCH1vGWJxfSeofSAs0K5PALDsaw1"));
+    assert!(line.contains("mongodb://[REDACTED]@db:27017"));
+}
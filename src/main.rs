@@ -1,80 +1,340 @@
-mod error;
-mod handlers;
-mod models;
-mod repository;
-mod logger;
-mod service;
-
-use log::info;
-use axum::{
-    routing::{get, post, delete, put},
-    Router,
-};
+use tracing::{error, info, warn};
+use clap::Parser;
 use dotenv::dotenv;
-use mongodb::{Client, Database};
-use std::env;
-use tower_http::cors::CorsLayer;
+use mongodb::Database;
+use tokio_util::sync::CancellationToken;
 
-use crate::handlers::project_handler::{
-    create_project, delete_project, get_all_projects, get_project, update_project,
-};
-use crate::handlers::account_handler::{
-    create_account, delete_account, get_all_accounts, get_account, update_account,
+use std::{sync::Arc, time::Duration};
+
+use telegram_ton_api::{app, logger, repository, shutdown};
+use telegram_ton_api::app::AppState;
+use telegram_ton_api::config::{CliArgs, Command, Config};
+use telegram_ton_api::crypto::SecretCipher;
+use telegram_ton_api::middleware::rate_limit::RateLimiter;
+use telegram_ton_api::migrations::Migrator;
+use telegram_ton_api::repository::project_repository::ProjectRepository;
+use telegram_ton_api::repository::account_repository::AccountRepository;
+use telegram_ton_api::repository::api_key_repository::ApiKeyRepository;
+use telegram_ton_api::repository::organization_repository::OrganizationRepository;
+use telegram_ton_api::repository::idempotency_repository::IdempotencyRepository;
+use telegram_ton_api::repository::webhook_repository::WebhookRepository;
+use telegram_ton_api::repository::webhook_delivery_repository::WebhookDeliveryRepository;
+use telegram_ton_api::repository::outbox_repository::OutboxRepository;
+use telegram_ton_api::repository::event_id_repository::EventIdRepository;
+use telegram_ton_api::repository::lead_repository::LeadRepository;
+use telegram_ton_api::repository::link_repository::LinkRepository;
+use telegram_ton_api::service::project_service::ProjectService;
+use telegram_ton_api::service::account_service::AccountService;
+use telegram_ton_api::service::api_key_service::ApiKeyService;
+use telegram_ton_api::service::organization_service::OrganizationService;
+use telegram_ton_api::service::idempotency_service::IdempotencyService;
+use telegram_ton_api::service::webhook_service::WebhookService;
+use telegram_ton_api::service::event_bus::EventBus;
+use telegram_ton_api::service::event_stream_service::EventStreamService;
+use telegram_ton_api::service::health_service::HealthService;
+use telegram_ton_api::service::token_health_service::TokenHealthService;
+use telegram_ton_api::service::lead_service::LeadService;
+use telegram_ton_api::service::link_service::LinkService;
+use telegram_ton_api::graph_client::{GraphClient, HttpGraphClient};
+use telegram_ton_api::ad_platform_client::HttpAdPlatformClient;
+use telegram_ton_api::service::ad_platform_service::AdPlatformService;
+use telegram_ton_api::telegram_client::{HttpTelegramClient, TelegramNotifier};
+use telegram_ton_api::worker::{
+    outbox_dispatch_worker, project_expiry_worker, token_health_worker, webhook_delivery_worker, Workers,
 };
-use crate::repository::project_repository::ProjectRepository;
-use crate::repository::account_repository::AccountRepository;
-use crate::service::project_service::ProjectService;
-use crate::service::account_service::AccountService;
-
-async fn create_db_client() -> Database {
-    let mongodb_uri = env::var("MONGODB_URL").expect("MONGODB_URL must be set");
-    let database_name = env::var("DATABASE_NAME").expect("DATABASE_NAME must be set");
-    
-    let client = Client::with_uri_str(mongodb_uri)
-        .await
-        .expect("Failed to create MongoDB client");
-    
-    client.database(&database_name)
+
+const INDEX_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const WEBHOOK_DELIVERY_INTERVAL: Duration = Duration::from_secs(5);
+const OUTBOX_DISPATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Applies pending migrations for the `migrate` subcommand and returns the exit code.
+async fn run_migrations(db: Database, dry_run: bool) -> i32 {
+    match Migrator::new(db).run(dry_run).await {
+        Ok(outcomes) if outcomes.is_empty() => {
+            println!("No pending migrations");
+            0
+        }
+        Ok(outcomes) => {
+            let verb = if dry_run { "would change" } else { "changed" };
+            for outcome in outcomes {
+                println!("{:04} {} {} {} document(s)", outcome.version, outcome.name, verb, outcome.affected);
+            }
+            0
+        }
+        Err(e) => {
+            error!(error = %e, "Migration failed");
+            1
+        }
+    }
+}
+
+/// Applies pending migrations before the server starts, retrying until they
+/// succeed. Returns false when shutdown was requested first.
+async fn migrate_on_startup(migrator: Migrator, token: &CancellationToken) -> bool {
+    loop {
+        let result = tokio::select! {
+            _ = token.cancelled() => return false,
+            result = migrator.run(false) => result,
+        };
+        match result {
+            Ok(outcomes) => {
+                info!(applied = outcomes.len(), "Database migrations applied");
+                return true;
+            }
+            Err(e) => error!(error = %e, "Failed to migrate database"),
+        }
+        tokio::select! {
+            _ = token.cancelled() => return false,
+            _ = tokio::time::sleep(INDEX_RETRY_INTERVAL) => {}
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() {
-    logger::init_logger();
     dotenv().ok();
-    info!("Environment variables loaded");
-    
-    let db = create_db_client().await;
+    let args = CliArgs::parse();
+    let config = match Config::load(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    logger::init_logger(&config.log, logger::Redactor::new(config.secrets()));
+    info!("Configuration loaded");
+    if config.telegram.bot_token.is_none() {
+        info!("TELEGRAM_BOT_TOKEN not set, Telegram notifications are disabled");
+    }
+    if !config.encryption.keys.is_empty() {
+        let key_ids: Vec<&str> = config.encryption.keys.iter().map(|k| k.id.as_str()).collect();
+        info!(
+            ?key_ids,
+            active = config.encryption.active_key_id.as_deref().unwrap_or("none"),
+            "Encryption keys loaded"
+        );
+    }
+
+    let client = match repository::connect(&config.mongo).await {
+        Ok(client) => client,
+        Err(e) => {
+            error!(error = %e, "Failed to create MongoDB client");
+            std::process::exit(1);
+        }
+    };
+    let db: Database = client.database(&config.mongo.database);
     info!("Database connection established");
+
+    if let Some(Command::Migrate { dry_run }) = args.command {
+        std::process::exit(run_migrations(db, dry_run).await);
+    }
     
-    let project_repository = ProjectRepository::new(db.clone());
-    let project_service = ProjectService::new(project_repository);
+    let cipher = SecretCipher::new(&config.encryption);
+    let project_repository = ProjectRepository::new(db.clone()).with_cipher(cipher.clone());
+    let outbox_repository = OutboxRepository::new(db.clone());
+
+    let webhook_repository = WebhookRepository::new(db.clone()).with_cipher(cipher);
+    let webhook_delivery_repository = WebhookDeliveryRepository::new(db.clone());
+    let webhook_service = WebhookService::new(
+        webhook_repository.clone(),
+        webhook_delivery_repository.clone(),
+        project_repository.clone(),
+        &config.webhooks,
+    );
+
+    let shutdown_token = CancellationToken::new();
+    let signal_token = shutdown_token.clone();
+    tokio::spawn(async move {
+        shutdown::shutdown_signal().await;
+        signal_token.cancel();
+    });
+
+    // Handlers and workers expect the current schema, so nothing runs before it
+    if config.migrations.run_on_startup && !migrate_on_startup(Migrator::new(db.clone()), &shutdown_token).await {
+        client.shutdown().await;
+        info!("Shutdown before migrations completed");
+        return;
+    }
+
+    let event_stream_service = EventStreamService::new(outbox_repository.clone(), shutdown_token.clone());
+    let event_bus = EventBus::new(outbox_repository.clone())
+        .subscribe(Arc::new(webhook_service.clone()))
+        .subscribe(Arc::new(event_stream_service.clone()));
+
+    let project_service = ProjectService::new(project_repository.clone(), outbox_repository.clone());
+    let telegram = config
+        .telegram
+        .bot_token
+        .clone()
+        .map(|token| Arc::new(HttpTelegramClient::new(token)) as Arc<dyn TelegramNotifier>);
+    let graph: Arc<dyn GraphClient> = Arc::new(HttpGraphClient::new(&config.facebook.graph_api_url));
+    let token_health_service = TokenHealthService::new(
+        project_repository.clone(),
+        graph.clone(),
+        telegram.clone(),
+        &config.facebook,
+    );
+    let lead_repository = LeadRepository::new(db.clone());
+    let lead_service = LeadService::new(
+        lead_repository.clone(),
+        project_repository.clone(),
+        graph,
+        telegram,
+        &config.facebook,
+    );
+    let link_repository = LinkRepository::new(db.clone());
+    let link_service = LinkService::new(link_repository.clone(), project_repository.clone(), &config.links);
+    let event_id_repository = EventIdRepository::new(db.clone());
+    let ad_platform_service = AdPlatformService::new(
+        project_repository.clone(),
+        event_id_repository.clone(),
+        Arc::new(HttpAdPlatformClient::new(&config.facebook.graph_api_url, &config.ad_platforms)),
+        config.ad_platforms.event_dedup_window,
+    );
     
     let account_repository = AccountRepository::new(db.clone());
-    let account_service = AccountService::new(account_repository);
-
-    let cors = CorsLayer::permissive();
-
-    let project_routes = Router::new()
-        .route("/projects", post(create_project))
-        .route("/projects", get(get_all_projects))
-        .route("/projects/:id", get(get_project))
-        .route("/projects/:id", put(update_project))
-        .route("/projects/:id", delete(delete_project))
-        .with_state(project_service);
-
-    let account_routes = Router::new()
-        .route("/accounts", post(create_account))
-        .route("/accounts", get(get_all_accounts))
-        .route("/accounts/:id", get(get_account))
-        .route("/accounts/:id", put(update_account))
-        .route("/accounts/:id", delete(delete_account))
-        .with_state(account_service);
-
-    let app = project_routes
-        .merge(account_routes)
-        .layer(cors);
-
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    info!("Server running on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app).await.unwrap();
+    let organization_repository = OrganizationRepository::new(db.clone());
+    let account_service = AccountService::new(
+        account_repository.clone(),
+        project_repository.clone(),
+        organization_repository.clone(),
+        outbox_repository.clone(),
+    );
+
+    let organization_service = OrganizationService::new(
+        organization_repository.clone(),
+        account_repository.clone(),
+        outbox_repository.clone(),
+    );
+
+    let api_key_repository = ApiKeyRepository::new(db.clone());
+    let api_key_service = ApiKeyService::new(
+        api_key_repository.clone(),
+        organization_repository.clone(),
+        config.auth.bootstrap_api_key.as_ref(),
+    );
+
+    let idempotency_repository = IdempotencyRepository::new(db.clone());
+    let idempotency_service = IdempotencyService::new(
+        idempotency_repository.clone(),
+        config.idempotency.ttl,
+    );
+
+    let workers = Workers::new(shutdown_token.clone());
+    let health_service = HealthService::new(db.clone(), workers.clone());
+
+    // Readiness stays down until the indexes exist, so keep retrying in the background
+    let index_health = health_service.clone();
+    let index_token = shutdown_token.clone();
+    tokio::spawn(async move {
+        loop {
+            let ensure = async {
+                // Detected once up front so a standalone server is warned about at startup
+                outbox_repository.is_replica_set().await?;
+                project_repository.ensure_indexes().await?;
+                account_repository.ensure_indexes().await?;
+                organization_repository.ensure_indexes().await?;
+                idempotency_repository.ensure_indexes().await?;
+                webhook_repository.ensure_indexes().await?;
+                webhook_delivery_repository.ensure_indexes().await?;
+                outbox_repository.ensure_indexes().await?;
+                lead_repository.ensure_indexes().await?;
+                link_repository.ensure_indexes().await?;
+                event_id_repository.ensure_indexes().await?;
+                api_key_repository.ensure_indexes().await
+            };
+            let result = tokio::select! {
+                _ = index_token.cancelled() => break,
+                result = ensure => result,
+            };
+            match result {
+                Ok(()) => {
+                    info!("Database indexes ensured");
+                    index_health.mark_indexes_ready();
+                    break;
+                }
+                Err(e) => error!(error = %e, "Failed to ensure database indexes"),
+            }
+            tokio::select! {
+                _ = index_token.cancelled() => break,
+                _ = tokio::time::sleep(INDEX_RETRY_INTERVAL) => {}
+            }
+        }
+    });
+
+    if !config.rate_limit.enabled {
+        warn!("Rate limiting is disabled");
+    }
+    if config.auth.bootstrap_api_key.is_none() {
+        warn!("BOOTSTRAP_API_KEY not set, API keys can only be minted by existing keys");
+    }
+
+    let expiry_service = project_service.clone();
+    let expiry_interval = config.workers.expiry_check_interval;
+    workers.spawn("project_expiry", move |token| {
+        project_expiry_worker::run(expiry_service, expiry_interval, token)
+    });
+
+    let token_check_interval = config.workers.token_check_interval;
+    workers.spawn("token_health", move |token| {
+        token_health_worker::run(token_health_service, token_check_interval, token)
+    });
+
+    workers.spawn("outbox_dispatch", move |token| {
+        outbox_dispatch_worker::run(event_bus, OUTBOX_DISPATCH_INTERVAL, token)
+    });
+
+    let delivery_service = webhook_service.clone();
+    workers.spawn("webhook_delivery", move |token| {
+        webhook_delivery_worker::run(delivery_service, WEBHOOK_DELIVERY_INTERVAL, token)
+    });
+
+    let rate_limiter = RateLimiter::in_memory(config.rate_limit.clone()).with_keys(api_key_service.clone());
+    let state = AppState {
+        project_service,
+        account_service,
+        api_key_service,
+        organization_service,
+        health_service,
+        rate_limiter,
+        idempotency_service,
+        webhook_service,
+        lead_service,
+        ad_platform_service,
+        link_service,
+        event_stream_service,
+        api: config.api.clone(),
+    };
+    let app = app::router(state, app::cors_layer(&config));
+
+    let listener = tokio::net::TcpListener::bind(config.server.bind_addr).await.unwrap();
+    info!(addr = %listener.local_addr().unwrap(), "Server running");
+
+    let deadline = tokio::spawn(shutdown::shutdown_deadline(
+        shutdown_token.clone(),
+        config.server.shutdown_timeout,
+    ));
+
+    if let Err(e) = shutdown::serve_with_shutdown(
+        listener,
+        app,
+        shutdown_token.clone(),
+        config.server.shutdown_timeout,
+    )
+    .await
+    {
+        error!(error = %e, "Server error");
+    }
+
+    // Also reached when the server failed without a shutdown signal
+    shutdown_token.cancel();
+    let deadline = deadline.await.expect("deadline task never panics");
+    if !workers.shutdown(deadline).await {
+        warn!("Some background workers were aborted");
+    }
+    if tokio::time::timeout_at(deadline, client.shutdown()).await.is_err() {
+        warn!("MongoDB client did not shut down in time");
+    }
+    info!("Shutdown complete");
 }
\ No newline at end of file
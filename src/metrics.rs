@@ -0,0 +1,147 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::{future::Future, sync::LazyLock, time::Instant};
+use tracing::{debug, info_span, warn, Instrument};
+
+use crate::error::ApiError;
+
+const HTTP_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+const DB_BUCKETS: &[f64] = &[0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests_total: IntCounterVec,
+    pub http_request_duration_seconds: HistogramVec,
+    pub db_operation_duration_seconds: HistogramVec,
+    pub projects: IntGaugeVec,
+    pub background_jobs_total: IntCounterVec,
+    pub rate_limited_total: IntCounterVec,
+    pub webhook_deliveries_total: IntCounterVec,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub fn global() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("tonapi".to_string()), None)
+            .expect("valid metrics prefix");
+
+        let http_requests_total = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric");
+        let http_request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency")
+                .buckets(HTTP_BUCKETS.to_vec()),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric");
+        let db_operation_duration_seconds = HistogramVec::new(
+            HistogramOpts::new("db_operation_duration_seconds", "MongoDB operation latency by repository method")
+                .buckets(DB_BUCKETS.to_vec()),
+            &["collection", "operation", "outcome"],
+        )
+        .expect("valid metric");
+        let projects = IntGaugeVec::new(
+            Opts::new("projects", "Number of projects by state"),
+            &["state"],
+        )
+        .expect("valid metric");
+        let background_jobs_total = IntCounterVec::new(
+            Opts::new("background_jobs_total", "Background job runs by outcome"),
+            &["job", "outcome"],
+        )
+        .expect("valid metric");
+        let rate_limited_total = IntCounterVec::new(
+            Opts::new("rate_limited_total", "Requests rejected by the rate limiter by route group"),
+            &["group"],
+        )
+        .expect("valid metric");
+        let webhook_deliveries_total = IntCounterVec::new(
+            Opts::new("webhook_deliveries_total", "Webhook delivery attempts by outcome"),
+            &["outcome"],
+        )
+        .expect("valid metric");
+
+        for collector in [
+            Box::new(http_requests_total.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_request_duration_seconds.clone()),
+            Box::new(db_operation_duration_seconds.clone()),
+            Box::new(projects.clone()),
+            Box::new(background_jobs_total.clone()),
+            Box::new(rate_limited_total.clone()),
+            Box::new(webhook_deliveries_total.clone()),
+        ] {
+            registry.register(collector).expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            http_requests_total,
+            http_request_duration_seconds,
+            db_operation_duration_seconds,
+            projects,
+            background_jobs_total,
+            rate_limited_total,
+            webhook_deliveries_total,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding never fails");
+        String::from_utf8(buffer).expect("metrics are valid utf-8")
+    }
+
+    pub fn record_job(&self, job: &str, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.background_jobs_total.with_label_values(&[job, outcome]).inc();
+    }
+
+    pub fn record_webhook_delivery(&self, outcome: &str) {
+        self.webhook_deliveries_total.with_label_values(&[outcome]).inc();
+    }
+
+    pub fn set_project_counts(&self, active: u64, expired: u64) {
+        self.projects.with_label_values(&["active"]).set(active as i64);
+        self.projects.with_label_values(&["expired"]).set(expired as i64);
+    }
+}
+
+/// Runs a repository call inside a child span, timing it under `collection`
+/// and `operation`.
+pub async fn track_db<T, F>(collection: &str, operation: &str, fut: F) -> Result<T, ApiError>
+where
+    F: Future<Output = Result<T, ApiError>>,
+{
+    let span = info_span!("db", collection, operation);
+    let start = Instant::now();
+    let result = fut.instrument(span.clone()).await;
+    let elapsed = start.elapsed();
+    let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+
+    // A missing document is a normal answer, not a database failure
+    let outcome = match &result {
+        Ok(_) | Err(ApiError::NotFound) => {
+            span.in_scope(|| debug!(elapsed_ms, "Database operation completed"));
+            "success"
+        }
+        Err(e) => {
+            span.in_scope(|| warn!(elapsed_ms, error = %e, "Database operation failed"));
+            "error"
+        }
+    };
+    global()
+        .db_operation_duration_seconds
+        .with_label_values(&[collection, operation, outcome])
+        .observe(elapsed.as_secs_f64());
+    result
+}
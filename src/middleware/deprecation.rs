@@ -0,0 +1,73 @@
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{HeaderName, LINK},
+        HeaderValue,
+    },
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tracing::warn;
+
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+pub const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+/// A deprecated API version, announced on each of its responses.
+#[derive(Debug, Clone)]
+pub struct Deprecation {
+    // How the version is named in logs, e.g. `unversioned`
+    pub version: &'static str,
+    pub deprecated_at: DateTime<Utc>,
+    // When the version stops being served
+    pub sunset: DateTime<Utc>,
+    // Path prefix of the version replacing it, e.g. `/v1`
+    pub successor: &'static str,
+}
+
+impl Deprecation {
+    /// `Deprecation` header value, a structured field date (RFC 9745).
+    pub fn deprecation_value(&self) -> String {
+        format!("@{}", self.deprecated_at.timestamp())
+    }
+
+    /// `Sunset` header value, an HTTP-date (RFC 8594).
+    pub fn sunset_value(&self) -> String {
+        self.sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+    }
+
+    /// `Link` to the same resource in the successor version.
+    pub fn successor_link(&self, path: &str) -> String {
+        format!("<{}{}>; rel=\"successor-version\"", self.successor, path)
+    }
+}
+
+/// Marks responses of a deprecated version with `Deprecation`, `Sunset` and a
+/// successor `Link`, and logs each call so remaining callers can be found.
+pub async fn deprecated(State(deprecation): State<Arc<Deprecation>>, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    let mut response = next.run(request).await;
+
+    warn!(
+        version = deprecation.version,
+        %method,
+        path = %path,
+        status = response.status().as_u16(),
+        "Deprecated API version called"
+    );
+    let headers = response.headers_mut();
+    let values = [
+        (DEPRECATION_HEADER, deprecation.deprecation_value()),
+        (SUNSET_HEADER, deprecation.sunset_value()),
+        (LINK, deprecation.successor_link(&path)),
+    ];
+    for (name, value) in values {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.append(name, value);
+        }
+    }
+    response
+}
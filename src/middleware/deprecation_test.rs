@@ -0,0 +1,40 @@
+use axum::{
+    body::Body,
+    http::{header::LINK, Request},
+    middleware::from_fn_with_state,
+    routing::get,
+    Router,
+};
+use chrono::{TimeZone, Utc};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+use crate::middleware::deprecation::{deprecated, Deprecation, DEPRECATION_HEADER, SUNSET_HEADER};
+
+fn deprecation() -> Deprecation {
+    Deprecation {
+        version: "unversioned",
+        deprecated_at: Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap(),
+        sunset: Utc.with_ymd_and_hms(2027, 4, 1, 0, 0, 0).unwrap(),
+        successor: "/v1",
+    }
+}
+
+fn app(deprecation: Deprecation) -> Router {
+    Router::new()
+        .route("/things/:id", get(|| async { "thing" }))
+        .layer(from_fn_with_state(Arc::new(deprecation), deprecated))
+}
+
+#[tokio::test]
+async fn test_deprecated_responses_announce_the_successor() {
+    let response = app(deprecation())
+        .oneshot(Request::get("/things/42").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    let headers = response.headers();
+    assert_eq!(headers[DEPRECATION_HEADER], "@1792368000");
+    assert_eq!(headers[SUNSET_HEADER], "Thu, 01 Apr 2027 00:00:00 GMT");
+    assert_eq!(headers[LINK], "</v1/things/42>; rel=\"successor-version\"");
+}
@@ -0,0 +1,123 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header::CONTENT_TYPE, request::Parts, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::{
+    auth::{api_key_from_headers, ORG_ID_HEADER},
+    error::ApiError,
+    models::idempotency::IdempotencyRecord,
+    service::idempotency_service::{IdempotencyService, IdempotencyStart},
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_KEY_LEN: usize = 255;
+// Matches the default body limit of axum's extractors
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+// Keys are scoped to the caller and route, so two callers picking the same
+// key can never see each other's responses
+fn record_id(parts: &Parts, key: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        api_key_from_headers(&parts.headers).unwrap_or("").trim(),
+        parts
+            .headers
+            .get(ORG_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or(""),
+        parts.method.as_str(),
+        parts.uri.path(),
+        key,
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn replay(record: IdempotencyRecord) -> Response {
+    let status = record
+        .status
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .unwrap_or(StatusCode::OK);
+    let mut response = (status, record.body.unwrap_or_default()).into_response();
+    let headers = response.headers_mut();
+    headers.remove(CONTENT_TYPE);
+    if let Some(value) = record.content_type.and_then(|ct| HeaderValue::from_str(&ct).ok()) {
+        headers.insert(CONTENT_TYPE, value);
+    }
+    headers.insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+/// Runs a request carrying an `Idempotency-Key` once, replaying the stored
+/// response for retries with the same key and body.
+pub async fn idempotent(State(service): State<IdempotencyService>, request: Request, next: Next) -> Response {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.trim().is_empty() && key.len() <= MAX_KEY_LEN => key.trim().to_string(),
+        _ => {
+            return ApiError::BadRequest(format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters",
+                MAX_KEY_LEN
+            ))
+            .into_response()
+        }
+    };
+
+    let (parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return ApiError::BadRequest("Request body too large".to_string()).into_response(),
+    };
+    let id = record_id(&parts, &key);
+    let request_hash = hex::encode(Sha256::digest(&bytes));
+
+    match service.start(&id, &request_hash).await {
+        Ok(IdempotencyStart::Started) => {}
+        Ok(IdempotencyStart::Replay(record)) => return replay(record),
+        Err(e) => return e.into_response(),
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    let status = response.status();
+
+    // Failures that may pass on retry release the key instead of being replayed
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        if let Err(e) = service.abandon(&id).await {
+            warn!(error = %e, "Failed to release idempotency key");
+        }
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!(error = %e, "Failed to read response body for idempotency key");
+            let _ = service.abandon(&id).await;
+            return ApiError::InternalServerError("Failed to read response".to_string()).into_response();
+        }
+    };
+    let content_type = parts
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let stored_body = String::from_utf8_lossy(&bytes).into_owned();
+    if let Err(e) = service.complete(&id, status.as_u16(), content_type, stored_body).await {
+        // The response is still good, a retry will just not be deduplicated
+        warn!(error = %e, "Failed to store idempotent response");
+        let _ = service.abandon(&id).await;
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
@@ -0,0 +1,101 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    middleware::from_fn_with_state,
+    routing::post,
+    Router,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tower::ServiceExt;
+
+use crate::{
+    middleware::idempotency::{idempotent, REPLAYED_HEADER},
+    repository::idempotency_repository::IdempotencyRepository,
+    service::idempotency_service::IdempotencyService,
+    test_support::{test_db, unreachable_db},
+};
+
+fn app(service: IdempotencyService, calls: Arc<AtomicUsize>) -> Router {
+    Router::new()
+        .route(
+            "/things",
+            post(move |body: String| {
+                let calls = calls.clone();
+                async move {
+                    let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                    if body == "fail" {
+                        return (StatusCode::INTERNAL_SERVER_ERROR, "failed".to_string());
+                    }
+                    (StatusCode::CREATED, format!("created {} #{}", body, n))
+                }
+            }),
+        )
+        .layer(from_fn_with_state(service, idempotent))
+}
+
+async fn send(app: &Router, api_key: &str, key: Option<&str>, body: &str) -> (StatusCode, bool, String) {
+    let mut request = Request::post("/things").header("x-api-key", api_key);
+    if let Some(key) = key {
+        request = request.header("idempotency-key", key);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let replayed = response.headers().contains_key(REPLAYED_HEADER);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, replayed, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_requests_without_key_skip_the_store() {
+    let service = IdempotencyService::new(IdempotencyRepository::new(unreachable_db().await), Duration::from_secs(60));
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = app(service, calls.clone());
+
+    assert_eq!(send(&app, "k", None, "a").await.0, StatusCode::CREATED);
+    assert_eq!(send(&app, "k", None, "a").await.0, StatusCode::CREATED);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    let (status, _, _) = send(&app, "k", Some(" "), "a").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_retries_are_replayed() {
+    let db = test_db("_idempotency").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let repository = IdempotencyRepository::new(db);
+    repository.ensure_indexes().await.expect("Failed to ensure indexes");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = app(IdempotencyService::new(repository, Duration::from_secs(60)), calls.clone());
+
+    let first = send(&app, "key-a", Some("retry-1"), "project").await;
+    assert_eq!(first, (StatusCode::CREATED, false, "created project #1".to_string()));
+
+    let retry = send(&app, "key-a", Some("retry-1"), "project").await;
+    assert_eq!(retry, (StatusCode::CREATED, true, "created project #1".to_string()));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // Same key, different payload
+    let (status, _, _) = send(&app, "key-a", Some("retry-1"), "other").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Keys are scoped to the caller
+    let other_caller = send(&app, "key-b", Some("retry-1"), "project").await;
+    assert_eq!(other_caller, (StatusCode::CREATED, false, "created project #2".to_string()));
+
+    // Server errors release the key so the request can be retried
+    assert_eq!(send(&app, "key-a", Some("retry-2"), "fail").await.0, StatusCode::INTERNAL_SERVER_ERROR);
+    let (status, replayed, _) = send(&app, "key-a", Some("retry-2"), "fail").await;
+    assert_eq!((status, replayed), (StatusCode::INTERNAL_SERVER_ERROR, false));
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
@@ -0,0 +1,33 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+
+use crate::metrics;
+
+/// Records request count and latency per matched route, method and status.
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    // Use the route template so ids in the path do not explode label cardinality
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = request.method().to_string();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    let labels = [method.as_str(), route.as_str(), status.as_str()];
+    let metrics = metrics::global();
+    metrics.http_requests_total.with_label_values(&labels).inc();
+    metrics
+        .http_request_duration_seconds
+        .with_label_values(&labels)
+        .observe(start.elapsed().as_secs_f64());
+
+    response
+}
@@ -0,0 +1,70 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware::from_fn,
+    routing::get,
+    Router,
+};
+use tower::ServiceExt;
+
+use crate::{error::ApiError, metrics, middleware::metrics::track_http};
+
+#[tokio::test]
+async fn test_requests_are_labelled_by_route_template() {
+    let app = Router::new()
+        .route("/metrics-test/:id", get(|| async { "ok" }))
+        .layer(from_fn(track_http));
+
+    for id in ["a", "b"] {
+        let response = app
+            .clone()
+            .oneshot(Request::get(format!("/metrics-test/{}", id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let output = metrics::global().render();
+    assert!(output.contains(
+        r#"tonapi_http_requests_total{method="GET",route="/metrics-test/:id",status="200"} 2"#
+    ));
+    assert!(output.contains(
+        r#"tonapi_http_request_duration_seconds_count{method="GET",route="/metrics-test/:id",status="200"} 2"#
+    ));
+}
+
+#[tokio::test]
+async fn test_db_operations_record_outcome() {
+    let _ = metrics::track_db("metrics_test", "found", async { Ok(1) }).await;
+    let _ = metrics::track_db::<(), _>("metrics_test", "missing", async { Err(ApiError::NotFound) }).await;
+    let _ = metrics::track_db::<(), _>("metrics_test", "broken", async {
+        Err(ApiError::InternalServerError("boom".to_string()))
+    })
+    .await;
+
+    let output = metrics::global().render();
+    assert!(output.contains(
+        r#"tonapi_db_operation_duration_seconds_count{collection="metrics_test",operation="found",outcome="success"} 1"#
+    ));
+    assert!(output.contains(
+        r#"tonapi_db_operation_duration_seconds_count{collection="metrics_test",operation="missing",outcome="success"} 1"#
+    ));
+    assert!(output.contains(
+        r#"tonapi_db_operation_duration_seconds_count{collection="metrics_test",operation="broken",outcome="error"} 1"#
+    ));
+}
+
+#[test]
+fn test_job_counters_and_project_gauges() {
+    let metrics = metrics::global();
+    metrics.record_job("metrics_test_job", true);
+    metrics.record_job("metrics_test_job", false);
+    metrics.record_job("metrics_test_job", false);
+    metrics.set_project_counts(7, 3);
+
+    let output = metrics.render();
+    assert!(output.contains(r#"tonapi_background_jobs_total{job="metrics_test_job",outcome="success"} 1"#));
+    assert!(output.contains(r#"tonapi_background_jobs_total{job="metrics_test_job",outcome="failure"} 2"#));
+    assert!(output.contains(r#"tonapi_projects{state="active"} 7"#));
+    assert!(output.contains(r#"tonapi_projects{state="expired"} 3"#));
+}
@@ -0,0 +1,16 @@
+pub mod deprecation;
+pub mod idempotency;
+pub mod metrics;
+pub mod rate_limit;
+pub mod request_tracing;
+
+#[cfg(test)]
+mod deprecation_test;
+#[cfg(test)]
+mod idempotency_test;
+#[cfg(test)]
+mod metrics_test;
+#[cfg(test)]
+mod rate_limit_test;
+#[cfg(test)]
+mod request_tracing_test;
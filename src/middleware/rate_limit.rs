@@ -0,0 +1,255 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, Request, State},
+    http::{header::RETRY_AFTER, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use lru_cache::LruCache;
+use serde_json::json;
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::{
+    auth::{self, api_key_from_headers, Caller},
+    config::{RateLimitConfig, RateLimitRule},
+    error::ApiError,
+    metrics,
+    service::api_key_service::ApiKeyService,
+};
+
+pub const TELEGRAM_USER_HEADER: &str = "x-telegram-user-id";
+// Past this many buckets, the least recently used one is dropped for a new one
+const MAX_BUCKETS: usize = 100_000;
+
+/// Outcome of taking a token from a bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the next token, zero when the request is allowed
+    pub retry_after: Duration,
+    /// Time until the bucket is full again
+    pub reset_after: Duration,
+}
+
+/// Holds the token buckets. The in-process store limits a single instance,
+/// implement this over a shared store to limit across instances.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    async fn acquire(&self, key: &str, rule: RateLimitRule) -> Result<RateLimitDecision, ApiError>;
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    rule: RateLimitRule,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second(self.rule)).min(self.rule.burst as f64);
+        self.updated = now;
+    }
+}
+
+fn per_second(rule: RateLimitRule) -> f64 {
+    rule.requests_per_minute as f64 / 60.0
+}
+
+pub struct InMemoryStore {
+    buckets: Mutex<LruCache<String, Bucket>>,
+}
+
+impl Default for InMemoryStore {
+    fn default() -> Self {
+        Self::with_capacity(MAX_BUCKETS)
+    }
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps at most `capacity` buckets. A dropped bucket starts full again,
+    /// which only favours callers that have been idle the longest.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buckets: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryStore {
+    async fn acquire(&self, key: &str, rule: RateLimitRule) -> Result<RateLimitDecision, ApiError> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("rate limit buckets poisoned");
+        if !buckets.contains_key(key) {
+            let bucket = Bucket {
+                tokens: rule.burst as f64,
+                updated: now,
+                rule,
+            };
+            buckets.insert(key.to_string(), bucket);
+        }
+        let bucket = buckets.get_mut(key).expect("bucket was just inserted");
+        bucket.rule = rule;
+        bucket.refill(now);
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let rate = per_second(rule);
+        let retry_after = if allowed {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - bucket.tokens) / rate)
+        };
+        Ok(RateLimitDecision {
+            allowed,
+            limit: rule.burst,
+            remaining: bucket.tokens.floor() as u32,
+            retry_after,
+            reset_after: Duration::from_secs_f64((rule.burst as f64 - bucket.tokens) / rate),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    config: Arc<RateLimitConfig>,
+    keys: Option<ApiKeyService>,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>, config: RateLimitConfig) -> Self {
+        Self {
+            store,
+            config: Arc::new(config),
+            keys: None,
+        }
+    }
+
+    pub fn in_memory(config: RateLimitConfig) -> Self {
+        Self::new(Arc::new(InMemoryStore::new()), config)
+    }
+
+    /// Limits callers with a valid API key per key. Without it every caller
+    /// is limited by peer IP.
+    pub fn with_keys(mut self, keys: ApiKeyService) -> Self {
+        self.keys = Some(keys);
+        self
+    }
+
+    // Keys are only trusted once authenticated, so made-up keys cannot open
+    // buckets of their own. Bot frontends are further limited per Telegram user.
+    async fn identify(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> (String, Option<Caller>) {
+        if let Some(keys) = self.keys.as_ref().filter(|_| api_key_from_headers(headers).is_some()) {
+            if let Ok(caller) = auth::authenticate(headers, keys).await {
+                let key = match caller.key_id {
+                    Some(id) => format!("key:{}", id.to_hex()),
+                    None => "key:bootstrap".to_string(),
+                };
+                let identity = match telegram_user(headers).filter(|_| caller.bot_frontend) {
+                    Some(user) => format!("{}:tg:{}", key, user),
+                    None => key,
+                };
+                return (identity, Some(caller));
+            }
+        }
+        let ip = peer.map_or_else(|| "ip:unknown".to_string(), |addr| format!("ip:{}", addr.ip()));
+        (ip, None)
+    }
+
+    /// State for `limit`, applying the rule configured for `group`.
+    pub fn group(&self, group: &'static str) -> GroupLimiter {
+        GroupLimiter {
+            limiter: self.clone(),
+            name: group,
+            rule: self.config.rule(group),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct GroupLimiter {
+    limiter: RateLimiter,
+    name: &'static str,
+    rule: RateLimitRule,
+}
+
+fn telegram_user(headers: &HeaderMap) -> Option<i64> {
+    headers
+        .get(TELEGRAM_USER_HEADER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+fn set_rate_limit_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    headers.insert("x-ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("x-ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("x-ratelimit-reset", HeaderValue::from(ceil_secs(decision.reset_after)));
+}
+
+/// Takes a token for the caller from the group's bucket, rejecting the
+/// request with `429` when the bucket is empty.
+pub async fn limit(State(group): State<GroupLimiter>, mut request: Request, next: Next) -> Response {
+    if !group.limiter.config.enabled {
+        return next.run(request).await;
+    }
+
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| *addr);
+    let (identity, caller) = group.limiter.identify(request.headers(), peer).await;
+    // Handlers reuse the caller instead of authenticating again
+    if let Some(caller) = caller {
+        request.extensions_mut().insert(caller);
+    }
+    let key = format!("{}:{}", group.name, identity);
+    let decision = match group.limiter.store.acquire(&key, group.rule).await {
+        Ok(decision) => decision,
+        Err(e) => {
+            // An unavailable store must not take the API down with it
+            warn!(error = %e, group = group.name, "Rate limit store failed, allowing request");
+            return next.run(request).await;
+        }
+    };
+
+    if !decision.allowed {
+        metrics::global()
+            .rate_limited_total
+            .with_label_values(&[group.name])
+            .inc();
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({ "error": "Rate limit exceeded" })),
+        )
+            .into_response();
+        let headers = response.headers_mut();
+        set_rate_limit_headers(headers, &decision);
+        headers.insert(RETRY_AFTER, HeaderValue::from(ceil_secs(decision.retry_after)));
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    set_rate_limit_headers(response.headers_mut(), &decision);
+    response
+}
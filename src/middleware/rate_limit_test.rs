@@ -0,0 +1,210 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+    middleware::from_fn_with_state,
+    routing::get,
+    Router,
+};
+use mongodb::Database;
+use std::{net::SocketAddr, time::Duration};
+use tower::ServiceExt;
+
+use crate::{
+    auth::Caller,
+    config::{RateLimitConfig, RateLimitRule, Secret},
+    metrics,
+    middleware::rate_limit::{self, InMemoryStore, RateLimitStore, RateLimiter},
+    models::api_key::{CreateApiKey, Scope},
+    repository::{api_key_repository::ApiKeyRepository, organization_repository::OrganizationRepository},
+    service::api_key_service::ApiKeyService,
+    test_support::{test_db, unreachable_db, BOOTSTRAP_KEY},
+};
+
+const RULE: RateLimitRule = RateLimitRule {
+    requests_per_minute: 60,
+    burst: 2,
+};
+
+fn key_service(db: Database) -> ApiKeyService {
+    ApiKeyService::new(
+        ApiKeyRepository::new(db.clone()),
+        OrganizationRepository::new(db),
+        Some(&Secret::new(BOOTSTRAP_KEY)),
+    )
+}
+
+async fn app(enabled: bool) -> Router {
+    app_with_keys(enabled, key_service(unreachable_db().await))
+}
+
+fn app_with_keys(enabled: bool, keys: ApiKeyService) -> Router {
+    let mut config = RateLimitConfig {
+        enabled,
+        ..RateLimitConfig::default()
+    };
+    config.groups.insert("projects".to_string(), RULE);
+    let limiter = RateLimiter::in_memory(config).with_keys(keys);
+    Router::new()
+        .route("/limited", get(|| async { "ok" }))
+        .route_layer(from_fn_with_state(limiter.group("projects"), rate_limit::limit))
+        .layer(MockConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))))
+}
+
+async fn get_status(app: &Router, headers: &[(&str, &str)]) -> (StatusCode, axum::http::HeaderMap) {
+    let mut request = Request::get("/limited");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    (response.status(), response.headers().clone())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_bucket_refills_over_time() {
+    let store = InMemoryStore::new();
+
+    assert!(store.acquire("k", RULE).await.unwrap().allowed);
+    let second = store.acquire("k", RULE).await.unwrap();
+    assert!(second.allowed);
+    assert_eq!(second.remaining, 0);
+
+    let denied = store.acquire("k", RULE).await.unwrap();
+    assert!(!denied.allowed);
+    assert_eq!(denied.retry_after, Duration::from_secs(1));
+    assert_eq!(denied.reset_after, Duration::from_secs(2));
+
+    // Other keys have their own bucket
+    assert!(store.acquire("other", RULE).await.unwrap().allowed);
+
+    tokio::time::advance(Duration::from_secs(1)).await;
+    assert!(store.acquire("k", RULE).await.unwrap().allowed);
+    assert!(!store.acquire("k", RULE).await.unwrap().allowed);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_least_recently_used_buckets_are_dropped_first() {
+    let store = InMemoryStore::with_capacity(2);
+    for key in ["idle", "busy"] {
+        store.acquire(key, RULE).await.unwrap();
+        store.acquire(key, RULE).await.unwrap();
+    }
+    assert!(!store.acquire("busy", RULE).await.unwrap().allowed);
+
+    // Room for a third bucket is made by dropping the idle one
+    assert!(store.acquire("new", RULE).await.unwrap().allowed);
+    assert!(!store.acquire("busy", RULE).await.unwrap().allowed);
+    assert_eq!(store.acquire("idle", RULE).await.unwrap().remaining, 1);
+}
+
+#[tokio::test]
+async fn test_exhausted_bucket_returns_429_with_headers() {
+    let app = app(true).await;
+
+    let (status, headers) = get_status(&app, &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["x-ratelimit-limit"], "2");
+    assert_eq!(headers["x-ratelimit-remaining"], "1");
+
+    get_status(&app, &[]).await;
+    let (status, headers) = get_status(&app, &[]).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(headers["retry-after"], "1");
+    assert_eq!(headers["x-ratelimit-remaining"], "0");
+    assert!(metrics::global().render().contains(r#"tonapi_rate_limited_total{group="projects"}"#));
+}
+
+#[tokio::test]
+async fn test_callers_are_limited_separately() {
+    let app = app(true).await;
+
+    // Exhaust the IP bucket
+    for _ in 0..2 {
+        get_status(&app, &[]).await;
+    }
+    assert_eq!(get_status(&app, &[]).await.0, StatusCode::TOO_MANY_REQUESTS);
+
+    // Keys that do not authenticate stay in the IP bucket, whatever they claim
+    for headers in [
+        vec![("x-api-key", "made-up")],
+        vec![("x-api-key", "tk_unknown"), ("x-telegram-user-id", "42")],
+        vec![("authorization", "Bearer other")],
+    ] {
+        assert_eq!(get_status(&app, &headers).await.0, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    // The same peer with a valid key gets the key's bucket
+    let key = [("x-api-key", BOOTSTRAP_KEY)];
+    for _ in 0..2 {
+        assert_eq!(get_status(&app, &key).await.0, StatusCode::OK);
+    }
+    assert_eq!(get_status(&app, &key).await.0, StatusCode::TOO_MANY_REQUESTS);
+
+    // Only bot frontend keys are split per Telegram user
+    let user = [("x-api-key", BOOTSTRAP_KEY), ("x-telegram-user-id", "42")];
+    assert_eq!(get_status(&app, &user).await.0, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_bot_frontend_keys_are_limited_per_telegram_user() {
+    let db = test_db("_rate_limit").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let keys = key_service(db);
+    let bootstrap = keys.authenticate(BOOTSTRAP_KEY, None).await.unwrap();
+    let mint = |bot_frontend: bool| CreateApiKey {
+        name: "bot".to_string(),
+        scopes: vec![Scope::ProjectsRead],
+        org_id: None,
+        account_id: None,
+        bot_frontend,
+    };
+    let frontend = keys.create_api_key(&bootstrap, mint(true)).await.unwrap().key;
+    let plain = keys.create_api_key(&bootstrap, mint(false)).await.unwrap().key;
+    let app = app_with_keys(true, keys);
+
+    // Users whose ids are far apart do not share a bucket either
+    for user in ["1", "2", "1025"] {
+        let headers = [("x-api-key", frontend.as_str()), ("x-telegram-user-id", user)];
+        for _ in 0..2 {
+            assert_eq!(get_status(&app, &headers).await.0, StatusCode::OK);
+        }
+        assert_eq!(get_status(&app, &headers).await.0, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let headers = [("x-api-key", plain.as_str()), ("x-telegram-user-id", "1")];
+    for _ in 0..2 {
+        get_status(&app, &headers).await;
+    }
+    let other_user = [("x-api-key", plain.as_str()), ("x-telegram-user-id", "2")];
+    assert_eq!(get_status(&app, &other_user).await.0, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_authenticated_caller_is_passed_to_handlers() {
+    let app = Router::new()
+        .route(
+            "/limited",
+            get(|request: Request<Body>| async move { request.extensions().get::<Caller>().unwrap().name.clone() }),
+        )
+        .route_layer(from_fn_with_state(
+            RateLimiter::in_memory(RateLimitConfig::default())
+                .with_keys(key_service(unreachable_db().await))
+                .group("projects"),
+            rate_limit::limit,
+        ));
+    let request = Request::get("/limited").header("x-api-key", BOOTSTRAP_KEY).body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], b"bootstrap");
+}
+
+#[tokio::test]
+async fn test_disabled_limiter_passes_everything() {
+    let app = app(false).await;
+
+    for _ in 0..5 {
+        let (status, headers) = get_status(&app, &[]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(headers.get("x-ratelimit-limit").is_none());
+    }
+}
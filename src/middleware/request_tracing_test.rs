@@ -0,0 +1,34 @@
+use axum::{body::Body, http::Request, routing::get, Router};
+use tower::ServiceExt;
+
+use crate::middleware::request_tracing::{self, REQUEST_ID_HEADER};
+
+fn app() -> Router {
+    request_tracing::layer(Router::new().route("/ping", get(|| async { "pong" })))
+}
+
+#[tokio::test]
+async fn test_request_id_is_generated() {
+    let response = app()
+        .oneshot(Request::get("/ping").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    let request_id = response.headers().get(&REQUEST_ID_HEADER).expect("missing request id");
+    assert_eq!(request_id.to_str().unwrap().len(), 36);
+}
+
+#[tokio::test]
+async fn test_request_id_is_propagated() {
+    let response = app()
+        .oneshot(
+            Request::get("/ping")
+                .header(&REQUEST_ID_HEADER, "caller-supplied-id")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.headers()[&REQUEST_ID_HEADER], "caller-supplied-id");
+}
@@ -0,0 +1,53 @@
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::{
+    bson::{doc, DateTime as BsonDateTime, Document},
+    Database,
+};
+use tracing::warn;
+
+use crate::{error::ApiError, migrations::Migration};
+
+/// `Project.expires_at` used to be serialized as an RFC 3339 string, which
+/// cannot be compared or indexed as a date.
+pub struct ExpiresAtToDate;
+
+#[async_trait]
+impl Migration for ExpiresAtToDate {
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "expires_at_to_date"
+    }
+
+    async fn apply(&self, db: &Database, dry_run: bool) -> Result<u64, ApiError> {
+        let projects = db.collection::<Document>("projects");
+        let filter = doc! { "expires_at": { "$type": "string" } };
+        let mut cursor = projects.find(filter, None).await?;
+        let mut converted = 0;
+
+        while cursor.advance().await? {
+            let doc = Document::from_reader(cursor.current().as_bytes())?;
+            let (Ok(id), Ok(text)) = (doc.get_object_id("_id"), doc.get_str("expires_at")) else {
+                continue;
+            };
+            let expires_at = match DateTime::parse_from_rfc3339(text) {
+                Ok(date) => date.with_timezone(&Utc),
+                Err(e) => {
+                    warn!(project_id = %id, value = text, error = %e, "Leaving unparsable expires_at as is");
+                    continue;
+                }
+            };
+            if !dry_run {
+                // Matching the old value leaves concurrent rewrites alone
+                let filter = doc! { "_id": id, "expires_at": text };
+                let update = doc! { "$set": { "expires_at": BsonDateTime::from_chrono(expires_at) } };
+                projects.update_one(filter, update, None).await?;
+            }
+            converted += 1;
+        }
+        Ok(converted)
+    }
+}
@@ -0,0 +1,47 @@
+use axum::async_trait;
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, DateTime as BsonDateTime, Document},
+    Database,
+};
+
+use crate::{error::ApiError, migrations::Migration, models::project::ProjectStatus};
+
+/// Projects used to carry their state in `is_active` alone. Active projects
+/// stay active, inactive ones become expired when their expiry has passed and
+/// suspended otherwise.
+pub struct ProjectStatusFromIsActive;
+
+#[async_trait]
+impl Migration for ProjectStatusFromIsActive {
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn name(&self) -> &'static str {
+        "project_status_from_is_active"
+    }
+
+    async fn apply(&self, db: &Database, dry_run: bool) -> Result<u64, ApiError> {
+        let projects = db.collection::<Document>("projects");
+        let now = BsonDateTime::from_chrono(Utc::now());
+        // Expired goes before suspended, which takes every inactive project left
+        let steps = [
+            (doc! { "is_active": true }, ProjectStatus::from_legacy(true, false)),
+            (doc! { "is_active": { "$ne": true }, "expires_at": { "$lte": now } }, ProjectStatus::from_legacy(false, true)),
+            (doc! { "is_active": { "$ne": true } }, ProjectStatus::from_legacy(false, false)),
+        ];
+
+        let mut migrated = 0;
+        for (mut filter, status) in steps {
+            filter.insert("status", doc! { "$exists": false });
+            migrated += if dry_run {
+                projects.count_documents(filter, None).await?
+            } else {
+                let update = doc! { "$set": { "status": status.as_str(), "status_history": [] } };
+                projects.update_many(filter, update, None).await?.modified_count
+            };
+        }
+        Ok(migrated)
+    }
+}
@@ -0,0 +1,53 @@
+use axum::async_trait;
+use mongodb::{
+    bson::{doc, Document},
+    Database,
+};
+
+use crate::{error::ApiError, migrations::Migration, models::ad_platform::AdPlatform};
+
+/// Credentials used to be Facebook only, in `facebook_credentials`. They move
+/// to `ad_credentials` under the same keys, tagged with their platform.
+pub struct FacebookCredentialsToAdCredentials;
+
+#[async_trait]
+impl Migration for FacebookCredentialsToAdCredentials {
+    fn version(&self) -> u32 {
+        3
+    }
+
+    fn name(&self) -> &'static str {
+        "facebook_credentials_to_ad_credentials"
+    }
+
+    async fn apply(&self, db: &Database, dry_run: bool) -> Result<u64, ApiError> {
+        let projects = db.collection::<Document>("projects");
+        let filter = doc! {
+            "facebook_credentials": { "$type": "object" },
+            "ad_credentials": { "$exists": false },
+        };
+        if dry_run {
+            return Ok(projects.count_documents(filter, None).await?);
+        }
+
+        let pipeline = vec![
+            doc! {
+                "$set": {
+                    "ad_credentials": {
+                        "$arrayToObject": {
+                            "$map": {
+                                "input": { "$objectToArray": "$facebook_credentials" },
+                                "in": {
+                                    "k": "$$this.k",
+                                    "v": { "$mergeObjects": ["$$this.v", { "platform": AdPlatform::Facebook.as_str() }] },
+                                },
+                            }
+                        }
+                    }
+                }
+            },
+            doc! { "$unset": "facebook_credentials" },
+        ];
+        Ok(projects.update_many(filter, pipeline, None).await?.modified_count)
+    }
+}
@@ -0,0 +1,107 @@
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime as BsonDateTime, Document};
+
+use crate::{
+    error::ApiError,
+    migrations::{all, MigrationOutcome, Migrator},
+    models::ad_platform::AdPlatform,
+    repository::project_repository::ProjectRepository,
+    test_support::test_db,
+};
+
+fn legacy_project(org_id: ObjectId, expires_at: Bson) -> Document {
+    doc! {
+        "_id": ObjectId::new(),
+        "org_id": org_id,
+        "name": "Legacy",
+        "telegram_chat_id": null,
+        "facebook_credentials": {},
+        "package": null,
+        "expires_at": expires_at,
+        "is_active": true,
+        "is_logging": false,
+        "created_at": BsonDateTime::now(),
+        "updated_at": BsonDateTime::now(),
+    }
+}
+
+#[test]
+fn test_versions_are_unique_and_ordered() {
+    let versions: Vec<u32> = all().iter().map(|migration| migration.version()).collect();
+    assert!(!versions.is_empty());
+    assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(versions[0] >= 1);
+}
+
+#[tokio::test]
+async fn test_migrations_are_recorded_and_run_once() {
+    let db = test_db("_migrations").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let projects = db.collection::<Document>("projects");
+    let org_id = ObjectId::new();
+    let mut legacy = legacy_project(org_id, Bson::String("2020-01-01T00:00:00.123456789Z".to_string()));
+    legacy.insert("facebook_credentials", doc! { "main": {
+        "app_id": "app",
+        "app_secret": "secret",
+        "access_token": "token",
+        "ad_account_id": "act_1",
+        "account_suffix": "suffix",
+    } });
+    let legacy_id = legacy.get_object_id("_id").unwrap();
+    let mut lapsed = legacy_project(org_id, Bson::DateTime(BsonDateTime::now()));
+    lapsed.insert("is_active", false);
+    let lapsed_id = lapsed.get_object_id("_id").unwrap();
+    projects
+        .insert_many(
+            vec![
+                legacy,
+                lapsed,
+                legacy_project(org_id, Bson::String("not a date".to_string())),
+            ],
+            None,
+        )
+        .await
+        .unwrap();
+    let migrator = Migrator::new(db.clone());
+
+    let expected = vec![
+        MigrationOutcome { version: 1, name: "expires_at_to_date", affected: 1 },
+        MigrationOutcome { version: 2, name: "project_status_from_is_active", affected: 3 },
+        MigrationOutcome { version: 3, name: "facebook_credentials_to_ad_credentials", affected: 3 },
+    ];
+    assert_eq!(migrator.run(true).await.unwrap(), expected);
+    let stored = projects.find_one(doc! { "_id": legacy_id }, None).await.unwrap().unwrap();
+    assert!(matches!(stored.get("expires_at"), Some(Bson::String(_))));
+    assert!(migrator.applied_versions().await.unwrap().is_empty());
+    // Until then legacy credentials are read as Facebook entries
+    let repository = ProjectRepository::new(db.clone());
+    let read = repository.get_by_id(&org_id, &legacy_id).await.unwrap();
+    assert_eq!(read.ad_credentials["main"].platform(), AdPlatform::Facebook);
+
+    assert_eq!(migrator.run(false).await.unwrap(), expected);
+    let stored = projects.find_one(doc! { "_id": legacy_id }, None).await.unwrap().unwrap();
+    assert!(matches!(stored.get("expires_at"), Some(Bson::DateTime(_))));
+    assert!(migrator.applied_versions().await.unwrap().contains(&1));
+    assert_eq!(stored.get_str("status"), Ok("active"));
+    let lapsed = projects.find_one(doc! { "_id": lapsed_id }, None).await.unwrap().unwrap();
+    assert_eq!(lapsed.get_str("status"), Ok("expired"));
+    assert!(!stored.contains_key("facebook_credentials"));
+    let credential = stored.get_document("ad_credentials").unwrap().get_document("main").unwrap();
+    assert_eq!((credential.get_str("platform"), credential.get_str("access_token")), (Ok("facebook"), Ok("token")));
+    let read = repository.get_by_id(&org_id, &legacy_id).await.unwrap();
+    assert_eq!(read.facebook_credentials().next().map(|(key, c)| (key.as_str(), c.app_secret.as_str())), Some(("main", "secret")));
+
+    // Converted projects are found by date queries
+    let expired = repository.find_expired(Utc::now()).await.unwrap();
+    assert!(expired.iter().any(|project| project.id == Some(legacy_id)));
+
+    assert!(migrator.run(false).await.unwrap().is_empty());
+    assert!(migrator.run(true).await.unwrap().is_empty());
+
+    // A run holding the lock blocks others until it finishes or goes stale
+    db.collection::<Document>("_migrations")
+        .insert_one(doc! { "_id": "lock", "locked_at": BsonDateTime::now() }, None)
+        .await
+        .unwrap();
+    assert!(matches!(migrator.run(false).await, Err(ApiError::Conflict(_))));
+}
@@ -0,0 +1,177 @@
+mod m0001_expires_at_to_date;
+mod m0002_project_status;
+mod m0003_ad_credentials;
+#[cfg(test)]
+mod migrations_test;
+
+use axum::async_trait;
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, Bson, DateTime as BsonDateTime, Document},
+    Collection, Database,
+};
+use serde::Serialize;
+use std::{collections::BTreeSet, time::Duration};
+use tracing::{error, info};
+
+use crate::{error::ApiError, metrics, repository::is_duplicate_key};
+
+const MIGRATIONS_COLLECTION: &str = "_migrations";
+const LOCK_ID: &str = "lock";
+// A lock older than this was left behind by a crashed run
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// One schema change. Migrations run in `version` order, once each, and
+/// must be safe to run again if a previous run stopped halfway.
+#[async_trait]
+pub trait Migration: Send + Sync {
+    fn version(&self) -> u32;
+
+    fn name(&self) -> &'static str;
+
+    /// Applies the change and returns how many documents it touched. With
+    /// `dry_run` nothing is written and the count is what would change.
+    async fn apply(&self, db: &Database, dry_run: bool) -> Result<u64, ApiError>;
+}
+
+/// Every migration, in the order they are applied.
+pub fn all() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(m0001_expires_at_to_date::ExpiresAtToDate),
+        Box::new(m0002_project_status::ProjectStatusFromIsActive),
+        Box::new(m0003_ad_credentials::FacebookCredentialsToAdCredentials),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationOutcome {
+    pub version: u32,
+    pub name: &'static str,
+    pub affected: u64,
+}
+
+/// Applies pending migrations and records them in `_migrations`.
+pub struct Migrator {
+    db: Database,
+    collection: Collection<Document>,
+    migrations: Vec<Box<dyn Migration>>,
+}
+
+impl Migrator {
+    pub fn new(db: Database) -> Self {
+        Self::with_migrations(db, all())
+    }
+
+    pub fn with_migrations(db: Database, mut migrations: Vec<Box<dyn Migration>>) -> Self {
+        migrations.sort_by_key(|migration| migration.version());
+        Self {
+            collection: db.collection(MIGRATIONS_COLLECTION),
+            db,
+            migrations,
+        }
+    }
+
+    pub async fn applied_versions(&self) -> Result<BTreeSet<u32>, ApiError> {
+        metrics::track_db(MIGRATIONS_COLLECTION, "applied_versions", async {
+            let mut cursor = self.collection.find(doc! { "_id": { "$type": "number" } }, None).await?;
+            let mut versions = BTreeSet::new();
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                match doc.get("_id") {
+                    Some(Bson::Int32(version)) => versions.insert(*version as u32),
+                    Some(Bson::Int64(version)) => versions.insert(*version as u32),
+                    _ => false,
+                };
+            }
+            Ok(versions)
+        })
+        .await
+    }
+
+    /// Runs every migration that has not been applied yet. A dry run reports
+    /// what each would change and records nothing.
+    pub async fn run(&self, dry_run: bool) -> Result<Vec<MigrationOutcome>, ApiError> {
+        if !dry_run {
+            self.lock().await?;
+        }
+        let result = self.run_pending(dry_run).await;
+        // The migration error matters more, a lock left behind goes stale on its own
+        if !dry_run {
+            if let Err(e) = self.unlock().await {
+                error!(error = %e, "Failed to release the migration lock");
+            }
+        }
+        result
+    }
+
+    async fn run_pending(&self, dry_run: bool) -> Result<Vec<MigrationOutcome>, ApiError> {
+        let applied = self.applied_versions().await?;
+        let mut outcomes = Vec::new();
+
+        for migration in self.migrations.iter().filter(|m| !applied.contains(&m.version())) {
+            let affected = migration.apply(&self.db, dry_run).await.map_err(|e| {
+                ApiError::InternalServerError(format!(
+                    "migration {} {} failed: {}",
+                    migration.version(),
+                    migration.name(),
+                    e
+                ))
+            })?;
+            if !dry_run {
+                self.record(migration.as_ref(), affected).await?;
+                info!(version = migration.version(), name = migration.name(), affected, "Migration applied");
+            }
+            outcomes.push(MigrationOutcome {
+                version: migration.version(),
+                name: migration.name(),
+                affected,
+            });
+        }
+        Ok(outcomes)
+    }
+
+    async fn record(&self, migration: &dyn Migration, affected: u64) -> Result<(), ApiError> {
+        metrics::track_db(MIGRATIONS_COLLECTION, "record", async {
+            let record = doc! {
+                "_id": migration.version() as i64,
+                "name": migration.name(),
+                "affected": affected as i64,
+                "applied_at": BsonDateTime::now(),
+            };
+            self.collection.insert_one(record, None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    // Keeps two instances starting at once from applying the same migration
+    async fn lock(&self) -> Result<(), ApiError> {
+        metrics::track_db(MIGRATIONS_COLLECTION, "lock", async {
+            let now = Utc::now();
+            let lock = doc! { "_id": LOCK_ID, "locked_at": BsonDateTime::from_chrono(now) };
+            match self.collection.insert_one(lock, None).await {
+                Ok(_) => return Ok(()),
+                Err(e) if !is_duplicate_key(&e) => return Err(e.into()),
+                Err(_) => {}
+            }
+
+            let stale = BsonDateTime::from_chrono(now - LOCK_TIMEOUT);
+            let filter = doc! { "_id": LOCK_ID, "locked_at": { "$lt": stale } };
+            let update = doc! { "$set": { "locked_at": BsonDateTime::from_chrono(now) } };
+            let result = self.collection.update_one(filter, update, None).await?;
+            if result.modified_count == 0 {
+                return Err(ApiError::Conflict("Another instance is running migrations".to_string()));
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn unlock(&self) -> Result<(), ApiError> {
+        metrics::track_db(MIGRATIONS_COLLECTION, "unlock", async {
+            self.collection.delete_one(doc! { "_id": LOCK_ID }, None).await?;
+            Ok(())
+        })
+        .await
+    }
+}
@@ -6,6 +6,9 @@ use serde::{Deserialize, Serialize};
 pub struct Account {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
+    // Owning organization, always set by the server from the caller's tenant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
     pub wallet_address: String,
     pub email: String,
     pub account_name: String,
@@ -17,4 +20,51 @@ pub struct Account {
     
     #[serde(with = "chrono_datetime_as_bson_datetime")]
     pub updated_at: DateTime<Utc>,
-} 
\ No newline at end of file
+} 
+/// Flat shape of an account in bulk imports and exports. `id` is only
+/// written by exports and ignored on import.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountRecord {
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub wallet_address: String,
+    pub email: String,
+    pub account_name: String,
+    /// Hex encoded project ids
+    #[serde(default)]
+    pub project_ids: Vec<String>,
+}
+
+impl From<Account> for AccountRecord {
+    fn from(account: Account) -> Self {
+        Self {
+            id: account.id.map(|id| id.to_hex()),
+            wallet_address: account.wallet_address,
+            email: account.email,
+            account_name: account.account_name,
+            project_ids: account.project_ids.into_iter().map(ObjectId::to_hex).collect(),
+        }
+    }
+}
+
+impl TryFrom<AccountRecord> for Account {
+    type Error = String;
+
+    fn try_from(record: AccountRecord) -> Result<Self, Self::Error> {
+        let project_ids = record
+            .project_ids
+            .iter()
+            .map(|id| ObjectId::parse_str(id).map_err(|_| format!("Invalid project id {:?}", id)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            id: None,
+            org_id: None,
+            wallet_address: record.wallet_address,
+            email: record.email,
+            account_name: record.account_name,
+            project_ids,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+    }
+}
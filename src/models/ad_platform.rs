@@ -0,0 +1,340 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt};
+
+use crate::crypto::SecretCipher;
+use crate::models::project::{FacebookCredential, MASKED_SECRET};
+
+pub const MAX_EVENT_NAME_LENGTH: usize = 100;
+pub const MAX_EVENT_ID_LENGTH: usize = 128;
+
+/// The ad networks a project can hold credentials for.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AdPlatform {
+    Facebook,
+    Tiktok,
+    GoogleAds,
+}
+
+impl AdPlatform {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AdPlatform::Facebook => "facebook",
+            AdPlatform::Tiktok => "tiktok",
+            AdPlatform::GoogleAds => "google_ads",
+        }
+    }
+}
+
+impl fmt::Display for AdPlatform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TiktokCredential {
+    pub app_id: String,
+    pub app_secret: String,
+    pub access_token: String,
+    pub advertiser_id: String,
+    // Events API pixel, conversions cannot be sent without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixel_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoogleAdsCredential {
+    pub developer_token: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: String,
+    // Ten digits, with or without the dashes the Google Ads UI shows
+    pub customer_id: String,
+    // Manager account the customer is reached through, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login_customer_id: Option<String>,
+    // Conversion action uploaded conversions count towards
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversion_action_id: Option<String>,
+}
+
+/// A project's credential for one ad platform, tagged with `platform`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "platform", rename_all = "snake_case")]
+pub enum AdPlatformCredential {
+    Facebook(FacebookCredential),
+    Tiktok(TiktokCredential),
+    GoogleAds(GoogleAdsCredential),
+}
+
+impl AdPlatformCredential {
+    pub fn platform(&self) -> AdPlatform {
+        match self {
+            AdPlatformCredential::Facebook(_) => AdPlatform::Facebook,
+            AdPlatformCredential::Tiktok(_) => AdPlatform::Tiktok,
+            AdPlatformCredential::GoogleAds(_) => AdPlatform::GoogleAds,
+        }
+    }
+
+    pub fn as_facebook(&self) -> Option<&FacebookCredential> {
+        match self {
+            AdPlatformCredential::Facebook(credential) => Some(credential),
+            _ => None,
+        }
+    }
+
+    pub fn into_facebook(self) -> Option<FacebookCredential> {
+        match self {
+            AdPlatformCredential::Facebook(credential) => Some(credential),
+            _ => None,
+        }
+    }
+
+    /// The ad account, advertiser or customer the credential works on.
+    pub fn account_id(&self) -> &str {
+        match self {
+            AdPlatformCredential::Facebook(credential) => &credential.ad_account_id,
+            AdPlatformCredential::Tiktok(credential) => &credential.advertiser_id,
+            AdPlatformCredential::GoogleAds(credential) => &credential.customer_id,
+        }
+    }
+
+    /// The token the platform's API is called with.
+    pub fn token(&self) -> &str {
+        match self {
+            AdPlatformCredential::Facebook(credential) => &credential.access_token,
+            AdPlatformCredential::Tiktok(credential) => &credential.access_token,
+            AdPlatformCredential::GoogleAds(credential) => &credential.refresh_token,
+        }
+    }
+
+    /// Checks the fields the credential's platform requires.
+    pub fn validate(&self) -> Result<(), String> {
+        let required = match self {
+            AdPlatformCredential::Facebook(credential) => vec![
+                ("app_id", &credential.app_id),
+                ("app_secret", &credential.app_secret),
+                ("access_token", &credential.access_token),
+                ("ad_account_id", &credential.ad_account_id),
+            ],
+            AdPlatformCredential::Tiktok(credential) => vec![
+                ("app_id", &credential.app_id),
+                ("app_secret", &credential.app_secret),
+                ("access_token", &credential.access_token),
+                ("advertiser_id", &credential.advertiser_id),
+            ],
+            AdPlatformCredential::GoogleAds(credential) => vec![
+                ("developer_token", &credential.developer_token),
+                ("client_id", &credential.client_id),
+                ("client_secret", &credential.client_secret),
+                ("refresh_token", &credential.refresh_token),
+                ("customer_id", &credential.customer_id),
+            ],
+        };
+        if let Some((field, _)) = required.iter().find(|(_, value)| value.trim().is_empty()) {
+            return Err(format!("{} cannot be empty", field));
+        }
+
+        match self {
+            AdPlatformCredential::Facebook(_) => Ok(()),
+            AdPlatformCredential::Tiktok(credential) => require_digits("advertiser_id", &credential.advertiser_id),
+            AdPlatformCredential::GoogleAds(credential) => {
+                let customer_ids = [Some(("customer_id", &credential.customer_id)), credential.login_customer_id.as_ref().map(|id| ("login_customer_id", id))];
+                for (field, id) in customer_ids.into_iter().flatten() {
+                    if google_customer_id(id).is_none() {
+                        return Err(format!("{} must be a ten digit Google Ads customer id", field));
+                    }
+                }
+                match &credential.conversion_action_id {
+                    Some(id) => require_digits("conversion_action_id", id),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    // Secrets are encrypted at rest and masked for callers without `secrets:reveal`
+    pub fn secrets(&self) -> Vec<&String> {
+        match self {
+            AdPlatformCredential::Facebook(credential) => vec![&credential.app_secret, &credential.access_token],
+            AdPlatformCredential::Tiktok(credential) => vec![&credential.app_secret, &credential.access_token],
+            AdPlatformCredential::GoogleAds(credential) => {
+                vec![&credential.developer_token, &credential.client_secret, &credential.refresh_token]
+            }
+        }
+    }
+
+    pub fn secrets_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            AdPlatformCredential::Facebook(credential) => vec![&mut credential.app_secret, &mut credential.access_token],
+            AdPlatformCredential::Tiktok(credential) => vec![&mut credential.app_secret, &mut credential.access_token],
+            AdPlatformCredential::GoogleAds(credential) => vec![
+                &mut credential.developer_token,
+                &mut credential.client_secret,
+                &mut credential.refresh_token,
+            ],
+        }
+    }
+
+    pub fn mask_secrets(&mut self) {
+        for secret in self.secrets_mut() {
+            *secret = MASKED_SECRET.to_string();
+        }
+    }
+
+    pub fn has_masked_secrets(&self) -> bool {
+        self.secrets().iter().any(|secret| *secret == MASKED_SECRET)
+    }
+
+    // A client value in the stored ciphertext format would fail to decrypt on every read
+    pub fn has_encrypted_secrets(&self) -> bool {
+        self.secrets().iter().any(|secret| SecretCipher::is_encrypted(secret))
+    }
+
+    /// Puts the secrets of `stored` back where this credential has the
+    /// masked placeholder. Fails when `stored` is for another platform.
+    pub fn restore_masked_secrets(&mut self, stored: &AdPlatformCredential) -> Result<(), String> {
+        if stored.platform() != self.platform() {
+            return Err(format!("stored credential is for {}", stored.platform()));
+        }
+        for (secret, stored) in self.secrets_mut().into_iter().zip(stored.secrets()) {
+            if *secret == MASKED_SECRET {
+                *secret = stored.clone();
+            }
+        }
+        Ok(())
+    }
+
+    /// Carries Facebook token health over, see [`FacebookCredential::keep_token_health`].
+    pub fn keep_token_health(&mut self, stored: Option<&AdPlatformCredential>) {
+        if let AdPlatformCredential::Facebook(credential) = self {
+            credential.keep_token_health(stored.and_then(AdPlatformCredential::as_facebook));
+        }
+    }
+}
+
+fn require_digits(field: &str, value: &str) -> Result<(), String> {
+    match !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        true => Ok(()),
+        false => Err(format!("{} may only contain digits", field)),
+    }
+}
+
+/// The ten digits of a Google Ads customer id written with or without dashes.
+pub fn google_customer_id(id: &str) -> Option<String> {
+    let digits: String = id.chars().filter(|c| *c != '-').collect();
+    (digits.len() == 10 && digits.chars().all(|c| c.is_ascii_digit())).then_some(digits)
+}
+
+/// Credentials in the `facebook_credentials` shape as `ad_credentials` entries.
+pub fn from_facebook_credentials(credentials: HashMap<String, FacebookCredential>) -> HashMap<String, AdPlatformCredential> {
+    credentials
+        .into_iter()
+        .map(|(key, credential)| (key, AdPlatformCredential::Facebook(credential)))
+        .collect()
+}
+
+/// The Facebook entries of `credentials` in the `facebook_credentials` shape.
+pub fn facebook_credentials(credentials: &HashMap<String, AdPlatformCredential>) -> HashMap<String, FacebookCredential> {
+    credentials
+        .iter()
+        .filter_map(|(key, credential)| Some((key.clone(), credential.as_facebook()?.clone())))
+        .collect()
+}
+
+pub fn validate_event_name(event_name: &str) -> Result<(), String> {
+    if event_name.trim().is_empty() {
+        return Err("event_name cannot be empty".to_string());
+    }
+    if event_name.chars().count() > MAX_EVENT_NAME_LENGTH {
+        return Err(format!("event_name cannot be longer than {} characters", MAX_EVENT_NAME_LENGTH));
+    }
+    Ok(())
+}
+
+pub fn validate_event_id(event_id: &str) -> Result<(), String> {
+    if event_id.is_empty() || event_id.len() > MAX_EVENT_ID_LENGTH {
+        return Err(format!("event_id must be 1 to {} characters", MAX_EVENT_ID_LENGTH));
+    }
+    if !event_id.chars().all(|c| c.is_ascii_graphic()) {
+        return Err("event_id may only contain printable ASCII characters".to_string());
+    }
+    Ok(())
+}
+
+/// A credential together with its key in `ad_credentials`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdCredentialEntry {
+    pub key: String,
+    #[serde(flatten)]
+    pub credential: AdPlatformCredential,
+}
+
+/// A conversion reported to the platform of a credential.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Conversion {
+    // Platform event name, e.g. `Purchase` or `CompletePayment`
+    pub event_name: String,
+    // Shared with the browser pixel so that the platform counts the event once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+    #[serde(default = "Utc::now")]
+    pub event_time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    // fbclid, ttclid or gclid of the ad click
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click_id: Option<String>,
+    // Contact details are only sent SHA-256 hashed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+}
+
+impl Conversion {
+    /// Checks the conversion can be reported to `platform`.
+    pub fn validate(&self, platform: AdPlatform) -> Result<(), String> {
+        validate_event_name(&self.event_name)?;
+        if let Some(event_id) = &self.event_id {
+            validate_event_id(event_id)?;
+        }
+        if self.value.is_some_and(|value| !value.is_finite() || value < 0.0) {
+            return Err("value must be a non-negative number".to_string());
+        }
+        if let Some(currency) = &self.currency {
+            if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+                return Err(format!("currency must be an ISO 4217 code, got {:?}", currency));
+            }
+        }
+        // Google Ads attributes uploads to a click or, with enhanced conversions, a user
+        if platform == AdPlatform::GoogleAds && self.click_id.is_none() && self.email.is_none() {
+            return Err("Google Ads conversions need a click_id or an email".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// What an ad account spent between two dates, both included.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Spend {
+    pub platform: AdPlatform,
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    pub amount: f64,
+    // In the ad account's currency, when the platform reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpendQuery {
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+}
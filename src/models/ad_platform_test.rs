@@ -0,0 +1,177 @@
+use chrono::{TimeZone, Utc};
+use mongodb::bson;
+use serde_json::json;
+
+use crate::models::{
+    ad_platform::{
+        google_customer_id, AdCredentialEntry, AdPlatform, AdPlatformCredential, Conversion, GoogleAdsCredential,
+        TiktokCredential,
+    },
+    project::{FacebookCredential, Project, TokenHealth, MASKED_SECRET},
+};
+
+fn facebook() -> FacebookCredential {
+    FacebookCredential {
+        app_id: "app".to_string(),
+        app_secret: "secret".to_string(),
+        access_token: "token".to_string(),
+        ad_account_id: "act".to_string(),
+        account_suffix: "suffix".to_string(),
+        pixel_id: None,
+        link_url: None,
+        page_id: None,
+        watermark: None,
+        token_health: None,
+    }
+}
+
+fn tiktok() -> TiktokCredential {
+    TiktokCredential {
+        app_id: "app".to_string(),
+        app_secret: "secret".to_string(),
+        access_token: "token".to_string(),
+        advertiser_id: "7000000001".to_string(),
+        pixel_code: Some("PIXEL".to_string()),
+    }
+}
+
+fn google() -> GoogleAdsCredential {
+    GoogleAdsCredential {
+        developer_token: "developer".to_string(),
+        client_id: "client".to_string(),
+        client_secret: "client-secret".to_string(),
+        refresh_token: "refresh".to_string(),
+        customer_id: "123-456-7890".to_string(),
+        login_customer_id: None,
+        conversion_action_id: Some("42".to_string()),
+    }
+}
+
+fn conversion() -> Conversion {
+    serde_json::from_value(json!({ "event_name": "Purchase", "value": 10.5, "currency": "USD" })).unwrap()
+}
+
+#[test]
+fn test_credentials_are_tagged_with_their_platform() {
+    let entry = AdCredentialEntry { key: "shop".to_string(), credential: AdPlatformCredential::GoogleAds(google()) };
+    let json = serde_json::to_value(&entry).unwrap();
+    assert_eq!(json["key"], "shop");
+    assert_eq!(json["platform"], "google_ads");
+    assert_eq!(json["customer_id"], "123-456-7890");
+
+    let decoded: AdCredentialEntry = serde_json::from_value(json).unwrap();
+    assert_eq!(decoded.credential.platform(), AdPlatform::GoogleAds);
+    assert_eq!(decoded.credential.token(), "refresh");
+
+    let untagged = json!({ "app_id": "app", "app_secret": "s", "access_token": "t", "advertiser_id": "1" });
+    assert!(serde_json::from_value::<AdPlatformCredential>(untagged).is_err());
+}
+
+#[test]
+fn test_token_health_survives_a_bson_round_trip() {
+    let checked_at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let mut credential = facebook();
+    credential.token_health = Some(TokenHealth {
+        valid: true,
+        expires_at: Some(checked_at),
+        scopes: vec![],
+        error: None,
+        checked_at,
+        warned_at: None,
+    });
+    let document = bson::to_document(&AdPlatformCredential::Facebook(credential)).unwrap();
+    assert_eq!(document.get_str("platform").unwrap(), "facebook");
+    assert!(document.get_document("token_health").unwrap().get_datetime("checked_at").is_ok());
+
+    let decoded: AdPlatformCredential = bson::from_document(document).unwrap();
+    assert_eq!(decoded.as_facebook().unwrap().token_health.as_ref().unwrap().checked_at, checked_at);
+}
+
+#[test]
+fn test_validation_follows_the_platform() {
+    assert!(AdPlatformCredential::Facebook(facebook()).validate().is_ok());
+    assert!(AdPlatformCredential::Tiktok(tiktok()).validate().is_ok());
+    assert!(AdPlatformCredential::GoogleAds(google()).validate().is_ok());
+
+    let mut credential = facebook();
+    credential.access_token = " ".to_string();
+    assert_eq!(AdPlatformCredential::Facebook(credential).validate(), Err("access_token cannot be empty".to_string()));
+
+    let mut credential = tiktok();
+    credential.advertiser_id = "act_1".to_string();
+    assert!(AdPlatformCredential::Tiktok(credential).validate().is_err());
+
+    let mut credential = google();
+    credential.login_customer_id = Some("12345".to_string());
+    assert!(AdPlatformCredential::GoogleAds(credential).validate().unwrap_err().starts_with("login_customer_id"));
+
+    let mut credential = google();
+    credential.conversion_action_id = Some("customers/1/conversionActions/42".to_string());
+    assert!(AdPlatformCredential::GoogleAds(credential).validate().is_err());
+
+    assert_eq!(google_customer_id("123-456-7890"), Some("1234567890".to_string()));
+    assert_eq!(google_customer_id("123-456-789"), None);
+}
+
+#[test]
+fn test_masked_secrets_are_restored_from_the_same_platform() {
+    let stored = AdPlatformCredential::GoogleAds(google());
+    let mut incoming = stored.clone();
+    incoming.mask_secrets();
+    assert!(incoming.has_masked_secrets());
+    assert!(incoming.secrets().iter().all(|secret| *secret == MASKED_SECRET));
+
+    incoming.restore_masked_secrets(&stored).unwrap();
+    assert_eq!(incoming.secrets(), stored.secrets());
+
+    let mut tiktok = AdPlatformCredential::Tiktok(tiktok());
+    tiktok.mask_secrets();
+    assert!(tiktok.restore_masked_secrets(&stored).is_err());
+}
+
+#[test]
+fn test_projects_read_and_show_legacy_facebook_credentials() {
+    let legacy = json!({
+        "name": "Shop",
+        "is_logging": false,
+        "created_at": { "$date": { "$numberLong": "0" } },
+        "updated_at": { "$date": { "$numberLong": "0" } },
+        "facebook_credentials": { "main": serde_json::to_value(facebook()).unwrap() },
+    });
+    let mut project: Project = serde_json::from_value(legacy).unwrap();
+    assert_eq!(project.ad_credentials["main"].platform(), AdPlatform::Facebook);
+
+    project.ad_credentials.insert("tiktok".to_string(), AdPlatformCredential::Tiktok(tiktok()));
+    let json = serde_json::to_value(&project).unwrap();
+    assert_eq!(json["ad_credentials"]["tiktok"]["platform"], "tiktok");
+    assert_eq!(json["facebook_credentials"].as_object().unwrap().len(), 1);
+    assert_eq!(json["facebook_credentials"]["main"]["app_id"], "app");
+
+    // Once ad_credentials is sent, the legacy view is ignored
+    let mut both = json.clone();
+    both["facebook_credentials"] = json!({});
+    let decoded: Project = serde_json::from_value(both).unwrap();
+    assert_eq!(decoded.ad_credentials.len(), 2);
+}
+
+#[test]
+fn test_conversions_are_validated() {
+    assert!(conversion().validate(AdPlatform::Facebook).is_ok());
+
+    let mut invalid = conversion();
+    invalid.event_name = "x".repeat(101);
+    assert!(invalid.validate(AdPlatform::Tiktok).is_err());
+
+    let mut invalid = conversion();
+    invalid.value = Some(-1.0);
+    assert!(invalid.validate(AdPlatform::Facebook).is_err());
+
+    let mut invalid = conversion();
+    invalid.currency = Some("usd".to_string());
+    assert!(invalid.validate(AdPlatform::Facebook).is_err());
+
+    let mut google = conversion();
+    assert!(google.validate(AdPlatform::GoogleAds).is_err());
+    google.click_id = Some("gclid".to_string());
+    assert!(google.validate(AdPlatform::GoogleAds).is_ok());
+}
@@ -0,0 +1,135 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime, DateTime as BsonDateTime};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Scope {
+    #[serde(rename = "projects:read")]
+    ProjectsRead,
+    #[serde(rename = "projects:write")]
+    ProjectsWrite,
+    #[serde(rename = "accounts:admin")]
+    AccountsAdmin,
+    #[serde(rename = "secrets:reveal")]
+    SecretsReveal,
+    #[serde(rename = "keys:admin")]
+    KeysAdmin,
+    #[serde(rename = "orgs:admin")]
+    OrgsAdmin,
+    #[serde(rename = "webhooks:admin")]
+    WebhooksAdmin,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 7] = [
+        Scope::ProjectsRead,
+        Scope::ProjectsWrite,
+        Scope::AccountsAdmin,
+        Scope::SecretsReveal,
+        Scope::KeysAdmin,
+        Scope::OrgsAdmin,
+        Scope::WebhooksAdmin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::ProjectsRead => "projects:read",
+            Scope::ProjectsWrite => "projects:write",
+            Scope::AccountsAdmin => "accounts:admin",
+            Scope::SecretsReveal => "secrets:reveal",
+            Scope::KeysAdmin => "keys:admin",
+            Scope::OrgsAdmin => "orgs:admin",
+            Scope::WebhooksAdmin => "webhooks:admin",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiKey {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub name: String,
+    // Organization the key acts for, platform keys have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
+    // Member the key acts for, its scopes are capped by the member's role
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<ObjectId>,
+    // First characters of the key, safe to display so keys can be told apart
+    pub prefix: String,
+    // SHA-256 of the full key, the key itself is never stored
+    pub key_hash: String,
+    pub scopes: Vec<Scope>,
+    // Held by a Telegram bot frontend on behalf of its users, see `X-Telegram-User-Id`
+    #[serde(default)]
+    pub bot_frontend: bool,
+
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<BsonDateTime>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<BsonDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateApiKey {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    // Only platform keys may choose the organization, org keys mint into their own
+    #[serde(default)]
+    pub org_id: Option<ObjectId>,
+    #[serde(default)]
+    pub account_id: Option<ObjectId>,
+    #[serde(default)]
+    pub bot_frontend: bool,
+}
+
+/// API key as returned by the API, without the hash.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiKeySummary {
+    pub id: Option<String>,
+    pub name: String,
+    pub org_id: Option<String>,
+    pub account_id: Option<String>,
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    #[serde(default)]
+    pub bot_frontend: bool,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKey> for ApiKeySummary {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id.map(|id| id.to_hex()),
+            name: key.name,
+            org_id: key.org_id.map(|id| id.to_hex()),
+            account_id: key.account_id.map(|id| id.to_hex()),
+            prefix: key.prefix,
+            scopes: key.scopes,
+            bot_frontend: key.bot_frontend,
+            created_at: key.created_at,
+            revoked_at: key.revoked_at.map(|d| d.to_chrono()),
+            last_used_at: key.last_used_at.map(|d| d.to_chrono()),
+        }
+    }
+}
+
+/// Returned once when a key is minted, the plaintext key cannot be retrieved later.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreatedApiKey {
+    pub key: String,
+    pub api_key: ApiKeySummary,
+}
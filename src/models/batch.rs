@@ -0,0 +1,122 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::project::{Project, ProjectAction, ProjectStatus, StatusChange, StatusReason};
+
+// Upper bound on ids in one batch request
+pub const MAX_BATCH_SIZE: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchGetProjects {
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchUpdateProjects {
+    pub ids: Vec<String>,
+    pub operation: BatchOperation,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchOperation {
+    Activate,
+    Suspend {
+        reason: StatusReason,
+        #[serde(default)]
+        note: Option<String>,
+    },
+    Resume,
+    Cancel {
+        reason: StatusReason,
+        #[serde(default)]
+        note: Option<String>,
+    },
+    /// Moves `expires_at` to `until`, or `days` past the later of the current
+    /// expiry and now. Expired projects are renewed, other statuses are kept.
+    Extend {
+        #[serde(default)]
+        days: Option<u32>,
+        #[serde(default)]
+        until: Option<DateTime<Utc>>,
+    },
+}
+
+/// What a batch operation does to one project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchPlan {
+    Unchanged,
+    // The project's status does not allow the operation
+    Conflict(String),
+    // Update the project, taking the action when there is one
+    Apply(Option<ProjectAction>),
+}
+
+impl BatchOperation {
+    /// Status action behind the operation, `None` for `Extend`.
+    pub fn action(&self) -> Option<ProjectAction> {
+        match self {
+            BatchOperation::Activate => Some(ProjectAction::Activate),
+            BatchOperation::Suspend { .. } => Some(ProjectAction::Suspend),
+            BatchOperation::Resume => Some(ProjectAction::Resume),
+            BatchOperation::Cancel { .. } => Some(ProjectAction::Cancel),
+            BatchOperation::Extend { .. } => None,
+        }
+    }
+
+    pub fn change(&self) -> StatusChange {
+        match self {
+            BatchOperation::Suspend { reason, note } | BatchOperation::Cancel { reason, note } => StatusChange {
+                reason: Some(*reason),
+                note: note.clone(),
+            },
+            _ => StatusChange::default(),
+        }
+    }
+
+    pub fn plan(&self, project: &Project) -> BatchPlan {
+        let Some(action) = self.action() else {
+            return match project.status {
+                ProjectStatus::Cancelled => BatchPlan::Conflict("Cannot extend a cancelled project".to_string()),
+                ProjectStatus::Expired => BatchPlan::Apply(Some(ProjectAction::Renew)),
+                _ => BatchPlan::Apply(None),
+            };
+        };
+        if action.apply(project.status).is_some() {
+            BatchPlan::Apply(Some(action))
+        } else if project.status == action.target() {
+            BatchPlan::Unchanged
+        } else {
+            BatchPlan::Conflict(format!("Cannot {} a {} project", action, project.status))
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Found,
+    Updated,
+    Unchanged,
+    Conflict,
+    NotFound,
+    InvalidId,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(bound(deserialize = "P: Deserialize<'de>"))]
+pub struct BatchResult<P = Project> {
+    pub id: String,
+    pub status: BatchStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<P>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One result per requested id, in request order. `P` is how projects are
+/// sent, which differs between API versions.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchResponse<P = Project> {
+    pub results: Vec<BatchResult<P>>,
+}
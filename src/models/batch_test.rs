@@ -0,0 +1,63 @@
+use chrono::Utc;
+use serde_json::json;
+use std::collections::HashMap;
+
+use crate::models::{
+    batch::{BatchOperation, BatchPlan, BatchResult, BatchStatus, BatchUpdateProjects},
+    project::{Project, ProjectAction, ProjectStatus, StatusReason},
+};
+
+fn project(status: ProjectStatus) -> Project {
+    Project {
+        id: None,
+        org_id: None,
+        name: "Project".to_string(),
+        telegram_chat_id: None,
+        ad_credentials: HashMap::new(),
+        package: None,
+        expires_at: None,
+        status,
+        is_active: status == ProjectStatus::Active,
+        status_history: vec![],
+        is_logging: false,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_operations_are_tagged_by_type() {
+    let request: BatchUpdateProjects = serde_json::from_value(json!({
+        "ids": ["a"],
+        "operation": { "type": "extend", "days": 30 },
+    }))
+    .unwrap();
+    assert_eq!(request.operation, BatchOperation::Extend { days: Some(30), until: None });
+
+    let operation: BatchOperation = serde_json::from_value(json!({ "type": "suspend", "reason": "non_payment" })).unwrap();
+    assert_eq!(operation, BatchOperation::Suspend { reason: StatusReason::NonPayment, note: None });
+    assert!(serde_json::from_value::<BatchOperation>(json!({ "type": "suspend" })).is_err());
+    assert!(serde_json::from_value::<BatchOperation>(json!({ "type": "delete" })).is_err());
+}
+
+#[test]
+fn test_operations_are_planned_from_the_current_status() {
+    assert_eq!(BatchOperation::Activate.plan(&project(ProjectStatus::Draft)), BatchPlan::Apply(Some(ProjectAction::Activate)));
+    assert_eq!(BatchOperation::Activate.plan(&project(ProjectStatus::Active)), BatchPlan::Unchanged);
+    assert_eq!(
+        BatchOperation::Activate.plan(&project(ProjectStatus::Suspended)),
+        BatchPlan::Conflict("Cannot activate a suspended project".to_string())
+    );
+    assert_eq!(BatchOperation::Resume.plan(&project(ProjectStatus::Suspended)), BatchPlan::Apply(Some(ProjectAction::Resume)));
+
+    let extend = BatchOperation::Extend { days: Some(1), until: None };
+    assert_eq!(extend.plan(&project(ProjectStatus::Expired)), BatchPlan::Apply(Some(ProjectAction::Renew)));
+    assert_eq!(extend.plan(&project(ProjectStatus::Suspended)), BatchPlan::Apply(None));
+    assert!(matches!(extend.plan(&project(ProjectStatus::Cancelled)), BatchPlan::Conflict(_)));
+}
+
+#[test]
+fn test_missing_projects_are_omitted_from_results() {
+    let result: BatchResult = BatchResult { id: "x".to_string(), status: BatchStatus::NotFound, project: None, error: None };
+    assert_eq!(serde_json::to_value(result).unwrap(), json!({ "id": "x", "status": "not_found" }));
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime, DateTime as BsonDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::models::{account::Account, organization::OrgRole, project::Project, webhook::WebhookEvent};
+
+/// Something that happened to an entity, recorded in the outbox together
+/// with the write that caused it. Projects never carry credential secrets.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum DomainEvent {
+    ProjectCreated { project: Project },
+    ProjectUpdated { project: Project },
+    ProjectDeleted { project_id: ObjectId },
+    ProjectExpired { project: Project },
+    AccountCreated { account: Account },
+    AccountUpdated { account: Account },
+    AccountDeleted { account_id: ObjectId },
+    // Account added to its organization's members, or given another role
+    AccountLinked { account_id: ObjectId, role: OrgRole },
+    AccountUnlinked { account_id: ObjectId },
+}
+
+impl DomainEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::ProjectCreated { .. } => "project_created",
+            DomainEvent::ProjectUpdated { .. } => "project_updated",
+            DomainEvent::ProjectDeleted { .. } => "project_deleted",
+            DomainEvent::ProjectExpired { .. } => "project_expired",
+            DomainEvent::AccountCreated { .. } => "account_created",
+            DomainEvent::AccountUpdated { .. } => "account_updated",
+            DomainEvent::AccountDeleted { .. } => "account_deleted",
+            DomainEvent::AccountLinked { .. } => "account_linked",
+            DomainEvent::AccountUnlinked { .. } => "account_unlinked",
+        }
+    }
+
+    /// Project the event is about, if any.
+    pub fn project_id(&self) -> Option<ObjectId> {
+        match self {
+            DomainEvent::ProjectCreated { project }
+            | DomainEvent::ProjectUpdated { project }
+            | DomainEvent::ProjectExpired { project } => project.id,
+            DomainEvent::ProjectDeleted { project_id } => Some(*project_id),
+            _ => None,
+        }
+    }
+
+    /// Entity the event is about and its id.
+    pub fn entity(&self) -> Option<(EventEntity, ObjectId)> {
+        match self {
+            DomainEvent::AccountCreated { account } | DomainEvent::AccountUpdated { account } => {
+                Some((EventEntity::Account, account.id?))
+            }
+            DomainEvent::AccountDeleted { account_id }
+            | DomainEvent::AccountLinked { account_id, .. }
+            | DomainEvent::AccountUnlinked { account_id } => Some((EventEntity::Account, *account_id)),
+            _ => Some((EventEntity::Project, self.project_id()?)),
+        }
+    }
+
+    /// The event as webhooks and the event stream publish it, with projects
+    /// sent as `P`. `None` for events that are not published.
+    pub fn published<P: From<Project> + Serialize>(&self) -> Option<(WebhookEvent, Value)> {
+        let project = |project: &Project| json!(P::from(project.clone()));
+        Some(match self {
+            DomainEvent::ProjectCreated { project: p } => (WebhookEvent::ProjectCreated, project(p)),
+            DomainEvent::ProjectUpdated { project: p } => (WebhookEvent::ProjectUpdated, project(p)),
+            DomainEvent::ProjectExpired { project: p } => (WebhookEvent::ProjectExpired, project(p)),
+            DomainEvent::ProjectDeleted { project_id } => {
+                (WebhookEvent::ProjectDeleted, json!({ "id": project_id.to_hex() }))
+            }
+            DomainEvent::AccountCreated { account } => (WebhookEvent::AccountCreated, json!(account)),
+            DomainEvent::AccountUpdated { account } => (WebhookEvent::AccountUpdated, json!(account)),
+            DomainEvent::AccountDeleted { account_id } => {
+                (WebhookEvent::AccountDeleted, json!({ "id": account_id.to_hex() }))
+            }
+            // Membership changes are not published
+            DomainEvent::AccountLinked { .. } | DomainEvent::AccountUnlinked { .. } => return None,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventEntity {
+    Project,
+    Account,
+}
+
+/// Query of `GET /events/stream`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EventStreamQuery {
+    #[serde(default)]
+    pub entity: Option<EventEntity>,
+    // Only events about the entity with this id
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+/// Which events a stream passes on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventFilter {
+    pub entities: Vec<EventEntity>,
+    pub id: Option<ObjectId>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &DomainEvent) -> bool {
+        let Some((entity, id)) = event.entity() else {
+            return false;
+        };
+        self.entities.contains(&entity) && self.id.is_none_or(|wanted| wanted == id)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutboxStatus {
+    Pending,
+    Dispatched,
+    // A subscriber kept failing, left for inspection
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutboxEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub org_id: ObjectId,
+    // Copy of the event name so the collection can be queried by it
+    pub event_type: String,
+    pub event: DomainEvent,
+    pub status: OutboxStatus,
+    // Subscribers that already handled the event and are skipped on retries
+    #[serde(default)]
+    pub handled_by: Vec<String>,
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+
+    // Also serves as a lease while the dispatcher works on the entry
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub next_attempt_at: DateTime<Utc>,
+
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispatched_at: Option<BsonDateTime>,
+}
+
+impl OutboxEntry {
+    pub fn new(org_id: ObjectId, event: DomainEvent) -> Self {
+        let now = Utc::now();
+        Self {
+            id: None,
+            org_id,
+            event_type: event.name().to_string(),
+            event,
+            status: OutboxStatus::Pending,
+            handled_by: vec![],
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: now,
+            created_at: now,
+            dispatched_at: None,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{
+    oid::ObjectId,
+    serde_helpers::{chrono_datetime_as_bson_datetime, chrono_datetime_as_bson_datetime_optional},
+};
+use serde::{Deserialize, Serialize};
+
+pub const EVENT_ID_PREFIX: &str = "ev_";
+
+/// An event id of a project, shared by a browser pixel fire and the server
+/// event for the same conversion so that the platform counts it once.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventIdRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
+    pub project_id: ObjectId,
+    // Unique per project
+    pub event_id: String,
+    pub event_name: String,
+    // Set when the id was issued for a pixel fire, absent for ids first seen on a server event
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "chrono_datetime_as_bson_datetime_optional")]
+    pub issued_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "chrono_datetime_as_bson_datetime_optional")]
+    pub server_sent_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_key: Option<String>,
+    // Server events with this id that were suppressed
+    #[serde(default)]
+    pub duplicates: i64,
+    // Removed by a TTL index once the dedup window has passed
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Body of `POST /projects/:id/event-ids`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventIdRequest {
+    pub event_name: String,
+}
+
+/// An event id to fire the pixel with, e.g. `fbq('track', event_name, {}, { eventID: event_id })`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IssuedEventId {
+    pub event_id: String,
+    pub event_name: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// What became of a server event.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversionStatus {
+    Sent,
+    // Already sent with this event id within the dedup window, not sent again
+    Duplicate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConversionReceipt {
+    pub event_id: String,
+    pub status: ConversionStatus,
+}
+
+/// Event id counts of a project over the records still in the dedup window.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+pub struct EventIdCounts {
+    #[serde(default)]
+    pub issued: u64,
+    #[serde(default)]
+    pub server_events: u64,
+    // Server events whose id was issued for a pixel fire
+    #[serde(default)]
+    pub matched: u64,
+    #[serde(default)]
+    pub duplicates: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DedupStats {
+    pub window_hours: u64,
+    pub issued: u64,
+    pub server_events: u64,
+    pub matched: u64,
+    pub server_only: u64,
+    pub duplicates_suppressed: u64,
+    // Share of server events the platform can deduplicate against a pixel fire
+    pub dedup_rate: f64,
+    // Share of server events received that were suppressed as duplicates
+    pub duplicate_rate: f64,
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    match whole {
+        0 => 0.0,
+        whole => part as f64 / whole as f64,
+    }
+}
+
+impl DedupStats {
+    pub fn new(counts: EventIdCounts, window_hours: u64) -> Self {
+        Self {
+            window_hours,
+            issued: counts.issued,
+            server_events: counts.server_events,
+            matched: counts.matched,
+            server_only: counts.server_events.saturating_sub(counts.matched),
+            duplicates_suppressed: counts.duplicates,
+            dedup_rate: ratio(counts.matched, counts.server_events),
+            duplicate_rate: ratio(counts.duplicates, counts.server_events + counts.duplicates),
+        }
+    }
+}
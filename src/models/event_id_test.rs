@@ -0,0 +1,42 @@
+use crate::{
+    models::{
+        ad_platform::validate_event_id,
+        event_id::{ConversionReceipt, ConversionStatus, DedupStats, EventIdCounts},
+    },
+    service::ad_platform_service::generate_event_id,
+};
+
+#[test]
+fn test_dedup_rates() {
+    let counts = EventIdCounts { issued: 10, server_events: 8, matched: 6, duplicates: 2 };
+    let stats = DedupStats::new(counts, 48);
+    assert_eq!((stats.server_only, stats.duplicates_suppressed), (2, 2));
+    assert_eq!((stats.dedup_rate, stats.duplicate_rate), (0.75, 0.2));
+
+    let empty = DedupStats::new(EventIdCounts::default(), 48);
+    assert_eq!((empty.dedup_rate, empty.duplicate_rate), (0.0, 0.0));
+}
+
+#[test]
+fn test_generated_event_ids_are_valid() {
+    let event_id = generate_event_id();
+    assert!(event_id.starts_with("ev_"));
+    assert_eq!(event_id.len(), 27);
+    assert!(validate_event_id(&event_id).is_ok());
+    assert_ne!(event_id, generate_event_id());
+}
+
+#[test]
+fn test_event_ids_are_printable_ascii() {
+    assert!(validate_event_id("order-1").is_ok());
+    assert!(validate_event_id("").is_err());
+    assert!(validate_event_id("order 1\n").is_err());
+    assert!(validate_event_id("заказ-1").is_err());
+    assert!(validate_event_id(&"x".repeat(129)).is_err());
+}
+
+#[test]
+fn test_receipt_status_is_snake_case() {
+    let receipt = ConversionReceipt { event_id: "ev_1".to_string(), status: ConversionStatus::Duplicate };
+    assert_eq!(serde_json::to_value(&receipt).unwrap(), serde_json::json!({ "event_id": "ev_1", "status": "duplicate" }));
+}
@@ -0,0 +1,90 @@
+use chrono::{TimeZone, Utc};
+use mongodb::bson::{from_document, oid::ObjectId, to_document};
+use std::collections::HashMap;
+
+use crate::models::{
+    event::{DomainEvent, EventEntity, EventFilter, OutboxEntry, OutboxStatus},
+    organization::OrgRole,
+    project::{Project, ProjectStatus, ProjectV2},
+    webhook::WebhookEvent,
+};
+
+fn project() -> Project {
+    Project {
+        id: Some(ObjectId::new()),
+        org_id: Some(ObjectId::new()),
+        name: "Outbox".to_string(),
+        telegram_chat_id: None,
+        ad_credentials: HashMap::new(),
+        package: None,
+        expires_at: Some(Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap()),
+        status: ProjectStatus::Suspended,
+        is_active: false,
+        status_history: vec![],
+        is_logging: false,
+        created_at: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        updated_at: Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap(),
+    }
+}
+
+#[test]
+fn test_outbox_entries_round_trip_through_bson() {
+    let project = project();
+    let entry = OutboxEntry::new(project.org_id.unwrap(), DomainEvent::ProjectExpired { project: project.clone() });
+
+    let doc = to_document(&entry).unwrap();
+    assert_eq!(doc.get_str("event_type").unwrap(), "project_expired");
+    assert!(doc.get_document("event").unwrap().contains_key("project_expired"));
+
+    let decoded: OutboxEntry = from_document(doc).unwrap();
+    assert_eq!(decoded.status, OutboxStatus::Pending);
+    assert_eq!(decoded.event.project_id(), project.id);
+    match decoded.event {
+        DomainEvent::ProjectExpired { project: decoded } => {
+            assert_eq!(decoded.name, project.name);
+            assert_eq!(decoded.expires_at, project.expires_at);
+            assert_eq!(decoded.updated_at, project.updated_at);
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    let account_id = ObjectId::new();
+    let linked = OutboxEntry::new(ObjectId::new(), DomainEvent::AccountLinked { account_id, role: OrgRole::Admin });
+    let decoded: OutboxEntry = from_document(to_document(&linked).unwrap()).unwrap();
+    assert!(matches!(
+        decoded.event,
+        DomainEvent::AccountLinked { account_id: id, role: OrgRole::Admin } if id == account_id
+    ));
+    assert_eq!(decoded.event.project_id(), None);
+}
+
+#[test]
+fn test_events_are_published_and_filtered_by_entity() {
+    let project = project();
+    let project_id = project.id.unwrap();
+    let updated = DomainEvent::ProjectUpdated { project };
+    let deleted = DomainEvent::AccountDeleted { account_id: ObjectId::new() };
+    let linked = DomainEvent::AccountLinked { account_id: ObjectId::new(), role: OrgRole::Viewer };
+
+    let (event, data) = updated.published::<Project>().unwrap();
+    assert_eq!(event, WebhookEvent::ProjectUpdated);
+    assert_eq!(data["is_active"], false);
+    let (_, data) = updated.published::<ProjectV2>().unwrap();
+    assert!(data.get("is_active").is_none());
+    assert_eq!(data["status"], "suspended");
+    let (event, data) = deleted.published::<Project>().unwrap();
+    assert_eq!(event, WebhookEvent::AccountDeleted);
+    assert!(data["id"].is_string());
+    assert!(linked.published::<Project>().is_none());
+
+    let projects = EventFilter { entities: vec![EventEntity::Project], id: None };
+    assert_eq!(updated.entity(), Some((EventEntity::Project, project_id)));
+    assert!(projects.matches(&updated));
+    assert!(!projects.matches(&deleted));
+    let one = EventFilter {
+        entities: vec![EventEntity::Project, EventEntity::Account],
+        id: Some(ObjectId::new()),
+    };
+    assert!(!one.matches(&updated));
+    assert!(EventFilter { id: Some(project_id), ..one }.matches(&updated));
+}
@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime;
+use serde::{Deserialize, Serialize};
+
+/// A request seen with an `Idempotency-Key`, and its response once it completed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IdempotencyRecord {
+    // Hash of the caller, route and key, so keys never collide across callers
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub request_hash: String,
+    // Unset while the first request is still being handled
+    #[serde(default)]
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+
+    // Removed by a TTL index once passed
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+// Keeps an atomic import's validated rows bounded in memory
+pub const MAX_IMPORT_ROWS: usize = 10_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Nothing is written unless every row is valid
+    #[default]
+    Atomic,
+    /// Valid rows are written, invalid ones are reported and skipped
+    BestEffort,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ImportParams {
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ExportParams {
+    /// `ndjson` or `csv`, defaults to the `Accept` header and then NDJSON
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RowError {
+    /// 1-based position among the data rows, a CSV header does not count
+    pub row: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    pub total: usize,
+    pub imported: usize,
+    pub failed: usize,
+    pub errors: Vec<RowError>,
+}
+
+impl ImportReport {
+    pub fn new(mode: ImportMode) -> Self {
+        Self {
+            mode,
+            total: 0,
+            imported: 0,
+            failed: 0,
+            errors: vec![],
+        }
+    }
+
+    pub fn reject(&mut self, row: usize, error: impl Into<String>) {
+        self.failed += 1;
+        self.errors.push(RowError { row, error: error.into() });
+    }
+}
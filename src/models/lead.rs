@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{
+    oid::ObjectId,
+    serde_helpers::{chrono_datetime_as_bson_datetime, chrono_datetime_as_bson_datetime_optional},
+};
+use serde::{Deserialize, Serialize};
+
+/// A Lead Ads lead received for one of a project's pages.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Lead {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
+    pub project_id: ObjectId,
+    // Unique per project, Facebook redelivers notifications it thinks failed
+    pub leadgen_id: String,
+    pub page_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ad_id: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<LeadField>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub received_at: DateTime<Utc>,
+    // Set once the lead was fetched and forwarded to the project's Telegram chat, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "chrono_datetime_as_bson_datetime_optional")]
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+/// One answer of the lead form.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LeadField {
+    pub name: String,
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+/// Body of a Webhooks notification for the `page` object.
+#[derive(Debug, Deserialize)]
+pub struct PageNotification {
+    pub object: String,
+    #[serde(default)]
+    pub entry: Vec<PageEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PageEntry {
+    // The page id
+    pub id: String,
+    #[serde(default)]
+    pub changes: Vec<PageChange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PageChange {
+    pub field: String,
+    pub value: serde_json::Value,
+}
+
+/// The `value` of a `leadgen` change.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct LeadgenChange {
+    pub leadgen_id: String,
+    pub page_id: String,
+    #[serde(default)]
+    pub form_id: Option<String>,
+    #[serde(default)]
+    pub ad_id: Option<String>,
+}
+
+impl PageNotification {
+    /// The leadgen changes of every entry, other fields are ignored.
+    pub fn leadgen_changes(&self) -> Vec<LeadgenChange> {
+        self.entry
+            .iter()
+            .flat_map(|entry| &entry.changes)
+            .filter(|change| change.field == "leadgen")
+            .filter_map(|change| serde_json::from_value(change.value.clone()).ok())
+            .collect()
+    }
+}
+
+/// Query of the Webhooks verification request.
+#[derive(Debug, Deserialize)]
+pub struct SubscriptionVerification {
+    #[serde(rename = "hub.mode")]
+    pub mode: Option<String>,
+    #[serde(rename = "hub.verify_token")]
+    pub verify_token: Option<String>,
+    #[serde(rename = "hub.challenge")]
+    pub challenge: Option<String>,
+}
+
+// Telegram rejects messages longer than 4096 characters
+const MAX_MESSAGE_CHARS: usize = 4000;
+
+impl Lead {
+    /// The Telegram message announcing the lead.
+    pub fn message(&self, project_name: &str) -> String {
+        let mut text = format!("New lead for {}", project_name);
+        if let Some(form_id) = &self.form_id {
+            text.push_str(&format!("\nForm: {}", form_id));
+        }
+        if let Some(ad_id) = &self.ad_id {
+            text.push_str(&format!("\nAd: {}", ad_id));
+        }
+        text.push('\n');
+        for field in &self.fields {
+            text.push_str(&format!("\n{}: {}", field.name, field.values.join(", ")));
+        }
+        match text.char_indices().nth(MAX_MESSAGE_CHARS) {
+            Some((end, _)) => format!("{}…", &text[..end]),
+            None => text,
+        }
+    }
+}
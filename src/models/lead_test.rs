@@ -0,0 +1,56 @@
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use serde_json::json;
+
+use crate::models::lead::{Lead, LeadField, LeadgenChange, PageNotification};
+
+#[test]
+fn test_only_leadgen_changes_are_read() {
+    let notification: PageNotification = serde_json::from_value(json!({
+        "object": "page",
+        "entry": [{
+            "id": "555",
+            "time": 1700000000,
+            "changes": [
+                { "field": "leadgen", "value": { "leadgen_id": "1001", "page_id": "555", "form_id": "77", "created_time": 1700000000 } },
+                { "field": "feed", "value": { "item": "post" } },
+                { "field": "leadgen", "value": { "page_id": "555" } },
+            ],
+        }],
+    }))
+    .unwrap();
+
+    assert_eq!(
+        notification.leadgen_changes(),
+        vec![LeadgenChange {
+            leadgen_id: "1001".to_string(),
+            page_id: "555".to_string(),
+            form_id: Some("77".to_string()),
+            ad_id: None,
+        }]
+    );
+}
+
+#[test]
+fn test_lead_message_lists_the_answers() {
+    let mut lead = Lead {
+        id: None,
+        org_id: None,
+        project_id: ObjectId::new(),
+        leadgen_id: "1001".to_string(),
+        page_id: "555".to_string(),
+        form_id: Some("77".to_string()),
+        ad_id: None,
+        fields: vec![
+            LeadField { name: "full_name".to_string(), values: vec!["Ann".to_string()] },
+            LeadField { name: "interests".to_string(), values: vec!["ads".to_string(), "bots".to_string()] },
+        ],
+        received_at: Utc::now(),
+        processed_at: None,
+    };
+
+    assert_eq!(lead.message("Shop"), "New lead for Shop\nForm: 77\n\nfull_name: Ann\ninterests: ads, bots");
+
+    lead.fields = vec![LeadField { name: "note".to_string(), values: vec!["x".repeat(5000)] }];
+    assert_eq!(lead.message("Shop").chars().count(), 4001);
+}
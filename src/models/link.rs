@@ -0,0 +1,187 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::http_client::HttpClient;
+
+pub const CODE_LENGTH: usize = 8;
+pub const MAX_UTM_LENGTH: usize = 200;
+pub const DEFAULT_UTM_SOURCE: &str = "facebook";
+pub const DEFAULT_UTM_MEDIUM: &str = "paid_social";
+// Days covered by the per-day click counts of link stats
+pub const STATS_DAYS: i64 = 90;
+pub const TOP_REFERRERS: i64 = 10;
+
+/// UTM parameters appended to a link's destination.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Utm {
+    pub source: String,
+    pub medium: String,
+    pub campaign: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+}
+
+impl Utm {
+    fn params(&self) -> Vec<(&'static str, &str)> {
+        let mut params = vec![
+            ("utm_source", self.source.as_str()),
+            ("utm_medium", self.medium.as_str()),
+            ("utm_campaign", self.campaign.as_str()),
+        ];
+        if let Some(content) = &self.content {
+            params.push(("utm_content", content));
+        }
+        if let Some(term) = &self.term {
+            params.push(("utm_term", term));
+        }
+        params
+    }
+
+    /// `link_url` with these parameters, replacing any UTM parameters it already has.
+    pub fn apply(&self, link_url: &str) -> Result<String, String> {
+        let mut url: Url = HttpClient::validate_url(link_url)?;
+        let kept: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| !name.starts_with("utm_"))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(kept).extend_pairs(self.params());
+        Ok(url.to_string())
+    }
+}
+
+/// A short code redirecting to a credential's `link_url` with UTM parameters.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrackedLink {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
+    pub project_id: ObjectId,
+    pub credential_key: String,
+    // Unique across organizations, `/l/:code` is not scoped
+    pub code: String,
+    pub utm: Utm,
+    // The credential's link_url with the UTM parameters, as of when the link was created
+    pub destination: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// A link as returned by the API, with its full short URL when `LINK_BASE_URL` is set.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkView {
+    #[serde(flatten)]
+    pub link: TrackedLink,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_url: Option<String>,
+}
+
+/// Body of `POST /projects/:id/links`. Source and medium default to
+/// `facebook` and `paid_social`, content to the credential's `account_suffix`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewLink {
+    pub credential_key: String,
+    pub campaign: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub medium: Option<String>,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub term: Option<String>,
+}
+
+impl NewLink {
+    /// The link's UTM parameters, filling in the defaults.
+    pub fn utm(&self, account_suffix: &str) -> Result<Utm, String> {
+        let value = |field: &str, value: Option<&String>, default: Option<&str>| -> Result<Option<String>, String> {
+            let value = value.map(|v| v.trim()).filter(|v| !v.is_empty()).or(default.filter(|d| !d.is_empty()));
+            match value {
+                Some(value) if value.chars().count() > MAX_UTM_LENGTH => {
+                    Err(format!("{} cannot be longer than {} characters", field, MAX_UTM_LENGTH))
+                }
+                value => Ok(value.map(str::to_string)),
+            }
+        };
+        Ok(Utm {
+            source: value("source", self.source.as_ref(), Some(DEFAULT_UTM_SOURCE))?.unwrap_or_default(),
+            medium: value("medium", self.medium.as_ref(), Some(DEFAULT_UTM_MEDIUM))?.unwrap_or_default(),
+            campaign: value("campaign", Some(&self.campaign), None)?.ok_or("campaign cannot be empty")?,
+            content: value("content", self.content.as_ref(), Some(account_suffix))?,
+            term: value("term", self.term.as_ref(), None)?,
+        })
+    }
+}
+
+/// One follow of a link.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LinkClick {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub link_id: ObjectId,
+    pub org_id: Option<ObjectId>,
+    pub project_id: ObjectId,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub clicked_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub referrer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    // Click id Facebook appends to ad links, passed on to the destination
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fbclid: Option<String>,
+}
+
+/// `destination` with the click's `fbclid`, so the site sees it as if linked directly.
+pub fn redirect_url(destination: &str, fbclid: Option<&str>) -> String {
+    match (Url::parse(destination), fbclid) {
+        (Ok(mut url), Some(fbclid)) => {
+            let kept: Vec<(String, String)> = url
+                .query_pairs()
+                .filter(|(name, _)| name != "fbclid")
+                .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                .collect();
+            url.query_pairs_mut().clear().extend_pairs(kept).append_pair("fbclid", fbclid);
+            url.to_string()
+        }
+        _ => destination.to_string(),
+    }
+}
+
+/// Whether `code` could have been issued, to skip lookups of anything else.
+pub fn is_code(code: &str) -> bool {
+    code.len() == CODE_LENGTH && code.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LinkStats {
+    pub link_id: ObjectId,
+    pub code: String,
+    pub clicks: u64,
+    pub fbclid_clicks: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_click_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_click_at: Option<DateTime<Utc>>,
+    // UTC days of the last `STATS_DAYS`, oldest first, days without clicks left out
+    pub by_day: Vec<DailyClicks>,
+    pub top_referrers: Vec<ReferrerClicks>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DailyClicks {
+    pub date: String,
+    pub clicks: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReferrerClicks {
+    pub referrer: String,
+    pub clicks: u64,
+}
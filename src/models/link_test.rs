@@ -0,0 +1,72 @@
+use crate::{
+    models::link::{is_code, redirect_url, NewLink, Utm, MAX_UTM_LENGTH},
+    service::link_service::generate_code,
+};
+
+fn new_link(campaign: &str) -> NewLink {
+    NewLink {
+        credential_key: "main".to_string(),
+        campaign: campaign.to_string(),
+        source: None,
+        medium: None,
+        content: None,
+        term: None,
+    }
+}
+
+#[test]
+fn test_utm_defaults_to_facebook_and_the_account_suffix() {
+    let utm = new_link(" spring ").utm("eu-1").unwrap();
+    assert_eq!(
+        utm,
+        Utm {
+            source: "facebook".to_string(),
+            medium: "paid_social".to_string(),
+            campaign: "spring".to_string(),
+            content: Some("eu-1".to_string()),
+            term: None,
+        }
+    );
+    assert_eq!(new_link("spring").utm("").unwrap().content, None);
+
+    let mut custom = new_link("spring");
+    custom.source = Some("instagram".to_string());
+    custom.content = Some("video".to_string());
+    let utm = custom.utm("eu-1").unwrap();
+    assert_eq!((utm.source.as_str(), utm.content.as_deref()), ("instagram", Some("video")));
+
+    assert!(new_link(" ").utm("eu-1").is_err());
+    assert!(new_link(&"c".repeat(MAX_UTM_LENGTH + 1)).utm("eu-1").is_err());
+}
+
+#[test]
+fn test_utm_replaces_the_link_url_utm_parameters() {
+    let utm = new_link("spring sale").utm("eu-1").unwrap();
+    let destination = utm.apply("https://shop.example.com/landing?ref=ads&utm_source=old").unwrap();
+    assert_eq!(
+        destination,
+        "https://shop.example.com/landing?ref=ads&utm_source=facebook&utm_medium=paid_social&utm_campaign=spring+sale&utm_content=eu-1"
+    );
+
+    assert!(utm.apply("javascript:alert(1)").is_err());
+    assert!(utm.apply("shop.example.com").is_err());
+}
+
+#[test]
+fn test_redirects_pass_the_fbclid_on() {
+    let destination = "https://shop.example.com/?utm_source=facebook&fbclid=stale";
+    assert_eq!(
+        redirect_url(destination, Some("IwAR0abc")),
+        "https://shop.example.com/?utm_source=facebook&fbclid=IwAR0abc"
+    );
+    assert_eq!(redirect_url(destination, None), destination);
+}
+
+#[test]
+fn test_generated_codes_are_codes() {
+    let code = generate_code();
+    assert!(is_code(&code), "{}", code);
+    assert_ne!(code, generate_code());
+    assert!(!is_code("abc"));
+    assert!(!is_code("abcd-efg"));
+}
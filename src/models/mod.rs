@@ -1,2 +1,30 @@
 pub mod project;
-pub mod account;
\ No newline at end of file
+pub mod account;
+pub mod health;
+pub mod api_key;
+pub mod organization;
+pub mod idempotency;
+pub mod webhook;
+pub mod event;
+pub mod import;
+pub mod batch;
+pub mod lead;
+pub mod ad_platform;
+pub mod link;
+pub mod event_id;
+#[cfg(test)]
+mod project_test;
+#[cfg(test)]
+mod organization_test;
+#[cfg(test)]
+mod event_test;
+#[cfg(test)]
+mod batch_test;
+#[cfg(test)]
+mod lead_test;
+#[cfg(test)]
+mod ad_platform_test;
+#[cfg(test)]
+mod link_test;
+#[cfg(test)]
+mod event_id_test;
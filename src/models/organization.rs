@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime};
+use serde::{Deserialize, Serialize};
+
+use crate::models::api_key::Scope;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum OrgRole {
+    Viewer,
+    Admin,
+    Owner,
+}
+
+impl OrgRole {
+    /// Whether an API key acting for a member with this role may use `scope`.
+    /// Managing organizations themselves is reserved for platform keys.
+    pub fn allows(&self, scope: Scope) -> bool {
+        match self {
+            OrgRole::Owner => scope != Scope::OrgsAdmin,
+            OrgRole::Admin => !matches!(scope, Scope::SecretsReveal | Scope::OrgsAdmin),
+            OrgRole::Viewer => scope == Scope::ProjectsRead,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct OrganizationMember {
+    pub account_id: ObjectId,
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Organization {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub name: String,
+    #[serde(default)]
+    pub members: Vec<OrganizationMember>,
+
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Organization {
+    pub fn role_of(&self, account_id: &ObjectId) -> Option<OrgRole> {
+        self.members
+            .iter()
+            .find(|m| &m.account_id == account_id)
+            .map(|m| m.role)
+    }
+
+    fn owner_count(&self) -> usize {
+        self.members.iter().filter(|m| m.role == OrgRole::Owner).count()
+    }
+
+    /// Adds a member or changes their role. `actor` is the role of the caller,
+    /// `None` for platform keys which act as owners.
+    pub fn set_member(&mut self, account_id: ObjectId, role: OrgRole, actor: Option<OrgRole>) -> Result<(), String> {
+        let current = self.role_of(&account_id);
+        if (role == OrgRole::Owner || current == Some(OrgRole::Owner)) && !acts_as_owner(actor) {
+            return Err("Only owners can grant or revoke the owner role".to_string());
+        }
+        if current == Some(OrgRole::Owner) && role != OrgRole::Owner && self.owner_count() == 1 {
+            return Err("An organization must keep at least one owner".to_string());
+        }
+
+        match self.members.iter_mut().find(|m| m.account_id == account_id) {
+            Some(member) => member.role = role,
+            None => self.members.push(OrganizationMember { account_id, role }),
+        }
+        Ok(())
+    }
+
+    /// Removes a member, returning false when the account was not one.
+    pub fn remove_member(&mut self, account_id: &ObjectId, actor: Option<OrgRole>) -> Result<bool, String> {
+        match self.role_of(account_id) {
+            None => return Ok(false),
+            Some(OrgRole::Owner) => {
+                if !acts_as_owner(actor) {
+                    return Err("Only owners can grant or revoke the owner role".to_string());
+                }
+                if self.owner_count() == 1 {
+                    return Err("An organization must keep at least one owner".to_string());
+                }
+            }
+            Some(_) => {}
+        }
+        self.members.retain(|m| &m.account_id != account_id);
+        Ok(true)
+    }
+}
+
+// Platform keys are not members and act with owner rights
+fn acts_as_owner(actor: Option<OrgRole>) -> bool {
+    actor.is_none_or(|role| role == OrgRole::Owner)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateOrganization {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetMemberRole {
+    pub role: OrgRole,
+}
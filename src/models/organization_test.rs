@@ -0,0 +1,58 @@
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+
+use crate::models::{
+    api_key::Scope,
+    organization::{OrgRole, Organization, OrganizationMember},
+};
+
+fn organization_with_owner(owner: ObjectId) -> Organization {
+    Organization {
+        id: Some(ObjectId::new()),
+        name: "Agency".to_string(),
+        members: vec![OrganizationMember { account_id: owner, role: OrgRole::Owner }],
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_role_scopes() {
+    assert!(OrgRole::Viewer.allows(Scope::ProjectsRead));
+    assert!(!OrgRole::Viewer.allows(Scope::ProjectsWrite));
+    assert!(OrgRole::Admin.allows(Scope::KeysAdmin));
+    assert!(!OrgRole::Admin.allows(Scope::SecretsReveal));
+    assert!(OrgRole::Owner.allows(Scope::SecretsReveal));
+    assert!(!OrgRole::Owner.allows(Scope::OrgsAdmin));
+}
+
+#[test]
+fn test_only_owners_manage_owners() {
+    let owner = ObjectId::new();
+    let member = ObjectId::new();
+    let mut org = organization_with_owner(owner);
+
+    org.set_member(member, OrgRole::Viewer, Some(OrgRole::Admin)).unwrap();
+    assert_eq!(org.role_of(&member), Some(OrgRole::Viewer));
+
+    assert!(org.set_member(member, OrgRole::Owner, Some(OrgRole::Admin)).is_err());
+    assert!(org.remove_member(&owner, Some(OrgRole::Admin)).is_err());
+
+    org.set_member(member, OrgRole::Owner, Some(OrgRole::Owner)).unwrap();
+    assert_eq!(org.role_of(&member), Some(OrgRole::Owner));
+}
+
+#[test]
+fn test_last_owner_cannot_leave() {
+    let owner = ObjectId::new();
+    let mut org = organization_with_owner(owner);
+
+    assert!(org.set_member(owner, OrgRole::Admin, None).is_err());
+    assert!(org.remove_member(&owner, None).is_err());
+    assert_eq!(org.remove_member(&ObjectId::new(), None), Ok(false));
+
+    let second = ObjectId::new();
+    org.set_member(second, OrgRole::Owner, None).unwrap();
+    assert_eq!(org.remove_member(&owner, None), Ok(true));
+    assert_eq!(org.members.len(), 1);
+}
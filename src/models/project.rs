@@ -1,7 +1,16 @@
 use chrono::{DateTime, Utc};
-use mongodb::bson::{oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime};
+use mongodb::bson::{
+    oid::ObjectId,
+    serde_helpers::{chrono_datetime_as_bson_datetime, chrono_datetime_as_bson_datetime_optional},
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crate::models::ad_platform::{self, AdPlatformCredential};
+
+// Placeholder returned instead of secrets to callers without `secrets:reveal`
+pub const MASKED_SECRET: &str = "********";
+pub const MAX_CREDENTIAL_KEY_LENGTH: usize = 64;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Watermark {
@@ -23,6 +32,76 @@ pub struct FacebookCredential {
     pub page_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub watermark: Option<Watermark>,
+    // Written by the token health checker, ignored when sent by clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_health: Option<TokenHealth>,
+}
+
+/// What Facebook's `debug_token` last reported about a credential's access token.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TokenHealth {
+    pub valid: bool,
+    // Absent for tokens that never expire
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "chrono_datetime_as_bson_datetime_optional")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub checked_at: DateTime<Utc>,
+    // Set once the project's Telegram chat was told the token needs attention
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "chrono_datetime_as_bson_datetime_optional")]
+    pub warned_at: Option<DateTime<Utc>>,
+}
+
+/// A token health check result for one credential, with the long-lived
+/// token that replaces its access token when one was exchanged.
+#[derive(Debug, Clone)]
+pub struct TokenHealthUpdate {
+    pub key: String,
+    pub health: TokenHealth,
+    pub access_token: Option<String>,
+}
+
+impl TokenHealth {
+    /// Whether the token is invalid or expires within `warn_before` of `now`.
+    pub fn needs_attention(&self, now: DateTime<Utc>, warn_before: chrono::Duration) -> bool {
+        !self.valid || self.expires_at.is_some_and(|at| at - now <= warn_before)
+    }
+}
+
+impl FacebookCredential {
+    /// Carries the stored token health over while the access token is
+    /// unchanged, a new token starts without one.
+    pub fn keep_token_health(&mut self, stored: Option<&FacebookCredential>) {
+        self.token_health = stored
+            .filter(|stored| stored.access_token == self.access_token)
+            .and_then(|stored| stored.token_health.clone());
+    }
+}
+
+/// Credential keys become part of a BSON field path (`ad_credentials.<key>`),
+/// so they are limited to ASCII letters, digits, `_` and `-`.
+pub fn validate_credential_key(key: &str) -> Result<(), String> {
+    if key.is_empty() {
+        return Err("Credential key cannot be empty".to_string());
+    }
+    if key.len() > MAX_CREDENTIAL_KEY_LENGTH {
+        return Err(format!("Credential key cannot be longer than {} characters", MAX_CREDENTIAL_KEY_LENGTH));
+    }
+    if !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("Credential key {:?} may only contain letters, digits, '_' and '-'", key));
+    }
+    Ok(())
+}
+
+/// A credential together with its key in `facebook_credentials`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FacebookCredentialEntry {
+    pub key: String,
+    #[serde(flatten)]
+    pub credential: FacebookCredential,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -31,24 +110,485 @@ pub struct Package {
     pub description: String,
 }
 
+/// Lifecycle of a project. Only active projects are served, and status only
+/// changes through a [`ProjectAction`].
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectStatus {
+    #[default]
+    Draft,
+    Active,
+    Suspended,
+    Expired,
+    Cancelled,
+}
+
+impl ProjectStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProjectStatus::Draft => "draft",
+            ProjectStatus::Active => "active",
+            ProjectStatus::Suspended => "suspended",
+            ProjectStatus::Expired => "expired",
+            ProjectStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Status of a project stored before statuses existed, when `is_active`
+    /// was all there was.
+    pub fn from_legacy(is_active: bool, expired: bool) -> Self {
+        match (is_active, expired) {
+            (true, _) => ProjectStatus::Active,
+            (false, true) => ProjectStatus::Expired,
+            (false, false) => ProjectStatus::Suspended,
+        }
+    }
+}
+
+impl FromStr for ProjectStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(value.to_string()))
+            .map_err(|_| format!("unknown status {:?}", value))
+    }
+}
+
+impl fmt::Display for ProjectStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectAction {
+    Activate,
+    Suspend,
+    Resume,
+    // Taken by the expiry worker once `expires_at` has passed
+    Expire,
+    // Taken when the expiry of an expired project is moved forward
+    Renew,
+    Cancel,
+}
+
+impl ProjectAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProjectAction::Activate => "activate",
+            ProjectAction::Suspend => "suspend",
+            ProjectAction::Resume => "resume",
+            ProjectAction::Expire => "expire",
+            ProjectAction::Renew => "renew",
+            ProjectAction::Cancel => "cancel",
+        }
+    }
+
+    /// Status every allowed use of the action leads to.
+    pub fn target(self) -> ProjectStatus {
+        match self {
+            ProjectAction::Activate | ProjectAction::Resume | ProjectAction::Renew => ProjectStatus::Active,
+            ProjectAction::Suspend => ProjectStatus::Suspended,
+            ProjectAction::Expire => ProjectStatus::Expired,
+            ProjectAction::Cancel => ProjectStatus::Cancelled,
+        }
+    }
+
+    /// Status reached by taking the action from `from`, or `None` when the
+    /// transition is not allowed.
+    pub fn apply(self, from: ProjectStatus) -> Option<ProjectStatus> {
+        use ProjectStatus::*;
+        let allowed = match self {
+            ProjectAction::Activate => from == Draft,
+            ProjectAction::Suspend => from == Active,
+            ProjectAction::Resume => from == Suspended,
+            ProjectAction::Expire => from == Active,
+            ProjectAction::Renew => from == Expired,
+            ProjectAction::Cancel => from != Cancelled,
+        };
+        allowed.then(|| self.target())
+    }
+
+    pub fn requires_reason(self) -> bool {
+        matches!(self, ProjectAction::Suspend | ProjectAction::Cancel)
+    }
+}
+
+impl fmt::Display for ProjectAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusReason {
+    NonPayment,
+    ClientRequest,
+    PolicyViolation,
+    Fraud,
+    Other,
+}
+
+impl FromStr for StatusReason {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(value.to_string()))
+            .map_err(|_| format!("unknown reason {:?}", value))
+    }
+}
+
+/// Body of a status change request. Suspending and cancelling need a reason.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StatusChange {
+    #[serde(default)]
+    pub reason: Option<StatusReason>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// One entry of a project's status history.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StatusTransition {
+    pub action: ProjectAction,
+    pub from: ProjectStatus,
+    pub to: ProjectStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<StatusReason>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(from = "RawProject", into = "RawProject")]
 pub struct Project {
-    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
+    // Owning organization, always set by the server from the caller's tenant
+    pub org_id: Option<ObjectId>,
     pub name: String,
     pub telegram_chat_id: Option<String>,
-    pub facebook_credentials: HashMap<String, FacebookCredential>,
+    pub ad_credentials: HashMap<String, AdPlatformCredential>,
     pub package: Option<Package>,
-    
-    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub expires_at: Option<DateTime<Utc>>,
-    
+    pub status: ProjectStatus,
+    // Mirrors `status == active` for readers that predate statuses
     pub is_active: bool,
+    pub status_history: Vec<StatusTransition>,
     pub is_logging: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How a project is stored and sent. `facebook_credentials` is written as a
+/// view of the Facebook entries of `ad_credentials`, and only read when
+/// `ad_credentials` is missing: from clients that predate it and from
+/// projects stored before migration 0003.
+#[derive(Serialize, Deserialize)]
+struct RawProject {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    org_id: Option<ObjectId>,
+    name: String,
+    telegram_chat_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ad_credentials: Option<HashMap<String, AdPlatformCredential>>,
+    #[serde(default)]
+    facebook_credentials: HashMap<String, FacebookCredential>,
+    package: Option<Package>,
+    
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "date_or_rfc3339_string")]
+    expires_at: Option<DateTime<Utc>>,
+    
+    #[serde(default)]
+    status: ProjectStatus,
+    #[serde(default)]
+    is_active: bool,
+    #[serde(default)]
+    status_history: Vec<StatusTransition>,
+    is_logging: bool,
     
     #[serde(with = "chrono_datetime_as_bson_datetime")]
-    pub created_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
     
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    updated_at: DateTime<Utc>,
+}
+
+impl From<RawProject> for Project {
+    fn from(raw: RawProject) -> Self {
+        Self {
+            id: raw.id,
+            org_id: raw.org_id,
+            name: raw.name,
+            telegram_chat_id: raw.telegram_chat_id,
+            ad_credentials: raw
+                .ad_credentials
+                .unwrap_or_else(|| ad_platform::from_facebook_credentials(raw.facebook_credentials)),
+            package: raw.package,
+            expires_at: raw.expires_at,
+            status: raw.status,
+            is_active: raw.is_active,
+            status_history: raw.status_history,
+            is_logging: raw.is_logging,
+            created_at: raw.created_at,
+            updated_at: raw.updated_at,
+        }
+    }
+}
+
+impl From<Project> for RawProject {
+    fn from(project: Project) -> Self {
+        Self {
+            id: project.id,
+            org_id: project.org_id,
+            name: project.name,
+            telegram_chat_id: project.telegram_chat_id,
+            facebook_credentials: ad_platform::facebook_credentials(&project.ad_credentials),
+            ad_credentials: Some(project.ad_credentials),
+            package: project.package,
+            expires_at: project.expires_at,
+            status: project.status,
+            is_active: project.is_active,
+            status_history: project.status_history,
+            is_logging: project.is_logging,
+            created_at: project.created_at,
+            updated_at: project.updated_at,
+        }
+    }
+}
+
+/// A project as `/v2` sends and reads it: without the `facebook_credentials`
+/// view of `ad_credentials` and the `is_active` mirror of `status`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProjectV2 {
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<ObjectId>,
+    pub name: String,
+    #[serde(default)]
+    pub telegram_chat_id: Option<String>,
+    #[serde(default)]
+    pub ad_credentials: HashMap<String, AdPlatformCredential>,
+    #[serde(default)]
+    pub package: Option<Package>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "date_or_rfc3339_string")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub status: ProjectStatus,
+    #[serde(default)]
+    pub status_history: Vec<StatusTransition>,
+    #[serde(default)]
+    pub is_logging: bool,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
     #[serde(with = "chrono_datetime_as_bson_datetime")]
     pub updated_at: DateTime<Utc>,
-} 
\ No newline at end of file
+}
+
+impl From<Project> for ProjectV2 {
+    fn from(project: Project) -> Self {
+        Self {
+            id: project.id,
+            org_id: project.org_id,
+            name: project.name,
+            telegram_chat_id: project.telegram_chat_id,
+            ad_credentials: project.ad_credentials,
+            package: project.package,
+            expires_at: project.expires_at,
+            status: project.status,
+            status_history: project.status_history,
+            is_logging: project.is_logging,
+            created_at: project.created_at,
+            updated_at: project.updated_at,
+        }
+    }
+}
+
+impl From<ProjectV2> for Project {
+    fn from(project: ProjectV2) -> Self {
+        Self {
+            id: project.id,
+            org_id: project.org_id,
+            name: project.name,
+            telegram_chat_id: project.telegram_chat_id,
+            ad_credentials: project.ad_credentials,
+            package: project.package,
+            expires_at: project.expires_at,
+            status: project.status,
+            is_active: project.status == ProjectStatus::Active,
+            status_history: project.status_history,
+            is_logging: project.is_logging,
+            created_at: project.created_at,
+            updated_at: project.updated_at,
+        }
+    }
+}
+
+impl Project {
+    /// The project's Facebook credentials by key.
+    pub fn facebook_credentials(&self) -> impl Iterator<Item = (&String, &FacebookCredential)> {
+        self.ad_credentials
+            .iter()
+            .filter_map(|(key, credential)| Some((key, credential.as_facebook()?)))
+    }
+
+    pub fn mask_secrets(&mut self) {
+        for credential in self.ad_credentials.values_mut() {
+            credential.mask_secrets();
+        }
+    }
+
+    pub fn has_masked_secrets(&self) -> bool {
+        self.ad_credentials.values().any(AdPlatformCredential::has_masked_secrets)
+    }
+
+    /// Puts the stored secrets from `existing` back where a client echoed the
+    /// masked placeholder. Returns the credential key that has no stored
+    /// secret of the same platform.
+    pub fn restore_masked_secrets(&mut self, existing: &Project) -> Result<(), String> {
+        for (key, credential) in self.ad_credentials.iter_mut() {
+            if !credential.has_masked_secrets() {
+                continue;
+            }
+            let stored = existing.ad_credentials.get(key).ok_or_else(|| key.clone())?;
+            credential.restore_masked_secrets(stored).map_err(|_| key.clone())?;
+        }
+        Ok(())
+    }
+
+    pub fn keep_token_health(&mut self, existing: Option<&Project>) {
+        for (key, credential) in self.ad_credentials.iter_mut() {
+            credential.keep_token_health(existing.and_then(|existing| existing.ad_credentials.get(key)));
+        }
+    }
+}
+
+impl Project {
+    /// Moves the project to the status `action` leads to and records it in
+    /// the history. Fails with the current status when it is not allowed.
+    pub fn transition(&mut self, action: ProjectAction, change: StatusChange, at: DateTime<Utc>) -> Result<&StatusTransition, ProjectStatus> {
+        let to = action.apply(self.status).ok_or(self.status)?;
+        self.status_history.push(StatusTransition {
+            action,
+            from: self.status,
+            to,
+            reason: change.reason,
+            note: change.note,
+            at,
+        });
+        self.status = to;
+        self.is_active = to == ProjectStatus::Active;
+        self.updated_at = at;
+        Ok(self.status_history.last().expect("transition was just recorded"))
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct ProjectStateCounts {
+    pub active: u64,
+    pub expired: u64,
+}
+
+/// Flat shape of a project in bulk imports and exports. `id` is only
+/// written by exports and ignored on import.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProjectRecord {
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub telegram_chat_id: Option<String>,
+    // Read only when `ad_credentials` is missing, as on `Project`
+    #[serde(default)]
+    pub facebook_credentials: HashMap<String, FacebookCredential>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ad_credentials: Option<HashMap<String, AdPlatformCredential>>,
+    #[serde(default)]
+    pub package: Option<Package>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    // Only draft and active are accepted on import, without it `is_active` decides
+    #[serde(default)]
+    pub status: Option<ProjectStatus>,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+    #[serde(default)]
+    pub is_logging: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl From<Project> for ProjectRecord {
+    fn from(project: Project) -> Self {
+        Self {
+            id: project.id.map(|id| id.to_hex()),
+            name: project.name,
+            telegram_chat_id: project.telegram_chat_id,
+            facebook_credentials: ad_platform::facebook_credentials(&project.ad_credentials),
+            ad_credentials: Some(project.ad_credentials),
+            package: project.package,
+            expires_at: project.expires_at,
+            status: Some(project.status),
+            is_active: project.is_active,
+            is_logging: project.is_logging,
+        }
+    }
+}
+
+impl From<ProjectRecord> for Project {
+    fn from(record: ProjectRecord) -> Self {
+        let status = record.status.unwrap_or(match record.is_active {
+            true => ProjectStatus::Active,
+            false => ProjectStatus::Draft,
+        });
+        Self {
+            id: None,
+            org_id: None,
+            name: record.name,
+            telegram_chat_id: record.telegram_chat_id,
+            ad_credentials: record
+                .ad_credentials
+                .unwrap_or_else(|| ad_platform::from_facebook_credentials(record.facebook_credentials)),
+            package: record.package,
+            expires_at: record.expires_at,
+            status,
+            is_active: status == ProjectStatus::Active,
+            status_history: vec![],
+            is_logging: record.is_logging,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// Writes an RFC 3339 string, as API clients expect, and reads both that
+/// and a BSON date. The repository stores `expires_at` as a BSON date;
+/// strings are how it was stored before migration 0001.
+mod date_or_rfc3339_string {
+    use chrono::{DateTime, Utc};
+    use mongodb::bson::Bson;
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        match Option::<Bson>::deserialize(deserializer)? {
+            None | Some(Bson::Null) => Ok(None),
+            Some(Bson::DateTime(date)) => Ok(Some(date.to_chrono())),
+            Some(Bson::String(text)) => DateTime::parse_from_rfc3339(&text)
+                .map(|date| Some(date.with_timezone(&Utc)))
+                .map_err(de::Error::custom),
+            Some(other) => Err(de::Error::custom(format!("expected a date, got {}", other))),
+        }
+    }
+}
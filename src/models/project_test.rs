@@ -0,0 +1,236 @@
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+
+use crate::models::ad_platform::AdPlatformCredential;
+use crate::models::project::{
+    validate_credential_key, FacebookCredential, FacebookCredentialEntry, Project, ProjectAction, ProjectStatus,
+    ProjectV2, StatusChange, StatusReason, TokenHealth, MASKED_SECRET,
+};
+
+fn project_with_credential(app_secret: &str, access_token: &str) -> Project {
+    let mut ad_credentials = HashMap::new();
+    ad_credentials.insert(
+        "main".to_string(),
+        AdPlatformCredential::Facebook(FacebookCredential {
+            app_id: "app".to_string(),
+            app_secret: app_secret.to_string(),
+            access_token: access_token.to_string(),
+            ad_account_id: "act".to_string(),
+            account_suffix: "suffix".to_string(),
+            pixel_id: None,
+            link_url: None,
+            page_id: None,
+            watermark: None,
+            token_health: None,
+        }),
+    );
+    Project {
+        id: None,
+        org_id: None,
+        name: "Project".to_string(),
+        telegram_chat_id: None,
+        ad_credentials,
+        package: None,
+        expires_at: None,
+        status: ProjectStatus::Active,
+        is_active: true,
+        status_history: vec![],
+        is_logging: false,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn facebook<'a>(project: &'a Project, key: &str) -> &'a FacebookCredential {
+    project.ad_credentials[key].as_facebook().unwrap()
+}
+
+fn facebook_mut<'a>(project: &'a mut Project, key: &str) -> &'a mut FacebookCredential {
+    match project.ad_credentials.get_mut(key) {
+        Some(AdPlatformCredential::Facebook(credential)) => credential,
+        _ => panic!("no Facebook credential {}", key),
+    }
+}
+
+#[test]
+fn test_mask_secrets_hides_credentials() {
+    let mut project = project_with_credential("secret", "token");
+
+    project.mask_secrets();
+
+    let credential = facebook(&project, "main");
+    assert_eq!(credential.app_secret, MASKED_SECRET);
+    assert_eq!(credential.access_token, MASKED_SECRET);
+    assert_eq!(credential.app_id, "app");
+    assert!(project.has_masked_secrets());
+}
+
+#[test]
+fn test_restore_masked_secrets_keeps_stored_values() {
+    let existing = project_with_credential("secret", "token");
+    let mut incoming = project_with_credential(MASKED_SECRET, "rotated-token");
+
+    incoming.restore_masked_secrets(&existing).expect("secrets should be restored");
+
+    let credential = facebook(&incoming, "main");
+    assert_eq!(credential.app_secret, "secret");
+    assert_eq!(credential.access_token, "rotated-token");
+}
+
+#[test]
+fn test_restore_masked_secrets_requires_stored_credential() {
+    let existing = project_with_credential("secret", "token");
+    let mut incoming = project_with_credential(MASKED_SECRET, MASKED_SECRET);
+    let credential = incoming.ad_credentials.remove("main").unwrap();
+    incoming.ad_credentials.insert("new".to_string(), credential);
+
+    assert_eq!(incoming.restore_masked_secrets(&existing), Err("new".to_string()));
+}
+
+#[test]
+fn test_expires_at_is_sent_as_rfc3339_and_reads_dates() {
+    use chrono::TimeZone;
+    use mongodb::bson::{from_document, to_document, Bson, DateTime as BsonDateTime};
+
+    let expires_at = Utc.with_ymd_and_hms(2030, 1, 2, 3, 4, 5).unwrap();
+    let mut project = project_with_credential("secret", "token");
+    project.expires_at = Some(expires_at);
+
+    // API clients send and receive strings, not extended JSON dates
+    let json = serde_json::to_value(&project).unwrap();
+    assert_eq!(json["expires_at"], "2030-01-02T03:04:05Z");
+    let decoded: Project = serde_json::from_value(json).unwrap();
+    assert_eq!(decoded.expires_at, Some(expires_at));
+    let json = serde_json::to_value(ProjectV2::from(project.clone())).unwrap();
+    assert_eq!(json["expires_at"], "2030-01-02T03:04:05Z");
+
+    // The repository stores a BSON date
+    let mut doc = to_document(&project).unwrap();
+    doc.insert("expires_at", BsonDateTime::from_chrono(expires_at));
+    let decoded: Project = from_document(doc.clone()).unwrap();
+    assert_eq!(decoded.expires_at, Some(expires_at));
+
+    // Documents written before migration 0001 hold an RFC 3339 string
+    doc.insert("expires_at", "2030-01-02T03:04:05Z");
+    let decoded: Project = from_document(doc.clone()).unwrap();
+    assert_eq!(decoded.expires_at, Some(expires_at));
+
+    doc.insert("expires_at", "next year");
+    assert!(from_document::<Project>(doc.clone()).is_err());
+    doc.insert("expires_at", Bson::Int32(1));
+    assert!(from_document::<Project>(doc).is_err());
+}
+
+#[test]
+fn test_transitions_follow_the_lifecycle() {
+    use ProjectStatus::*;
+    assert_eq!(ProjectAction::Activate.apply(Draft), Some(Active));
+    assert_eq!(ProjectAction::Suspend.apply(Active), Some(Suspended));
+    assert_eq!(ProjectAction::Resume.apply(Suspended), Some(Active));
+    assert_eq!(ProjectAction::Expire.apply(Active), Some(Expired));
+    assert_eq!(ProjectAction::Renew.apply(Expired), Some(Active));
+    assert_eq!(ProjectAction::Cancel.apply(Expired), Some(Cancelled));
+
+    assert_eq!(ProjectAction::Activate.apply(Suspended), None);
+    assert_eq!(ProjectAction::Resume.apply(Expired), None);
+    assert_eq!(ProjectAction::Suspend.apply(Draft), None);
+    for action in [ProjectAction::Activate, ProjectAction::Resume, ProjectAction::Renew, ProjectAction::Cancel] {
+        assert_eq!(action.apply(Cancelled), None, "{}", action);
+    }
+}
+
+#[test]
+fn test_transition_records_history_and_mirrors_is_active() {
+    let mut project = project_with_credential("secret", "token");
+    let change = StatusChange { reason: Some(StatusReason::NonPayment), note: Some("Invoice 42".to_string()) };
+    let at = Utc::now();
+
+    let transition = project.transition(ProjectAction::Suspend, change, at).unwrap().clone();
+    assert_eq!((transition.from, transition.to), (ProjectStatus::Active, ProjectStatus::Suspended));
+    assert_eq!(transition.reason, Some(StatusReason::NonPayment));
+    assert_eq!(project.status, ProjectStatus::Suspended);
+    assert!(!project.is_active);
+    assert_eq!(project.updated_at, at);
+
+    assert_eq!(project.transition(ProjectAction::Suspend, StatusChange::default(), at).unwrap_err(), ProjectStatus::Suspended);
+    assert_eq!(project.status_history.len(), 1);
+
+    project.transition(ProjectAction::Resume, StatusChange::default(), at).unwrap();
+    assert!(project.is_active);
+    assert_eq!(project.status_history.len(), 2);
+}
+
+#[test]
+fn test_legacy_projects_map_to_a_status() {
+    assert_eq!(ProjectStatus::from_legacy(true, true), ProjectStatus::Active);
+    assert_eq!(ProjectStatus::from_legacy(false, true), ProjectStatus::Expired);
+    assert_eq!(ProjectStatus::from_legacy(false, false), ProjectStatus::Suspended);
+    assert_eq!("non_payment".parse::<StatusReason>(), Ok(StatusReason::NonPayment));
+    assert!("late".parse::<StatusReason>().is_err());
+}
+
+#[test]
+fn test_credential_keys_are_safe_field_names() {
+    for key in ["main", "page_2", "EU-west"] {
+        assert!(validate_credential_key(key).is_ok(), "{}", key);
+    }
+    for key in ["", "a.b", "$where", "with space", "ключ", &"k".repeat(65)] {
+        assert!(validate_credential_key(key).is_err(), "{}", key);
+    }
+}
+
+#[test]
+fn test_credential_entries_flatten_the_credential() {
+    let project = project_with_credential("secret", "token");
+    let entry = FacebookCredentialEntry { key: "main".to_string(), credential: facebook(&project, "main").clone() };
+    let json = serde_json::to_value(&entry).unwrap();
+    assert_eq!((json["key"].as_str(), json["app_id"].as_str()), (Some("main"), Some("app")));
+
+    let decoded: FacebookCredentialEntry = serde_json::from_value(json).unwrap();
+    assert_eq!(decoded.credential.access_token, "token");
+}
+
+#[test]
+fn test_token_health_follows_the_access_token() {
+    let now = Utc::now();
+    let mut stored = project_with_credential("secret", "token");
+    facebook_mut(&mut stored, "main").token_health = Some(TokenHealth {
+        valid: true,
+        expires_at: Some(now + Duration::days(3)),
+        scopes: vec!["ads_read".to_string()],
+        error: None,
+        checked_at: now,
+        warned_at: None,
+    });
+    let health = facebook(&stored, "main").token_health.clone().unwrap();
+    assert!(health.needs_attention(now, Duration::days(7)));
+    assert!(!health.needs_attention(now, Duration::days(1)));
+
+    let mut unchanged = project_with_credential("secret", "token");
+    unchanged.keep_token_health(Some(&stored));
+    assert_eq!(facebook(&unchanged, "main").token_health, Some(health));
+
+    let mut replaced = project_with_credential("secret", "new-token");
+    facebook_mut(&mut replaced, "main").token_health = facebook(&stored, "main").token_health.clone();
+    replaced.keep_token_health(Some(&stored));
+    assert_eq!(facebook(&replaced, "main").token_health, None);
+}
+
+#[test]
+fn test_v2_projects_drop_the_legacy_views() {
+    let project = project_with_credential("secret", "token");
+    assert_eq!(project.status, ProjectStatus::Active);
+
+    let v1 = serde_json::to_value(&project).unwrap();
+    assert!(v1["facebook_credentials"]["main"].is_object());
+    assert_eq!(v1["is_active"], true);
+
+    let v2 = serde_json::to_value(ProjectV2::from(project.clone())).unwrap();
+    assert!(v2.get("facebook_credentials").is_none());
+    assert!(v2.get("is_active").is_none());
+    assert_eq!(v2["status"], "active");
+    assert_eq!(v2["ad_credentials"], v1["ad_credentials"]);
+
+    let read: ProjectV2 = serde_json::from_value(v2).unwrap();
+    assert!(Project::from(read).is_active);
+}
@@ -0,0 +1,135 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime, DateTime as BsonDateTime};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebhookEvent {
+    #[serde(rename = "project.created")]
+    ProjectCreated,
+    #[serde(rename = "project.updated")]
+    ProjectUpdated,
+    #[serde(rename = "project.deleted")]
+    ProjectDeleted,
+    #[serde(rename = "project.expired")]
+    ProjectExpired,
+    #[serde(rename = "account.created")]
+    AccountCreated,
+    #[serde(rename = "account.updated")]
+    AccountUpdated,
+    #[serde(rename = "account.deleted")]
+    AccountDeleted,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::ProjectCreated => "project.created",
+            WebhookEvent::ProjectUpdated => "project.updated",
+            WebhookEvent::ProjectDeleted => "project.deleted",
+            WebhookEvent::ProjectExpired => "project.expired",
+            WebhookEvent::AccountCreated => "account.created",
+            WebhookEvent::AccountUpdated => "account.updated",
+            WebhookEvent::AccountDeleted => "account.deleted",
+        }
+    }
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A target URL receiving the events of an organization, or of a single
+/// project when `project_id` is set.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookSubscription {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub org_id: ObjectId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<ObjectId>,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    // Key for the HMAC signature, only returned when the subscription is created
+    pub secret: String,
+
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateWebhook {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    #[serde(default)]
+    pub project_id: Option<ObjectId>,
+}
+
+/// Subscription as returned by the API, without its secret.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookSummary {
+    pub id: Option<String>,
+    pub project_id: Option<String>,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WebhookSubscription> for WebhookSummary {
+    fn from(webhook: WebhookSubscription) -> Self {
+        Self {
+            id: webhook.id.map(|id| id.to_hex()),
+            project_id: webhook.project_id.map(|id| id.to_hex()),
+            url: webhook.url,
+            events: webhook.events,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreatedWebhook {
+    pub secret: String,
+    pub webhook: WebhookSummary,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    // Gave up after the maximum number of attempts
+    Dead,
+}
+
+/// One event for one subscription, stored before it is sent so deliveries
+/// survive restarts.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookDelivery {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub org_id: ObjectId,
+    pub webhook_id: ObjectId,
+    // Outbox entry that produced the delivery, one delivery per entry and subscription
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<ObjectId>,
+    pub event: WebhookEvent,
+    // Exact JSON body that is signed and sent
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+
+    // Also serves as a lease while a worker is sending the delivery
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub next_attempt_at: DateTime<Utc>,
+
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivered_at: Option<BsonDateTime>,
+}
@@ -0,0 +1,110 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use mongodb::bson::oid::ObjectId;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    app,
+    test_support::{test_db, test_state, unreachable_db, BOOTSTRAP_KEY},
+};
+
+async fn send(app: &Router, uri: &str, org_id: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::post(uri).header("x-api-key", BOOTSTRAP_KEY);
+    if let Some(org_id) = org_id {
+        request = request.header("x-org-id", org_id);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_only_user_actions_have_endpoints() {
+    let app = app::router(test_state(unreachable_db().await), CorsLayer::permissive());
+    let project_id = ObjectId::new().to_hex();
+
+    // Naming an organization would look it up, the action is checked before that
+    for action in ["expire", "renew", "frobnicate"] {
+        let (status, _) = send(&app, &format!("/projects/{}/{}", project_id, action), None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", action);
+    }
+    let (status, _) = send(&app, "/projects/not-an-id/resume", None, None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_status_transitions_are_enforced() {
+    let db = test_db("_project_status").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let app = app::router(test_state(db), CorsLayer::permissive());
+    let (_, org) = send(&app, "/organizations", None, Some(json!({ "name": "Lifecycle" }))).await;
+    let org = org["_id"]["$oid"].as_str().unwrap().to_string();
+    let org_id = Some(org.as_str());
+
+    let project = json!({
+        "name": "Draft",
+        "facebook_credentials": {},
+        "status": "draft",
+        "is_logging": false,
+        "created_at": { "$date": { "$numberLong": "0" } },
+        "updated_at": { "$date": { "$numberLong": "0" } },
+    });
+    let (status, created) = send(&app, "/projects", org_id, Some(project)).await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+    assert_eq!((created["status"].as_str(), created["is_active"].as_bool()), (Some("draft"), Some(false)));
+    let project_uri = format!("/projects/{}", created["_id"]["$oid"].as_str().unwrap());
+    let uri = |action: &str| format!("{}/{}", project_uri, action);
+
+    let (status, _) = send(&app, &uri("resume"), org_id, None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, activated) = send(&app, &uri("activate"), org_id, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(activated["is_active"], true);
+
+    let (status, body) = send(&app, &uri("suspend"), org_id, None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "A reason is required to suspend a project");
+    let change = json!({ "reason": "non_payment", "note": "Invoice 42" });
+    let (status, suspended) = send(&app, &uri("suspend"), org_id, Some(change)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(suspended["status"], "suspended");
+    let history = suspended["status_history"].as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!((history[1]["from"].as_str(), history[1]["reason"].as_str()), (Some("active"), Some("non_payment")));
+
+    let (status, body) = send(&app, &uri("suspend"), org_id, Some(json!({ "reason": "fraud" }))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"], "Cannot suspend a suspended project");
+
+    // Updates keep the status, it only changes through transitions
+    let mut edited = suspended.clone();
+    edited["status"] = json!("active");
+    edited["is_active"] = json!(true);
+    let request = Request::put(&project_uri)
+        .header("x-api-key", BOOTSTRAP_KEY)
+        .header("x-org-id", &org)
+        .header("content-type", "application/json")
+        .body(Body::from(edited.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let updated: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(updated["status"], "suspended");
+
+    let (status, _) = send(&app, &uri("cancel"), org_id, Some(json!({ "reason": "client_request" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, &uri("resume"), org_id, None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
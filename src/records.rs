@@ -0,0 +1,507 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::{stream, Stream, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::VecDeque, marker::PhantomData};
+use tracing::warn;
+
+use crate::{
+    error::ApiError,
+    models::{
+        account::AccountRecord,
+        import::{ImportMode, ImportReport},
+        project::{Package, ProjectRecord},
+    },
+};
+
+// Longest single row accepted in an import body
+const MAX_RECORD_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    Ndjson,
+    Csv,
+}
+
+impl RecordFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ndjson" | "jsonl" => Some(Self::Ndjson),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn from_media_type(value: &str) -> Option<Self> {
+        let media_type = value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        match media_type.as_str() {
+            "application/x-ndjson" | "application/ndjson" | "application/jsonl" => Some(Self::Ndjson),
+            "text/csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Ndjson => "application/x-ndjson",
+            Self::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Ndjson => "ndjson",
+            Self::Csv => "csv",
+        }
+    }
+
+    /// Format of an import body, taken from its `Content-Type`.
+    pub fn of_request(headers: &HeaderMap) -> Result<Self, ApiError> {
+        headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Self::from_media_type)
+            .ok_or_else(|| ApiError::BadRequest("Content-Type must be application/x-ndjson or text/csv".to_string()))
+    }
+
+    /// Format of an export: the `format` query parameter, then `Accept`, then NDJSON.
+    pub fn of_export(format: Option<&str>, headers: &HeaderMap) -> Result<Self, ApiError> {
+        if let Some(format) = format {
+            return Self::parse(format).ok_or_else(|| ApiError::BadRequest("format must be ndjson or csv".to_string()));
+        }
+        let accepted = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').find_map(Self::from_media_type));
+        Ok(accepted.unwrap_or(Self::Ndjson))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    /// One row is invalid, the rows after it can still be read
+    Row(String),
+    /// The body cannot be read any further
+    Fatal(String),
+}
+
+/// Columns of a record in CSV form.
+pub trait CsvRecord: Sized {
+    const COLUMNS: &'static [&'static str];
+    const REQUIRED: &'static [&'static str];
+
+    fn to_csv(&self) -> Vec<String>;
+
+    fn from_csv(row: &CsvRow) -> Result<Self, String>;
+}
+
+/// One CSV data row, addressed by header name.
+pub struct CsvRow<'a> {
+    columns: &'a [String],
+    values: Vec<String>,
+}
+
+impl CsvRow<'_> {
+    /// The trimmed value of `column`, `None` when the column is missing or empty.
+    pub fn get(&self, column: &str) -> Option<&str> {
+        let index = self.columns.iter().position(|name| name == column)?;
+        Some(self.values[index].trim()).filter(|value| !value.is_empty())
+    }
+
+    pub fn required(&self, column: &str) -> Result<String, String> {
+        self.get(column)
+            .map(str::to_string)
+            .ok_or_else(|| format!("{} is required", column))
+    }
+
+    pub fn bool(&self, column: &str, default: bool) -> Result<bool, String> {
+        match self.get(column).map(str::to_ascii_lowercase).as_deref() {
+            None => Ok(default),
+            Some("true" | "yes" | "1") => Ok(true),
+            Some("false" | "no" | "0") => Ok(false),
+            Some(other) => Err(format!("{} must be true or false, got {:?}", column, other)),
+        }
+    }
+}
+
+/// Splits a byte stream into records: lines for NDJSON, and for CSV lines
+/// that do not end inside a quoted field.
+pub struct RecordSplitter {
+    format: RecordFormat,
+    buffer: Vec<u8>,
+    scanned: usize,
+    in_quotes: bool,
+}
+
+impl RecordSplitter {
+    pub fn new(format: RecordFormat) -> Self {
+        Self {
+            format,
+            buffer: Vec::new(),
+            scanned: 0,
+            in_quotes: false,
+        }
+    }
+
+    /// Adds a chunk and returns the records it completed.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<String>, String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut records = Vec::new();
+        let mut start = 0;
+        for i in self.scanned..self.buffer.len() {
+            match self.buffer[i] {
+                b'"' if self.format == RecordFormat::Csv => self.in_quotes = !self.in_quotes,
+                b'\n' if !self.in_quotes => {
+                    records.push(decode_utf8(&self.buffer[start..i])?);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        self.buffer.drain(..start);
+        self.scanned = self.buffer.len();
+        if self.buffer.len() > MAX_RECORD_BYTES {
+            return Err(format!("Rows cannot be longer than {} bytes", MAX_RECORD_BYTES));
+        }
+        Ok(records)
+    }
+
+    /// The last record when the body does not end with a newline.
+    pub fn finish(&mut self) -> Result<Option<String>, String> {
+        if self.in_quotes {
+            return Err("Unterminated quoted field".to_string());
+        }
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+        let record = decode_utf8(&self.buffer)?;
+        self.buffer.clear();
+        Ok(Some(record))
+    }
+}
+
+fn decode_utf8(bytes: &[u8]) -> Result<String, String> {
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    String::from_utf8(bytes.to_vec()).map_err(|_| "Row is not valid UTF-8".to_string())
+}
+
+pub fn parse_csv_fields(record: &str) -> Result<Vec<String>, String> {
+    enum State {
+        FieldStart,
+        Unquoted,
+        Quoted,
+        AfterQuote,
+    }
+
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut state = State::FieldStart;
+    for c in record.chars() {
+        state = match (state, c) {
+            (State::FieldStart | State::Unquoted | State::AfterQuote, ',') => {
+                fields.push(std::mem::take(&mut field));
+                State::FieldStart
+            }
+            (State::FieldStart, '"') => State::Quoted,
+            (State::Unquoted, '"') => return Err("Unexpected quote inside an unquoted field".to_string()),
+            (State::FieldStart | State::Unquoted, c) => {
+                field.push(c);
+                State::Unquoted
+            }
+            (State::Quoted, '"') => State::AfterQuote,
+            (State::Quoted, c) => {
+                field.push(c);
+                State::Quoted
+            }
+            (State::AfterQuote, '"') => {
+                field.push('"');
+                State::Quoted
+            }
+            (State::AfterQuote, c) => return Err(format!("Unexpected {:?} after a closing quote", c)),
+        };
+    }
+    if matches!(state, State::Quoted) {
+        return Err("Unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+pub fn write_csv_fields(fields: &[String]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) || field.trim() != field {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+/// Turns records into values of `T`, skipping blank lines and the CSV header.
+pub struct RecordDecoder<T> {
+    format: RecordFormat,
+    columns: Option<Vec<String>>,
+    record: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned + CsvRecord> RecordDecoder<T> {
+    pub fn new(format: RecordFormat) -> Self {
+        Self {
+            format,
+            columns: None,
+            record: PhantomData,
+        }
+    }
+
+    pub fn decode(&mut self, record: &str) -> Result<Option<T>, RecordError> {
+        let record = record.trim_start_matches('\u{feff}');
+        if record.trim().is_empty() {
+            return Ok(None);
+        }
+        if self.format == RecordFormat::Ndjson {
+            return serde_json::from_str(record)
+                .map(Some)
+                .map_err(|e| RecordError::Row(e.to_string()));
+        }
+
+        let fields = parse_csv_fields(record).map_err(RecordError::Row)?;
+        let Some(columns) = &self.columns else {
+            self.columns = Some(Self::header(fields)?);
+            return Ok(None);
+        };
+        if fields.len() != columns.len() {
+            return Err(RecordError::Row(format!(
+                "Expected {} fields, got {}",
+                columns.len(),
+                fields.len()
+            )));
+        }
+        T::from_csv(&CsvRow { columns, values: fields })
+            .map(Some)
+            .map_err(RecordError::Row)
+    }
+
+    fn header(fields: Vec<String>) -> Result<Vec<String>, RecordError> {
+        let columns: Vec<String> = fields.iter().map(|field| field.trim().to_string()).collect();
+        if let Some(unknown) = columns.iter().find(|column| !T::COLUMNS.contains(&column.as_str())) {
+            return Err(RecordError::Fatal(format!(
+                "Unknown CSV column {:?}, expected {}",
+                unknown,
+                T::COLUMNS.join(",")
+            )));
+        }
+        if let Some(missing) = T::REQUIRED.iter().find(|column| !columns.iter().any(|c| c == *column)) {
+            return Err(RecordError::Fatal(format!("CSV header is missing the {} column", missing)));
+        }
+        Ok(columns)
+    }
+}
+
+/// Decodes an import body as it arrives. A fatal error is the last item.
+pub fn decode_body<T>(format: RecordFormat, body: Body) -> impl Stream<Item = Result<T, RecordError>> + Send
+where
+    T: DeserializeOwned + CsvRecord + Send + 'static,
+{
+    struct State<T> {
+        chunks: axum::body::BodyDataStream,
+        splitter: RecordSplitter,
+        decoder: RecordDecoder<T>,
+        pending: VecDeque<String>,
+        finished: bool,
+    }
+
+    let state = State {
+        chunks: body.into_data_stream(),
+        splitter: RecordSplitter::new(format),
+        decoder: RecordDecoder::new(format),
+        pending: VecDeque::new(),
+        finished: false,
+    };
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(record) = state.pending.pop_front() {
+                match state.decoder.decode(&record) {
+                    Ok(None) => continue,
+                    Ok(Some(value)) => return Some((Ok(value), state)),
+                    Err(error @ RecordError::Fatal(_)) => {
+                        state.pending.clear();
+                        state.finished = true;
+                        return Some((Err(error), state));
+                    }
+                    Err(error) => return Some((Err(error), state)),
+                }
+            }
+            if state.finished {
+                return None;
+            }
+
+            let records = match state.chunks.next().await {
+                Some(Ok(chunk)) => state.splitter.push(&chunk),
+                Some(Err(e)) => Err(format!("Failed to read request body: {}", e)),
+                None => {
+                    state.finished = true;
+                    state.splitter.finish().map(|record| record.into_iter().collect())
+                }
+            };
+            match records {
+                Ok(records) => state.pending.extend(records),
+                Err(error) => {
+                    state.finished = true;
+                    return Some((Err(RecordError::Fatal(error)), state));
+                }
+            }
+        }
+    })
+}
+
+/// Streams `records` as an attachment, one row per value.
+pub fn export_response<T, S>(format: RecordFormat, name: &str, records: S) -> Response
+where
+    T: Serialize + CsvRecord,
+    S: Stream<Item = Result<T, ApiError>> + Send + 'static,
+{
+    let header = match format {
+        RecordFormat::Csv => Some(Ok(write_csv_fields(
+            &T::COLUMNS.iter().map(|column| column.to_string()).collect::<Vec<_>>(),
+        ))),
+        RecordFormat::Ndjson => None,
+    };
+    let rows = records.map(move |record| {
+        let record = record?;
+        match format {
+            RecordFormat::Csv => Ok(write_csv_fields(&record.to_csv())),
+            RecordFormat::Ndjson => serde_json::to_string(&record)
+                .map(|line| line + "\n")
+                .map_err(|e| ApiError::InternalServerError(format!("Failed to serialize record: {}", e))),
+        }
+    });
+    let body = stream::iter(header).chain(rows).inspect(|line| {
+        if let Err(e) = line {
+            // Headers are already sent, the client sees a truncated body
+            warn!(error = %e, "Export aborted");
+        }
+    });
+
+    let disposition = format!("attachment; filename=\"{}.{}\"", name, format.extension());
+    (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(format.content_type())),
+            (
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_str(&disposition).unwrap_or(HeaderValue::from_static("attachment")),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
+
+/// `422` when an atomic import was rejected, `200` otherwise.
+pub fn import_response(report: ImportReport) -> (StatusCode, Json<ImportReport>) {
+    let status = if report.mode == ImportMode::Atomic && report.failed > 0 {
+        StatusCode::UNPROCESSABLE_ENTITY
+    } else {
+        StatusCode::OK
+    };
+    (status, Json(report))
+}
+
+fn optional(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+impl CsvRecord for ProjectRecord {
+    // Credentials are nested and secret, they only travel in NDJSON
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "telegram_chat_id",
+        "expires_at",
+        "status",
+        "is_active",
+        "is_logging",
+        "package_name",
+        "package_description",
+    ];
+    const REQUIRED: &'static [&'static str] = &["name"];
+
+    fn to_csv(&self) -> Vec<String> {
+        vec![
+            optional(&self.id),
+            self.name.clone(),
+            optional(&self.telegram_chat_id),
+            self.expires_at.map(|date| date.to_rfc3339()).unwrap_or_default(),
+            self.status.map(|status| status.to_string()).unwrap_or_default(),
+            self.is_active.to_string(),
+            self.is_logging.to_string(),
+            self.package.as_ref().map(|package| package.name.clone()).unwrap_or_default(),
+            self.package.as_ref().map(|package| package.description.clone()).unwrap_or_default(),
+        ]
+    }
+
+    fn from_csv(row: &CsvRow) -> Result<Self, String> {
+        let expires_at = row
+            .get("expires_at")
+            .map(|value| {
+                chrono::DateTime::parse_from_rfc3339(value)
+                    .map(|date| date.with_timezone(&chrono::Utc))
+                    .map_err(|_| format!("expires_at must be an RFC 3339 timestamp, got {:?}", value))
+            })
+            .transpose()?;
+        let package = row.get("package_name").map(|name| Package {
+            name: name.to_string(),
+            description: row.get("package_description").unwrap_or_default().to_string(),
+        });
+        Ok(Self {
+            id: None,
+            name: row.required("name")?,
+            telegram_chat_id: row.get("telegram_chat_id").map(str::to_string),
+            facebook_credentials: Default::default(),
+            ad_credentials: None,
+            package,
+            expires_at,
+            status: row.get("status").map(str::parse).transpose()?,
+            is_active: row.bool("is_active", true)?,
+            is_logging: row.bool("is_logging", false)?,
+        })
+    }
+}
+
+impl CsvRecord for AccountRecord {
+    const COLUMNS: &'static [&'static str] = &["id", "wallet_address", "email", "account_name", "project_ids"];
+    const REQUIRED: &'static [&'static str] = &["wallet_address", "email", "account_name"];
+
+    fn to_csv(&self) -> Vec<String> {
+        vec![
+            optional(&self.id),
+            self.wallet_address.clone(),
+            self.email.clone(),
+            self.account_name.clone(),
+            self.project_ids.join(";"),
+        ]
+    }
+
+    fn from_csv(row: &CsvRow) -> Result<Self, String> {
+        let project_ids = row
+            .get("project_ids")
+            .map(|ids| ids.split(';').map(|id| id.trim().to_string()).filter(|id| !id.is_empty()).collect())
+            .unwrap_or_default();
+        Ok(Self {
+            id: None,
+            wallet_address: row.required("wallet_address")?,
+            email: row.required("email")?,
+            account_name: row.required("account_name")?,
+            project_ids,
+        })
+    }
+}
@@ -0,0 +1,116 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue},
+};
+use futures_util::{stream, StreamExt};
+
+use crate::{
+    models::{account::AccountRecord, project::ProjectRecord},
+    records::{
+        decode_body, parse_csv_fields, write_csv_fields, RecordDecoder, RecordError, RecordFormat, RecordSplitter,
+    },
+};
+
+#[test]
+fn test_splitter_joins_chunks_and_keeps_quoted_newlines() {
+    let mut splitter = RecordSplitter::new(RecordFormat::Csv);
+    assert!(splitter.push(b"name,telegram").unwrap().is_empty());
+    assert_eq!(splitter.push(b"_chat_id\r\n\"Two\nlines\",1\n3,").unwrap(), vec![
+        "name,telegram_chat_id".to_string(),
+        "\"Two\nlines\",1".to_string(),
+    ]);
+    assert_eq!(splitter.finish().unwrap(), Some("3,".to_string()));
+
+    let mut splitter = RecordSplitter::new(RecordFormat::Ndjson);
+    assert_eq!(splitter.push(b"{\"a\":\"\\\"\"}\n{}\n").unwrap().len(), 2);
+    assert_eq!(splitter.finish().unwrap(), None);
+}
+
+#[test]
+fn test_splitter_rejects_unterminated_quotes_and_oversized_rows() {
+    let mut splitter = RecordSplitter::new(RecordFormat::Csv);
+    splitter.push(b"\"open\n").unwrap();
+    assert!(splitter.finish().is_err());
+
+    let mut splitter = RecordSplitter::new(RecordFormat::Ndjson);
+    assert!(splitter.push(&vec![b'x'; 65 * 1024]).is_err());
+}
+
+#[test]
+fn test_csv_fields_round_trip() {
+    let fields = vec![
+        "plain".to_string(),
+        "with, comma".to_string(),
+        "say \"hi\"".to_string(),
+        "multi\nline".to_string(),
+        String::new(),
+    ];
+    let line = write_csv_fields(&fields);
+    assert_eq!(line, "plain,\"with, comma\",\"say \"\"hi\"\"\",\"multi\nline\",\n");
+    assert_eq!(parse_csv_fields(line.trim_end_matches('\n')).unwrap(), fields);
+
+    assert!(parse_csv_fields("a\"b").is_err());
+    assert!(parse_csv_fields("\"closed\"x").is_err());
+}
+
+#[test]
+fn test_csv_rows_are_mapped_by_header() {
+    let mut decoder = RecordDecoder::<ProjectRecord>::new(RecordFormat::Csv);
+    assert!(decoder.decode("\u{feff}name,expires_at,is_active,package_name").unwrap().is_none());
+
+    let project = decoder.decode("Shop,2030-01-01T00:00:00Z,no,Gold").unwrap().unwrap();
+    assert_eq!(project.name, "Shop");
+    assert!(!project.is_active);
+    assert_eq!(project.expires_at.unwrap().to_rfc3339(), "2030-01-01T00:00:00+00:00");
+    assert_eq!(project.package.unwrap().name, "Gold");
+
+    assert!(matches!(decoder.decode("Shop,tomorrow,yes,"), Err(RecordError::Row(_))));
+    assert!(matches!(decoder.decode(",,,"), Err(RecordError::Row(e)) if e == "name is required"));
+    assert!(matches!(decoder.decode("too,few"), Err(RecordError::Row(_))));
+    assert!(decoder.decode("   ").unwrap().is_none());
+}
+
+#[test]
+fn test_bad_csv_headers_are_fatal() {
+    let mut decoder = RecordDecoder::<AccountRecord>::new(RecordFormat::Csv);
+    assert!(matches!(decoder.decode("email,wallet,account_name"), Err(RecordError::Fatal(_))));
+
+    let mut decoder = RecordDecoder::<AccountRecord>::new(RecordFormat::Csv);
+    assert!(matches!(decoder.decode("email,account_name"), Err(RecordError::Fatal(_))));
+}
+
+#[test]
+fn test_formats_are_negotiated() {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/csv; charset=utf-8"));
+    headers.insert(header::ACCEPT, HeaderValue::from_static("text/html, text/csv"));
+
+    assert_eq!(RecordFormat::of_request(&headers).unwrap(), RecordFormat::Csv);
+    assert_eq!(RecordFormat::of_export(None, &headers).unwrap(), RecordFormat::Csv);
+    assert_eq!(RecordFormat::of_export(Some("ndjson"), &headers).unwrap(), RecordFormat::Ndjson);
+    assert!(RecordFormat::of_export(Some("xml"), &headers).is_err());
+    assert_eq!(RecordFormat::of_export(None, &HeaderMap::new()).unwrap(), RecordFormat::Ndjson);
+    assert!(RecordFormat::of_request(&HeaderMap::new()).is_err());
+}
+
+#[tokio::test]
+async fn test_body_is_decoded_row_by_row() {
+    let chunks = ["{\"name\":\"One\"}\n{\"na", "me\":\"Two\",\"is_active\":false}\nnot json\n", "{\"name\":\"Last\"}"];
+    let body = Body::from_stream(stream::iter(chunks.map(Ok::<_, std::io::Error>)));
+    let rows: Vec<_> = decode_body::<ProjectRecord>(RecordFormat::Ndjson, body).collect().await;
+
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[0].as_ref().unwrap().name, "One");
+    assert!(!rows[1].as_ref().unwrap().is_active);
+    assert!(matches!(rows[2], Err(RecordError::Row(_))));
+    assert_eq!(rows[3].as_ref().unwrap().name, "Last");
+}
+
+#[tokio::test]
+async fn test_decoding_stops_after_a_fatal_error() {
+    let body = Body::from("unknown\nvalue\n");
+    let rows: Vec<_> = decode_body::<ProjectRecord>(RecordFormat::Csv, body).collect().await;
+
+    assert_eq!(rows.len(), 1);
+    assert!(matches!(rows[0], Err(RecordError::Fatal(_))));
+}
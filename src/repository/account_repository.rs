@@ -1,72 +1,206 @@
 use mongodb::{
     bson::{doc, oid::ObjectId, Document, from_document, to_document},
-    Collection, Database,
-    options::UpdateOptions,
+    Collection, Database, IndexModel,
+    options::{FindOneAndUpdateOptions, FindOptions, IndexOptions, ReturnDocument},
+};
+use futures_util::{Stream, StreamExt};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
 };
 use crate::models::account::Account;
+use crate::repository::{conflict_on_duplicate, is_duplicate_key, transaction::Transaction};
 use crate::error::ApiError;
+use crate::metrics;
+use tracing::warn;
 
 #[derive(Clone)]
 pub struct AccountRepository {
     collection: Collection<Document>,
+    // Set once the unique email index is known to exist
+    email_index: Arc<AtomicBool>,
 }
 
 impl AccountRepository {
     pub fn new(db: Database) -> Self {
         Self {
             collection: db.collection("accounts"),
+            email_index: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    pub async fn create(&self, account: Account) -> Result<Account, ApiError> {
-        let doc = to_document(&account)?;
-        let result = self.collection.insert_one(doc, None).await?;
-        let id = result.inserted_id.as_object_id()
-            .ok_or_else(|| ApiError::InternalServerError("Failed to get inserted ID".into()))?;
-        self.get_by_id(&id).await
+    /// Whether emails are known to be unique per organization through the
+    /// index, rather than only checked before writes.
+    pub fn has_email_index(&self) -> bool {
+        self.email_index.load(Ordering::Acquire)
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("accounts", "ensure_indexes", async {
+            let email = IndexModel::builder()
+                .keys(doc! { "org_id": 1, "email": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build();
+            match self.collection.create_indexes(vec![email], None).await {
+                Ok(_) => {}
+                Err(e) if is_duplicate_key(&e) => {
+                    return Err(ApiError::Conflict(format!(
+                        "Accounts share an email within an organization, resolve them before the email index can be built: {}",
+                        self.duplicate_emails().await?.join(", ")
+                    )));
+                }
+                Err(e) => return Err(e.into()),
+            }
+            self.email_index.store(true, Ordering::Release);
+            Ok(())
+        })
+        .await
     }
 
-    pub async fn update(&self, id: &ObjectId, account: Account) -> Result<Account, ApiError> {
-        let filter = doc! { "_id": id };
-        let update = doc! {
-            "$set": to_document(&account)?
-        };
-
-        let options = UpdateOptions::default();
-        match self.collection.update_one(filter, update, options).await {
-            Ok(result) if result.modified_count == 1 => self.get_by_id(id).await,
-            Ok(_) => Err(ApiError::NotFound),
-            Err(e) => Err(ApiError::MongoDB(e)),
+    // `org_id/email` of every email used by more than one account of an organization
+    async fn duplicate_emails(&self) -> Result<Vec<String>, ApiError> {
+        let pipeline = vec![
+            doc! { "$group": { "_id": { "org_id": "$org_id", "email": "$email" }, "count": { "$sum": 1 } } },
+            doc! { "$match": { "count": { "$gt": 1 } } },
+            doc! { "$sort": { "_id.org_id": 1, "_id.email": 1 } },
+        ];
+        let mut cursor = self.collection.aggregate(pipeline, None).await?;
+        let mut duplicates = Vec::new();
+        while let Some(doc) = cursor.next().await {
+            let key = doc?.get_document("_id").cloned().unwrap_or_default();
+            let org_id = key.get_object_id("org_id").map(|id| id.to_hex()).unwrap_or_else(|_| "-".into());
+            duplicates.push(format!("{}/{}", org_id, key.get_str("email").unwrap_or_default()));
         }
+        Ok(duplicates)
     }
 
-    pub async fn delete(&self, id: &ObjectId) -> Result<bool, ApiError> {
-        let filter = doc! { "_id": id };
-        let result = self.collection.delete_one(filter, None).await?;
-        Ok(result.deleted_count > 0)
+    /// Whether another account of the organization than `except` uses `email`.
+    pub async fn email_taken(&self, org_id: &ObjectId, email: &str, except: Option<&ObjectId>) -> Result<bool, ApiError> {
+        metrics::track_db("accounts", "email_taken", async {
+            let mut filter = doc! { "org_id": org_id, "email": email };
+            if let Some(id) = except {
+                filter.insert("_id", doc! { "$ne": id });
+            }
+            Ok(self.collection.count_documents(filter, None).await? > 0)
+        })
+        .await
     }
 
-    pub async fn get_by_id(&self, id: &ObjectId) -> Result<Account, ApiError> {
-        let filter = doc! { "_id": id };
-        println!("Filter: {:?}", filter);
-        let doc = self.collection.find_one(filter, None).await?
-            .ok_or(ApiError::NotFound)?;
-        Ok(from_document(doc)?)
+    pub async fn create(&self, tx: &mut Transaction, mut account: Account) -> Result<Account, ApiError> {
+        metrics::track_db("accounts", "create", async {
+            if account.org_id.is_none() {
+                return Err(ApiError::InternalServerError("Account has no organization".into()));
+            }
+            let doc = to_document(&account)?;
+            let result = match tx.session() {
+                Some(session) => self.collection.insert_one_with_session(doc, None, session).await,
+                None => self.collection.insert_one(doc, None).await,
+            }
+            .map_err(|e| email_taken(e, &account))?;
+            let id = result.inserted_id.as_object_id()
+                .ok_or_else(|| ApiError::InternalServerError("Failed to get inserted ID".into()))?;
+            account.id = Some(id);
+            Ok(account)
+        })
+        .await
     }
 
-    pub async fn get_all(&self) -> Result<Vec<Account>, ApiError> {
-        let mut cursor = self.collection.find(None, None).await?;
-        let mut accounts = Vec::new();
+    pub async fn update(&self, tx: &mut Transaction, org_id: &ObjectId, id: &ObjectId, account: Account) -> Result<Account, ApiError> {
+        metrics::track_db("accounts", "update", async {
+            let filter = doc! { "_id": id, "org_id": org_id };
+            let update = doc! {
+                "$set": to_document(&account)?
+            };
+
+            // Read back inside the transaction, a separate read would not see the write yet
+            let options = FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build();
+            let doc = match tx.session() {
+                Some(session) => self.collection.find_one_and_update_with_session(filter, update, options, session).await,
+                None => self.collection.find_one_and_update(filter, update, options).await,
+            }
+            .map_err(|e| email_taken(e, &account))?;
+            Ok(from_document(doc.ok_or(ApiError::NotFound)?)?)
+        })
+        .await
+    }
+
+    pub async fn delete(&self, tx: &mut Transaction, org_id: &ObjectId, id: &ObjectId) -> Result<bool, ApiError> {
+        metrics::track_db("accounts", "delete", async {
+            let filter = doc! { "_id": id, "org_id": org_id };
+            let result = match tx.session() {
+                Some(session) => self.collection.delete_one_with_session(filter, None, session).await?,
+                None => self.collection.delete_one(filter, None).await?,
+            };
+            Ok(result.deleted_count > 0)
+        })
+        .await
+    }
+
+    pub async fn get_by_id(&self, org_id: &ObjectId, id: &ObjectId) -> Result<Account, ApiError> {
+        metrics::track_db("accounts", "get_by_id", async {
+            let filter = doc! { "_id": id, "org_id": org_id };
+            let doc = self.collection.find_one(filter, None).await?
+                .ok_or(ApiError::NotFound)?;
+            Ok(from_document(doc)?)
+        })
+        .await
+    }
+
+    pub async fn get_all(&self, org_id: &ObjectId) -> Result<Vec<Account>, ApiError> {
+        metrics::track_db("accounts", "get_all", async {
+            let mut cursor = self.collection.find(doc! { "org_id": org_id }, None).await?;
+            let mut accounts = Vec::new();
         
-        while cursor.advance().await? {
-            let raw_doc = cursor.current();
-            if let Ok(doc) = Document::from_reader(raw_doc.as_bytes()) {
+            while cursor.advance().await? {
+                let raw_doc = cursor.current();
+                if let Ok(doc) = Document::from_reader(raw_doc.as_bytes()) {
+                    match from_document(doc) {
+                        Ok(account) => accounts.push(account),
+                        Err(e) => warn!(error = %e, "Skipping account that failed to deserialize"),
+                    }
+                }
+            }
+            Ok(accounts)
+        })
+        .await
+    }
+
+    /// The organization's accounts in id order, read from the cursor as the
+    /// stream is polled.
+    pub async fn stream_all(&self, org_id: &ObjectId) -> Result<impl Stream<Item = Result<Account, ApiError>> + Send + 'static, ApiError> {
+        let cursor = metrics::track_db("accounts", "stream_all", async {
+            let options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+            Ok(self.collection.find(doc! { "org_id": org_id }, options).await?)
+        })
+        .await?;
+        Ok(cursor.map(|doc| Ok(from_document(doc?)?)))
+    }
+
+    /// Every account across organizations, for operator tooling.
+    pub async fn find_all(&self) -> Result<Vec<Account>, ApiError> {
+        metrics::track_db("accounts", "find_all", async {
+            let mut cursor = self.collection.find(doc! {}, None).await?;
+            let mut accounts = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
                 match from_document(doc) {
                     Ok(account) => accounts.push(account),
-                    Err(e) => eprintln!("Error deserializing account: {}", e),
+                    Err(e) => warn!(error = %e, "Skipping account that failed to deserialize"),
                 }
             }
-        }
-        Ok(accounts)
+            Ok(accounts)
+        })
+        .await
     }
-}
\ No newline at end of file
+}
+
+fn email_taken(error: mongodb::error::Error, account: &Account) -> ApiError {
+    conflict_on_duplicate(error, || email_exists(&account.email))
+}
+
+pub(crate) fn email_exists(email: &str) -> String {
+    format!("Email {} already exists", email)
+}
@@ -7,8 +7,10 @@ use mongodb::{
 use dotenv::dotenv;
 
 use crate::{
+    error::ApiError,
     models::account::Account,
-    repository::account_repository::AccountRepository,
+    repository::{account_repository::AccountRepository, transaction::Transaction},
+    test_support::test_db,
 };
 
 async fn setup_test_db() -> Database {
@@ -29,6 +31,7 @@ async fn setup_test_db() -> Database {
 fn create_test_account() -> Account {
     Account {
         id: None,
+        org_id: Some(ObjectId::new()),
         wallet_address: "0x123456789".to_string(),
         email: "test@example.com".to_string(),
         account_name: "Test Account".to_string(),
@@ -52,7 +55,8 @@ async fn test_crud_operations() {
 
     // Test Create
     let test_account = create_test_account();
-    let created_account = repo.create(test_account.clone())
+    let org_id = test_account.org_id.unwrap();
+    let created_account = repo.create(&mut Transaction::none(), test_account.clone())
         .await
         .expect("Failed to create account");
     
@@ -62,7 +66,7 @@ async fn test_crud_operations() {
 
     // Test Get by ID
     let account_id = created_account.id.unwrap();
-    let retrieved_account = repo.get_by_id(&account_id)
+    let retrieved_account = repo.get_by_id(&org_id, &account_id)
         .await
         .expect("Failed to get account by ID");
     
@@ -73,14 +77,14 @@ async fn test_crud_operations() {
     let mut updated_account = retrieved_account.clone();
     updated_account.account_name = "Updated Test Account".to_string();
     
-    let result = repo.update(&account_id, updated_account.clone())
+    let result = repo.update(&mut Transaction::none(), &org_id, &account_id, updated_account.clone())
         .await
         .expect("Failed to update account");
     
     assert_eq!(result.account_name, "Updated Test Account");
 
     // Test Get All
-    let all_accounts = repo.get_all()
+    let all_accounts = repo.get_all(&org_id)
         .await
         .expect("Failed to get all accounts");
     
@@ -88,14 +92,14 @@ async fn test_crud_operations() {
     assert_eq!(all_accounts[0].id, Some(account_id));
 
     // Test Delete
-    let delete_result = repo.delete(&account_id)
+    let delete_result = repo.delete(&mut Transaction::none(), &org_id, &account_id)
         .await
         .expect("Failed to delete account");
     
     assert!(delete_result);
 
     // Verify deletion
-    let all_accounts_after_delete = repo.get_all()
+    let all_accounts_after_delete = repo.get_all(&org_id)
         .await
         .expect("Failed to get all accounts after delete");
     
@@ -108,7 +112,7 @@ async fn test_get_nonexistent_account() {
     let repo = AccountRepository::new(db);
     
     let nonexistent_id = ObjectId::new();
-    let result = repo.get_by_id(&nonexistent_id).await;
+    let result = repo.get_by_id(&ObjectId::new(), &nonexistent_id).await;
     
     assert!(matches!(result, Err(crate::error::ApiError::NotFound)));
 }
@@ -121,7 +125,7 @@ async fn test_update_nonexistent_account() {
     let nonexistent_id = ObjectId::new();
     let test_account = create_test_account();
     
-    let result = repo.update(&nonexistent_id, test_account).await;
+    let result = repo.update(&mut Transaction::none(), &ObjectId::new(), &nonexistent_id, test_account).await;
     
     assert!(matches!(result, Err(crate::error::ApiError::NotFound)));
 }
@@ -132,7 +136,58 @@ async fn test_delete_nonexistent_account() {
     let repo = AccountRepository::new(db);
     
     let nonexistent_id = ObjectId::new();
-    let result = repo.delete(&nonexistent_id).await;
+    let result = repo.delete(&mut Transaction::none(), &ObjectId::new(), &nonexistent_id).await;
     
     assert!(matches!(result, Ok(false)));
-} 
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_duplicate_email_is_a_conflict_within_an_organization() {
+    let db = test_db("account_emails").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let repo = AccountRepository::new(db);
+    repo.ensure_indexes().await.expect("Failed to ensure indexes");
+
+    let account = create_test_account();
+    let org_id = account.org_id.unwrap();
+    repo.create(&mut Transaction::none(), account.clone()).await.unwrap();
+    let result = repo.create(&mut Transaction::none(), account.clone()).await;
+    assert!(matches!(result, Err(ApiError::Conflict(_))));
+
+    // Another organization may use the same email
+    let other = Account { org_id: Some(ObjectId::new()), ..account.clone() };
+    repo.create(&mut Transaction::none(), other).await.unwrap();
+
+    let second = Account { email: "second@example.com".to_string(), ..account.clone() };
+    let second = repo.create(&mut Transaction::none(), second).await.unwrap();
+    let result = repo.update(&mut Transaction::none(), &org_id, &second.id.unwrap(), account).await;
+    assert!(matches!(result, Err(ApiError::Conflict(_))));
+}
+
+#[tokio::test]
+async fn test_duplicate_emails_keep_the_index_from_being_built() {
+    let db = test_db("account_email_duplicates").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let repo = AccountRepository::new(db);
+
+    // Stored before the index existed
+    let account = create_test_account();
+    let org_id = account.org_id.unwrap();
+    let first = repo.create(&mut Transaction::none(), account.clone()).await.unwrap();
+    let second = repo.create(&mut Transaction::none(), account.clone()).await.unwrap();
+
+    match repo.ensure_indexes().await {
+        Err(ApiError::Conflict(message)) => {
+            assert!(message.contains(&format!("{}/{}", org_id.to_hex(), account.email)));
+        }
+        other => panic!("expected a conflict, got {:?}", other.map(|_| ())),
+    }
+    assert!(!repo.has_email_index());
+    assert!(repo.email_taken(&org_id, &account.email, None).await.unwrap());
+    assert!(repo.email_taken(&org_id, &account.email, first.id.as_ref()).await.unwrap());
+
+    repo.delete(&mut Transaction::none(), &org_id, &second.id.unwrap()).await.unwrap();
+    repo.ensure_indexes().await.expect("Failed to ensure indexes");
+    assert!(repo.has_email_index());
+    assert!(!repo.email_taken(&org_id, &account.email, first.id.as_ref()).await.unwrap());
+}
@@ -0,0 +1,103 @@
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document, from_document, to_document, DateTime as BsonDateTime},
+    Collection, Database, IndexModel,
+    options::IndexOptions,
+};
+use crate::models::api_key::ApiKey;
+use crate::error::ApiError;
+use crate::metrics;
+
+#[derive(Clone)]
+pub struct ApiKeyRepository {
+    collection: Collection<Document>,
+}
+
+impl ApiKeyRepository {
+    pub fn new(db: Database) -> Self {
+        Self {
+            collection: db.collection("api_keys"),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("api_keys", "ensure_indexes", async {
+            let key_hash = IndexModel::builder()
+                .keys(doc! { "key_hash": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build();
+            self.collection.create_indexes(vec![key_hash], None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn create(&self, api_key: ApiKey) -> Result<ApiKey, ApiError> {
+        metrics::track_db("api_keys", "create", async {
+            let doc = to_document(&api_key)?;
+            let result = self.collection.insert_one(doc, None).await?;
+            let id = result.inserted_id.as_object_id()
+                .ok_or_else(|| ApiError::InternalServerError("Failed to get inserted ID".into()))?;
+            self.get_by_id(&id).await
+        })
+        .await
+    }
+
+    pub async fn get_by_id(&self, id: &ObjectId) -> Result<ApiKey, ApiError> {
+        metrics::track_db("api_keys", "get_by_id", async {
+            let filter = doc! { "_id": id };
+            let doc = self.collection.find_one(filter, None).await?
+                .ok_or(ApiError::NotFound)?;
+            Ok(from_document(doc)?)
+        })
+        .await
+    }
+
+    pub async fn find_active_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, ApiError> {
+        metrics::track_db("api_keys", "find_active_by_hash", async {
+            let filter = doc! { "key_hash": key_hash, "revoked_at": null };
+            match self.collection.find_one(filter, None).await? {
+                Some(doc) => Ok(Some(from_document(doc)?)),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    pub async fn get_all(&self, org_id: Option<&ObjectId>) -> Result<Vec<ApiKey>, ApiError> {
+        metrics::track_db("api_keys", "get_all", async {
+            let filter = org_id.map(|org_id| doc! { "org_id": org_id });
+            let mut cursor = self.collection.find(filter, None).await?;
+            let mut api_keys = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                api_keys.push(from_document(doc)?);
+            }
+            Ok(api_keys)
+        })
+        .await
+    }
+
+    pub async fn revoke(&self, org_id: Option<&ObjectId>, id: &ObjectId) -> Result<bool, ApiError> {
+        metrics::track_db("api_keys", "revoke", async {
+            let mut filter = doc! { "_id": id, "revoked_at": null };
+            if let Some(org_id) = org_id {
+                filter.insert("org_id", org_id);
+            }
+            let update = doc! { "$set": { "revoked_at": BsonDateTime::now() } };
+            let result = self.collection.update_one(filter, update, None).await?;
+            Ok(result.modified_count > 0)
+        })
+        .await
+    }
+
+    pub async fn touch(&self, id: &ObjectId) -> Result<(), ApiError> {
+        metrics::track_db("api_keys", "touch", async {
+            let filter = doc! { "_id": id };
+            let update = doc! { "$set": { "last_used_at": BsonDateTime::now() } };
+            self.collection.update_one(filter, update, None).await?;
+            Ok(())
+        })
+        .await
+    }
+}
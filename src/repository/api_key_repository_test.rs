@@ -0,0 +1,72 @@
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+
+use crate::{
+    models::api_key::{ApiKey, Scope},
+    repository::api_key_repository::ApiKeyRepository,
+    service::api_key_service::{generate_key, hash_key},
+    test_support::test_db,
+};
+
+fn create_test_api_key(key: &str) -> ApiKey {
+    ApiKey {
+        id: None,
+        name: "Test Key".to_string(),
+        org_id: None,
+        account_id: None,
+        prefix: key[..11].to_string(),
+        key_hash: hash_key(key),
+        scopes: vec![Scope::ProjectsRead],
+        bot_frontend: false,
+        created_at: Utc::now(),
+        revoked_at: None,
+        last_used_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_create_find_and_revoke() {
+    let db = test_db("_api_keys").await;
+    let repo = ApiKeyRepository::new(db.clone());
+
+    db.collection::<mongodb::bson::Document>("api_keys")
+        .drop(None)
+        .await
+        .expect("Failed to drop collection");
+    repo.ensure_indexes().await.expect("Failed to create indexes");
+
+    let key = generate_key();
+    let created = repo.create(create_test_api_key(&key))
+        .await
+        .expect("Failed to create API key");
+    let id = created.id.expect("missing id");
+
+    let found = repo.find_active_by_hash(&hash_key(&key))
+        .await
+        .expect("Failed to find API key");
+    assert_eq!(found.map(|k| k.id), Some(Some(id)));
+
+    // The same hash cannot be stored twice
+    assert!(repo.create(create_test_api_key(&key)).await.is_err());
+
+    repo.touch(&id).await.expect("Failed to touch API key");
+    assert!(repo.get_by_id(&id).await.unwrap().last_used_at.is_some());
+
+    assert!(repo.revoke(None, &id).await.expect("Failed to revoke API key"));
+    assert!(!repo.revoke(None, &id).await.expect("Failed to revoke API key"));
+
+    let found = repo.find_active_by_hash(&hash_key(&key))
+        .await
+        .expect("Failed to find API key");
+    assert!(found.is_none());
+    assert_eq!(repo.get_all(None).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_get_nonexistent_api_key() {
+    let repo = ApiKeyRepository::new(test_db("_api_keys").await);
+
+    let result = repo.get_by_id(&ObjectId::new()).await;
+
+    assert!(matches!(result, Err(crate::error::ApiError::NotFound)));
+}
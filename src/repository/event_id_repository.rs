@@ -0,0 +1,152 @@
+use chrono::{DateTime, Utc};
+use mongodb::{
+    bson::{doc, from_document, oid::ObjectId, to_document, DateTime as BsonDateTime, Document},
+    options::{IndexOptions, UpdateOptions},
+    Collection, Database, IndexModel,
+};
+use std::time::Duration;
+
+use crate::error::ApiError;
+use crate::metrics;
+use crate::repository::is_duplicate_key;
+use crate::models::event_id::{EventIdCounts, EventIdRecord};
+
+/// Whether a server event was the first with its id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Claim {
+    First,
+    Duplicate,
+}
+
+#[derive(Clone)]
+pub struct EventIdRepository {
+    collection: Collection<Document>,
+}
+
+impl EventIdRepository {
+    pub fn new(db: Database) -> Self {
+        Self {
+            collection: db.collection("event_ids"),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("event_ids", "ensure_indexes", async {
+            let event_id = IndexModel::builder()
+                .keys(doc! { "project_id": 1, "event_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build();
+            let project = IndexModel::builder()
+                .keys(doc! { "org_id": 1, "project_id": 1 })
+                .build();
+            let ttl = IndexModel::builder()
+                .keys(doc! { "expires_at": 1 })
+                .options(IndexOptions::builder().expire_after(Duration::ZERO).build())
+                .build();
+            self.collection.create_indexes(vec![event_id, project, ttl], None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Stores an issued id, failing with a conflict when the project has it already.
+    pub async fn issue(&self, record: &EventIdRecord) -> Result<(), ApiError> {
+        metrics::track_db("event_ids", "issue", async {
+            match self.collection.insert_one(to_document(record)?, None).await {
+                Ok(_) => Ok(()),
+                Err(e) if is_duplicate_key(&e) => Err(ApiError::Conflict(format!("Event id {} is taken", record.event_id))),
+                Err(e) => Err(e.into()),
+            }
+        })
+        .await
+    }
+
+    /// Marks the id of a server event as sent. An issued id is claimed by its
+    /// first server event; an id that was not issued is recorded by it.
+    /// Fails when the id was issued or sent for another event name.
+    pub async fn claim(&self, record: &EventIdRecord, sent_at: DateTime<Utc>) -> Result<Claim, ApiError> {
+        metrics::track_db("event_ids", "claim", async {
+            let filter = doc! {
+                "project_id": record.project_id,
+                "event_id": &record.event_id,
+                "event_name": &record.event_name,
+                "server_sent_at": { "$exists": false },
+            };
+            let mut set = doc! { "server_sent_at": BsonDateTime::from_chrono(sent_at) };
+            if let Some(key) = &record.credential_key {
+                set.insert("credential_key", key);
+            }
+            let update = doc! {
+                "$set": set,
+                "$setOnInsert": {
+                    "org_id": record.org_id,
+                    "duplicates": 0_i64,
+                    "expires_at": BsonDateTime::from_chrono(record.expires_at),
+                },
+            };
+            let options = UpdateOptions::builder().upsert(true).build();
+            match self.collection.update_one(filter, update, options).await {
+                Ok(_) => return Ok(Claim::First),
+                Err(e) if is_duplicate_key(&e) => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            // Either sent already or taken by another event name
+            let filter = doc! { "project_id": record.project_id, "event_id": &record.event_id };
+            let stored: EventIdRecord = match self.collection.find_one(filter.clone(), None).await? {
+                Some(doc) => from_document(doc)?,
+                None => return Err(ApiError::Conflict(format!("Event id {} is being claimed", record.event_id))),
+            };
+            if stored.event_name != record.event_name {
+                return Err(ApiError::BadRequest(format!(
+                    "Event id {} belongs to a {} event",
+                    record.event_id, stored.event_name
+                )));
+            }
+            self.collection.update_one(filter, doc! { "$inc": { "duplicates": 1 } }, None).await?;
+            Ok(Claim::Duplicate)
+        })
+        .await
+    }
+
+    /// Undoes a claim whose event could not be sent, so that a retry is not a duplicate.
+    pub async fn release(&self, project_id: &ObjectId, event_id: &str) -> Result<(), ApiError> {
+        metrics::track_db("event_ids", "release", async {
+            let filter = doc! { "project_id": project_id, "event_id": event_id };
+            let mut unissued = filter.clone();
+            unissued.insert("issued_at", doc! { "$exists": false });
+            unissued.insert("duplicates", 0_i64);
+            if self.collection.delete_one(unissued, None).await?.deleted_count == 0 {
+                self.collection.update_one(filter, doc! { "$unset": { "server_sent_at": "" } }, None).await?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn counts(&self, org_id: &ObjectId, project_id: &ObjectId) -> Result<EventIdCounts, ApiError> {
+        metrics::track_db("event_ids", "counts", async {
+            let is_date = |field: &str| doc! { "$eq": [{ "$type": field }, "date"] };
+            let pipeline = vec![
+                doc! { "$match": { "org_id": org_id, "project_id": project_id } },
+                doc! {
+                    "$group": {
+                        "_id": null,
+                        "issued": { "$sum": { "$cond": [is_date("$issued_at"), 1, 0] } },
+                        "server_events": { "$sum": { "$cond": [is_date("$server_sent_at"), 1, 0] } },
+                        "matched": {
+                            "$sum": { "$cond": [{ "$and": [is_date("$issued_at"), is_date("$server_sent_at")] }, 1, 0] }
+                        },
+                        "duplicates": { "$sum": "$duplicates" },
+                    }
+                },
+            ];
+            let mut cursor = self.collection.aggregate(pipeline, None).await?;
+            match cursor.advance().await? {
+                true => Ok(from_document(Document::from_reader(cursor.current().as_bytes())?)?),
+                false => Ok(EventIdCounts::default()),
+            }
+        })
+        .await
+    }
+}
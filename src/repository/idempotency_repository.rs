@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+use mongodb::{
+    bson::{doc, Document, from_document, to_document, DateTime as BsonDateTime},
+    Collection, Database, IndexModel,
+    options::IndexOptions,
+};
+use std::time::Duration;
+use crate::models::idempotency::IdempotencyRecord;
+use crate::error::ApiError;
+use crate::metrics;
+use crate::repository::is_duplicate_key;
+
+#[derive(Clone)]
+pub struct IdempotencyRepository {
+    collection: Collection<Document>,
+}
+
+impl IdempotencyRepository {
+    pub fn new(db: Database) -> Self {
+        Self {
+            collection: db.collection("idempotency_keys"),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("idempotency_keys", "ensure_indexes", async {
+            let ttl = IndexModel::builder()
+                .keys(doc! { "expires_at": 1 })
+                .options(IndexOptions::builder().expire_after(Duration::ZERO).build())
+                .build();
+            self.collection.create_indexes(vec![ttl], None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Stores a pending record, returning false when one with the same id exists.
+    pub async fn insert(&self, record: &IdempotencyRecord) -> Result<bool, ApiError> {
+        metrics::track_db("idempotency_keys", "insert", async {
+            match self.collection.insert_one(to_document(record)?, None).await {
+                Ok(_) => Ok(true),
+                Err(e) if is_duplicate_key(&e) => Ok(false),
+                Err(e) => Err(e.into()),
+            }
+        })
+        .await
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<IdempotencyRecord>, ApiError> {
+        metrics::track_db("idempotency_keys", "get", async {
+            match self.collection.find_one(doc! { "_id": id }, None).await? {
+                Some(doc) => Ok(Some(from_document(doc)?)),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    /// Replaces a pending record whose lock expired, e.g. after a crash.
+    pub async fn take_over_expired(&self, record: &IdempotencyRecord, now: DateTime<Utc>) -> Result<bool, ApiError> {
+        metrics::track_db("idempotency_keys", "take_over_expired", async {
+            let filter = doc! {
+                "_id": &record.id,
+                "status": null,
+                "expires_at": { "$lte": BsonDateTime::from_chrono(now) },
+            };
+            let result = self.collection.replace_one(filter, to_document(record)?, None).await?;
+            Ok(result.modified_count == 1)
+        })
+        .await
+    }
+
+    pub async fn complete(
+        &self,
+        id: &str,
+        status: u16,
+        content_type: Option<String>,
+        body: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), ApiError> {
+        metrics::track_db("idempotency_keys", "complete", async {
+            let update = doc! {
+                "$set": {
+                    "status": status as i32,
+                    "content_type": content_type,
+                    "body": body,
+                    "expires_at": BsonDateTime::from_chrono(expires_at),
+                }
+            };
+            self.collection.update_one(doc! { "_id": id }, update, None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<(), ApiError> {
+        metrics::track_db("idempotency_keys", "delete", async {
+            self.collection.delete_one(doc! { "_id": id }, None).await?;
+            Ok(())
+        })
+        .await
+    }
+}
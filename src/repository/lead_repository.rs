@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document, from_document, to_bson, to_document, DateTime as BsonDateTime},
+    Collection, Database, IndexModel,
+    options::{FindOptions, IndexOptions},
+};
+use crate::models::lead::{Lead, LeadField};
+use crate::error::ApiError;
+use crate::metrics;
+use crate::repository::is_duplicate_key;
+
+const LEAD_LIST_LIMIT: i64 = 100;
+
+#[derive(Clone)]
+pub struct LeadRepository {
+    collection: Collection<Document>,
+}
+
+impl LeadRepository {
+    pub fn new(db: Database) -> Self {
+        Self {
+            collection: db.collection("leads"),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("leads", "ensure_indexes", async {
+            let leadgen = IndexModel::builder()
+                .keys(doc! { "project_id": 1, "leadgen_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build();
+            let recent = IndexModel::builder()
+                .keys(doc! { "org_id": 1, "project_id": 1, "received_at": -1 })
+                .build();
+            self.collection.create_indexes(vec![leadgen, recent], None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Stores `lead` unless the project already has one with its leadgen id,
+    /// and returns the stored lead either way.
+    pub async fn claim(&self, lead: Lead) -> Result<Lead, ApiError> {
+        metrics::track_db("leads", "claim", async {
+            match self.collection.insert_one(to_document(&lead)?, None).await {
+                Ok(result) => Ok(Lead { id: result.inserted_id.as_object_id(), ..lead }),
+                Err(e) if is_duplicate_key(&e) => {
+                    let filter = doc! { "project_id": lead.project_id, "leadgen_id": &lead.leadgen_id };
+                    let doc = self.collection.find_one(filter, None).await?.ok_or(ApiError::NotFound)?;
+                    Ok(from_document(doc)?)
+                }
+                Err(e) => Err(e.into()),
+            }
+        })
+        .await
+    }
+
+    pub async fn set_fields(&self, id: &ObjectId, fields: &[LeadField]) -> Result<(), ApiError> {
+        metrics::track_db("leads", "set_fields", async {
+            let update = doc! { "$set": { "fields": to_bson(fields)? } };
+            self.collection.update_one(doc! { "_id": id }, update, None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn mark_processed(&self, id: &ObjectId, at: DateTime<Utc>) -> Result<(), ApiError> {
+        metrics::track_db("leads", "mark_processed", async {
+            let update = doc! { "$set": { "processed_at": BsonDateTime::from_chrono(at) } };
+            self.collection.update_one(doc! { "_id": id }, update, None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// The most recent leads of a project, newest first.
+    pub async fn get_by_project(&self, org_id: &ObjectId, project_id: &ObjectId) -> Result<Vec<Lead>, ApiError> {
+        metrics::track_db("leads", "get_by_project", async {
+            let filter = doc! { "org_id": org_id, "project_id": project_id };
+            let options = FindOptions::builder()
+                .sort(doc! { "received_at": -1 })
+                .limit(LEAD_LIST_LIMIT)
+                .build();
+            let mut cursor = self.collection.find(filter, options).await?;
+            let mut leads = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                leads.push(from_document(doc)?);
+            }
+            Ok(leads)
+        })
+        .await
+    }
+}
@@ -0,0 +1,192 @@
+use chrono::{DateTime, Duration, Utc};
+use mongodb::{
+    bson::{doc, from_document, oid::ObjectId, to_document, DateTime as BsonDateTime, Document},
+    options::{FindOptions, IndexOptions},
+    Collection, Database, IndexModel,
+};
+use serde::Deserialize;
+
+use crate::error::ApiError;
+use crate::metrics;
+use crate::repository::is_duplicate_key;
+use crate::models::link::{DailyClicks, LinkClick, LinkStats, ReferrerClicks, TrackedLink, STATS_DAYS, TOP_REFERRERS};
+
+#[derive(Deserialize)]
+struct ClickTotals {
+    clicks: u64,
+    fbclid_clicks: u64,
+    first_click_at: BsonDateTime,
+    last_click_at: BsonDateTime,
+}
+
+#[derive(Deserialize)]
+struct ClickFacets {
+    totals: Vec<ClickTotals>,
+    by_day: Vec<DailyClicks>,
+    top_referrers: Vec<ReferrerClicks>,
+}
+
+#[derive(Clone)]
+pub struct LinkRepository {
+    links: Collection<Document>,
+    clicks: Collection<Document>,
+}
+
+impl LinkRepository {
+    pub fn new(db: Database) -> Self {
+        Self {
+            links: db.collection("links"),
+            clicks: db.collection("link_clicks"),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("links", "ensure_indexes", async {
+            let code = IndexModel::builder()
+                .keys(doc! { "code": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build();
+            let project = IndexModel::builder()
+                .keys(doc! { "org_id": 1, "project_id": 1, "created_at": -1 })
+                .build();
+            self.links.create_indexes(vec![code, project], None).await?;
+
+            let clicks = IndexModel::builder()
+                .keys(doc! { "link_id": 1, "clicked_at": -1 })
+                .build();
+            self.clicks.create_index(clicks, None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Stores `link`, failing with a conflict when its code is taken.
+    pub async fn create(&self, link: TrackedLink) -> Result<TrackedLink, ApiError> {
+        metrics::track_db("links", "create", async {
+            match self.links.insert_one(to_document(&link)?, None).await {
+                Ok(result) => Ok(TrackedLink { id: result.inserted_id.as_object_id(), ..link }),
+                Err(e) if is_duplicate_key(&e) => Err(ApiError::Conflict(format!("Code {} is taken", link.code))),
+                Err(e) => Err(e.into()),
+            }
+        })
+        .await
+    }
+
+    pub async fn get_by_code(&self, code: &str) -> Result<Option<TrackedLink>, ApiError> {
+        metrics::track_db("links", "get_by_code", async {
+            match self.links.find_one(doc! { "code": code }, None).await? {
+                Some(doc) => Ok(Some(from_document(doc)?)),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    pub async fn get_by_id(&self, org_id: &ObjectId, project_id: &ObjectId, id: &ObjectId) -> Result<TrackedLink, ApiError> {
+        metrics::track_db("links", "get_by_id", async {
+            let filter = doc! { "_id": id, "org_id": org_id, "project_id": project_id };
+            let doc = self.links.find_one(filter, None).await?.ok_or(ApiError::NotFound)?;
+            Ok(from_document(doc)?)
+        })
+        .await
+    }
+
+    /// A project's links, newest first.
+    pub async fn get_by_project(&self, org_id: &ObjectId, project_id: &ObjectId) -> Result<Vec<TrackedLink>, ApiError> {
+        metrics::track_db("links", "get_by_project", async {
+            let filter = doc! { "org_id": org_id, "project_id": project_id };
+            let options = FindOptions::builder().sort(doc! { "created_at": -1 }).build();
+            let mut cursor = self.links.find(filter, options).await?;
+            let mut links = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                links.push(from_document(doc)?);
+            }
+            Ok(links)
+        })
+        .await
+    }
+
+    /// Deletes a link together with its clicks.
+    pub async fn delete(&self, org_id: &ObjectId, project_id: &ObjectId, id: &ObjectId) -> Result<bool, ApiError> {
+        metrics::track_db("links", "delete", async {
+            let filter = doc! { "_id": id, "org_id": org_id, "project_id": project_id };
+            let deleted = self.links.delete_one(filter, None).await?.deleted_count > 0;
+            if deleted {
+                self.clicks.delete_many(doc! { "link_id": id }, None).await?;
+            }
+            Ok(deleted)
+        })
+        .await
+    }
+
+    pub async fn record_click(&self, click: &LinkClick) -> Result<(), ApiError> {
+        metrics::track_db("link_clicks", "record_click", async {
+            self.clicks.insert_one(to_document(click)?, None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Click counts of `link`, per day over the last `STATS_DAYS` days as of `now`.
+    pub async fn stats(&self, link: &TrackedLink, now: DateTime<Utc>) -> Result<LinkStats, ApiError> {
+        metrics::track_db("link_clicks", "stats", async {
+            let link_id = link.id.ok_or(ApiError::NotFound)?;
+            let since = BsonDateTime::from_chrono(now - Duration::days(STATS_DAYS));
+            let pipeline = vec![
+                doc! { "$match": { "link_id": link_id } },
+                doc! {
+                    "$facet": {
+                        "totals": [{
+                            "$group": {
+                                "_id": null,
+                                "clicks": { "$sum": 1 },
+                                "fbclid_clicks": {
+                                    "$sum": { "$cond": [{ "$eq": [{ "$type": "$fbclid" }, "string"] }, 1, 0] }
+                                },
+                                "first_click_at": { "$min": "$clicked_at" },
+                                "last_click_at": { "$max": "$clicked_at" },
+                            }
+                        }],
+                        "by_day": [
+                            { "$match": { "clicked_at": { "$gte": since } } },
+                            {
+                                "$group": {
+                                    "_id": { "$dateToString": { "format": "%Y-%m-%d", "date": "$clicked_at" } },
+                                    "clicks": { "$sum": 1 },
+                                }
+                            },
+                            { "$sort": { "_id": 1 } },
+                            { "$project": { "_id": 0, "date": "$_id", "clicks": 1 } },
+                        ],
+                        "top_referrers": [
+                            { "$match": { "referrer": { "$type": "string" } } },
+                            { "$group": { "_id": "$referrer", "clicks": { "$sum": 1 } } },
+                            { "$sort": { "clicks": -1, "_id": 1 } },
+                            { "$limit": TOP_REFERRERS },
+                            { "$project": { "_id": 0, "referrer": "$_id", "clicks": 1 } },
+                        ],
+                    }
+                },
+            ];
+            let mut cursor = self.clicks.aggregate(pipeline, None).await?;
+            let facets: ClickFacets = match cursor.advance().await? {
+                true => from_document(Document::from_reader(cursor.current().as_bytes())?)?,
+                false => ClickFacets { totals: vec![], by_day: vec![], top_referrers: vec![] },
+            };
+            let totals = facets.totals.into_iter().next();
+            Ok(LinkStats {
+                link_id,
+                code: link.code.clone(),
+                clicks: totals.as_ref().map_or(0, |t| t.clicks),
+                fbclid_clicks: totals.as_ref().map_or(0, |t| t.fbclid_clicks),
+                first_click_at: totals.as_ref().map(|t| t.first_click_at.to_chrono()),
+                last_click_at: totals.as_ref().map(|t| t.last_click_at.to_chrono()),
+                by_day: facets.by_day,
+                top_referrers: facets.top_referrers,
+            })
+        })
+        .await
+    }
+}
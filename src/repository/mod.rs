@@ -1,6 +1,63 @@
 pub mod project_repository;
 pub mod account_repository;
+pub mod api_key_repository;
+pub mod organization_repository;
+pub mod idempotency_repository;
+pub mod webhook_repository;
+pub mod webhook_delivery_repository;
+pub mod outbox_repository;
+pub mod lead_repository;
+pub mod link_repository;
+pub mod event_id_repository;
+pub mod transaction;
 #[cfg(test)]
 mod project_repository_test;
 #[cfg(test)]
-mod account_repository_test;
\ No newline at end of file
+mod account_repository_test;
+#[cfg(test)]
+mod api_key_repository_test;
+
+use mongodb::{
+    error::{ErrorKind, WriteFailure},
+    options::ClientOptions,
+    Client,
+};
+
+use crate::{config::MongoConfig, error::ApiError};
+
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+pub async fn connect(config: &MongoConfig) -> Result<Client, mongodb::error::Error> {
+    let mut options = ClientOptions::parse(config.uri.expose()).await?;
+    options.max_pool_size = config.max_pool_size;
+    options.min_pool_size = config.min_pool_size;
+    options.connect_timeout = Some(config.connect_timeout);
+
+    Client::with_options(options)
+}
+
+/// Whether `error` failed only on unique indexes, for a single or a bulk write.
+/// `findAndModify` and index builds report it as a command error.
+pub(crate) fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(e)) => e.code == DUPLICATE_KEY_CODE,
+        ErrorKind::Command(e) => e.code == DUPLICATE_KEY_CODE,
+        ErrorKind::BulkWrite(failure) => {
+            failure.write_concern_error.is_none()
+                && failure
+                    .write_errors
+                    .as_ref()
+                    .is_some_and(|errors| errors.iter().all(|e| e.code == DUPLICATE_KEY_CODE))
+        }
+        _ => false,
+    }
+}
+
+/// `error` as a 409 with `message` when it failed on a unique index.
+pub(crate) fn conflict_on_duplicate(error: mongodb::error::Error, message: impl FnOnce() -> String) -> ApiError {
+    if is_duplicate_key(&error) {
+        ApiError::Conflict(message())
+    } else {
+        ApiError::MongoDB(error)
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document, from_document, to_bson, to_document},
+    Collection, Database, IndexModel,
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+};
+use crate::models::organization::{Organization, OrganizationMember};
+use crate::repository::transaction::Transaction;
+use crate::error::ApiError;
+use crate::metrics;
+
+#[derive(Clone)]
+pub struct OrganizationRepository {
+    collection: Collection<Document>,
+}
+
+impl OrganizationRepository {
+    pub fn new(db: Database) -> Self {
+        Self {
+            collection: db.collection("organizations"),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("organizations", "ensure_indexes", async {
+            let members = IndexModel::builder()
+                .keys(doc! { "members.account_id": 1 })
+                .build();
+            self.collection.create_indexes(vec![members], None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn create(&self, organization: Organization) -> Result<Organization, ApiError> {
+        metrics::track_db("organizations", "create", async {
+            let doc = to_document(&organization)?;
+            let result = self.collection.insert_one(doc, None).await?;
+            let id = result.inserted_id.as_object_id()
+                .ok_or_else(|| ApiError::InternalServerError("Failed to get inserted ID".into()))?;
+            self.get_by_id(&id).await
+        })
+        .await
+    }
+
+    pub async fn get_by_id(&self, id: &ObjectId) -> Result<Organization, ApiError> {
+        metrics::track_db("organizations", "get_by_id", async {
+            let filter = doc! { "_id": id };
+            let doc = self.collection.find_one(filter, None).await?
+                .ok_or(ApiError::NotFound)?;
+            Ok(from_document(doc)?)
+        })
+        .await
+    }
+
+    pub async fn get_all(&self) -> Result<Vec<Organization>, ApiError> {
+        metrics::track_db("organizations", "get_all", async {
+            let mut cursor = self.collection.find(None, None).await?;
+            let mut organizations = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                organizations.push(from_document(doc)?);
+            }
+            Ok(organizations)
+        })
+        .await
+    }
+
+    /// Replaces the member list, only if it is unchanged since `previous` was read.
+    pub async fn update_members(
+        &self,
+        tx: &mut Transaction,
+        id: &ObjectId,
+        previous: &[OrganizationMember],
+        members: &[OrganizationMember],
+    ) -> Result<Organization, ApiError> {
+        metrics::track_db("organizations", "update_members", async {
+            let filter = doc! { "_id": id, "members": to_bson(previous)? };
+            let update = doc! {
+                "$set": {
+                    "members": to_bson(members)?,
+                    "updated_at": mongodb::bson::DateTime::from_chrono(Utc::now()),
+                }
+            };
+            let options = FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build();
+            let doc = match tx.session() {
+                Some(session) => self.collection.find_one_and_update_with_session(filter, update, options, session).await?,
+                None => self.collection.find_one_and_update(filter, update, options).await?,
+            };
+            match doc {
+                Some(doc) => Ok(from_document(doc)?),
+                None => {
+                    // Either gone or changed concurrently, a retry re-reads the members
+                    self.get_by_id(id).await?;
+                    Err(ApiError::BadRequest("Organization members changed concurrently, retry".to_string()))
+                }
+            }
+        })
+        .await
+    }
+}
@@ -0,0 +1,227 @@
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt};
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson, Document, from_bson, from_document, to_bson, to_document, DateTime as BsonDateTime},
+    change_stream::event::ResumeToken,
+    Collection, Database, IndexModel,
+    options::{ChangeStreamOptions, FindOneAndUpdateOptions, FindOptions, IndexOptions, ReturnDocument},
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::OnceCell;
+use tracing::warn;
+use crate::models::event::{DomainEvent, OutboxEntry};
+use crate::repository::transaction::Transaction;
+use crate::error::ApiError;
+use crate::metrics;
+
+// Dispatched entries are kept for a week to help debugging subscribers
+const DISPATCHED_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Clone)]
+pub struct OutboxRepository {
+    collection: Collection<Document>,
+    supports_transactions: Arc<OnceCell<bool>>,
+}
+
+impl OutboxRepository {
+    pub fn new(db: Database) -> Self {
+        Self {
+            collection: db.collection("outbox"),
+            supports_transactions: Arc::new(OnceCell::new()),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("outbox", "ensure_indexes", async {
+            let due = IndexModel::builder()
+                .keys(doc! { "status": 1, "next_attempt_at": 1 })
+                .build();
+            let retention = IndexModel::builder()
+                .keys(doc! { "dispatched_at": 1 })
+                .options(IndexOptions::builder().expire_after(DISPATCHED_RETENTION).build())
+                .build();
+            self.collection.create_indexes(vec![due, retention], None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Whether the server supports transactions and change streams, asked once.
+    pub async fn is_replica_set(&self) -> Result<bool, ApiError> {
+        let supported = self
+            .supports_transactions
+            .get_or_try_init(|| async {
+                let hello = self
+                    .collection
+                    .client()
+                    .database("admin")
+                    .run_command(doc! { "hello": 1 }, None)
+                    .await?;
+                // Replica set members report their set name, mongos reports isdbgrid
+                let supported = hello.contains_key("setName") || hello.get_str("msg") == Ok("isdbgrid");
+                if !supported {
+                    warn!("MongoDB is not a replica set, writes and their outbox events are not atomic and a crash between them can lose the event");
+                }
+                Ok::<_, ApiError>(supported)
+            })
+            .await?;
+        Ok(*supported)
+    }
+
+    /// Starts a transaction for an entity write and its events, or a
+    /// non-transactional stand-in when the server is not a replica set.
+    pub async fn begin(&self) -> Result<Transaction, ApiError> {
+        if self.is_replica_set().await? {
+            Transaction::start(self.collection.client()).await
+        } else {
+            Ok(Transaction::none())
+        }
+    }
+
+    pub async fn append(&self, tx: &mut Transaction, org_id: &ObjectId, event: DomainEvent) -> Result<(), ApiError> {
+        metrics::track_db("outbox", "append", async {
+            let doc = to_document(&OutboxEntry::new(*org_id, event))?;
+            match tx.session() {
+                Some(session) => self.collection.insert_one_with_session(doc, None, session).await?,
+                None => self.collection.insert_one(doc, None).await?,
+            };
+            Ok(())
+        })
+        .await
+    }
+
+    /// The organization's entries written after `after`, in id order, read
+    /// from the cursor as the stream is polled.
+    pub async fn stream_after(
+        &self,
+        org_id: &ObjectId,
+        after: &ObjectId,
+    ) -> Result<impl Stream<Item = Result<OutboxEntry, ApiError>> + Send + 'static, ApiError> {
+        let cursor = metrics::track_db("outbox", "stream_after", async {
+            let filter = doc! { "org_id": org_id, "_id": { "$gt": after } };
+            let options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+            Ok(self.collection.find(filter, options).await?)
+        })
+        .await?;
+        Ok(cursor.map(|doc| Ok(from_document(doc?)?)))
+    }
+
+    /// The organization's entries in commit order from a change stream,
+    /// each with the resume token it can be followed again from, see
+    /// [`resume_token`]. Starts after `resume_after`, or with the next entry
+    /// committed. Needs a replica set.
+    pub async fn watch(
+        &self,
+        org_id: &ObjectId,
+        resume_after: Option<ResumeToken>,
+    ) -> Result<impl Stream<Item = Result<(String, OutboxEntry), ApiError>> + Send + 'static, ApiError> {
+        let stream = metrics::track_db("outbox", "watch", async {
+            let pipeline = [doc! {
+                "$match": { "operationType": "insert", "fullDocument.org_id": org_id }
+            }];
+            let options = ChangeStreamOptions::builder().resume_after(resume_after).build();
+            Ok(self.collection.watch(pipeline, options).await?)
+        })
+        .await?;
+        Ok(stream.filter_map(|event| async move {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => return Some(Err(e.into())),
+            };
+            let token = resume_token_id(&event.id);
+            event.full_document.map(|doc| Ok((token?, from_document(doc)?)))
+        }))
+    }
+
+    /// Takes the oldest due entry, pushing its next attempt to `lease_until`
+    /// so a concurrent dispatcher does not pick it up too.
+    pub async fn claim_due(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<Option<OutboxEntry>, ApiError> {
+        metrics::track_db("outbox", "claim_due", async {
+            let filter = doc! {
+                "status": "pending",
+                "next_attempt_at": { "$lte": BsonDateTime::from_chrono(now) },
+            };
+            let update = doc! { "$set": { "next_attempt_at": BsonDateTime::from_chrono(lease_until) } };
+            let options = FindOneAndUpdateOptions::builder()
+                .sort(doc! { "next_attempt_at": 1 })
+                .return_document(ReturnDocument::After)
+                .build();
+            match self.collection.find_one_and_update(filter, update, options).await? {
+                Some(doc) => Ok(Some(from_document(doc)?)),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    pub async fn mark_handled(&self, id: &ObjectId, subscriber: &str) -> Result<(), ApiError> {
+        metrics::track_db("outbox", "mark_handled", async {
+            let update = doc! { "$addToSet": { "handled_by": subscriber } };
+            self.collection.update_one(doc! { "_id": id }, update, None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn mark_dispatched(&self, id: &ObjectId, attempts: u32) -> Result<(), ApiError> {
+        metrics::track_db("outbox", "mark_dispatched", async {
+            let update = doc! {
+                "$set": {
+                    "status": "dispatched",
+                    "attempts": attempts,
+                    "last_error": null,
+                    "dispatched_at": BsonDateTime::now(),
+                }
+            };
+            self.collection.update_one(doc! { "_id": id }, update, None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Records a failed dispatch, giving up on the entry when there is no
+    /// `next_attempt_at`.
+    pub async fn mark_failed(
+        &self,
+        id: &ObjectId,
+        attempts: u32,
+        error: &str,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<(), ApiError> {
+        metrics::track_db("outbox", "mark_failed", async {
+            let mut set = doc! { "attempts": attempts, "last_error": error };
+            match next_attempt_at {
+                Some(at) => set.insert("next_attempt_at", BsonDateTime::from_chrono(at)),
+                None => set.insert("status", "failed"),
+            };
+            self.collection.update_one(doc! { "_id": id }, doc! { "$set": set }, None).await?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// The change stream resume token that `id` names, as returned with the
+/// entries of [`OutboxRepository::watch`].
+pub fn resume_token(id: &str) -> Result<ResumeToken, ApiError> {
+    let id = id.trim();
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(ApiError::BadRequest(format!("Invalid resume token {}", id)));
+    }
+    Ok(from_bson(Bson::Document(doc! { "_data": id }))?)
+}
+
+// Tokens are documents holding a hex `_data` string, which is what clients get
+fn resume_token_id(token: &ResumeToken) -> Result<String, ApiError> {
+    match to_bson(token)? {
+        Bson::Document(doc) => match doc.get_str("_data") {
+            Ok(id) => Ok(id.to_string()),
+            Err(_) => Err(ApiError::InternalServerError(format!("Unexpected resume token {}", doc))),
+        },
+        other => Err(ApiError::InternalServerError(format!("Unexpected resume token {}", other))),
+    }
+}
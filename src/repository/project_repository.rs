@@ -1,71 +1,557 @@
+use chrono::{DateTime, Utc};
 use mongodb::{
-    bson::{doc, oid::ObjectId, Document, from_document, to_document},
-    Collection, Database,
-    options::UpdateOptions,
+    bson::{doc, oid::ObjectId, Bson, Document, from_document, to_bson, to_document},
+    Collection, Database, IndexModel,
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
 };
-use crate::models::project::Project;
+use futures_util::{Stream, StreamExt};
+use crate::crypto::SecretCipher;
+use crate::models::batch::BatchOperation;
+use crate::models::ad_platform::{AdPlatform, AdPlatformCredential};
+use crate::models::project::{Project, ProjectStateCounts, ProjectStatus, StatusTransition, TokenHealthUpdate};
+use crate::repository::transaction::Transaction;
 use crate::error::ApiError;
+use crate::metrics;
+use tracing::warn;
 
 #[derive(Clone)]
 pub struct ProjectRepository {
     collection: Collection<Document>,
+    cipher: SecretCipher,
 }
 
 impl ProjectRepository {
     pub fn new(db: Database) -> Self {
         Self {
             collection: db.collection("projects"),
+            cipher: SecretCipher::default(),
         }
     }
 
-    pub async fn create(&self, project: Project) -> Result<Project, ApiError> {
-        let doc = to_document(&project)?;
-        let result = self.collection.insert_one(doc, None).await?;
-        let id = result.inserted_id.as_object_id()
-            .ok_or_else(|| ApiError::InternalServerError("Failed to get inserted ID".into()))?;
-        self.get_by_id(&id).await
+    /// Encrypts credential secrets at rest with `cipher`.
+    pub fn with_cipher(mut self, cipher: SecretCipher) -> Self {
+        self.cipher = cipher;
+        self
     }
 
-    pub async fn update(&self, id: &ObjectId, project: Project) -> Result<Project, ApiError> {
-        let filter = doc! { "_id": id };
-        let update = doc! {
-            "$set": to_document(&project)?
-        };
+    fn seal(&self, mut project: Project) -> Result<Project, ApiError> {
+        for credential in project.ad_credentials.values_mut() {
+            self.seal_credential(credential)?;
+        }
+        Ok(project)
+    }
+
+    fn seal_credential(&self, credential: &mut AdPlatformCredential) -> Result<(), ApiError> {
+        for secret in credential.secrets_mut() {
+            *secret = self.cipher.encrypt(secret)?;
+        }
+        Ok(())
+    }
 
-        let options = UpdateOptions::default();
-        match self.collection.update_one(filter, update, options).await {
-            Ok(result) if result.modified_count == 1 => self.get_by_id(id).await,
-            Ok(_) => Err(ApiError::NotFound),
-            Err(e) => Err(ApiError::MongoDB(e)),
+    // The `facebook_credentials` view is only for readers, `ad_credentials` is stored.
+    // `expires_at` is sent as a string but stored as a date, for the expiry queries
+    fn stored_document(&self, project: Project) -> Result<Document, ApiError> {
+        let expires_at = project.expires_at;
+        let mut doc = to_document(&self.seal(project)?)?;
+        doc.remove("facebook_credentials");
+        if let Some(expires_at) = expires_at {
+            doc.insert("expires_at", mongodb::bson::DateTime::from_chrono(expires_at));
         }
+        Ok(doc)
+    }
+
+    fn open(&self, mut doc: Document) -> Result<Project, ApiError> {
+        // Projects stored before statuses existed, until migration 0002 has run
+        if !doc.contains_key("status") {
+            let is_active = doc.get_bool("is_active").unwrap_or_default();
+            let expired = matches!(doc.get("expires_at"), Some(Bson::DateTime(at)) if at.to_chrono() <= Utc::now());
+            doc.insert("status", ProjectStatus::from_legacy(is_active, expired).as_str());
+        }
+        let mut project: Project = from_document(doc)?;
+        for credential in project.ad_credentials.values_mut() {
+            for secret in credential.secrets_mut() {
+                *secret = self.cipher.decrypt(secret)?;
+            }
+        }
+        Ok(project)
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("projects", "ensure_indexes", async {
+            let expiry = IndexModel::builder()
+                .keys(doc! { "status": 1, "expires_at": 1 })
+                .build();
+            let tenant = IndexModel::builder()
+                .keys(doc! { "org_id": 1 })
+                .build();
+            self.collection.create_indexes(vec![expiry, tenant], None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn create(&self, tx: &mut Transaction, mut project: Project) -> Result<Project, ApiError> {
+        metrics::track_db("projects", "create", async {
+            if project.org_id.is_none() {
+                return Err(ApiError::InternalServerError("Project has no organization".into()));
+            }
+            let doc = self.stored_document(project.clone())?;
+            let result = match tx.session() {
+                Some(session) => self.collection.insert_one_with_session(doc, None, session).await?,
+                None => self.collection.insert_one(doc, None).await?,
+            };
+            let id = result.inserted_id.as_object_id()
+                .ok_or_else(|| ApiError::InternalServerError("Failed to get inserted ID".into()))?;
+            project.id = Some(id);
+            Ok(project)
+        })
+        .await
+    }
+
+    pub async fn update(&self, tx: &mut Transaction, org_id: &ObjectId, id: &ObjectId, project: Project) -> Result<Project, ApiError> {
+        metrics::track_db("projects", "update", async {
+            let filter = doc! { "_id": id, "org_id": org_id };
+            let mut set = self.stored_document(project)?;
+            // Status only changes through `transition`
+            for field in ["status", "is_active", "status_history"] {
+                set.remove(field);
+            }
+            // Left on projects stored before migration 0003
+            let update = doc! { "$set": set, "$unset": { "facebook_credentials": "" } };
+
+            // Read back inside the transaction, a separate read would not see the write yet
+            let options = FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build();
+            let doc = match tx.session() {
+                Some(session) => self.collection.find_one_and_update_with_session(filter, update, options, session).await?,
+                None => self.collection.find_one_and_update(filter, update, options).await?,
+            };
+            self.open(doc.ok_or(ApiError::NotFound)?)
+        })
+        .await
+    }
+
+    /// Sets one credential with a `$set` on its path, leaving the others
+    /// alone. The key must already be set when `exists` is true and must not
+    /// be otherwise; `None` means the project or that condition did not match.
+    pub async fn set_credential(
+        &self,
+        tx: &mut Transaction,
+        org_id: &ObjectId,
+        id: &ObjectId,
+        key: &str,
+        mut credential: AdPlatformCredential,
+        exists: bool,
+    ) -> Result<Option<Project>, ApiError> {
+        metrics::track_db("projects", "set_credential", async {
+            let path = format!("ad_credentials.{}", key);
+            self.seal_credential(&mut credential)?;
+            let filter = doc! { "_id": id, "org_id": org_id, &path: { "$exists": exists } };
+            let update = doc! {
+                "$set": { &path: to_bson(&credential)?, "updated_at": mongodb::bson::DateTime::now() }
+            };
+            let options = FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build();
+            let doc = match tx.session() {
+                Some(session) => self.collection.find_one_and_update_with_session(filter, update, options, session).await?,
+                None => self.collection.find_one_and_update(filter, update, options).await?,
+            };
+            doc.map(|doc| self.open(doc)).transpose()
+        })
+        .await
     }
 
-    pub async fn delete(&self, id: &ObjectId) -> Result<bool, ApiError> {
-        let filter = doc! { "_id": id };
-        let result = self.collection.delete_one(filter, None).await?;
-        Ok(result.deleted_count > 0)
+    /// Removes one credential with an `$unset` on its path, only when it is
+    /// for `platform` if one is given. `None` means the project or such a
+    /// credential does not exist.
+    pub async fn unset_credential(
+        &self,
+        tx: &mut Transaction,
+        org_id: &ObjectId,
+        id: &ObjectId,
+        key: &str,
+        platform: Option<AdPlatform>,
+    ) -> Result<Option<Project>, ApiError> {
+        metrics::track_db("projects", "unset_credential", async {
+            let path = format!("ad_credentials.{}", key);
+            let mut filter = doc! { "_id": id, "org_id": org_id, &path: { "$exists": true } };
+            if let Some(platform) = platform {
+                filter.insert(format!("{}.platform", path), platform.as_str());
+            }
+            let update = doc! {
+                "$unset": { &path: "" },
+                "$set": { "updated_at": mongodb::bson::DateTime::now() },
+            };
+            let options = FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build();
+            let doc = match tx.session() {
+                Some(session) => self.collection.find_one_and_update_with_session(filter, update, options, session).await?,
+                None => self.collection.find_one_and_update(filter, update, options).await?,
+            };
+            doc.map(|doc| self.open(doc)).transpose()
+        })
+        .await
+    }
+
+    pub async fn delete(&self, tx: &mut Transaction, org_id: &ObjectId, id: &ObjectId) -> Result<bool, ApiError> {
+        metrics::track_db("projects", "delete", async {
+            let filter = doc! { "_id": id, "org_id": org_id };
+            let result = match tx.session() {
+                Some(session) => self.collection.delete_one_with_session(filter, None, session).await?,
+                None => self.collection.delete_one(filter, None).await?,
+            };
+            Ok(result.deleted_count > 0)
+        })
+        .await
     }
 
-    pub async fn get_by_id(&self, id: &ObjectId) -> Result<Project, ApiError> {
-        let filter = doc! { "_id": id };
-        let doc = self.collection.find_one(filter, None).await?
-            .ok_or(ApiError::NotFound)?;
-        Ok(from_document(doc)?)
+    pub async fn get_by_id(&self, org_id: &ObjectId, id: &ObjectId) -> Result<Project, ApiError> {
+        metrics::track_db("projects", "get_by_id", async {
+            let filter = doc! { "_id": id, "org_id": org_id };
+            let doc = self.collection.find_one(filter, None).await?
+                .ok_or(ApiError::NotFound)?;
+            self.open(doc)
+        })
+        .await
     }
 
-    pub async fn get_all(&self) -> Result<Vec<Project>, ApiError> {
-        let mut cursor = self.collection.find(None, None).await?;
-        let mut projects = Vec::new();
+    pub async fn get_all(&self, org_id: &ObjectId) -> Result<Vec<Project>, ApiError> {
+        metrics::track_db("projects", "get_all", async {
+            let mut cursor = self.collection.find(doc! { "org_id": org_id }, None).await?;
+            let mut projects = Vec::new();
         
-        while cursor.advance().await? {
-            let raw_doc = cursor.current();
-            if let Ok(doc) = Document::from_reader(raw_doc.as_bytes()) {
-                match from_document(doc) {
+            while cursor.advance().await? {
+                let raw_doc = cursor.current();
+                if let Ok(doc) = Document::from_reader(raw_doc.as_bytes()) {
+                    match self.open(doc) {
+                        Ok(project) => projects.push(project),
+                        Err(e) => warn!(error = %e, "Skipping project that failed to deserialize"),
+                    }
+                }
+            }
+            Ok(projects)
+        })
+        .await
+    }
+
+    pub async fn get_many(&self, org_id: &ObjectId, ids: &[ObjectId]) -> Result<Vec<Project>, ApiError> {
+        metrics::track_db("projects", "get_many", async {
+            let filter = doc! { "_id": { "$in": ids }, "org_id": org_id };
+            let mut cursor = self.collection.find(filter, None).await?;
+            let mut projects = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                projects.push(self.open(doc)?);
+            }
+            Ok(projects)
+        })
+        .await
+    }
+
+    /// Applies `operation` to the organization's projects among `ids` that
+    /// are still in status `from`, recording `transition` on each when the
+    /// operation changes their status. Returns the projects it updated.
+    pub async fn update_many(
+        &self,
+        tx: &mut Transaction,
+        org_id: &ObjectId,
+        ids: &[ObjectId],
+        from: ProjectStatus,
+        operation: &BatchOperation,
+        transition: Option<&StatusTransition>,
+    ) -> Result<Vec<Project>, ApiError> {
+        metrics::track_db("projects", "update_many", async {
+            let now = mongodb::bson::DateTime::from_chrono(transition.map(|transition| transition.at).unwrap_or_else(Utc::now));
+            let mut set = doc! { "updated_at": now };
+            match operation {
+                BatchOperation::Extend { until: Some(until), .. } => {
+                    set.insert("expires_at", mongodb::bson::DateTime::from_chrono(*until));
+                }
+                BatchOperation::Extend { days, .. } => {
+                    let millis = i64::from(days.unwrap_or_default()) * 24 * 60 * 60 * 1000;
+                    // $max ignores a missing expiry, so those start from now
+                    set.insert("expires_at", doc! { "$add": [{ "$max": ["$expires_at", now] }, millis] });
+                }
+                _ => {}
+            }
+            let mut to = from;
+            if let Some(transition) = transition {
+                to = transition.to;
+                set.insert("status", to.as_str());
+                set.insert("is_active", to == ProjectStatus::Active);
+                // $literal keeps the entry from being read as an expression
+                set.insert("status_history", doc! {
+                    "$concatArrays": [{ "$ifNull": ["$status_history", []] }, [{ "$literal": to_bson(transition)? }]]
+                });
+            }
+            let filter = doc! { "_id": { "$in": ids }, "org_id": org_id, "status": from.as_str() };
+            let update = vec![doc! { "$set": set }];
+            // Projects another request moved out of `from` meanwhile are left out
+            let updated = doc! { "_id": { "$in": ids }, "org_id": org_id, "status": to.as_str(), "updated_at": now };
+
+            let mut projects = Vec::new();
+            match tx.session() {
+                Some(session) => {
+                    self.collection.update_many_with_session(filter, update, None, session).await?;
+                    let mut cursor = self.collection.find_with_session(updated, None, session).await?;
+                    while let Some(doc) = cursor.next(session).await {
+                        projects.push(self.open(doc?)?);
+                    }
+                }
+                None => {
+                    self.collection.update_many(filter, update, None).await?;
+                    let mut cursor = self.collection.find(updated, None).await?;
+                    while cursor.advance().await? {
+                        let doc = Document::from_reader(cursor.current().as_bytes())?;
+                        projects.push(self.open(doc)?);
+                    }
+                }
+            }
+            Ok(projects)
+        })
+        .await
+    }
+
+    /// The organization's projects in id order, read from the cursor as the
+    /// stream is polled.
+    pub async fn stream_all(&self, org_id: &ObjectId) -> Result<impl Stream<Item = Result<Project, ApiError>> + Send + 'static, ApiError> {
+        let cursor = metrics::track_db("projects", "stream_all", async {
+            let options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+            Ok(self.collection.find(doc! { "org_id": org_id }, options).await?)
+        })
+        .await?;
+        let repository = self.clone();
+        Ok(cursor.map(move |doc| repository.open(doc?)))
+    }
+
+    /// Looks a project up in any organization, for operator tooling.
+    pub async fn find_by_id(&self, id: &ObjectId) -> Result<Project, ApiError> {
+        metrics::track_db("projects", "find_by_id", async {
+            let doc = self.collection.find_one(doc! { "_id": id }, None).await?
+                .ok_or(ApiError::NotFound)?;
+            self.open(doc)
+        })
+        .await
+    }
+
+    /// Every project across organizations, for operator tooling.
+    pub async fn find_all(&self) -> Result<Vec<Project>, ApiError> {
+        metrics::track_db("projects", "find_all", async {
+            let mut cursor = self.collection.find(doc! {}, None).await?;
+            let mut projects = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                match self.open(doc) {
                     Ok(project) => projects.push(project),
-                    Err(e) => eprintln!("Error deserializing project: {}", e),
+                    Err(e) => warn!(error = %e, "Skipping project that failed to deserialize"),
                 }
             }
-        }
-        Ok(projects)
+            Ok(projects)
+        })
+        .await
     }
-}
\ No newline at end of file
+
+    /// Re-encrypts credential secrets that are plaintext or sealed with a key
+    /// other than the active one, returning how many projects were affected.
+    pub async fn rotate_secrets(&self, dry_run: bool) -> Result<u64, ApiError> {
+        metrics::track_db("projects", "rotate_secrets", async {
+            if self.cipher.active_key_id().is_none() {
+                return Err(ApiError::BadRequest("No active encryption key is configured".to_string()));
+            }
+            let filter = doc! { "ad_credentials": { "$ne": {} } };
+            let mut cursor = self.collection.find(filter, None).await?;
+            let mut rotated = 0;
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                let stale = from_document::<Project>(doc.clone())?.ad_credentials.values().any(|credential| {
+                    credential.secrets().into_iter().any(|secret| self.cipher.needs_rotation(secret))
+                });
+                if !stale {
+                    continue;
+                }
+                rotated += 1;
+                if dry_run {
+                    continue;
+                }
+
+                let previous = doc.get("ad_credentials").cloned();
+                let project = self.seal(self.open(doc)?)?;
+                // Matching the old credentials leaves concurrently edited projects alone
+                let filter = doc! { "_id": project.id, "ad_credentials": previous };
+                let update = doc! { "$set": { "ad_credentials": to_document(&project.ad_credentials)? } };
+                self.collection.update_one(filter, update, None).await?;
+            }
+            Ok(rotated)
+        })
+        .await
+    }
+
+    /// Projects with at least one credential, except cancelled ones.
+    pub async fn find_with_credentials(&self) -> Result<Vec<Project>, ApiError> {
+        metrics::track_db("projects", "find_with_credentials", async {
+            let filter = doc! {
+                "ad_credentials": { "$ne": {} },
+                "status": { "$ne": ProjectStatus::Cancelled.as_str() },
+            };
+            let mut cursor = self.collection.find(filter, None).await?;
+            let mut projects = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                match self.open(doc) {
+                    Ok(project) => projects.push(project),
+                    Err(e) => warn!(error = %e, "Skipping project that failed to deserialize"),
+                }
+            }
+            Ok(projects)
+        })
+        .await
+    }
+
+    /// Projects in any organization with a credential for `page_id`, except
+    /// cancelled ones.
+    pub async fn find_by_page_id(&self, page_id: &str) -> Result<Vec<Project>, ApiError> {
+        metrics::track_db("projects", "find_by_page_id", async {
+            // Credentials are a map keyed by name, so the page ids are compared as an array
+            let filter = doc! {
+                "status": { "$ne": ProjectStatus::Cancelled.as_str() },
+                "$expr": {
+                    "$in": [
+                        page_id,
+                        {
+                            "$map": {
+                                "input": { "$objectToArray": { "$ifNull": ["$ad_credentials", {}] } },
+                                "in": "$$this.v.page_id",
+                            }
+                        },
+                    ]
+                },
+            };
+            let mut cursor = self.collection.find(filter, None).await?;
+            let mut projects = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                projects.push(self.open(doc)?);
+            }
+            Ok(projects)
+        })
+        .await
+    }
+
+    /// Stores token health results, and exchanged access tokens, on a project
+    /// last changed at `updated_at`. The project's `updated_at` stays as it is,
+    /// and `false` means it was edited or its credentials removed meanwhile.
+    pub async fn update_token_health(
+        &self,
+        id: &ObjectId,
+        updated_at: DateTime<Utc>,
+        updates: &[TokenHealthUpdate],
+    ) -> Result<bool, ApiError> {
+        metrics::track_db("projects", "update_token_health", async {
+            let mut filter = doc! { "_id": id, "updated_at": mongodb::bson::DateTime::from_chrono(updated_at) };
+            let mut set = Document::new();
+            for update in updates {
+                let path = format!("ad_credentials.{}", update.key);
+                filter.insert(&path, doc! { "$exists": true });
+                set.insert(format!("{}.token_health", path), to_bson(&update.health)?);
+                if let Some(access_token) = &update.access_token {
+                    set.insert(format!("{}.access_token", path), self.cipher.encrypt(access_token)?);
+                }
+            }
+            if set.is_empty() {
+                return Ok(true);
+            }
+            let result = self.collection.update_one(filter, doc! { "$set": set }, None).await?;
+            Ok(result.matched_count > 0)
+        })
+        .await
+    }
+
+    /// Active projects whose expiry has passed.
+    pub async fn find_expired(&self, now: DateTime<Utc>) -> Result<Vec<Project>, ApiError> {
+        metrics::track_db("projects", "find_expired", async {
+            // Only dates compare here, string values are converted by migration 0001
+            let filter = doc! {
+                "status": ProjectStatus::Active.as_str(),
+                "expires_at": { "$lte": mongodb::bson::DateTime::from_chrono(now) },
+            };
+            let mut cursor = self.collection.find(filter, None).await?;
+            let mut expired = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                expired.push(self.open(doc)?);
+            }
+            Ok(expired)
+        })
+        .await
+    }
+
+    /// Records `transition` on a project still in `transition.from`, moving
+    /// the expiry along with it when given. Returns `None` when the project
+    /// is gone or its status changed meanwhile. Without `org_id` any
+    /// organization's project matches, for background jobs.
+    pub async fn transition(
+        &self,
+        tx: &mut Transaction,
+        org_id: Option<&ObjectId>,
+        id: &ObjectId,
+        transition: &StatusTransition,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<Project>, ApiError> {
+        metrics::track_db("projects", "transition", async {
+            // Filtering on the old status keeps concurrent changes from both applying
+            let mut filter = doc! { "_id": id, "status": transition.from.as_str() };
+            if let Some(org_id) = org_id {
+                filter.insert("org_id", org_id);
+            }
+            let mut set = doc! {
+                "status": transition.to.as_str(),
+                "is_active": transition.to == ProjectStatus::Active,
+                "updated_at": mongodb::bson::DateTime::from_chrono(transition.at),
+            };
+            if let Some(expires_at) = expires_at {
+                set.insert("expires_at", mongodb::bson::DateTime::from_chrono(expires_at));
+            }
+            let update = doc! { "$set": set, "$push": { "status_history": to_bson(transition)? } };
+            let options = FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build();
+            let doc = match tx.session() {
+                Some(session) => self.collection.find_one_and_update_with_session(filter, update, options, session).await?,
+                None => self.collection.find_one_and_update(filter, update, options).await?,
+            };
+            doc.map(|doc| self.open(doc)).transpose()
+        })
+        .await
+    }
+
+    pub async fn count_by_state(&self, now: DateTime<Utc>) -> Result<ProjectStateCounts, ApiError> {
+        metrics::track_db("projects", "count_by_state", async {
+            let now = mongodb::bson::DateTime::from_chrono(now);
+            // Active projects past their expiry count as expired before the worker gets to them
+            let filter = doc! {
+                "status": ProjectStatus::Active.as_str(),
+                "expires_at": { "$not": { "$lte": now } },
+            };
+            let active = self.collection.count_documents(filter, None).await?;
+            let filter = doc! {
+                "$or": [
+                    { "status": ProjectStatus::Expired.as_str() },
+                    { "status": ProjectStatus::Active.as_str(), "expires_at": { "$lte": now } },
+                ]
+            };
+            let expired = self.collection.count_documents(filter, None).await?;
+
+            Ok(ProjectStateCounts { active, expired })
+        })
+        .await
+    }
+}
@@ -1,6 +1,6 @@
 use chrono::Utc;
 use mongodb::{
-    bson::oid::ObjectId,
+    bson::{doc, oid::ObjectId, Document},
     Client,
     Database,
 };
@@ -8,8 +8,16 @@ use std::collections::HashMap;
 use dotenv::dotenv;
 
 use crate::{
-    models::project::{Project, Package, FacebookCredential},
-    repository::project_repository::ProjectRepository,
+    config::{EncryptionConfig, EncryptionKey},
+    crypto::SecretCipher,
+    error::ApiError,
+    models::ad_platform::{AdPlatform, AdPlatformCredential, TiktokCredential},
+    models::project::{
+        FacebookCredential, Package, Project, ProjectAction, ProjectStateCounts, ProjectStatus, StatusChange, TokenHealth,
+        TokenHealthUpdate,
+    },
+    repository::{project_repository::ProjectRepository, transaction::Transaction},
+    test_support::test_db,
 };
 
 async fn setup_test_db() -> Database {
@@ -28,10 +36,10 @@ async fn setup_test_db() -> Database {
 }
 
 fn create_test_project() -> Project {
-    let mut facebook_credentials = HashMap::new();
-    facebook_credentials.insert(
+    let mut ad_credentials = HashMap::new();
+    ad_credentials.insert(
         "test_page".to_string(),
-        FacebookCredential {
+        AdPlatformCredential::Facebook(FacebookCredential {
             app_id: "test_app_id".to_string(),
             app_secret: "test_app_secret".to_string(),
             access_token: "test_token".to_string(),
@@ -41,20 +49,24 @@ fn create_test_project() -> Project {
             link_url: None,
             page_id: Some("test_page_id".to_string()),
             watermark: None,
-        },
+            token_health: None,
+        }),
     );
 
     Project {
         id: None,
+        org_id: Some(ObjectId::new()),
         name: "Test Project".to_string(),
         telegram_chat_id: Some("123456789".to_string()),
-        facebook_credentials,
+        ad_credentials,
         package: Some(Package {
             name: "Test Package".to_string(),
             description: "Test Description".to_string(),
         }),
         expires_at: Some(Utc::now()),
+        status: ProjectStatus::Active,
         is_active: true,
+        status_history: vec![],
         is_logging: false,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -75,7 +87,8 @@ async fn test_crud_operations() {
 
     // Test Create
     let test_project = create_test_project();
-    let created_project = repo.create(test_project.clone())
+    let org_id = test_project.org_id.unwrap();
+    let created_project = repo.create(&mut Transaction::none(), test_project.clone())
         .await
         .expect("Failed to create project");
     
@@ -84,7 +97,7 @@ async fn test_crud_operations() {
 
     // Test Get by ID
     let project_id = created_project.id.unwrap();
-    let retrieved_project = repo.get_by_id(&project_id)
+    let retrieved_project = repo.get_by_id(&org_id, &project_id)
         .await
         .expect("Failed to get project by ID");
     
@@ -95,14 +108,14 @@ async fn test_crud_operations() {
     let mut updated_project = retrieved_project.clone();
     updated_project.name = "Updated Test Project".to_string();
     
-    let result = repo.update(&project_id, updated_project.clone())
+    let result = repo.update(&mut Transaction::none(), &org_id, &project_id, updated_project.clone())
         .await
         .expect("Failed to update project");
     
     assert_eq!(result.name, "Updated Test Project");
 
     // Test Get All
-    let all_projects = repo.get_all()
+    let all_projects = repo.get_all(&org_id)
         .await
         .expect("Failed to get all projects");
     
@@ -110,14 +123,14 @@ async fn test_crud_operations() {
     assert_eq!(all_projects[0].id, Some(project_id));
 
     // Test Delete
-    let delete_result = repo.delete(&project_id)
+    let delete_result = repo.delete(&mut Transaction::none(), &org_id, &project_id)
         .await
         .expect("Failed to delete project");
     
     assert!(delete_result);
 
     // Verify deletion
-    let all_projects_after_delete = repo.get_all()
+    let all_projects_after_delete = repo.get_all(&org_id)
         .await
         .expect("Failed to get all projects after delete");
     
@@ -130,7 +143,7 @@ async fn test_get_nonexistent_project() {
     let repo = ProjectRepository::new(db);
     
     let nonexistent_id = ObjectId::new();
-    let result = repo.get_by_id(&nonexistent_id).await;
+    let result = repo.get_by_id(&ObjectId::new(), &nonexistent_id).await;
     
     assert!(matches!(result, Err(crate::error::ApiError::NotFound)));
 }
@@ -143,7 +156,7 @@ async fn test_update_nonexistent_project() {
     let nonexistent_id = ObjectId::new();
     let test_project = create_test_project();
     
-    let result = repo.update(&nonexistent_id, test_project).await;
+    let result = repo.update(&mut Transaction::none(), &ObjectId::new(), &nonexistent_id, test_project).await;
     
     assert!(matches!(result, Err(crate::error::ApiError::NotFound)));
 }
@@ -154,7 +167,219 @@ async fn test_delete_nonexistent_project() {
     let repo = ProjectRepository::new(db);
     
     let nonexistent_id = ObjectId::new();
-    let result = repo.delete(&nonexistent_id).await;
+    let result = repo.delete(&mut Transaction::none(), &ObjectId::new(), &nonexistent_id).await;
     
     assert!(matches!(result, Ok(false)));
-} 
\ No newline at end of file
+} 
+
+#[tokio::test]
+async fn test_expire_overdue_projects() {
+    let db = test_db("_expiry").await;
+    let repo = ProjectRepository::new(db.clone());
+
+    db.collection::<mongodb::bson::Document>("projects")
+        .drop(None)
+        .await
+        .expect("Failed to drop collection");
+
+    let mut expired = create_test_project();
+    expired.expires_at = Some(Utc::now() - chrono::Duration::hours(1));
+    let expired = repo.create(&mut Transaction::none(), expired).await.expect("Failed to create project");
+
+    let mut current = create_test_project();
+    current.expires_at = Some(Utc::now() + chrono::Duration::days(30));
+    let current = repo.create(&mut Transaction::none(), current).await.expect("Failed to create project");
+
+    let mut unlimited = create_test_project();
+    unlimited.expires_at = None;
+    let unlimited = repo.create(&mut Transaction::none(), unlimited).await.expect("Failed to create project");
+
+    // Stored as a date so that the expiry query can compare it
+    let stored = db
+        .collection::<mongodb::bson::Document>("projects")
+        .find_one(doc! { "_id": expired.id }, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(stored.get("expires_at"), Some(mongodb::bson::Bson::DateTime(_))));
+
+    // Overdue projects are counted as expired, and only as expired, before the worker runs
+    let counts = repo.count_by_state(Utc::now()).await.expect("Failed to count projects");
+    assert_eq!(counts, ProjectStateCounts { active: 2, expired: 1 });
+
+    let found = repo.find_expired(Utc::now())
+        .await
+        .expect("Failed to find expired projects");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, expired.id);
+
+    let mut transitioned = found[0].clone();
+    let transition = transitioned
+        .transition(ProjectAction::Expire, StatusChange::default(), Utc::now())
+        .unwrap()
+        .clone();
+    let stored = repo.transition(&mut Transaction::none(), None, &expired.id.unwrap(), &transition, None)
+        .await
+        .expect("Failed to expire project")
+        .expect("Project was not expired");
+    assert_eq!(stored.status, ProjectStatus::Expired);
+    assert!(!stored.is_active);
+    assert_eq!(stored.status_history, vec![transition.clone()]);
+
+    let current = repo.get_by_id(&current.org_id.unwrap(), &current.id.unwrap()).await.unwrap();
+    let unlimited = repo.get_by_id(&unlimited.org_id.unwrap(), &unlimited.id.unwrap()).await.unwrap();
+    assert_eq!(current.status, ProjectStatus::Active);
+    assert_eq!(unlimited.status, ProjectStatus::Active);
+    let counts = repo.count_by_state(Utc::now()).await.expect("Failed to count projects");
+    assert_eq!(counts, ProjectStateCounts { active: 2, expired: 1 });
+
+    // Expired projects are not reported again, and the transition does not apply twice
+    assert!(repo.find_expired(Utc::now()).await.unwrap().is_empty());
+    let again = repo.transition(&mut Transaction::none(), None, &expired.id.unwrap(), &transition, None).await.unwrap();
+    assert!(again.is_none());
+}
+
+fn cipher(keys: &[&str], active: &str) -> SecretCipher {
+    SecretCipher::new(&EncryptionConfig {
+        keys: keys
+            .iter()
+            .enumerate()
+            .map(|(i, id)| EncryptionKey { id: id.to_string(), key: [i as u8 + 1; 32] })
+            .collect(),
+        active_key_id: Some(active.to_string()),
+    })
+}
+
+#[tokio::test]
+async fn test_credentials_are_encrypted_at_rest_and_rotated() {
+    let db = test_db("project_secrets").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let raw = db.collection::<Document>("projects");
+
+    let plain = ProjectRepository::new(db.clone());
+    let legacy = plain.create(&mut Transaction::none(), create_test_project()).await.unwrap();
+    let legacy_id = legacy.id.unwrap();
+
+    let repo = ProjectRepository::new(db.clone()).with_cipher(cipher(&["k1"], "k1"));
+    let sealed = repo.create(&mut Transaction::none(), create_test_project()).await.unwrap();
+    let sealed_id = sealed.id.unwrap();
+    let stored = raw.find_one(doc! { "_id": sealed_id }, None).await.unwrap().unwrap();
+    let token = stored.get_document("ad_credentials").unwrap()
+        .get_document("test_page").unwrap()
+        .get_str("access_token").unwrap();
+    assert_eq!(SecretCipher::key_id(token), Some("k1"));
+    assert_eq!(repo.find_by_id(&sealed_id).await.unwrap().ad_credentials["test_page"].as_facebook().unwrap().access_token, "test_token");
+    // Plaintext written before encryption was enabled still reads
+    assert_eq!(repo.find_by_id(&legacy_id).await.unwrap().ad_credentials["test_page"].as_facebook().unwrap().app_secret, "test_app_secret");
+
+    let rotated = ProjectRepository::new(db.clone()).with_cipher(cipher(&["k1", "k2"], "k2"));
+    assert_eq!(rotated.rotate_secrets(true).await.unwrap(), 2);
+    assert_eq!(rotated.rotate_secrets(false).await.unwrap(), 2);
+    assert_eq!(rotated.rotate_secrets(false).await.unwrap(), 0);
+
+    for id in [legacy_id, sealed_id] {
+        let stored = raw.find_one(doc! { "_id": id }, None).await.unwrap().unwrap();
+        let secret = stored.get_document("ad_credentials").unwrap()
+            .get_document("test_page").unwrap()
+            .get_str("app_secret").unwrap();
+        assert_eq!(SecretCipher::key_id(secret), Some("k2"));
+        assert_eq!(rotated.find_by_id(&id).await.unwrap().ad_credentials["test_page"].as_facebook().unwrap().app_secret, "test_app_secret");
+    }
+    assert!(matches!(plain.rotate_secrets(false).await, Err(ApiError::BadRequest(_))));
+}
+
+#[tokio::test]
+async fn test_credentials_are_set_and_unset_one_key_at_a_time() {
+    let db = test_db("project_credentials").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let raw = db.collection::<Document>("projects");
+    let repo = ProjectRepository::new(db.clone()).with_cipher(cipher(&["k1"], "k1"));
+    let project = repo.create(&mut Transaction::none(), create_test_project()).await.unwrap();
+    let (org_id, id) = (project.org_id.unwrap(), project.id.unwrap());
+
+    let mut credential = project.ad_credentials["test_page"].clone();
+    if let AdPlatformCredential::Facebook(facebook) = &mut credential {
+        facebook.access_token = "second_token".to_string();
+    }
+    let added = repo.set_credential(&mut Transaction::none(), &org_id, &id, "second", credential.clone(), false)
+        .await
+        .unwrap()
+        .expect("Credential was not added");
+    assert_eq!(added.ad_credentials.len(), 2);
+    assert_eq!(added.ad_credentials["second"].token(), "second_token");
+    let stored = raw.find_one(doc! { "_id": id }, None).await.unwrap().unwrap();
+    let token = stored.get_document("ad_credentials").unwrap()
+        .get_document("second").unwrap()
+        .get_str("access_token").unwrap();
+    assert_eq!(SecretCipher::key_id(token), Some("k1"));
+
+    // Adding a taken key and replacing a missing one do not match
+    let taken = repo.set_credential(&mut Transaction::none(), &org_id, &id, "second", credential.clone(), false).await.unwrap();
+    assert!(taken.is_none());
+    let missing = repo.set_credential(&mut Transaction::none(), &org_id, &id, "third", credential.clone(), true).await.unwrap();
+    assert!(missing.is_none());
+    let other_org = repo.set_credential(&mut Transaction::none(), &ObjectId::new(), &id, "third", credential, false).await.unwrap();
+    assert!(other_org.is_none());
+
+    // Removing through a platform only matches credentials of that platform
+    let tiktok = AdPlatformCredential::Tiktok(TiktokCredential {
+        app_id: "app".to_string(),
+        app_secret: "secret".to_string(),
+        access_token: "tiktok_token".to_string(),
+        advertiser_id: "123".to_string(),
+        pixel_code: None,
+    });
+    repo.set_credential(&mut Transaction::none(), &org_id, &id, "tiktok", tiktok, false).await.unwrap().unwrap();
+    let facebook = Some(AdPlatform::Facebook);
+    assert!(repo.unset_credential(&mut Transaction::none(), &org_id, &id, "tiktok", facebook).await.unwrap().is_none());
+
+    let removed = repo.unset_credential(&mut Transaction::none(), &org_id, &id, "test_page", facebook)
+        .await
+        .unwrap()
+        .expect("Credential was not removed");
+    let mut keys: Vec<&String> = removed.ad_credentials.keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec!["second", "tiktok"]);
+    assert!(repo.unset_credential(&mut Transaction::none(), &org_id, &id, "test_page", None).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_token_health_is_stored_without_touching_updated_at() {
+    let db = test_db("project_token_health").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let raw = db.collection::<Document>("projects");
+    let repo = ProjectRepository::new(db.clone()).with_cipher(cipher(&["k1"], "k1"));
+    let project = repo.create(&mut Transaction::none(), create_test_project()).await.unwrap();
+    let (org_id, id) = (project.org_id.unwrap(), project.id.unwrap());
+    let now = Utc::now();
+    let update = TokenHealthUpdate {
+        key: "test_page".to_string(),
+        health: TokenHealth {
+            valid: true,
+            expires_at: Some(now + chrono::Duration::days(60)),
+            scopes: vec!["ads_read".to_string()],
+            error: None,
+            checked_at: now,
+            warned_at: None,
+        },
+        access_token: Some("long_lived_token".to_string()),
+    };
+    let updates = vec![update];
+
+    assert!(repo.update_token_health(&id, project.updated_at, &updates).await.unwrap());
+    let stored = repo.get_by_id(&org_id, &id).await.unwrap();
+    let credential = stored.ad_credentials["test_page"].as_facebook().unwrap();
+    assert_eq!(credential.access_token, "long_lived_token");
+    assert_eq!(credential.token_health.as_ref().unwrap().scopes, vec!["ads_read"]);
+    assert_eq!(stored.updated_at, project.updated_at);
+    let raw_doc = raw.find_one(doc! { "_id": id }, None).await.unwrap().unwrap();
+    let token = raw_doc.get_document("ad_credentials").unwrap()
+        .get_document("test_page").unwrap()
+        .get_str("access_token").unwrap();
+    assert_eq!(SecretCipher::key_id(token), Some("k1"));
+
+    // A project edited since it was read is left alone
+    let edited = repo.unset_credential(&mut Transaction::none(), &org_id, &id, "test_page", None).await.unwrap().unwrap();
+    assert!(!repo.update_token_health(&id, project.updated_at, &updates).await.unwrap());
+    assert!(!repo.update_token_health(&id, edited.updated_at, &updates).await.unwrap());
+}
@@ -0,0 +1,35 @@
+use mongodb::{Client, ClientSession};
+
+use crate::error::ApiError;
+
+/// Groups repository writes so they commit together. Standalone MongoDB
+/// servers have no transactions, there the writes apply one by one.
+pub struct Transaction {
+    session: Option<ClientSession>,
+}
+
+impl Transaction {
+    pub fn none() -> Self {
+        Self { session: None }
+    }
+
+    pub async fn start(client: &Client) -> Result<Self, ApiError> {
+        let mut session = client.start_session(None).await?;
+        session.start_transaction(None).await?;
+        Ok(Self {
+            session: Some(session),
+        })
+    }
+
+    pub fn session(&mut self) -> Option<&mut ClientSession> {
+        self.session.as_mut()
+    }
+
+    /// Dropping a transaction without committing aborts it.
+    pub async fn commit(self) -> Result<(), ApiError> {
+        if let Some(mut session) = self.session {
+            session.commit_transaction().await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use chrono::{DateTime, Utc};
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document, from_document, to_document, DateTime as BsonDateTime},
+    Collection, Database, IndexModel,
+    options::{FindOneAndUpdateOptions, FindOptions, IndexOptions, InsertManyOptions, ReturnDocument},
+};
+use crate::models::webhook::WebhookDelivery;
+use crate::error::ApiError;
+use crate::metrics;
+use crate::repository::is_duplicate_key;
+
+const DELIVERY_HISTORY_LIMIT: i64 = 100;
+
+#[derive(Clone)]
+pub struct WebhookDeliveryRepository {
+    collection: Collection<Document>,
+}
+
+impl WebhookDeliveryRepository {
+    pub fn new(db: Database) -> Self {
+        Self {
+            collection: db.collection("webhook_deliveries"),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("webhook_deliveries", "ensure_indexes", async {
+            let due = IndexModel::builder()
+                .keys(doc! { "status": 1, "next_attempt_at": 1 })
+                .build();
+            let history = IndexModel::builder()
+                .keys(doc! { "webhook_id": 1, "created_at": -1 })
+                .build();
+            let event = IndexModel::builder()
+                .keys(doc! { "event_id": 1, "webhook_id": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .unique(true)
+                        .partial_filter_expression(doc! { "event_id": { "$exists": true } })
+                        .build(),
+                )
+                .build();
+            self.collection.create_indexes(vec![due, history, event], None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Inserts deliveries, skipping those already queued for the same event.
+    pub async fn create_many(&self, deliveries: &[WebhookDelivery]) -> Result<(), ApiError> {
+        metrics::track_db("webhook_deliveries", "create_many", async {
+            if deliveries.is_empty() {
+                return Ok(());
+            }
+            let docs = deliveries
+                .iter()
+                .map(to_document)
+                .collect::<Result<Vec<_>, _>>()?;
+            // Unordered so one duplicate does not stop the rest of the batch
+            let options = InsertManyOptions::builder().ordered(false).build();
+            match self.collection.insert_many(docs, options).await {
+                Ok(_) => Ok(()),
+                Err(e) if is_duplicate_key(&e) => Ok(()),
+                Err(e) => Err(e.into()),
+            }
+        })
+        .await
+    }
+
+    /// Takes the oldest due delivery of a subscription not in `skip`, pushing
+    /// its next attempt to `lease_until` so no other worker picks it up while
+    /// it is being sent.
+    pub async fn claim_due(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        skip: &[ObjectId],
+    ) -> Result<Option<WebhookDelivery>, ApiError> {
+        metrics::track_db("webhook_deliveries", "claim_due", async {
+            let filter = doc! {
+                "status": "pending",
+                "next_attempt_at": { "$lte": BsonDateTime::from_chrono(now) },
+                "webhook_id": { "$nin": skip },
+            };
+            let update = doc! { "$set": { "next_attempt_at": BsonDateTime::from_chrono(lease_until) } };
+            let options = FindOneAndUpdateOptions::builder()
+                .sort(doc! { "next_attempt_at": 1 })
+                .return_document(ReturnDocument::After)
+                .build();
+            match self.collection.find_one_and_update(filter, update, options).await? {
+                Some(doc) => Ok(Some(from_document(doc)?)),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    pub async fn mark_delivered(&self, id: &ObjectId, attempts: u32) -> Result<(), ApiError> {
+        metrics::track_db("webhook_deliveries", "mark_delivered", async {
+            let update = doc! {
+                "$set": {
+                    "status": "delivered",
+                    "attempts": attempts,
+                    "last_error": null,
+                    "delivered_at": BsonDateTime::now(),
+                }
+            };
+            self.collection.update_one(doc! { "_id": id }, update, None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Records a failed attempt, dead-lettering the delivery when there is
+    /// no `next_attempt_at`.
+    pub async fn mark_failed(
+        &self,
+        id: &ObjectId,
+        attempts: u32,
+        error: &str,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<(), ApiError> {
+        metrics::track_db("webhook_deliveries", "mark_failed", async {
+            let mut set = doc! { "attempts": attempts, "last_error": error };
+            match next_attempt_at {
+                Some(at) => set.insert("next_attempt_at", BsonDateTime::from_chrono(at)),
+                None => set.insert("status", "dead"),
+            };
+            self.collection.update_one(doc! { "_id": id }, doc! { "$set": set }, None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Most recent deliveries of a subscription, newest first.
+    pub async fn get_recent(&self, org_id: &ObjectId, webhook_id: &ObjectId) -> Result<Vec<WebhookDelivery>, ApiError> {
+        metrics::track_db("webhook_deliveries", "get_recent", async {
+            let filter = doc! { "org_id": org_id, "webhook_id": webhook_id };
+            let options = FindOptions::builder()
+                .sort(doc! { "created_at": -1 })
+                .limit(DELIVERY_HISTORY_LIMIT)
+                .build();
+            let mut cursor = self.collection.find(filter, options).await?;
+            let mut deliveries = Vec::new();
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                deliveries.push(from_document(doc)?);
+            }
+            Ok(deliveries)
+        })
+        .await
+    }
+
+    /// Queues a delivery again with a fresh attempt budget.
+    pub async fn redeliver(&self, org_id: &ObjectId, webhook_id: &ObjectId, id: &ObjectId) -> Result<bool, ApiError> {
+        metrics::track_db("webhook_deliveries", "redeliver", async {
+            let filter = doc! { "_id": id, "org_id": org_id, "webhook_id": webhook_id };
+            let update = doc! {
+                "$set": {
+                    "status": "pending",
+                    "attempts": 0,
+                    "last_error": null,
+                    "next_attempt_at": BsonDateTime::now(),
+                },
+                "$unset": { "delivered_at": "" },
+            };
+            let result = self.collection.update_one(filter, update, None).await?;
+            Ok(result.matched_count > 0)
+        })
+        .await
+    }
+
+    pub async fn delete_pending_for_webhook(&self, webhook_id: &ObjectId) -> Result<u64, ApiError> {
+        metrics::track_db("webhook_deliveries", "delete_pending_for_webhook", async {
+            let filter = doc! { "webhook_id": webhook_id, "status": "pending" };
+            let result = self.collection.delete_many(filter, None).await?;
+            Ok(result.deleted_count)
+        })
+        .await
+    }
+}
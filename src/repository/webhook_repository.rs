@@ -0,0 +1,144 @@
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document, from_document, to_document},
+    Collection, Database, IndexModel,
+};
+use crate::crypto::SecretCipher;
+use crate::models::webhook::{WebhookEvent, WebhookSubscription};
+use crate::error::ApiError;
+use crate::metrics;
+
+#[derive(Clone)]
+pub struct WebhookRepository {
+    collection: Collection<Document>,
+    cipher: SecretCipher,
+}
+
+impl WebhookRepository {
+    pub fn new(db: Database) -> Self {
+        Self {
+            collection: db.collection("webhooks"),
+            cipher: SecretCipher::default(),
+        }
+    }
+
+    /// Encrypts signing secrets at rest with `cipher`.
+    pub fn with_cipher(mut self, cipher: SecretCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    fn open(&self, doc: Document) -> Result<WebhookSubscription, ApiError> {
+        let mut webhook: WebhookSubscription = from_document(doc)?;
+        webhook.secret = self.cipher.decrypt(&webhook.secret)?;
+        Ok(webhook)
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), ApiError> {
+        metrics::track_db("webhooks", "ensure_indexes", async {
+            let tenant = IndexModel::builder()
+                .keys(doc! { "org_id": 1, "events": 1 })
+                .build();
+            self.collection.create_indexes(vec![tenant], None).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn create(&self, webhook: WebhookSubscription) -> Result<WebhookSubscription, ApiError> {
+        metrics::track_db("webhooks", "create", async {
+            let org_id = webhook.org_id;
+            let secret = self.cipher.encrypt(&webhook.secret)?;
+            let doc = to_document(&WebhookSubscription { secret, ..webhook })?;
+            let result = self.collection.insert_one(doc, None).await?;
+            let id = result.inserted_id.as_object_id()
+                .ok_or_else(|| ApiError::InternalServerError("Failed to get inserted ID".into()))?;
+            self.get_by_id(&org_id, &id).await
+        })
+        .await
+    }
+
+    pub async fn get_by_id(&self, org_id: &ObjectId, id: &ObjectId) -> Result<WebhookSubscription, ApiError> {
+        metrics::track_db("webhooks", "get_by_id", async {
+            let filter = doc! { "_id": id, "org_id": org_id };
+            let doc = self.collection.find_one(filter, None).await?
+                .ok_or(ApiError::NotFound)?;
+            self.open(doc)
+        })
+        .await
+    }
+
+    pub async fn get_all(&self, org_id: &ObjectId) -> Result<Vec<WebhookSubscription>, ApiError> {
+        metrics::track_db("webhooks", "get_all", async {
+            self.find(doc! { "org_id": org_id }).await
+        })
+        .await
+    }
+
+    /// Subscriptions of the organization listening to `event`, either for every
+    /// project or for `project_id`.
+    pub async fn find_matching(
+        &self,
+        org_id: &ObjectId,
+        project_id: Option<&ObjectId>,
+        event: WebhookEvent,
+    ) -> Result<Vec<WebhookSubscription>, ApiError> {
+        metrics::track_db("webhooks", "find_matching", async {
+            let mut scopes = vec![doc! { "project_id": null }];
+            if let Some(project_id) = project_id {
+                scopes.push(doc! { "project_id": project_id });
+            }
+            self.find(doc! { "org_id": org_id, "events": event.as_str(), "$or": scopes }).await
+        })
+        .await
+    }
+
+    pub async fn delete(&self, org_id: &ObjectId, id: &ObjectId) -> Result<bool, ApiError> {
+        metrics::track_db("webhooks", "delete", async {
+            let filter = doc! { "_id": id, "org_id": org_id };
+            let result = self.collection.delete_one(filter, None).await?;
+            Ok(result.deleted_count > 0)
+        })
+        .await
+    }
+
+    /// Re-encrypts signing secrets that are plaintext or sealed with a key
+    /// other than the active one, returning how many webhooks were affected.
+    pub async fn rotate_secrets(&self, dry_run: bool) -> Result<u64, ApiError> {
+        metrics::track_db("webhooks", "rotate_secrets", async {
+            if self.cipher.active_key_id().is_none() {
+                return Err(ApiError::BadRequest("No active encryption key is configured".to_string()));
+            }
+            let mut cursor = self.collection.find(None, None).await?;
+            let mut rotated = 0;
+
+            while cursor.advance().await? {
+                let doc = Document::from_reader(cursor.current().as_bytes())?;
+                let stored = doc.get_str("secret").unwrap_or_default().to_string();
+                if !self.cipher.needs_rotation(&stored) {
+                    continue;
+                }
+                rotated += 1;
+                if dry_run {
+                    continue;
+                }
+                let webhook = self.open(doc)?;
+                let filter = doc! { "_id": webhook.id, "secret": stored };
+                let update = doc! { "$set": { "secret": self.cipher.encrypt(&webhook.secret)? } };
+                self.collection.update_one(filter, update, None).await?;
+            }
+            Ok(rotated)
+        })
+        .await
+    }
+
+    async fn find(&self, filter: Document) -> Result<Vec<WebhookSubscription>, ApiError> {
+        let mut cursor = self.collection.find(filter, None).await?;
+        let mut webhooks = Vec::new();
+
+        while cursor.advance().await? {
+            let doc = Document::from_reader(cursor.current().as_bytes())?;
+            webhooks.push(self.open(doc)?);
+        }
+        Ok(webhooks)
+    }
+}
@@ -1,22 +1,51 @@
+use futures_util::{Stream, StreamExt};
 use mongodb::bson::oid::ObjectId;
+use std::{collections::HashSet, pin::pin};
 use crate::{
-    models::account::Account,
-    repository::account_repository::AccountRepository,
+    models::{
+        account::{Account, AccountRecord},
+        event::DomainEvent,
+        import::{ImportMode, ImportReport, MAX_IMPORT_ROWS},
+        organization::OrgRole,
+    },
+    records::RecordError,
+    repository::{
+        account_repository::{email_exists, AccountRepository}, organization_repository::OrganizationRepository,
+        outbox_repository::OutboxRepository, project_repository::ProjectRepository,
+    },
+    service::{rejection, require_transactions},
     error::ApiError,
 };
 
 #[derive(Clone)]
 pub struct AccountService {
     repository: AccountRepository,
+    project_repository: ProjectRepository,
+    organization_repository: OrganizationRepository,
+    outbox: OutboxRepository,
 }
 
 impl AccountService {
-    pub fn new(repository: AccountRepository) -> Self {
-        Self { repository }
+    pub fn new(
+        repository: AccountRepository,
+        project_repository: ProjectRepository,
+        organization_repository: OrganizationRepository,
+        outbox: OutboxRepository,
+    ) -> Self {
+        Self { repository, project_repository, organization_repository, outbox }
     }
 
-    pub async fn create_account(&self, mut account: Account) -> Result<Account, ApiError> {
-        // Add business logic here
+    pub async fn create_account(&self, org_id: &ObjectId, account: Account) -> Result<Account, ApiError> {
+        let account = Self::prepare_new(org_id, account)?;
+        self.check_projects(org_id, &account).await?;
+        self.check_email(org_id, &account, None).await?;
+
+        let mut accounts = self.insert(org_id, vec![account]).await?;
+        Ok(accounts.remove(0))
+    }
+
+    fn prepare_new(org_id: &ObjectId, mut account: Account) -> Result<Account, ApiError> {
+        account.org_id = Some(*org_id);
         account.created_at = chrono::Utc::now();
         account.updated_at = chrono::Utc::now();
         
@@ -27,19 +56,134 @@ impl AccountService {
         if account.wallet_address.is_empty() {
             return Err(ApiError::BadRequest("Wallet address cannot be empty".to_string()));
         }
-        
-        // Additional business logic could go here
-        // For example, check if email is unique
-        if let Ok(existing_accounts) = self.repository.get_all().await {
-            if existing_accounts.iter().any(|a| a.email == account.email) {
-                return Err(ApiError::BadRequest("Email already exists".to_string()));
+        Ok(account)
+    }
+
+    // Accounts may only reference projects of their own organization
+    async fn check_projects(&self, org_id: &ObjectId, account: &Account) -> Result<(), ApiError> {
+        for project_id in &account.project_ids {
+            match self.project_repository.get_by_id(org_id, project_id).await {
+                Ok(_) => {}
+                Err(ApiError::NotFound) => {
+                    return Err(ApiError::BadRequest(format!("Project {} does not exist", project_id.to_hex())));
+                }
+                Err(e) => return Err(e),
             }
         }
-        
-        self.repository.create(account).await
+        Ok(())
+    }
+
+    // Emails are unique within the organization through the accounts index.
+    // Until it is built, e.g. while stored duplicates keep it from being
+    // created, they are checked before the write instead.
+    async fn check_email(&self, org_id: &ObjectId, account: &Account, except: Option<&ObjectId>) -> Result<(), ApiError> {
+        if !self.repository.has_email_index() && self.repository.email_taken(org_id, &account.email, except).await? {
+            return Err(ApiError::Conflict(email_exists(&account.email)));
+        }
+        Ok(())
+    }
+
+    // Creates the accounts and their events in one transaction
+    async fn insert(&self, org_id: &ObjectId, accounts: Vec<Account>) -> Result<Vec<Account>, ApiError> {
+        let mut tx = self.outbox.begin().await?;
+        let mut created = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            let account = self.repository.create(&mut tx, account).await?;
+            self.outbox.append(&mut tx, org_id, DomainEvent::AccountCreated { account: account.clone() }).await?;
+            created.push(account);
+        }
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// Creates accounts from `rows` as they are read. In atomic mode nothing
+    /// is written unless every row is valid.
+    pub async fn import_accounts<S>(&self, org_id: &ObjectId, rows: S, mode: ImportMode) -> Result<ImportReport, ApiError>
+    where
+        S: Stream<Item = Result<AccountRecord, RecordError>>,
+    {
+        let mut rows = pin!(rows);
+        let mut report = ImportReport::new(mode);
+        let mut valid = Vec::new();
+        // Emails stay unique within the organization, including across rows
+        let mut emails: HashSet<String> = self
+            .repository
+            .get_all(org_id)
+            .await?
+            .into_iter()
+            .map(|account| account.email)
+            .collect();
+
+        while let Some(row) = rows.next().await {
+            if report.total == MAX_IMPORT_ROWS {
+                report.reject(0, format!("Imports are limited to {} rows", MAX_IMPORT_ROWS));
+                break;
+            }
+            let record = match row {
+                Ok(record) => record,
+                Err(RecordError::Row(error)) => {
+                    report.total += 1;
+                    report.reject(report.total, error);
+                    continue;
+                }
+                Err(RecordError::Fatal(error)) => {
+                    report.reject(0, error);
+                    break;
+                }
+            };
+            report.total += 1;
+
+            let account = Account::try_from(record)
+                .map_err(ApiError::BadRequest)
+                .and_then(|account| Self::prepare_new(org_id, account));
+            let account = match account {
+                Ok(account) if emails.contains(&account.email) => {
+                    report.reject(report.total, "Email already exists");
+                    continue;
+                }
+                Ok(account) => account,
+                Err(e) => {
+                    report.reject(report.total, rejection(e));
+                    continue;
+                }
+            };
+            match self.check_projects(org_id, &account).await {
+                Ok(()) => {}
+                Err(ApiError::BadRequest(error)) => {
+                    report.reject(report.total, error);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+            match mode {
+                ImportMode::Atomic => {
+                    emails.insert(account.email.clone());
+                    valid.push(account);
+                }
+                // A failed write only loses its own row
+                ImportMode::BestEffort => match self.insert(org_id, vec![account.clone()]).await {
+                    Ok(_) => {
+                        emails.insert(account.email);
+                        report.imported += 1;
+                    }
+                    Err(e) => report.reject(report.total, rejection(e)),
+                },
+            }
+        }
+
+        if mode == ImportMode::Atomic && report.failed == 0 {
+            require_transactions(&self.outbox).await?;
+            report.imported = self.insert(org_id, valid).await?.len();
+        }
+        Ok(report)
+    }
+
+    pub async fn export_accounts(&self, org_id: &ObjectId) -> Result<impl Stream<Item = Result<Account, ApiError>> + Send + 'static, ApiError> {
+        self.repository.stream_all(org_id).await
     }
 
-    pub async fn update_account(&self, id: &ObjectId, mut account: Account) -> Result<Account, ApiError> {
+    pub async fn update_account(&self, org_id: &ObjectId, id: &ObjectId, mut account: Account) -> Result<Account, ApiError> {
+        account.org_id = Some(*org_id);
         account.updated_at = chrono::Utc::now();
         
         // Validation
@@ -49,21 +193,48 @@ impl AccountService {
         if account.wallet_address.is_empty() {
             return Err(ApiError::BadRequest("Wallet address cannot be empty".to_string()));
         }
-        
-        self.repository.update(id, account).await
+        self.check_projects(org_id, &account).await?;
+        self.check_email(org_id, &account, Some(id)).await?;
+
+        let mut tx = self.outbox.begin().await?;
+        let account = self.repository.update(&mut tx, org_id, id, account).await?;
+        self.outbox.append(&mut tx, org_id, DomainEvent::AccountUpdated { account: account.clone() }).await?;
+        tx.commit().await?;
+        Ok(account)
+    }
+
+    /// Deletes an account and its membership in the organization. `actor` is
+    /// the role of the caller, as for removing a member.
+    pub async fn delete_account(&self, org_id: &ObjectId, id: &ObjectId, actor: Option<OrgRole>) -> Result<bool, ApiError> {
+        let mut organization = self.organization_repository.get_by_id(org_id).await?;
+        let previous = organization.members.clone();
+        let unlinked = organization.remove_member(id, actor).map_err(ApiError::Forbidden)?;
+
+        let mut tx = self.outbox.begin().await?;
+        if !self.repository.delete(&mut tx, org_id, id).await? {
+            return Ok(false);
+        }
+        if unlinked {
+            self.organization_repository
+                .update_members(&mut tx, org_id, &previous, &organization.members)
+                .await?;
+            self.outbox.append(&mut tx, org_id, DomainEvent::AccountUnlinked { account_id: *id }).await?;
+        }
+        self.outbox.append(&mut tx, org_id, DomainEvent::AccountDeleted { account_id: *id }).await?;
+        tx.commit().await?;
+        Ok(true)
     }
 
-    pub async fn delete_account(&self, id: &ObjectId) -> Result<bool, ApiError> {
-        // Add any deletion-specific business logic here
-        // For example, check if the account has any associated projects
-        self.repository.delete(id).await
+    pub async fn get_account(&self, org_id: &ObjectId, id: &ObjectId) -> Result<Account, ApiError> {
+        self.repository.get_by_id(org_id, id).await
     }
 
-    pub async fn get_account(&self, id: &ObjectId) -> Result<Account, ApiError> {
-        self.repository.get_by_id(id).await
+    pub async fn get_all_accounts(&self, org_id: &ObjectId) -> Result<Vec<Account>, ApiError> {
+        self.repository.get_all(org_id).await
     }
 
-    pub async fn get_all_accounts(&self) -> Result<Vec<Account>, ApiError> {
-        self.repository.get_all().await
+    /// Accounts of every organization, for operator tooling.
+    pub async fn find_all_accounts(&self) -> Result<Vec<Account>, ApiError> {
+        self.repository.find_all().await
     }
 } 
\ No newline at end of file
@@ -0,0 +1,166 @@
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
+use std::{sync::Arc, time::Duration};
+use tracing::warn;
+
+use crate::{
+    ad_platform_client::AdPlatformClient,
+    error::ApiError,
+    models::{
+        ad_platform::{validate_event_name, AdPlatformCredential, Conversion, Spend, SpendQuery},
+        event_id::{
+            ConversionReceipt, ConversionStatus, DedupStats, EventIdRecord, EventIdRequest, IssuedEventId,
+            EVENT_ID_PREFIX,
+        },
+        project::{validate_credential_key, Project, ProjectStatus},
+    },
+    repository::{
+        event_id_repository::{Claim, EventIdRepository},
+        project_repository::ProjectRepository,
+    },
+};
+
+// Longest date range spend is reported for
+pub const MAX_SPEND_DAYS: i64 = 366;
+const EVENT_ID_LENGTH: usize = 24;
+const EVENT_ID_ATTEMPTS: usize = 3;
+
+pub fn generate_event_id() -> String {
+    let random: String = OsRng.sample_iter(&Alphanumeric).take(EVENT_ID_LENGTH).map(char::from).collect();
+    format!("{}{}", EVENT_ID_PREFIX, random)
+}
+
+/// Sends conversions and reads spend through a project's ad credentials,
+/// whichever platform they are for. Server events are deduplicated by
+/// event id against each other and share ids issued for pixel fires.
+#[derive(Clone)]
+pub struct AdPlatformService {
+    repository: ProjectRepository,
+    event_ids: EventIdRepository,
+    client: Arc<dyn AdPlatformClient>,
+    dedup_window: Duration,
+}
+
+impl AdPlatformService {
+    pub fn new(
+        repository: ProjectRepository,
+        event_ids: EventIdRepository,
+        client: Arc<dyn AdPlatformClient>,
+        dedup_window: Duration,
+    ) -> Self {
+        Self { repository, event_ids, client, dedup_window }
+    }
+
+    fn expires_at(&self, from: chrono::DateTime<Utc>) -> chrono::DateTime<Utc> {
+        from + chrono::Duration::from_std(self.dedup_window).unwrap_or(chrono::Duration::MAX)
+    }
+
+    async fn credential(&self, org_id: &ObjectId, id: &ObjectId, key: &str) -> Result<(Project, AdPlatformCredential), ApiError> {
+        validate_credential_key(key).map_err(ApiError::BadRequest)?;
+        let mut project = self.repository.get_by_id(org_id, id).await?;
+        let credential = project.ad_credentials.remove(key).ok_or(ApiError::NotFound)?;
+        Ok((project, credential))
+    }
+
+    /// Reports a conversion through the credential under `key`. Only active
+    /// projects send conversions. A conversion without an event id is given
+    /// one; a conversion whose id was already sent within the dedup window
+    /// is not sent again.
+    pub async fn send_conversion(
+        &self,
+        org_id: &ObjectId,
+        id: &ObjectId,
+        key: &str,
+        mut conversion: Conversion,
+    ) -> Result<ConversionReceipt, ApiError> {
+        let (project, credential) = self.credential(org_id, id, key).await?;
+        if project.status != ProjectStatus::Active {
+            return Err(ApiError::Conflict(format!("Project is {}", project.status)));
+        }
+        conversion.validate(credential.platform()).map_err(ApiError::BadRequest)?;
+        let event_id = conversion.event_id.get_or_insert_with(generate_event_id).clone();
+
+        let now = Utc::now();
+        let record = EventIdRecord {
+            id: None,
+            org_id: Some(*org_id),
+            project_id: *id,
+            event_id: event_id.clone(),
+            event_name: conversion.event_name.clone(),
+            issued_at: None,
+            server_sent_at: Some(now),
+            credential_key: Some(key.to_string()),
+            duplicates: 0,
+            expires_at: self.expires_at(now),
+        };
+        if self.event_ids.claim(&record, now).await? == Claim::Duplicate {
+            return Ok(ConversionReceipt { event_id, status: ConversionStatus::Duplicate });
+        }
+
+        if let Err(e) = self.client.send_conversion(&credential, &conversion).await {
+            if let Err(release) = self.event_ids.release(id, &event_id).await {
+                warn!(error = %release, event_id, "Failed to release event id of an unsent conversion");
+            }
+            return Err(ApiError::BadGateway(e));
+        }
+        Ok(ConversionReceipt { event_id, status: ConversionStatus::Sent })
+    }
+
+    /// Issues an event id for a pixel fire, for the server event of the same
+    /// conversion to carry.
+    pub async fn issue_event_id(&self, org_id: &ObjectId, id: &ObjectId, request: EventIdRequest) -> Result<IssuedEventId, ApiError> {
+        validate_event_name(&request.event_name).map_err(ApiError::BadRequest)?;
+        self.repository.get_by_id(org_id, id).await?;
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let now = Utc::now();
+            let record = EventIdRecord {
+                id: None,
+                org_id: Some(*org_id),
+                project_id: *id,
+                event_id: generate_event_id(),
+                event_name: request.event_name.clone(),
+                issued_at: Some(now),
+                server_sent_at: None,
+                credential_key: None,
+                duplicates: 0,
+                expires_at: self.expires_at(now),
+            };
+            match self.event_ids.issue(&record).await {
+                Err(ApiError::Conflict(_)) if attempt < EVENT_ID_ATTEMPTS => continue,
+                Err(e) => return Err(e),
+                Ok(()) => {
+                    return Ok(IssuedEventId {
+                        event_id: record.event_id,
+                        event_name: record.event_name,
+                        expires_at: record.expires_at,
+                    })
+                }
+            }
+        }
+    }
+
+    /// How the project's server events within the dedup window matched pixel fires.
+    pub async fn dedup_stats(&self, org_id: &ObjectId, id: &ObjectId) -> Result<DedupStats, ApiError> {
+        self.repository.get_by_id(org_id, id).await?;
+        let counts = self.event_ids.counts(org_id, id).await?;
+        Ok(DedupStats::new(counts, self.dedup_window.as_secs() / 3600))
+    }
+
+    pub async fn fetch_spend(&self, org_id: &ObjectId, id: &ObjectId, key: &str, query: SpendQuery) -> Result<Spend, ApiError> {
+        if query.since > query.until {
+            return Err(ApiError::BadRequest("since cannot be after until".to_string()));
+        }
+        if (query.until - query.since).num_days() >= MAX_SPEND_DAYS {
+            return Err(ApiError::BadRequest(format!("Spend is reported for at most {} days", MAX_SPEND_DAYS)));
+        }
+        let (_, credential) = self.credential(org_id, id, key).await?;
+        self.client
+            .fetch_spend(&credential, query.since, query.until)
+            .await
+            .map_err(ApiError::BadGateway)
+    }
+}
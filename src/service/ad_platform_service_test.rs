@@ -0,0 +1,49 @@
+use chrono::NaiveDate;
+use mongodb::bson::oid::ObjectId;
+
+use crate::{
+    ad_platform_client::MockAdPlatformClient,
+    error::ApiError,
+    models::{ad_platform::SpendQuery, event_id::EventIdRequest},
+    service::ad_platform_service::AdPlatformService,
+    test_support::{test_ad_platform_service, unreachable_db},
+};
+
+async fn service() -> AdPlatformService {
+    test_ad_platform_service(unreachable_db().await, MockAdPlatformClient::new())
+}
+
+fn query(since: (i32, u32, u32), until: (i32, u32, u32)) -> SpendQuery {
+    SpendQuery {
+        since: NaiveDate::from_ymd_opt(since.0, since.1, since.2).unwrap(),
+        until: NaiveDate::from_ymd_opt(until.0, until.1, until.2).unwrap(),
+    }
+}
+
+// Rejected before the project is looked up, so no database is needed
+#[tokio::test]
+async fn test_spend_ranges_are_validated() {
+    let service = service().await;
+    let (org_id, id) = (ObjectId::new(), ObjectId::new());
+
+    let reversed = service.fetch_spend(&org_id, &id, "main", query((2024, 5, 2), (2024, 5, 1))).await;
+    assert!(matches!(reversed, Err(ApiError::BadRequest(_))));
+
+    let too_long = service.fetch_spend(&org_id, &id, "main", query((2024, 1, 1), (2025, 1, 1))).await;
+    assert!(matches!(too_long, Err(ApiError::BadRequest(_))));
+
+    let bad_key = service.fetch_spend(&org_id, &id, "a.b", query((2024, 1, 1), (2024, 12, 31))).await;
+    assert!(matches!(bad_key, Err(ApiError::BadRequest(_))));
+}
+
+#[tokio::test]
+async fn test_event_ids_are_issued_for_named_events() {
+    let service = service().await;
+    let (org_id, id) = (ObjectId::new(), ObjectId::new());
+
+    for event_name in ["", &"x".repeat(101)] {
+        let request = EventIdRequest { event_name: event_name.to_string() };
+        let issued = service.issue_event_id(&org_id, &id, request).await;
+        assert!(matches!(issued, Err(ApiError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,230 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use tracing::warn;
+
+use crate::{
+    auth::Caller,
+    config::Secret,
+    error::ApiError,
+    models::{
+        api_key::{ApiKey, CreateApiKey, CreatedApiKey, Scope},
+        organization::OrgRole,
+    },
+    repository::{api_key_repository::ApiKeyRepository, organization_repository::OrganizationRepository},
+};
+
+const KEY_PREFIX: &str = "tk_";
+const KEY_BYTES: usize = 32;
+const DISPLAY_PREFIX_LEN: usize = 11;
+// Avoid a write per request, last use is only tracked to the minute
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+pub fn generate_key() -> String {
+    let mut bytes = [0u8; KEY_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{}{}", KEY_PREFIX, URL_SAFE_NO_PAD.encode(bytes))
+}
+
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+#[derive(Clone)]
+pub struct ApiKeyService {
+    repository: ApiKeyRepository,
+    organization_repository: OrganizationRepository,
+    bootstrap_key_hash: Option<String>,
+}
+
+impl ApiKeyService {
+    pub fn new(
+        repository: ApiKeyRepository,
+        organization_repository: OrganizationRepository,
+        bootstrap_key: Option<&Secret>,
+    ) -> Self {
+        Self {
+            repository,
+            organization_repository,
+            bootstrap_key_hash: bootstrap_key.map(|key| hash_key(key.expose())),
+        }
+    }
+
+    /// Resolves the caller of `key`, acting on `requested_org` when the
+    /// request names one.
+    pub async fn authenticate(&self, key: &str, requested_org: Option<ObjectId>) -> Result<Caller, ApiError> {
+        let mut caller = self.caller_for_key(key).await?;
+        caller.tenant_id = match (caller.org_id, requested_org) {
+            (Some(org_id), Some(requested)) if org_id != requested => {
+                return Err(ApiError::Forbidden("API key belongs to another organization".to_string()));
+            }
+            (Some(org_id), _) => Some(org_id),
+            (None, Some(requested)) => {
+                match self.organization_repository.get_by_id(&requested).await {
+                    Ok(_) => Some(requested),
+                    Err(ApiError::NotFound) => {
+                        return Err(ApiError::BadRequest("Unknown organization".to_string()));
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            (None, None) => None,
+        };
+        Ok(caller)
+    }
+
+    async fn caller_for_key(&self, key: &str) -> Result<Caller, ApiError> {
+        let key_hash = hash_key(key);
+
+        // The configured bootstrap key holds every scope so the first keys can be minted
+        if self.bootstrap_key_hash.as_deref() == Some(key_hash.as_str()) {
+            return Ok(Caller {
+                key_id: None,
+                name: "bootstrap".to_string(),
+                scopes: Scope::ALL.into_iter().collect(),
+                org_id: None,
+                role: None,
+                tenant_id: None,
+                bot_frontend: false,
+            });
+        }
+
+        if !key.starts_with(KEY_PREFIX) {
+            return Err(ApiError::Unauthorized);
+        }
+
+        let api_key = self
+            .repository
+            .find_active_by_hash(&key_hash)
+            .await?
+            .ok_or(ApiError::Unauthorized)?;
+        let id = api_key.id.ok_or(ApiError::Unauthorized)?;
+
+        let stale = api_key
+            .last_used_at
+            .map(|at| (Utc::now() - at.to_chrono()).num_seconds() >= TOUCH_INTERVAL_SECS)
+            .unwrap_or(true);
+        if stale {
+            if let Err(e) = self.repository.touch(&id).await {
+                warn!(error = %e, key_id = %id, "Failed to record API key use");
+            }
+        }
+
+        // Keys acting for a member can never exceed what the member's role allows
+        let role = match (api_key.org_id, api_key.account_id) {
+            (Some(org_id), Some(account_id)) => {
+                let organization = match self.organization_repository.get_by_id(&org_id).await {
+                    Err(ApiError::NotFound) => return Err(ApiError::Unauthorized),
+                    result => result?,
+                };
+                Some(organization.role_of(&account_id).ok_or(ApiError::Unauthorized)?)
+            }
+            _ => None,
+        };
+        let scopes = api_key
+            .scopes
+            .into_iter()
+            .filter(|scope| match (api_key.org_id, role) {
+                (_, Some(role)) => role.allows(*scope),
+                (Some(_), None) => *scope != Scope::OrgsAdmin,
+                (None, None) => true,
+            })
+            .collect();
+
+        Ok(Caller {
+            key_id: Some(id),
+            name: api_key.name,
+            scopes,
+            org_id: api_key.org_id,
+            role,
+            tenant_id: None,
+            bot_frontend: api_key.bot_frontend,
+        })
+    }
+
+    pub async fn create_api_key(&self, caller: &Caller, request: CreateApiKey) -> Result<CreatedApiKey, ApiError> {
+        if request.name.trim().is_empty() {
+            return Err(ApiError::BadRequest("API key name cannot be empty".to_string()));
+        }
+        if request.scopes.is_empty() {
+            return Err(ApiError::BadRequest("API key needs at least one scope".to_string()));
+        }
+        if let Some(scope) = request.scopes.iter().find(|scope| !caller.has_scope(**scope)) {
+            return Err(ApiError::Forbidden(format!("Cannot grant the {} scope", scope)));
+        }
+        // Bot frontend keys choose the Telegram user they are rate limited as
+        let bootstrap = caller.key_id.is_none();
+        if request.bot_frontend && !bootstrap && caller.role != Some(OrgRole::Owner) {
+            return Err(ApiError::Forbidden(
+                "Only the bootstrap key or an organization owner can mint bot frontend keys".to_string(),
+            ));
+        }
+
+        // Org keys can only mint keys for their own organization
+        let org_id = match (caller.org_id, request.org_id) {
+            (Some(own), Some(requested)) if own != requested => {
+                return Err(ApiError::Forbidden("API key belongs to another organization".to_string()));
+            }
+            (Some(own), _) => Some(own),
+            (None, requested) => requested,
+        };
+        if let Some(org_id) = org_id {
+            if request.scopes.contains(&Scope::OrgsAdmin) {
+                return Err(ApiError::BadRequest("Organization keys cannot hold the orgs:admin scope".to_string()));
+            }
+            let organization = match self.organization_repository.get_by_id(&org_id).await {
+                Err(ApiError::NotFound) => return Err(ApiError::BadRequest("Unknown organization".to_string())),
+                result => result?,
+            };
+            if let Some(account_id) = request.account_id {
+                if organization.role_of(&account_id).is_none() {
+                    return Err(ApiError::BadRequest("Account is not a member of the organization".to_string()));
+                }
+            }
+        } else if request.account_id.is_some() {
+            return Err(ApiError::BadRequest("Member keys need an organization".to_string()));
+        }
+
+        let key = generate_key();
+        let scopes: BTreeSet<Scope> = request.scopes.into_iter().collect();
+        let api_key = ApiKey {
+            id: None,
+            name: request.name,
+            org_id,
+            account_id: request.account_id,
+            prefix: key[..DISPLAY_PREFIX_LEN].to_string(),
+            key_hash: hash_key(&key),
+            scopes: scopes.into_iter().collect(),
+            bot_frontend: request.bot_frontend,
+            created_at: Utc::now(),
+            revoked_at: None,
+            last_used_at: None,
+        };
+
+        let api_key = self.repository.create(api_key).await?;
+        Ok(CreatedApiKey {
+            key,
+            api_key: api_key.into(),
+        })
+    }
+
+    pub async fn revoke_api_key(&self, caller: &Caller, id: &ObjectId) -> Result<bool, ApiError> {
+        self.repository.revoke(caller.org_id.as_ref(), id).await
+    }
+
+    pub async fn get_api_key(&self, caller: &Caller, id: &ObjectId) -> Result<ApiKey, ApiError> {
+        let api_key = self.repository.get_by_id(id).await?;
+        if caller.org_id.is_some() && api_key.org_id != caller.org_id {
+            return Err(ApiError::NotFound);
+        }
+        Ok(api_key)
+    }
+
+    /// Platform keys see every key, org keys only those of their organization.
+    pub async fn get_all_api_keys(&self, caller: &Caller) -> Result<Vec<ApiKey>, ApiError> {
+        self.repository.get_all(caller.org_id.as_ref()).await
+    }
+}
@@ -0,0 +1,96 @@
+use axum::async_trait;
+use chrono::Utc;
+use std::{sync::Arc, time::Duration};
+use tracing::{info, warn};
+
+use crate::{
+    error::ApiError,
+    metrics,
+    models::event::OutboxEntry,
+    repository::outbox_repository::OutboxRepository,
+};
+
+// A claimed entry is dispatched again by another pass if this one dies
+const DISPATCH_LEASE: Duration = Duration::from_secs(60);
+const RETRY_BASE: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+const MAX_ATTEMPTS: u32 = 12;
+const ENTRIES_PER_PASS: usize = 100;
+
+/// Receives every event written to the outbox. Delivery is at least once,
+/// so handlers must tolerate seeing the same entry again.
+#[async_trait]
+pub trait EventSubscriber: Send + Sync {
+    /// Stable name, recorded on entries the subscriber has handled.
+    fn name(&self) -> &'static str;
+
+    async fn handle(&self, entry: &OutboxEntry) -> Result<(), ApiError>;
+}
+
+/// Fans outbox entries out to the in-process subscribers.
+#[derive(Clone)]
+pub struct EventBus {
+    outbox: OutboxRepository,
+    subscribers: Vec<Arc<dyn EventSubscriber>>,
+}
+
+impl EventBus {
+    pub fn new(outbox: OutboxRepository) -> Self {
+        Self {
+            outbox,
+            subscribers: vec![],
+        }
+    }
+
+    pub fn subscribe(mut self, subscriber: Arc<dyn EventSubscriber>) -> Self {
+        self.subscribers.push(subscriber);
+        self
+    }
+
+    /// Dispatches due entries, returning how many were attempted.
+    pub async fn dispatch_due(&self) -> Result<usize, ApiError> {
+        let mut attempted = 0;
+        while attempted < ENTRIES_PER_PASS {
+            let now = Utc::now();
+            let Some(entry) = self.outbox.claim_due(now, now + DISPATCH_LEASE).await? else {
+                break;
+            };
+            self.dispatch(entry).await?;
+            attempted += 1;
+        }
+        Ok(attempted)
+    }
+
+    async fn dispatch(&self, entry: OutboxEntry) -> Result<(), ApiError> {
+        let id = entry.id.ok_or_else(|| ApiError::InternalServerError("Outbox entry has no id".into()))?;
+        let attempts = entry.attempts + 1;
+
+        let mut errors = Vec::new();
+        for subscriber in &self.subscribers {
+            if entry.handled_by.iter().any(|name| name == subscriber.name()) {
+                continue;
+            }
+            match subscriber.handle(&entry).await {
+                Ok(()) => self.outbox.mark_handled(&id, subscriber.name()).await?,
+                Err(e) => errors.push(format!("{}: {}", subscriber.name(), e)),
+            }
+        }
+
+        if errors.is_empty() {
+            metrics::global().record_job("outbox_dispatch", true);
+            return self.outbox.mark_dispatched(&id, attempts).await;
+        }
+
+        metrics::global().record_job("outbox_dispatch", false);
+        let error = errors.join("; ");
+        if attempts >= MAX_ATTEMPTS {
+            warn!(entry_id = %id, event = entry.event_type, attempts, %error, "Giving up on outbox entry");
+            return self.outbox.mark_failed(&id, attempts, &error, None).await;
+        }
+        let delay = RETRY_BASE
+            .saturating_mul(2u32.saturating_pow(attempts - 1))
+            .min(MAX_RETRY_DELAY);
+        info!(entry_id = %id, event = entry.event_type, attempts, %error, "Outbox dispatch failed, will retry");
+        self.outbox.mark_failed(&id, attempts, &error, Some(Utc::now() + delay)).await
+    }
+}
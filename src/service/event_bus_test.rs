@@ -0,0 +1,131 @@
+use axum::async_trait;
+use mongodb::bson::{doc, Document};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    error::ApiError,
+    models::{account::Account, event::OutboxEntry, organization::Organization},
+    repository::{
+        account_repository::AccountRepository, organization_repository::OrganizationRepository,
+        outbox_repository::OutboxRepository, project_repository::ProjectRepository,
+    },
+    service::{
+        account_service::AccountService,
+        event_bus::{EventBus, EventSubscriber},
+    },
+    test_support::test_db,
+};
+
+#[derive(Default)]
+struct Recorder {
+    name: &'static str,
+    // Number of calls that fail before the subscriber starts succeeding
+    failures: Mutex<u32>,
+    seen: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl EventSubscriber for Recorder {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn handle(&self, entry: &OutboxEntry) -> Result<(), ApiError> {
+        self.seen.lock().unwrap().push(entry.event_type.clone());
+        let mut failures = self.failures.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            return Err(ApiError::InternalServerError("subscriber unavailable".into()));
+        }
+        Ok(())
+    }
+}
+
+fn account(email: &str) -> Account {
+    Account {
+        id: None,
+        org_id: None,
+        wallet_address: "EQ-wallet".to_string(),
+        email: email.to_string(),
+        account_name: "Events".to_string(),
+        project_ids: vec![],
+        created_at: chrono::Utc::now(),
+        updated_at: chrono::Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn test_outbox_entries_reach_every_subscriber_at_least_once() {
+    let db = test_db("_outbox").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let outbox = OutboxRepository::new(db.clone());
+    let organizations = OrganizationRepository::new(db.clone());
+    let accounts = AccountService::new(
+        AccountRepository::new(db.clone()),
+        ProjectRepository::new(db.clone()),
+        organizations.clone(),
+        outbox.clone(),
+    );
+    let steady = Arc::new(Recorder { name: "steady", ..Default::default() });
+    let flaky = Arc::new(Recorder { name: "flaky", failures: Mutex::new(1), ..Default::default() });
+    let bus = EventBus::new(outbox)
+        .subscribe(steady.clone())
+        .subscribe(flaky.clone());
+
+    let organization = organizations
+        .create(Organization {
+            id: None,
+            name: "Events".to_string(),
+            members: vec![],
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        })
+        .await
+        .unwrap();
+    let org_id = organization.id.unwrap();
+    let created = accounts.create_account(&org_id, account("events@example.com")).await.unwrap();
+    let stored = db
+        .collection::<Document>("outbox")
+        .find_one(doc! { "org_id": org_id }, None)
+        .await
+        .unwrap()
+        .expect("Event was not written to the outbox");
+    assert_eq!(stored.get_str("event_type").unwrap(), "account_created");
+    assert_eq!(stored.get_str("status").unwrap(), "pending");
+
+    assert_eq!(bus.dispatch_due().await.unwrap(), 1);
+    assert_eq!(*steady.seen.lock().unwrap(), vec!["account_created"]);
+    assert_eq!(flaky.seen.lock().unwrap().len(), 1);
+
+    // Only the failed subscriber sees the entry again once the retry is due
+    assert_eq!(bus.dispatch_due().await.unwrap(), 0);
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(bus.dispatch_due().await.unwrap(), 1);
+    assert_eq!(steady.seen.lock().unwrap().len(), 1);
+    assert_eq!(flaky.seen.lock().unwrap().len(), 2);
+
+    let stored = db
+        .collection::<Document>("outbox")
+        .find_one(doc! { "org_id": org_id }, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.get_str("status").unwrap(), "dispatched");
+    assert_eq!(stored.get_i64("attempts").unwrap(), 2);
+
+    accounts.delete_account(&org_id, &created.id.unwrap(), None).await.unwrap();
+    assert_eq!(bus.dispatch_due().await.unwrap(), 1);
+    assert_eq!(steady.seen.lock().unwrap().last().unwrap(), "account_deleted");
+}
+
+#[tokio::test]
+async fn test_standalone_servers_write_without_transactions() {
+    let db = test_db("_outbox_standalone").await;
+    let outbox = OutboxRepository::new(db);
+    let replica_set = outbox.is_replica_set().await.unwrap();
+    let mut tx = outbox.begin().await.expect("Writes are accepted either way");
+    assert_eq!(tx.session().is_some(), replica_set);
+}
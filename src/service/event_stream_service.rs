@@ -0,0 +1,158 @@
+use axum::async_trait;
+use futures_util::{
+    future::ready,
+    stream::{self, BoxStream},
+    Stream, StreamExt,
+};
+use mongodb::bson::oid::ObjectId;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::{
+    error::ApiError,
+    models::event::{EventFilter, OutboxEntry},
+    repository::outbox_repository::{resume_token, OutboxRepository},
+    service::event_bus::EventSubscriber,
+};
+
+// Entries a slow in-process subscriber may fall behind before its stream ends
+const BROADCAST_CAPACITY: usize = 1024;
+
+pub type EntryStream = BoxStream<'static, Result<OutboxEntry, ApiError>>;
+
+/// Streams an organization's outbox entries as they are written, each with
+/// the event id a client resumes from. Entries come from a change stream on a
+/// replica set, where ids are resume tokens and follow commit order.
+/// Otherwise they come from the event bus of this process, which sees them
+/// once they are dispatched, and ids are the entry ids.
+#[derive(Clone)]
+pub struct EventStreamService {
+    outbox: OutboxRepository,
+    sender: broadcast::Sender<OutboxEntry>,
+    // Open streams end on shutdown instead of holding the server up
+    shutdown: CancellationToken,
+}
+
+impl EventStreamService {
+    pub fn new(outbox: OutboxRepository, shutdown: CancellationToken) -> Self {
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        Self { outbox, sender, shutdown }
+    }
+
+    /// The organization's entries matching `filter` with their event ids,
+    /// starting after `last_event_id` when resuming and otherwise with the
+    /// next one written.
+    pub async fn subscribe(
+        &self,
+        org_id: ObjectId,
+        filter: EventFilter,
+        last_event_id: Option<&str>,
+    ) -> Result<impl Stream<Item = (String, OutboxEntry)> + Send + 'static, ApiError> {
+        let shutdown = self.shutdown.clone().cancelled_owned();
+        if self.outbox.is_replica_set().await? {
+            let resume_after = last_event_id.map(resume_token).transpose()?;
+            let entries = self.outbox.watch(&org_id, resume_after).await?.boxed();
+            return Ok(follow(org_id, filter, entries).take_until(shutdown).boxed());
+        }
+
+        let last_event_id = last_event_id
+            .map(|id| ObjectId::parse_str(id.trim()))
+            .transpose()
+            .map_err(|_| ApiError::BadRequest("Invalid Last-Event-ID header".to_string()))?;
+        // Follow new entries before reading the missed ones, so none falls in between
+        let live = broadcast_stream(self.sender.subscribe()).boxed();
+        let missed = match &last_event_id {
+            Some(after) => self.outbox.stream_after(&org_id, after).await?.boxed(),
+            None => stream::empty().boxed(),
+        };
+        let entries = feed(org_id, filter, last_event_id, missed, live)
+            .filter_map(|entry| ready(entry.id.map(|id| (id.to_hex(), entry))));
+        Ok(entries.take_until(shutdown).boxed())
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for EventStreamService {
+    fn name(&self) -> &'static str {
+        "event_stream"
+    }
+
+    async fn handle(&self, entry: &OutboxEntry) -> Result<(), ApiError> {
+        // Fails only when no stream is open, which is fine
+        let _ = self.sender.send(entry.clone());
+        Ok(())
+    }
+}
+
+fn broadcast_stream(receiver: broadcast::Receiver<OutboxEntry>) -> impl Stream<Item = Result<OutboxEntry, ApiError>> {
+    stream::unfold(receiver, |mut receiver| async move {
+        match receiver.recv().await {
+            Ok(entry) => Some((Ok(entry), receiver)),
+            Err(RecvError::Lagged(skipped)) => {
+                let error = ApiError::InternalServerError(format!("Event stream fell {} entries behind", skipped));
+                Some((Err(error), receiver))
+            }
+            Err(RecvError::Closed) => None,
+        }
+    })
+}
+
+/// Change stream `entries` matching `filter`, ending at the first error; the
+/// client then resumes from its last event.
+pub(crate) fn follow(
+    org_id: ObjectId,
+    filter: EventFilter,
+    entries: BoxStream<'static, Result<(String, OutboxEntry), ApiError>>,
+) -> impl Stream<Item = (String, OutboxEntry)> + Send + 'static {
+    entries
+        .scan((), move |_, entry| {
+            let (id, entry) = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!(%org_id, error = %e, "Event stream interrupted");
+                    return ready(None);
+                }
+            };
+            let wanted = entry.org_id == org_id && filter.matches(&entry.event);
+            ready(Some(wanted.then_some((id, entry))))
+        })
+        .filter_map(ready)
+}
+
+/// Without a replica set: the `missed` entries followed by the `live` ones,
+/// skipping live entries already sent and those of other organizations or not
+/// matching `filter`. Ends at the first error; the client then resumes from
+/// its last event. Entry ids only roughly follow write order, so an entry
+/// written concurrently with the last one a client saw can be missed.
+pub(crate) fn feed(
+    org_id: ObjectId,
+    filter: EventFilter,
+    last_event_id: Option<ObjectId>,
+    missed: EntryStream,
+    live: EntryStream,
+) -> impl Stream<Item = OutboxEntry> + Send + 'static {
+    missed
+        .map(|entry| (false, entry))
+        .chain(live.map(|entry| (true, entry)))
+        .scan(last_event_id, move |sent_up_to, (live, entry)| {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!(%org_id, error = %e, "Event stream interrupted");
+                    return ready(None);
+                }
+            };
+            let Some(id) = entry.id else {
+                return ready(Some(None));
+            };
+            if !live {
+                *sent_up_to = Some(id);
+            } else if sent_up_to.is_some_and(|sent| id <= sent) {
+                return ready(Some(None));
+            }
+            let wanted = entry.org_id == org_id && filter.matches(&entry.event);
+            ready(Some(wanted.then_some(entry)))
+        })
+        .filter_map(ready)
+}
@@ -0,0 +1,93 @@
+use futures_util::{stream, StreamExt};
+use mongodb::bson::oid::ObjectId;
+
+use crate::{
+    error::ApiError,
+    models::event::{DomainEvent, EventEntity, EventFilter, OutboxEntry},
+    repository::outbox_repository::resume_token,
+    service::event_stream_service::{feed, follow, EntryStream},
+};
+
+fn entry(org_id: ObjectId, event: DomainEvent) -> OutboxEntry {
+    OutboxEntry {
+        id: Some(ObjectId::new()),
+        ..OutboxEntry::new(org_id, event)
+    }
+}
+
+fn deleted(org_id: ObjectId) -> OutboxEntry {
+    entry(org_id, DomainEvent::ProjectDeleted { project_id: ObjectId::new() })
+}
+
+fn entries(entries: Vec<Result<OutboxEntry, ApiError>>) -> EntryStream {
+    stream::iter(entries).boxed()
+}
+
+fn ids(entries: &[OutboxEntry]) -> Vec<Option<ObjectId>> {
+    entries.iter().map(|entry| entry.id).collect()
+}
+
+#[tokio::test]
+async fn test_feed_resumes_and_follows_without_repeats() {
+    let org_id = ObjectId::new();
+    let last_seen = deleted(org_id);
+    let (first, second) = (deleted(org_id), deleted(org_id));
+    let other_org = deleted(ObjectId::new());
+    let account = entry(org_id, DomainEvent::AccountDeleted { account_id: ObjectId::new() });
+    let third = deleted(org_id);
+    let filter = EventFilter { entities: vec![EventEntity::Project], id: None };
+
+    // The live side also saw `second` while the missed entries were read
+    let missed = entries(vec![Ok(first.clone()), Ok(second.clone())]);
+    let live = entries(vec![Ok(second.clone()), Ok(other_org), Ok(account), Ok(third.clone())]);
+    let sent: Vec<_> = feed(org_id, filter, last_seen.id, missed, live).collect().await;
+
+    assert_eq!(ids(&sent), ids(&[first, second, third]));
+}
+
+#[tokio::test]
+async fn test_feed_ends_at_the_first_error() {
+    let org_id = ObjectId::new();
+    let filter = EventFilter { entities: vec![EventEntity::Project], id: None };
+    let first = deleted(org_id);
+    let live = entries(vec![
+        Ok(first.clone()),
+        Err(ApiError::InternalServerError("lagged".to_string())),
+        Ok(deleted(org_id)),
+    ]);
+
+    let sent: Vec<_> = feed(org_id, filter, None, entries(vec![]), live).collect().await;
+
+    assert_eq!(ids(&sent), ids(&[first]));
+}
+
+#[tokio::test]
+async fn test_follow_passes_matching_entries_with_their_tokens() {
+    let org_id = ObjectId::new();
+    let filter = EventFilter { entities: vec![EventEntity::Project], id: None };
+    let (first, second) = (deleted(org_id), deleted(org_id));
+    let account = entry(org_id, DomainEvent::AccountDeleted { account_id: ObjectId::new() });
+    let changes = stream::iter(vec![
+        Ok(("82a1".to_string(), first.clone())),
+        Ok(("82a2".to_string(), account)),
+        Ok(("82a3".to_string(), deleted(ObjectId::new()))),
+        Ok(("82a4".to_string(), second.clone())),
+        Err(ApiError::InternalServerError("history lost".to_string())),
+        Ok(("82a5".to_string(), deleted(org_id))),
+    ])
+    .boxed();
+
+    let sent: Vec<_> = follow(org_id, filter, changes).collect().await;
+
+    let tokens: Vec<_> = sent.iter().map(|(token, _)| token.as_str()).collect();
+    assert_eq!(tokens, ["82a1", "82a4"]);
+    let sent: Vec<_> = sent.into_iter().map(|(_, entry)| entry).collect();
+    assert_eq!(ids(&sent), ids(&[first, second]));
+}
+
+#[test]
+fn test_resume_tokens_are_hex_strings() {
+    assert!(resume_token("8263F0A1B2000000012B022C0100296E5A1004").is_ok());
+    assert!(matches!(resume_token("not-a-token"), Err(ApiError::BadRequest(_))));
+    assert!(matches!(resume_token(""), Err(ApiError::BadRequest(_))));
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+use mongodb::{bson::doc, Database};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    models::health::{DependencyCheck, ReadinessReport, VersionInfo},
+    worker::Workers,
+};
+
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+pub struct HealthService {
+    db: Database,
+    workers: Workers,
+    indexes_ready: Arc<AtomicBool>,
+    started_at: DateTime<Utc>,
+    started: Instant,
+}
+
+impl HealthService {
+    pub fn new(db: Database, workers: Workers) -> Self {
+        Self {
+            db,
+            workers,
+            indexes_ready: Arc::new(AtomicBool::new(false)),
+            started_at: Utc::now(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn mark_indexes_ready(&self) {
+        self.indexes_ready.store(true, Ordering::Release);
+    }
+
+    pub async fn readiness(&self) -> ReadinessReport {
+        let mut checks = BTreeMap::new();
+        checks.insert("mongodb".to_string(), self.check_mongodb().await);
+        checks.insert("indexes".to_string(), self.check_indexes());
+        checks.insert("workers".to_string(), self.check_workers());
+        ReadinessReport::from_checks(checks)
+    }
+
+    pub fn version(&self) -> VersionInfo {
+        VersionInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("GIT_SHA").to_string(),
+            started_at: self.started_at,
+            uptime_seconds: self.started.elapsed().as_secs(),
+        }
+    }
+
+    async fn check_mongodb(&self) -> DependencyCheck {
+        let start = Instant::now();
+        let ping = self.db.run_command(doc! { "ping": 1 }, None);
+        let result = tokio::time::timeout(PING_TIMEOUT, ping).await;
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        match result {
+            Ok(Ok(_)) => DependencyCheck::up(),
+            Ok(Err(e)) => DependencyCheck::down(e.to_string()),
+            Err(_) => DependencyCheck::down(format!("ping timed out after {:?}", PING_TIMEOUT)),
+        }
+        .with_latency(latency_ms)
+    }
+
+    fn check_indexes(&self) -> DependencyCheck {
+        if self.indexes_ready.load(Ordering::Acquire) {
+            DependencyCheck::up()
+        } else {
+            DependencyCheck::down("indexes have not been ensured yet")
+        }
+    }
+
+    fn check_workers(&self) -> DependencyCheck {
+        let statuses = self.workers.statuses();
+        let stopped: Vec<&str> = statuses
+            .iter()
+            .filter(|(_, running)| !running)
+            .map(|(name, _)| *name)
+            .collect();
+
+        let mut check = if stopped.is_empty() {
+            DependencyCheck::up()
+        } else {
+            DependencyCheck::down(format!("workers not running: {}", stopped.join(", ")))
+        };
+        check.details = statuses
+            .into_iter()
+            .map(|(name, running)| {
+                let state = if running { "running" } else { "stopped" };
+                (name.to_string(), state.to_string())
+            })
+            .collect();
+        check
+    }
+}
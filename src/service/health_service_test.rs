@@ -0,0 +1,51 @@
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    models::health::CheckStatus,
+    service::health_service::HealthService,
+    test_support::unreachable_db,
+    worker::Workers,
+};
+
+#[tokio::test]
+async fn test_readiness_reports_each_dependency() {
+    let workers = Workers::new(CancellationToken::new());
+    workers.spawn("idle_worker", |token| async move { token.cancelled().await });
+    let service = HealthService::new(unreachable_db().await, workers.clone());
+
+    let report = service.readiness().await;
+
+    assert!(!report.is_ready());
+    let mongodb = &report.checks["mongodb"];
+    assert_eq!(mongodb.status, CheckStatus::Down);
+    assert!(mongodb.latency_ms.is_some());
+    assert!(mongodb.error.is_some());
+    assert_eq!(report.checks["indexes"].status, CheckStatus::Down);
+    assert_eq!(report.checks["workers"].status, CheckStatus::Up);
+    assert_eq!(report.checks["workers"].details["idle_worker"], "running");
+
+    service.mark_indexes_ready();
+    assert!(workers.shutdown(Instant::now() + Duration::from_secs(1)).await);
+
+    let report = service.readiness().await;
+    assert_eq!(report.checks["indexes"].status, CheckStatus::Up);
+    assert_eq!(report.checks["workers"].status, CheckStatus::Down);
+    assert_eq!(report.checks["workers"].details["idle_worker"], "stopped");
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["status"], "down");
+    assert_eq!(json["checks"]["indexes"]["status"], "up");
+}
+
+#[tokio::test]
+async fn test_version_info() {
+    let service = HealthService::new(unreachable_db().await, Workers::new(CancellationToken::new()));
+
+    let version = service.version();
+
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+    assert!(!version.git_sha.is_empty());
+    assert!(version.started_at <= chrono::Utc::now());
+}
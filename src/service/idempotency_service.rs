@@ -0,0 +1,88 @@
+use chrono::Utc;
+use std::time::Duration;
+
+use crate::{
+    error::ApiError,
+    models::idempotency::IdempotencyRecord,
+    repository::idempotency_repository::IdempotencyRepository,
+};
+
+// A request holding a key for longer than this is assumed to have died
+const PENDING_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What to do with a request carrying an `Idempotency-Key`.
+#[derive(Debug)]
+pub enum IdempotencyStart {
+    /// First time the key is seen, handle the request and `complete` it
+    Started,
+    /// The key was used before with the same payload, replay its response
+    Replay(IdempotencyRecord),
+}
+
+#[derive(Clone)]
+pub struct IdempotencyService {
+    repository: IdempotencyRepository,
+    ttl: Duration,
+}
+
+impl IdempotencyService {
+    pub fn new(repository: IdempotencyRepository, ttl: Duration) -> Self {
+        Self { repository, ttl }
+    }
+
+    pub async fn start(&self, id: &str, request_hash: &str) -> Result<IdempotencyStart, ApiError> {
+        let now = Utc::now();
+        let pending = IdempotencyRecord {
+            id: id.to_string(),
+            request_hash: request_hash.to_string(),
+            status: None,
+            content_type: None,
+            body: None,
+            created_at: now,
+            expires_at: now + PENDING_TIMEOUT,
+        };
+        if self.repository.insert(&pending).await? {
+            return Ok(IdempotencyStart::Started);
+        }
+
+        let Some(existing) = self.repository.get(id).await? else {
+            // Expired between the insert and the read
+            return match self.repository.insert(&pending).await? {
+                true => Ok(IdempotencyStart::Started),
+                false => Err(in_progress()),
+            };
+        };
+        if existing.request_hash != request_hash {
+            return Err(ApiError::UnprocessableEntity(
+                "Idempotency-Key was already used with a different request body".to_string(),
+            ));
+        }
+        if existing.status.is_some() {
+            return Ok(IdempotencyStart::Replay(existing));
+        }
+        if existing.expires_at <= now && self.repository.take_over_expired(&pending, now).await? {
+            return Ok(IdempotencyStart::Started);
+        }
+        Err(in_progress())
+    }
+
+    pub async fn complete(
+        &self,
+        id: &str,
+        status: u16,
+        content_type: Option<String>,
+        body: String,
+    ) -> Result<(), ApiError> {
+        let expires_at = Utc::now() + self.ttl;
+        self.repository.complete(id, status, content_type, body, expires_at).await
+    }
+
+    /// Releases the key so the request can be retried.
+    pub async fn abandon(&self, id: &str) -> Result<(), ApiError> {
+        self.repository.delete(id).await
+    }
+}
+
+fn in_progress() -> ApiError {
+    ApiError::Conflict("A request with this Idempotency-Key is still in progress".to_string())
+}
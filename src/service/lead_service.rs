@@ -0,0 +1,187 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use mongodb::bson::oid::ObjectId;
+use sha2::Sha256;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::{
+    config::{FacebookConfig, Secret},
+    error::ApiError,
+    graph_client::GraphClient,
+    models::{
+        lead::{Lead, LeadgenChange, PageNotification, SubscriptionVerification},
+        project::{FacebookCredential, Project},
+    },
+    repository::{lead_repository::LeadRepository, project_repository::ProjectRepository},
+    telegram_client::TelegramNotifier,
+};
+
+pub const SIGNATURE_HEADER: &str = "x-hub-signature-256";
+
+/// Whether `signature` is the `sha256=<hex>` HMAC of `body` keyed with `app_secret`.
+pub fn signature_matches(app_secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(expected) = signature.strip_prefix("sha256=").and_then(|hex| hex::decode(hex).ok()) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(app_secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+// Compares MACs of both tokens so the comparison takes constant time
+fn tokens_match(expected: &str, token: &str) -> bool {
+    let mac = |value: &str| {
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"verify_token").expect("HMAC accepts any key length");
+        mac.update(value.as_bytes());
+        mac
+    };
+    mac(token).verify_slice(&mac(expected).finalize().into_bytes()).is_ok()
+}
+
+/// What became of the leads of one notification.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LeadReceipt {
+    pub forwarded: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+}
+
+/// Receives Lead Ads notifications, stores the leads per project and
+/// forwards them to the project's Telegram chat.
+#[derive(Clone)]
+pub struct LeadService {
+    leads: LeadRepository,
+    projects: ProjectRepository,
+    graph: Arc<dyn GraphClient>,
+    notifier: Option<Arc<dyn TelegramNotifier>>,
+    verify_token: Option<Secret>,
+}
+
+impl LeadService {
+    pub fn new(
+        leads: LeadRepository,
+        projects: ProjectRepository,
+        graph: Arc<dyn GraphClient>,
+        notifier: Option<Arc<dyn TelegramNotifier>>,
+        config: &FacebookConfig,
+    ) -> Self {
+        Self {
+            leads,
+            projects,
+            graph,
+            notifier,
+            verify_token: config.webhook_verify_token.clone(),
+        }
+    }
+
+    /// Answers the Webhooks subscription handshake with its challenge.
+    pub fn verify_subscription(&self, query: &SubscriptionVerification) -> Result<String, ApiError> {
+        let verified = match (&self.verify_token, query.mode.as_deref(), &query.verify_token) {
+            (Some(expected), Some("subscribe"), Some(token)) => tokens_match(expected.expose(), token),
+            _ => false,
+        };
+        match (verified, &query.challenge) {
+            (true, Some(challenge)) => Ok(challenge.clone()),
+            _ => Err(ApiError::Forbidden("Subscription verification failed".to_string())),
+        }
+    }
+
+    /// Handles a notification. Each lead is taken only for projects holding a
+    /// credential for its page whose app secret signed the body, and fails the
+    /// whole request when it cannot be fetched or forwarded, so that Facebook
+    /// delivers it again. Leads already forwarded are skipped.
+    pub async fn receive(&self, signature: Option<&str>, body: &[u8]) -> Result<LeadReceipt, ApiError> {
+        let notification: PageNotification = serde_json::from_slice(body)
+            .map_err(|e| ApiError::BadRequest(format!("Invalid notification: {}", e)))?;
+        let mut receipt = LeadReceipt::default();
+        if notification.object != "page" {
+            return Ok(receipt);
+        }
+
+        let mut unsigned = false;
+        for change in notification.leadgen_changes() {
+            for project in self.projects.find_by_page_id(&change.page_id).await? {
+                let signed_by = project.facebook_credentials().map(|(_, credential)| credential).find(|credential| {
+                    credential.page_id.as_deref() == Some(change.page_id.as_str())
+                        && signature.is_some_and(|signature| signature_matches(&credential.app_secret, body, signature))
+                });
+                let (Some(credential), Some(project_id)) = (signed_by, project.id) else {
+                    unsigned = true;
+                    continue;
+                };
+                match self.take(&project, project_id, credential, &change).await {
+                    Ok(true) => receipt.forwarded += 1,
+                    Ok(false) => receipt.duplicates += 1,
+                    Err(e) => {
+                        warn!(project_id = %project_id, leadgen_id = %change.leadgen_id, error = %e, "Failed to take lead");
+                        receipt.failed += 1;
+                    }
+                }
+            }
+        }
+
+        if unsigned && receipt == LeadReceipt::default() {
+            return Err(ApiError::Forbidden("Invalid signature".to_string()));
+        }
+        if receipt.failed > 0 {
+            return Err(ApiError::InternalServerError(format!("{} lead(s) could not be taken", receipt.failed)));
+        }
+        Ok(receipt)
+    }
+
+    /// Stores, fetches and forwards one lead, returning `false` when it was
+    /// forwarded before.
+    async fn take(
+        &self,
+        project: &Project,
+        project_id: ObjectId,
+        credential: &FacebookCredential,
+        change: &LeadgenChange,
+    ) -> Result<bool, ApiError> {
+        let mut lead = self
+            .leads
+            .claim(Lead {
+                id: None,
+                org_id: project.org_id,
+                project_id,
+                leadgen_id: change.leadgen_id.clone(),
+                page_id: change.page_id.clone(),
+                form_id: change.form_id.clone(),
+                ad_id: change.ad_id.clone(),
+                fields: vec![],
+                received_at: Utc::now(),
+                processed_at: None,
+            })
+            .await?;
+        if lead.processed_at.is_some() {
+            return Ok(false);
+        }
+        let id = lead.id.ok_or_else(|| ApiError::InternalServerError("Lead has no id".to_string()))?;
+
+        if lead.fields.is_empty() {
+            lead.fields = self
+                .graph
+                .get_lead(&credential.access_token, &lead.leadgen_id)
+                .await
+                .map_err(|e| ApiError::InternalServerError(format!("Failed to fetch lead: {}", e)))?;
+            self.leads.set_fields(&id, &lead.fields).await?;
+        }
+
+        // Without a chat the lead is only stored
+        if let (Some(notifier), Some(chat_id)) = (&self.notifier, &project.telegram_chat_id) {
+            notifier
+                .send_message(chat_id, &lead.message(&project.name))
+                .await
+                .map_err(|e| ApiError::InternalServerError(format!("Failed to forward lead: {}", e)))?;
+        }
+        self.leads.mark_processed(&id, Utc::now()).await?;
+        Ok(true)
+    }
+
+    /// The most recent leads of a project.
+    pub async fn get_leads(&self, org_id: &ObjectId, project_id: &ObjectId) -> Result<Vec<Lead>, ApiError> {
+        self.projects.get_by_id(org_id, project_id).await?;
+        self.leads.get_by_project(org_id, project_id).await
+    }
+}
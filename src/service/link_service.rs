@@ -0,0 +1,135 @@
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
+use tracing::warn;
+
+use crate::{
+    config::LinksConfig,
+    error::ApiError,
+    models::{
+        link::{is_code, redirect_url, LinkClick, LinkStats, LinkView, NewLink, TrackedLink, CODE_LENGTH},
+        project::validate_credential_key,
+    },
+    repository::{link_repository::LinkRepository, project_repository::ProjectRepository},
+};
+
+// A taken code means a collision out of 62^8, a few attempts are plenty
+const CODE_ATTEMPTS: usize = 3;
+// Referrers and user agents are client supplied, keep what is stored bounded
+const MAX_CLICK_FIELD_LENGTH: usize = 512;
+
+pub fn generate_code() -> String {
+    OsRng.sample_iter(&Alphanumeric).take(CODE_LENGTH).map(char::from).collect()
+}
+
+/// What the redirect request said about the click.
+#[derive(Debug, Default, Clone)]
+pub struct ClickContext {
+    pub referrer: Option<String>,
+    pub user_agent: Option<String>,
+    pub fbclid: Option<String>,
+}
+
+fn bounded(value: Option<String>) -> Option<String> {
+    value
+        .filter(|value| !value.is_empty())
+        .map(|value| value.chars().take(MAX_CLICK_FIELD_LENGTH).collect())
+}
+
+/// Issues short codes for a project's credential links and redirects them,
+/// recording every click.
+#[derive(Clone)]
+pub struct LinkService {
+    links: LinkRepository,
+    projects: ProjectRepository,
+    base_url: Option<String>,
+}
+
+impl LinkService {
+    pub fn new(links: LinkRepository, projects: ProjectRepository, config: &LinksConfig) -> Self {
+        Self { links, projects, base_url: config.base_url.clone() }
+    }
+
+    fn view(&self, link: TrackedLink) -> LinkView {
+        let short_url = self.base_url.as_ref().map(|base| format!("{}/l/{}", base, link.code));
+        LinkView { link, short_url }
+    }
+
+    /// Creates a link to the `link_url` of a Facebook credential of the project.
+    pub async fn create_link(&self, org_id: &ObjectId, project_id: &ObjectId, new_link: NewLink) -> Result<LinkView, ApiError> {
+        validate_credential_key(&new_link.credential_key).map_err(ApiError::BadRequest)?;
+        let project = self.projects.get_by_id(org_id, project_id).await?;
+        let credential = project
+            .ad_credentials
+            .get(&new_link.credential_key)
+            .ok_or_else(|| ApiError::BadRequest(format!("Project has no credential {}", new_link.credential_key)))?
+            .as_facebook()
+            .ok_or_else(|| ApiError::BadRequest(format!("Credential {} is not a Facebook credential", new_link.credential_key)))?;
+        let link_url = credential
+            .link_url
+            .as_deref()
+            .ok_or_else(|| ApiError::BadRequest(format!("Credential {} has no link_url", new_link.credential_key)))?;
+        let utm = new_link.utm(&credential.account_suffix).map_err(ApiError::BadRequest)?;
+        let destination = utm
+            .apply(link_url)
+            .map_err(|e| ApiError::BadRequest(format!("link_url of credential {}: {}", new_link.credential_key, e)))?;
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let link = TrackedLink {
+                id: None,
+                org_id: Some(*org_id),
+                project_id: *project_id,
+                credential_key: new_link.credential_key.clone(),
+                code: generate_code(),
+                utm: utm.clone(),
+                destination: destination.clone(),
+                created_at: Utc::now(),
+            };
+            match self.links.create(link).await {
+                Err(ApiError::Conflict(_)) if attempt < CODE_ATTEMPTS => continue,
+                result => return result.map(|link| self.view(link)),
+            }
+        }
+    }
+
+    pub async fn get_links(&self, org_id: &ObjectId, project_id: &ObjectId) -> Result<Vec<LinkView>, ApiError> {
+        self.projects.get_by_id(org_id, project_id).await?;
+        let links = self.links.get_by_project(org_id, project_id).await?;
+        Ok(links.into_iter().map(|link| self.view(link)).collect())
+    }
+
+    pub async fn delete_link(&self, org_id: &ObjectId, project_id: &ObjectId, id: &ObjectId) -> Result<bool, ApiError> {
+        self.links.delete(org_id, project_id, id).await
+    }
+
+    pub async fn get_stats(&self, org_id: &ObjectId, project_id: &ObjectId, id: &ObjectId) -> Result<LinkStats, ApiError> {
+        let link = self.links.get_by_id(org_id, project_id, id).await?;
+        self.links.stats(&link, Utc::now()).await
+    }
+
+    /// Where the link with `code` redirects to. The click is recorded on the
+    /// way, a failure to record it does not fail the redirect.
+    pub async fn follow(&self, code: &str, context: ClickContext) -> Result<String, ApiError> {
+        if !is_code(code) {
+            return Err(ApiError::NotFound);
+        }
+        let link = self.links.get_by_code(code).await?.ok_or(ApiError::NotFound)?;
+        let link_id = link.id.ok_or(ApiError::NotFound)?;
+        let click = LinkClick {
+            id: None,
+            link_id,
+            org_id: link.org_id,
+            project_id: link.project_id,
+            clicked_at: Utc::now(),
+            referrer: bounded(context.referrer),
+            user_agent: bounded(context.user_agent),
+            fbclid: bounded(context.fbclid),
+        };
+        if let Err(e) = self.links.record_click(&click).await {
+            warn!(error = %e, code, "Failed to record link click");
+        }
+        Ok(redirect_url(&link.destination, click.fbclid.as_deref()))
+    }
+}
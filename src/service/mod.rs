@@ -1,2 +1,46 @@
 pub mod project_service;
-pub mod account_service; 
\ No newline at end of file
+pub mod account_service;
+pub mod health_service;
+pub mod api_key_service;
+pub mod organization_service;
+pub mod idempotency_service;
+pub mod webhook_service;
+pub mod event_bus;
+pub mod event_stream_service;
+pub mod lead_service;
+pub mod token_health_service;
+pub mod ad_platform_service;
+pub mod link_service;
+#[cfg(test)]
+mod health_service_test;
+#[cfg(test)]
+mod webhook_service_test;
+#[cfg(test)]
+mod event_bus_test;
+#[cfg(test)]
+mod event_stream_service_test;
+#[cfg(test)]
+mod token_health_service_test;
+#[cfg(test)]
+mod ad_platform_service_test;
+
+use crate::{error::ApiError, repository::outbox_repository::OutboxRepository};
+
+/// Message reported for an import row the service refused.
+pub(crate) fn rejection(error: ApiError) -> String {
+    match error {
+        ApiError::BadRequest(message) | ApiError::Conflict(message) | ApiError::UnprocessableEntity(message) => message,
+        other => other.to_string(),
+    }
+}
+
+/// Atomic imports write every row in one transaction, which a standalone
+/// server cannot give even when writes are allowed there.
+pub(crate) async fn require_transactions(outbox: &OutboxRepository) -> Result<(), ApiError> {
+    if !outbox.is_replica_set().await? {
+        return Err(ApiError::BadRequest(
+            "Atomic imports need a MongoDB replica set, use mode=best_effort".to_string(),
+        ));
+    }
+    Ok(())
+}
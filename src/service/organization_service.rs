@@ -0,0 +1,100 @@
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use crate::{
+    models::{
+        event::DomainEvent,
+        organization::{CreateOrganization, OrgRole, Organization},
+    },
+    repository::{
+        account_repository::AccountRepository, organization_repository::OrganizationRepository,
+        outbox_repository::OutboxRepository,
+    },
+    error::ApiError,
+};
+
+#[derive(Clone)]
+pub struct OrganizationService {
+    repository: OrganizationRepository,
+    account_repository: AccountRepository,
+    outbox: OutboxRepository,
+}
+
+impl OrganizationService {
+    pub fn new(
+        repository: OrganizationRepository,
+        account_repository: AccountRepository,
+        outbox: OutboxRepository,
+    ) -> Self {
+        Self { repository, account_repository, outbox }
+    }
+
+    pub async fn create_organization(&self, request: CreateOrganization) -> Result<Organization, ApiError> {
+        if request.name.trim().is_empty() {
+            return Err(ApiError::BadRequest("Organization name cannot be empty".to_string()));
+        }
+
+        let organization = Organization {
+            id: None,
+            name: request.name,
+            members: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        self.repository.create(organization).await
+    }
+
+    pub async fn get_organization(&self, id: &ObjectId) -> Result<Organization, ApiError> {
+        self.repository.get_by_id(id).await
+    }
+
+    pub async fn get_all_organizations(&self) -> Result<Vec<Organization>, ApiError> {
+        self.repository.get_all().await
+    }
+
+    /// `actor` is the role of the calling member, `None` for platform keys.
+    pub async fn set_member_role(
+        &self,
+        org_id: &ObjectId,
+        account_id: ObjectId,
+        role: OrgRole,
+        actor: Option<OrgRole>,
+    ) -> Result<Organization, ApiError> {
+        // Members are accounts of the same organization
+        self.account_repository.get_by_id(org_id, &account_id).await?;
+
+        let mut organization = self.repository.get_by_id(org_id).await?;
+        let previous = organization.members.clone();
+        organization
+            .set_member(account_id, role, actor)
+            .map_err(ApiError::Forbidden)?;
+
+        let mut tx = self.outbox.begin().await?;
+        let organization = self
+            .repository
+            .update_members(&mut tx, org_id, &previous, &organization.members)
+            .await?;
+        self.outbox.append(&mut tx, org_id, DomainEvent::AccountLinked { account_id, role }).await?;
+        tx.commit().await?;
+        Ok(organization)
+    }
+
+    pub async fn remove_member(
+        &self,
+        org_id: &ObjectId,
+        account_id: &ObjectId,
+        actor: Option<OrgRole>,
+    ) -> Result<bool, ApiError> {
+        let mut organization = self.repository.get_by_id(org_id).await?;
+        let previous = organization.members.clone();
+        if !organization.remove_member(account_id, actor).map_err(ApiError::Forbidden)? {
+            return Ok(false);
+        }
+        let mut tx = self.outbox.begin().await?;
+        self.repository
+            .update_members(&mut tx, org_id, &previous, &organization.members)
+            .await?;
+        self.outbox.append(&mut tx, org_id, DomainEvent::AccountUnlinked { account_id: *account_id }).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
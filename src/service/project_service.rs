@@ -1,54 +1,538 @@
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt};
 use mongodb::bson::oid::ObjectId;
+use std::{
+    collections::{HashMap, HashSet},
+    pin::pin,
+};
 use crate::{
-    models::project::Project,
-    repository::project_repository::ProjectRepository,
+    models::{
+        batch::{BatchOperation, BatchPlan, BatchResult, BatchStatus, BatchUpdateProjects, MAX_BATCH_SIZE},
+        event::DomainEvent,
+        import::{ImportMode, ImportReport, MAX_IMPORT_ROWS},
+        ad_platform::{AdCredentialEntry, AdPlatform, AdPlatformCredential},
+        project::{
+            validate_credential_key, Project, ProjectAction, ProjectRecord, ProjectStateCounts, ProjectStatus,
+            StatusChange, StatusTransition,
+        },
+    },
+    records::RecordError,
+    repository::{
+        outbox_repository::OutboxRepository, project_repository::ProjectRepository, transaction::Transaction,
+    },
+    service::{rejection, require_transactions},
     error::ApiError,
 };
 
+const MAX_EXTEND_DAYS: u32 = 3650;
+const MAX_NOTE_LENGTH: usize = 500;
+
+// Event subscribers never receive credential secrets
+fn masked(project: &Project) -> Project {
+    let mut project = project.clone();
+    project.mask_secrets();
+    project
+}
+
 #[derive(Clone)]
 pub struct ProjectService {
     repository: ProjectRepository,
+    outbox: OutboxRepository,
 }
 
 impl ProjectService {
-    pub fn new(repository: ProjectRepository) -> Self {
-        Self { repository }
+    pub fn new(repository: ProjectRepository, outbox: OutboxRepository) -> Self {
+        Self { repository, outbox }
     }
 
-    pub async fn create_project(&self, mut project: Project) -> Result<Project, ApiError> {
-        // Add business logic here
+    pub async fn create_project(&self, org_id: &ObjectId, project: Project) -> Result<Project, ApiError> {
+        let project = Self::prepare_new(org_id, project)?;
+        let mut projects = self.insert(org_id, vec![project]).await?;
+        Ok(projects.remove(0))
+    }
+
+    fn prepare_new(org_id: &ObjectId, mut project: Project) -> Result<Project, ApiError> {
+        project.org_id = Some(*org_id);
         project.created_at = chrono::Utc::now();
         project.updated_at = chrono::Utc::now();
         
-        // Additional validation could go here
         if project.name.is_empty() {
             return Err(ApiError::BadRequest("Project name cannot be empty".to_string()));
         }
-        
-        self.repository.create(project).await
+        project.keep_token_health(None);
+        validate_credentials(&project)?;
+        if project.has_masked_secrets() {
+            return Err(ApiError::BadRequest("Credentials cannot use the masked secret placeholder".to_string()));
+        }
+        // Clients that predate statuses create projects with `is_active` alone
+        project.status = match project.status {
+            ProjectStatus::Draft if project.is_active => ProjectStatus::Active,
+            status @ (ProjectStatus::Draft | ProjectStatus::Active) => status,
+            status => return Err(ApiError::BadRequest(format!("Projects cannot be created as {}", status))),
+        };
+        project.is_active = project.status == ProjectStatus::Active;
+        project.status_history = vec![];
+        Ok(project)
     }
 
-    pub async fn update_project(&self, id: &ObjectId, mut project: Project) -> Result<Project, ApiError> {
+    // Creates the projects and their events in one transaction
+    async fn insert(&self, org_id: &ObjectId, projects: Vec<Project>) -> Result<Vec<Project>, ApiError> {
+        let mut tx = self.outbox.begin().await?;
+        let mut created = Vec::with_capacity(projects.len());
+        for project in projects {
+            let project = self.repository.create(&mut tx, project).await?;
+            self.outbox.append(&mut tx, org_id, DomainEvent::ProjectCreated { project: masked(&project) }).await?;
+            created.push(project);
+        }
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// Creates projects from `rows` as they are read. In atomic mode nothing
+    /// is written unless every row is valid.
+    pub async fn import_projects<S>(&self, org_id: &ObjectId, rows: S, mode: ImportMode) -> Result<ImportReport, ApiError>
+    where
+        S: Stream<Item = Result<ProjectRecord, RecordError>>,
+    {
+        let mut rows = pin!(rows);
+        let mut report = ImportReport::new(mode);
+        let mut valid = Vec::new();
+
+        while let Some(row) = rows.next().await {
+            if report.total == MAX_IMPORT_ROWS {
+                report.reject(0, format!("Imports are limited to {} rows", MAX_IMPORT_ROWS));
+                break;
+            }
+            let record = match row {
+                Ok(record) => record,
+                Err(RecordError::Row(error)) => {
+                    report.total += 1;
+                    report.reject(report.total, error);
+                    continue;
+                }
+                Err(RecordError::Fatal(error)) => {
+                    report.reject(0, error);
+                    break;
+                }
+            };
+            report.total += 1;
+
+            let project = match Self::prepare_new(org_id, record.into()) {
+                Ok(project) => project,
+                Err(e) => {
+                    report.reject(report.total, rejection(e));
+                    continue;
+                }
+            };
+            match mode {
+                ImportMode::Atomic => valid.push(project),
+                // A failed write only loses its own row
+                ImportMode::BestEffort => match self.insert(org_id, vec![project]).await {
+                    Ok(_) => report.imported += 1,
+                    Err(e) => report.reject(report.total, rejection(e)),
+                },
+            }
+        }
+
+        if mode == ImportMode::Atomic && report.failed == 0 {
+            require_transactions(&self.outbox).await?;
+            report.imported = self.insert(org_id, valid).await?.len();
+        }
+        Ok(report)
+    }
+
+    pub async fn export_projects(&self, org_id: &ObjectId) -> Result<impl Stream<Item = Result<Project, ApiError>> + Send + 'static, ApiError> {
+        self.repository.stream_all(org_id).await
+    }
+
+    pub async fn update_project(&self, org_id: &ObjectId, id: &ObjectId, mut project: Project) -> Result<Project, ApiError> {
         // Add business logic here
+        project.org_id = Some(*org_id);
         project.updated_at = chrono::Utc::now();
         
         // Additional validation could go here
         if project.name.is_empty() {
             return Err(ApiError::BadRequest("Project name cannot be empty".to_string()));
         }
+        validate_credentials(&project)?;
+
+        // Clients that read the project without secrets:reveal send the placeholder back
+        let existing = self.repository.get_by_id(org_id, id).await?;
+        project.restore_masked_secrets(&existing).map_err(|key| {
+            ApiError::BadRequest(format!("Credential {} has no stored secret to keep", key))
+        })?;
+        project.keep_token_health(Some(&existing));
         
-        self.repository.update(id, project).await
+        let mut tx = self.outbox.begin().await?;
+        let project = self.repository.update(&mut tx, org_id, id, project).await?;
+        self.outbox.append(&mut tx, org_id, DomainEvent::ProjectUpdated { project: masked(&project) }).await?;
+        tx.commit().await?;
+        Ok(project)
+    }
+
+    pub async fn delete_project(&self, org_id: &ObjectId, id: &ObjectId) -> Result<bool, ApiError> {
+        let mut tx = self.outbox.begin().await?;
+        let deleted = self.repository.delete(&mut tx, org_id, id).await?;
+        if deleted {
+            self.outbox.append(&mut tx, org_id, DomainEvent::ProjectDeleted { project_id: *id }).await?;
+        }
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    pub async fn get_credentials(&self, org_id: &ObjectId, id: &ObjectId) -> Result<HashMap<String, AdPlatformCredential>, ApiError> {
+        Ok(self.repository.get_by_id(org_id, id).await?.ad_credentials)
+    }
+
+    /// Adds a credential under a new key. Fails with a conflict when the key
+    /// is already taken, by a credential of any platform.
+    pub async fn add_credential(&self, org_id: &ObjectId, id: &ObjectId, entry: AdCredentialEntry) -> Result<AdCredentialEntry, ApiError> {
+        validate_credential_key(&entry.key).map_err(ApiError::BadRequest)?;
+        entry.credential.validate().map_err(ApiError::BadRequest)?;
+        reject_encrypted_secrets(&entry.credential)?;
+        if entry.credential.has_masked_secrets() {
+            return Err(ApiError::BadRequest("Credentials cannot use the masked secret placeholder".to_string()));
+        }
+        let AdCredentialEntry { key, mut credential } = entry;
+        credential.keep_token_health(None);
+
+        let mut tx = self.outbox.begin().await?;
+        let Some(project) = self.repository.set_credential(&mut tx, org_id, id, &key, credential, false).await? else {
+            drop(tx);
+            // Tell a missing project apart from a taken key
+            self.repository.get_by_id(org_id, id).await?;
+            return Err(ApiError::Conflict(format!("Credential {} already exists", key)));
+        };
+        self.credential_written(tx, org_id, project, key).await
+    }
+
+    /// Replaces the credential under `key` with one for the same platform.
+    /// Secrets sent back as the masked placeholder keep their stored value.
+    pub async fn replace_credential(
+        &self,
+        org_id: &ObjectId,
+        id: &ObjectId,
+        key: &str,
+        mut credential: AdPlatformCredential,
+    ) -> Result<AdCredentialEntry, ApiError> {
+        validate_credential_key(key).map_err(ApiError::BadRequest)?;
+        credential.validate().map_err(ApiError::BadRequest)?;
+        reject_encrypted_secrets(&credential)?;
+        let existing = self.repository.get_by_id(org_id, id).await?;
+        let stored = existing.ad_credentials.get(key).ok_or(ApiError::NotFound)?;
+        if stored.platform() != credential.platform() {
+            return Err(ApiError::Conflict(format!("Credential {} is a {} credential", key, stored.platform())));
+        }
+        credential.restore_masked_secrets(stored).map_err(ApiError::Conflict)?;
+        credential.keep_token_health(Some(stored));
+
+        let mut tx = self.outbox.begin().await?;
+        let project = self
+            .repository
+            .set_credential(&mut tx, org_id, id, key, credential, true)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        self.credential_written(tx, org_id, project, key.to_string()).await
+    }
+
+    async fn credential_written(
+        &self,
+        mut tx: Transaction,
+        org_id: &ObjectId,
+        mut project: Project,
+        key: String,
+    ) -> Result<AdCredentialEntry, ApiError> {
+        self.outbox.append(&mut tx, org_id, DomainEvent::ProjectUpdated { project: masked(&project) }).await?;
+        tx.commit().await?;
+        let credential = project
+            .ad_credentials
+            .remove(&key)
+            .ok_or_else(|| ApiError::InternalServerError(format!("Credential {} was not stored", key)))?;
+        Ok(AdCredentialEntry { key, credential })
+    }
+
+    /// Removes the credential under `key`, if it is for `platform` when one
+    /// is given.
+    pub async fn remove_credential(
+        &self,
+        org_id: &ObjectId,
+        id: &ObjectId,
+        key: &str,
+        platform: Option<AdPlatform>,
+    ) -> Result<bool, ApiError> {
+        validate_credential_key(key).map_err(ApiError::BadRequest)?;
+        let mut tx = self.outbox.begin().await?;
+        let Some(project) = self.repository.unset_credential(&mut tx, org_id, id, key, platform).await? else {
+            return Ok(false);
+        };
+        self.outbox.append(&mut tx, org_id, DomainEvent::ProjectUpdated { project: masked(&project) }).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn get_project(&self, org_id: &ObjectId, id: &ObjectId) -> Result<Project, ApiError> {
+        self.repository.get_by_id(org_id, id).await
+    }
+
+    pub async fn get_all_projects(&self, org_id: &ObjectId) -> Result<Vec<Project>, ApiError> {
+        self.repository.get_all(org_id).await
+    }
+
+    /// Looks up many projects at once. Unknown and malformed ids get their
+    /// own result instead of failing the request.
+    pub async fn batch_get_projects(&self, org_id: &ObjectId, ids: Vec<String>) -> Result<Vec<BatchResult>, ApiError> {
+        let parsed = parse_batch_ids(&ids)?;
+        let valid: Vec<ObjectId> = parsed.iter().flatten().copied().collect();
+        let found = by_id(self.repository.get_many(org_id, &valid).await?);
+
+        Ok(batch_results(ids, parsed, |id| match found.get(id) {
+            Some(project) => (BatchStatus::Found, Some(project.clone()), None),
+            None => (BatchStatus::NotFound, None, None),
+        }))
+    }
+
+    /// Applies one operation to many projects, with one update per current
+    /// status. Projects already in the requested status are reported as
+    /// unchanged, and those whose status does not allow it as conflicts.
+    pub async fn batch_update_projects(&self, org_id: &ObjectId, request: BatchUpdateProjects) -> Result<Vec<BatchResult>, ApiError> {
+        let now = Utc::now();
+        validate_operation(&request.operation, now)?;
+        let parsed = parse_batch_ids(&request.ids)?;
+        let valid: Vec<ObjectId> = parsed.iter().flatten().copied().collect();
+
+        let existing = by_id(self.repository.get_many(org_id, &valid).await?);
+        let mut groups: HashMap<(ProjectStatus, Option<ProjectAction>), Vec<ObjectId>> = HashMap::new();
+        let mut conflicts = HashMap::new();
+        for (id, project) in &existing {
+            match request.operation.plan(project) {
+                BatchPlan::Unchanged => {}
+                BatchPlan::Conflict(error) => {
+                    conflicts.insert(*id, error);
+                }
+                BatchPlan::Apply(action) => groups.entry((project.status, action)).or_default().push(*id),
+            }
+        }
+        let planned: HashSet<ObjectId> = groups.values().flatten().copied().collect();
+
+        let mut tx = self.outbox.begin().await?;
+        let mut updated = Vec::new();
+        for ((from, action), mut ids) in groups {
+            ids.sort();
+            let change = request.operation.change();
+            let transition = action.map(|action| StatusTransition {
+                action,
+                from,
+                to: action.target(),
+                reason: change.reason,
+                note: change.note,
+                at: now,
+            });
+            let operation = &request.operation;
+            updated.extend(self.repository.update_many(&mut tx, org_id, &ids, from, operation, transition.as_ref()).await?);
+        }
+        for project in &updated {
+            self.outbox.append(&mut tx, org_id, DomainEvent::ProjectUpdated { project: masked(project) }).await?;
+        }
+        tx.commit().await?;
+
+        let updated = by_id(updated);
+        Ok(batch_results(request.ids, parsed, |id| {
+            if let Some(project) = updated.get(id) {
+                return (BatchStatus::Updated, Some(project.clone()), None);
+            }
+            let Some(project) = existing.get(id) else {
+                return (BatchStatus::NotFound, None, None);
+            };
+            match conflicts.get(id) {
+                Some(error) => (BatchStatus::Conflict, Some(project.clone()), Some(error.clone())),
+                // Another request changed the status between the read and the update
+                None if planned.contains(id) => {
+                    (BatchStatus::Conflict, Some(project.clone()), Some("Project status changed meanwhile".to_string()))
+                }
+                None => (BatchStatus::Unchanged, Some(project.clone()), None),
+            }
+        }))
     }
 
-    pub async fn delete_project(&self, id: &ObjectId) -> Result<bool, ApiError> {
-        self.repository.delete(id).await
+    /// Looks a project up in any organization, for operator tooling. API
+    /// handlers stay scoped to the caller's organization.
+    pub async fn find_project(&self, id: &ObjectId) -> Result<Project, ApiError> {
+        self.repository.find_by_id(id).await
     }
 
-    pub async fn get_project(&self, id: &ObjectId) -> Result<Project, ApiError> {
-        self.repository.get_by_id(id).await
+    /// Projects of every organization, for operator tooling.
+    pub async fn find_all_projects(&self) -> Result<Vec<Project>, ApiError> {
+        self.repository.find_all().await
     }
 
-    pub async fn get_all_projects(&self) -> Result<Vec<Project>, ApiError> {
-        self.repository.get_all().await
+    /// Takes `action` on the project. Fails with a conflict when its current
+    /// status does not allow it.
+    pub async fn transition_project(
+        &self,
+        org_id: &ObjectId,
+        id: &ObjectId,
+        action: ProjectAction,
+        change: StatusChange,
+    ) -> Result<Project, ApiError> {
+        validate_change(action, &change)?;
+        let project = self.repository.get_by_id(org_id, id).await?;
+        self.apply_transition(org_id, project, action, change, None).await
     }
-} 
\ No newline at end of file
+
+    /// Moves the expiry to `expires_at`, renewing the project if it expired.
+    pub async fn extend_project(
+        &self,
+        org_id: &ObjectId,
+        id: &ObjectId,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Project, ApiError> {
+        if expires_at <= Utc::now() {
+            return Err(ApiError::BadRequest("New expiry must be in the future".to_string()));
+        }
+        let mut project = self.repository.get_by_id(org_id, id).await?;
+        match project.status {
+            ProjectStatus::Cancelled => Err(ApiError::Conflict("Cannot extend a cancelled project".to_string())),
+            ProjectStatus::Expired => {
+                self.apply_transition(org_id, project, ProjectAction::Renew, StatusChange::default(), Some(expires_at)).await
+            }
+            _ => {
+                project.expires_at = Some(expires_at);
+                self.update_project(org_id, id, project).await
+            }
+        }
+    }
+
+    async fn apply_transition(
+        &self,
+        org_id: &ObjectId,
+        mut project: Project,
+        action: ProjectAction,
+        change: StatusChange,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Project, ApiError> {
+        let id = project.id.ok_or(ApiError::NotFound)?;
+        let transition = project
+            .transition(action, change, Utc::now())
+            .map_err(|status| ApiError::Conflict(format!("Cannot {} a {} project", action, status)))?
+            .clone();
+
+        let mut tx = self.outbox.begin().await?;
+        let project = self
+            .repository
+            .transition(&mut tx, Some(org_id), &id, &transition, expires_at)
+            .await?
+            .ok_or_else(|| ApiError::Conflict("Project status changed meanwhile".to_string()))?;
+        self.outbox.append(&mut tx, org_id, DomainEvent::ProjectUpdated { project: masked(&project) }).await?;
+        tx.commit().await?;
+        Ok(project)
+    }
+
+    /// Re-encrypts stored credential secrets with the active encryption key.
+    pub async fn rotate_encryption_key(&self, dry_run: bool) -> Result<u64, ApiError> {
+        self.repository.rotate_secrets(dry_run).await
+    }
+
+    /// Moves active projects whose expiry has passed to expired.
+    pub async fn expire_projects(&self) -> Result<usize, ApiError> {
+        let now = chrono::Utc::now();
+        let mut expired = 0;
+        for mut project in self.repository.find_expired(now).await? {
+            let Some(id) = project.id else { continue };
+            let Ok(transition) = project.transition(ProjectAction::Expire, StatusChange::default(), now) else {
+                continue;
+            };
+            let transition = transition.clone();
+            let mut tx = self.outbox.begin().await?;
+            let Some(project) = self.repository.transition(&mut tx, None, &id, &transition, None).await? else {
+                continue;
+            };
+            // Projects created before organizations have nobody to notify
+            if let Some(org_id) = project.org_id {
+                self.outbox.append(&mut tx, &org_id, DomainEvent::ProjectExpired { project: masked(&project) }).await?;
+            }
+            tx.commit().await?;
+            expired += 1;
+        }
+        Ok(expired)
+    }
+
+    pub async fn count_projects_by_state(&self) -> Result<ProjectStateCounts, ApiError> {
+        self.repository.count_by_state(chrono::Utc::now()).await
+    }
+}
+
+fn parse_batch_ids(ids: &[String]) -> Result<Vec<Option<ObjectId>>, ApiError> {
+    if ids.is_empty() {
+        return Err(ApiError::BadRequest("ids cannot be empty".to_string()));
+    }
+    if ids.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::BadRequest(format!("At most {} ids per batch", MAX_BATCH_SIZE)));
+    }
+    Ok(ids.iter().map(|id| ObjectId::parse_str(id).ok()).collect())
+}
+
+fn by_id(projects: Vec<Project>) -> HashMap<ObjectId, Project> {
+    projects
+        .into_iter()
+        .filter_map(|project| Some((project.id?, project)))
+        .collect()
+}
+
+fn batch_results(
+    ids: Vec<String>,
+    parsed: Vec<Option<ObjectId>>,
+    result: impl Fn(&ObjectId) -> (BatchStatus, Option<Project>, Option<String>),
+) -> Vec<BatchResult> {
+    ids.into_iter()
+        .zip(parsed)
+        .map(|(id, object_id)| {
+            let (status, project, error) = match object_id {
+                Some(object_id) => result(&object_id),
+                None => (BatchStatus::InvalidId, None, None),
+            };
+            BatchResult { id, status, project, error }
+        })
+        .collect()
+}
+
+fn validate_credentials(project: &Project) -> Result<(), ApiError> {
+    project
+        .ad_credentials
+        .keys()
+        .try_for_each(|key| validate_credential_key(key))
+        .map_err(ApiError::BadRequest)?;
+    project.ad_credentials.values().try_for_each(reject_encrypted_secrets)
+}
+
+fn reject_encrypted_secrets(credential: &AdPlatformCredential) -> Result<(), ApiError> {
+    match credential.has_encrypted_secrets() {
+        true => Err(ApiError::BadRequest("Credential secrets cannot use the enc:v1: prefix".to_string())),
+        false => Ok(()),
+    }
+}
+
+fn validate_change(action: ProjectAction, change: &StatusChange) -> Result<(), ApiError> {
+    if action.requires_reason() && change.reason.is_none() {
+        return Err(ApiError::BadRequest(format!("A reason is required to {} a project", action)));
+    }
+    if change.note.as_ref().is_some_and(|note| note.chars().count() > MAX_NOTE_LENGTH) {
+        return Err(ApiError::BadRequest(format!("note cannot be longer than {} characters", MAX_NOTE_LENGTH)));
+    }
+    Ok(())
+}
+
+fn validate_operation(operation: &BatchOperation, now: DateTime<Utc>) -> Result<(), ApiError> {
+    let BatchOperation::Extend { days, until } = operation else {
+        return match operation.action() {
+            Some(action) => validate_change(action, &operation.change()),
+            None => Ok(()),
+        };
+    };
+    match (days, until) {
+        (Some(days), None) if (1..=MAX_EXTEND_DAYS).contains(days) => Ok(()),
+        (Some(_), None) => Err(ApiError::BadRequest(format!("days must be between 1 and {}", MAX_EXTEND_DAYS))),
+        (None, Some(until)) if *until > now => Ok(()),
+        (None, Some(_)) => Err(ApiError::BadRequest("until must be in the future".to_string())),
+        _ => Err(ApiError::BadRequest("extend needs exactly one of days or until".to_string())),
+    }
+}
@@ -0,0 +1,168 @@
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::{
+    config::FacebookConfig,
+    error::ApiError,
+    graph_client::GraphClient,
+    models::project::{FacebookCredential, Project, TokenHealth, TokenHealthUpdate},
+    repository::project_repository::ProjectRepository,
+    telegram_client::TelegramNotifier,
+};
+
+/// Tokens expiring sooner than this are short-lived and get exchanged.
+const SHORT_LIVED_TOKEN_WINDOW: chrono::Duration = chrono::Duration::days(1);
+
+/// Totals of one pass over every credential.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TokenCheckSummary {
+    pub checked: usize,
+    pub exchanged: usize,
+    pub invalid: usize,
+    pub warned: usize,
+    pub failed: usize,
+}
+
+/// Checks Facebook access tokens with `debug_token`, swaps short-lived
+/// tokens for long-lived ones and warns project chats before they expire.
+#[derive(Clone)]
+pub struct TokenHealthService {
+    repository: ProjectRepository,
+    graph: Arc<dyn GraphClient>,
+    notifier: Option<Arc<dyn TelegramNotifier>>,
+    warn_before: chrono::Duration,
+}
+
+impl TokenHealthService {
+    pub fn new(
+        repository: ProjectRepository,
+        graph: Arc<dyn GraphClient>,
+        notifier: Option<Arc<dyn TelegramNotifier>>,
+        config: &FacebookConfig,
+    ) -> Self {
+        Self {
+            repository,
+            graph,
+            notifier,
+            warn_before: chrono::Duration::from_std(config.token_warning).unwrap_or(chrono::Duration::MAX),
+        }
+    }
+
+    pub async fn check_tokens(&self) -> Result<TokenCheckSummary, ApiError> {
+        let now = Utc::now();
+        let mut summary = TokenCheckSummary::default();
+        for project in self.repository.find_with_credentials().await? {
+            let Some(id) = project.id else { continue };
+            let updates = self.check_project(&project, now, &mut summary).await;
+            if !self.repository.update_token_health(&id, project.updated_at, &updates).await? {
+                info!(project_id = %id, "Project changed during the token check, it is checked again next pass");
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Checks every Facebook credential of `project` and returns what to store. A
+    /// credential the Graph API could not be asked about is left out.
+    pub async fn check_project(
+        &self,
+        project: &Project,
+        now: DateTime<Utc>,
+        summary: &mut TokenCheckSummary,
+    ) -> Vec<TokenHealthUpdate> {
+        let mut updates = Vec::new();
+        for (key, credential) in project.facebook_credentials() {
+            let (mut health, access_token) = match self.check_credential(credential, now).await {
+                Ok(checked) => checked,
+                Err(e) => {
+                    warn!(project_id = ?project.id, key, error = %e, "Failed to check Facebook access token");
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+            summary.checked += 1;
+            if access_token.is_some() {
+                summary.exchanged += 1;
+            }
+            if !health.valid {
+                summary.invalid += 1;
+            }
+
+            // One warning per token, and another should it become invalid after that
+            let previous = credential.token_health.as_ref();
+            let became_invalid = previous.is_some_and(|previous| previous.valid) && !health.valid;
+            if health.needs_attention(now, self.warn_before)
+                && (health.warned_at.is_none() || became_invalid)
+                && self.warn(project, key, &health).await
+            {
+                health.warned_at = Some(now);
+                summary.warned += 1;
+            }
+            updates.push(TokenHealthUpdate { key: key.clone(), health, access_token });
+        }
+        updates
+    }
+
+    async fn check_credential(
+        &self,
+        credential: &FacebookCredential,
+        now: DateTime<Utc>,
+    ) -> Result<(TokenHealth, Option<String>), String> {
+        let FacebookCredential { app_id, app_secret, access_token, .. } = credential;
+        let info = self.graph.debug_token(app_id, app_secret, access_token).await?;
+        let mut health = TokenHealth {
+            valid: info.valid,
+            expires_at: info.expires_at,
+            scopes: info.scopes,
+            error: info.error,
+            checked_at: now,
+            warned_at: credential.token_health.as_ref().and_then(|health| health.warned_at),
+        };
+
+        let short_lived = health.expires_at.is_some_and(|at| at - now < SHORT_LIVED_TOKEN_WINDOW);
+        if !health.valid || !short_lived {
+            return Ok((health, None));
+        }
+        match self.graph.exchange_token(app_id, app_secret, access_token).await {
+            Ok(exchanged) => {
+                health.expires_at = exchanged.expires_at;
+                health.warned_at = None;
+                Ok((health, Some(exchanged.access_token)))
+            }
+            Err(e) => {
+                // The short-lived token keeps working until it expires, and the warning covers it
+                warn!(error = %e, "Failed to exchange Facebook access token for a long-lived one");
+                Ok((health, None))
+            }
+        }
+    }
+
+    /// Tells the project's Telegram chat about the token, returning whether
+    /// the message went out.
+    async fn warn(&self, project: &Project, key: &str, health: &TokenHealth) -> bool {
+        let (Some(notifier), Some(chat_id)) = (&self.notifier, &project.telegram_chat_id) else {
+            return false;
+        };
+        let text = match (health.valid, health.expires_at) {
+            (true, Some(expires_at)) => format!(
+                "Facebook access token \"{}\" of project {} expires on {}. Replace it before then to keep ads attributing.",
+                key,
+                project.name,
+                expires_at.format("%Y-%m-%d %H:%M UTC"),
+            ),
+            _ => format!(
+                "Facebook access token \"{}\" of project {} is no longer valid ({}). Ads stop attributing until it is replaced.",
+                key,
+                project.name,
+                health.error.as_deref().unwrap_or("expired"),
+            ),
+        };
+        match notifier.send_message(chat_id, &text).await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!(project_id = ?project.id, key, error = %e, "Failed to send token warning to Telegram");
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use chrono::{DateTime, Duration, Utc};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    graph_client::{ExchangedToken, MockGraphClient, TokenInfo},
+    models::ad_platform::AdPlatformCredential,
+    models::project::{FacebookCredential, Project, ProjectStatus, TokenHealth},
+    repository::project_repository::ProjectRepository,
+    service::token_health_service::{TokenCheckSummary, TokenHealthService},
+    test_support::{test_facebook_config, unreachable_db, RecordingNotifier},
+};
+
+fn credential(access_token: &str, token_health: Option<TokenHealth>) -> FacebookCredential {
+    FacebookCredential {
+        app_id: "app".to_string(),
+        app_secret: "secret".to_string(),
+        access_token: access_token.to_string(),
+        ad_account_id: "act".to_string(),
+        account_suffix: "suffix".to_string(),
+        pixel_id: None,
+        link_url: None,
+        page_id: None,
+        watermark: None,
+        token_health,
+    }
+}
+
+fn project(credentials: Vec<(&str, FacebookCredential)>) -> Project {
+    Project {
+        id: None,
+        org_id: None,
+        name: "Shop".to_string(),
+        telegram_chat_id: Some("-100".to_string()),
+        ad_credentials: credentials
+            .into_iter()
+            .map(|(key, c)| (key.to_string(), AdPlatformCredential::Facebook(c)))
+            .collect::<HashMap<_, _>>(),
+        package: None,
+        expires_at: None,
+        status: ProjectStatus::Active,
+        is_active: true,
+        status_history: vec![],
+        is_logging: false,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn valid(expires_at: Option<DateTime<Utc>>) -> TokenInfo {
+    TokenInfo { valid: true, expires_at, scopes: vec!["ads_read".to_string()], error: None }
+}
+
+async fn service(graph: MockGraphClient, notifier: &RecordingNotifier) -> TokenHealthService {
+    TokenHealthService::new(
+        ProjectRepository::new(unreachable_db().await),
+        Arc::new(graph),
+        Some(Arc::new(notifier.clone())),
+        &test_facebook_config(),
+    )
+}
+
+#[tokio::test]
+async fn test_short_lived_tokens_are_exchanged() {
+    let now = Utc::now();
+    let long_expiry = Some(now + Duration::days(60));
+    let graph = MockGraphClient::new()
+        .with_token("short", valid(Some(now + Duration::hours(2))))
+        .with_exchange("short", ExchangedToken { access_token: "long".to_string(), expires_at: long_expiry });
+    let notifier = RecordingNotifier::default();
+    let service = service(graph, &notifier).await;
+    let mut summary = TokenCheckSummary::default();
+
+    let updates = service.check_project(&project(vec![("main", credential("short", None))]), now, &mut summary).await;
+
+    assert_eq!(updates[0].access_token.as_deref(), Some("long"));
+    assert_eq!(updates[0].health.expires_at, long_expiry);
+    assert_eq!(updates[0].health.scopes, vec!["ads_read"]);
+    assert_eq!(summary, TokenCheckSummary { checked: 1, exchanged: 1, ..Default::default() });
+    assert!(notifier.sent.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_chats_are_warned_once_before_expiry() {
+    let now = Utc::now();
+    let graph = MockGraphClient::new()
+        .with_token("expiring", valid(Some(now + Duration::days(3))))
+        .with_token("fresh", valid(Some(now + Duration::days(50))))
+        .with_token("forever", valid(None));
+    let notifier = RecordingNotifier::default();
+    let service = service(graph, &notifier).await;
+    let mut summary = TokenCheckSummary::default();
+
+    let checked = project(vec![
+        ("a", credential("expiring", None)),
+        ("b", credential("fresh", None)),
+        ("c", credential("forever", None)),
+    ]);
+    let updates = service.check_project(&checked, now, &mut summary).await;
+
+    assert_eq!(summary.warned, 1);
+    let sent = notifier.sent.lock().unwrap().clone();
+    assert_eq!(sent[0].0, "-100");
+    assert!(sent[0].1.contains("\"a\" of project Shop expires on"));
+    let warned = updates.iter().find(|update| update.key == "a").unwrap();
+    assert_eq!(warned.health.warned_at, Some(now));
+
+    // The stored warning keeps the next pass quiet
+    let checked = project(vec![("a", credential("expiring", Some(warned.health.clone())))]);
+    service.check_project(&checked, now + Duration::hours(6), &mut summary).await;
+    assert_eq!(notifier.sent.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_invalid_tokens_are_reported() {
+    let now = Utc::now();
+    let notifier = RecordingNotifier::default();
+    let service = service(MockGraphClient::new(), &notifier).await;
+    let mut summary = TokenCheckSummary::default();
+    let warned_earlier = TokenHealth {
+        valid: true,
+        expires_at: Some(now),
+        scopes: vec![],
+        error: None,
+        checked_at: now - Duration::hours(6),
+        warned_at: Some(now - Duration::days(3)),
+    };
+
+    let updates = service
+        .check_project(&project(vec![("main", credential("revoked", Some(warned_earlier)))]), now, &mut summary)
+        .await;
+
+    assert!(!updates[0].health.valid);
+    assert_eq!(updates[0].health.error.as_deref(), Some("Invalid OAuth access token"));
+    assert_eq!(updates[0].access_token, None);
+    assert_eq!((summary.invalid, summary.warned), (1, 1));
+    assert!(notifier.sent.lock().unwrap()[0].1.contains("is no longer valid (Invalid OAuth access token)"));
+}
@@ -0,0 +1,371 @@
+use axum::{async_trait, body::Bytes};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use hmac::{Hmac, Mac};
+use mongodb::bson::oid::ObjectId;
+use rand::{rngs::OsRng, RngCore};
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use std::{
+    collections::{HashMap, HashSet},
+    pin::pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tracing::{info, warn};
+
+use crate::{
+    config::WebhooksConfig,
+    error::ApiError,
+    http_client::HttpClient,
+    metrics,
+    models::{
+        event::OutboxEntry,
+        project::Project,
+        webhook::{
+            CreateWebhook, CreatedWebhook, DeliveryStatus, WebhookDelivery, WebhookEvent, WebhookSubscription,
+            WebhookSummary,
+        },
+    },
+    repository::{
+        project_repository::ProjectRepository, webhook_delivery_repository::WebhookDeliveryRepository,
+        webhook_repository::WebhookRepository,
+    },
+    service::event_bus::EventSubscriber,
+};
+
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+pub const EVENT_HEADER: &str = "x-webhook-event";
+pub const DELIVERY_ID_HEADER: &str = "x-webhook-id";
+
+const SECRET_PREFIX: &str = "whsec_";
+const SECRET_BYTES: usize = 32;
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+// A claimed delivery is retried by another pass if its sender dies
+const DELIVERY_LEASE: Duration = Duration::from_secs(60);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+const DELIVERIES_PER_PASS: usize = 100;
+const DELIVERY_CONCURRENCY: usize = 8;
+
+/// `sha256=<hex>` HMAC of `{timestamp}.{payload}` keyed with the subscription secret.
+pub fn sign(secret: &str, timestamp: i64, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Delay before retrying after `attempts` failed attempts, doubling each time.
+pub fn retry_delay(base: Duration, attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    base.saturating_mul(factor).min(MAX_RETRY_DELAY)
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{}{}", SECRET_PREFIX, URL_SAFE_NO_PAD.encode(bytes))
+}
+
+// A failing subscription is not sent to again before `until`
+#[derive(Debug, Clone, Copy)]
+struct EndpointBackoff {
+    failures: u32,
+    until: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct WebhookService {
+    repository: WebhookRepository,
+    delivery_repository: WebhookDeliveryRepository,
+    project_repository: ProjectRepository,
+    client: HttpClient,
+    max_attempts: u32,
+    retry_base: Duration,
+    backoff: Arc<Mutex<HashMap<ObjectId, EndpointBackoff>>>,
+}
+
+impl WebhookService {
+    pub fn new(
+        repository: WebhookRepository,
+        delivery_repository: WebhookDeliveryRepository,
+        project_repository: ProjectRepository,
+        config: &WebhooksConfig,
+    ) -> Self {
+        Self {
+            repository,
+            delivery_repository,
+            project_repository,
+            client: HttpClient::new(SEND_TIMEOUT, config.allow_private_targets),
+            max_attempts: config.max_attempts,
+            retry_base: config.retry_base,
+            backoff: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn create_webhook(&self, org_id: &ObjectId, request: CreateWebhook) -> Result<CreatedWebhook, ApiError> {
+        HttpClient::validate_url(&request.url).map_err(ApiError::BadRequest)?;
+        let mut events = request.events;
+        events.sort_by_key(|event| event.as_str());
+        events.dedup();
+        if events.is_empty() {
+            return Err(ApiError::BadRequest("Webhook needs at least one event".to_string()));
+        }
+        if let Some(project_id) = &request.project_id {
+            // Only the organization's own projects can be subscribed to
+            self.project_repository.get_by_id(org_id, project_id).await.map_err(|e| match e {
+                ApiError::NotFound => ApiError::BadRequest("Unknown project".to_string()),
+                e => e,
+            })?;
+        }
+
+        let secret = generate_secret();
+        let webhook = WebhookSubscription {
+            id: None,
+            org_id: *org_id,
+            project_id: request.project_id,
+            url: request.url,
+            events,
+            secret: secret.clone(),
+            created_at: Utc::now(),
+        };
+        let webhook = self.repository.create(webhook).await?;
+        Ok(CreatedWebhook {
+            secret,
+            webhook: webhook.into(),
+        })
+    }
+
+    pub async fn get_webhook(&self, org_id: &ObjectId, id: &ObjectId) -> Result<WebhookSummary, ApiError> {
+        Ok(self.repository.get_by_id(org_id, id).await?.into())
+    }
+
+    pub async fn get_all_webhooks(&self, org_id: &ObjectId) -> Result<Vec<WebhookSummary>, ApiError> {
+        let webhooks = self.repository.get_all(org_id).await?;
+        Ok(webhooks.into_iter().map(WebhookSummary::from).collect())
+    }
+
+    pub async fn delete_webhook(&self, org_id: &ObjectId, id: &ObjectId) -> Result<bool, ApiError> {
+        let deleted = self.repository.delete(org_id, id).await?;
+        if deleted {
+            self.delivery_repository.delete_pending_for_webhook(id).await?;
+        }
+        Ok(deleted)
+    }
+
+    pub async fn get_deliveries(&self, org_id: &ObjectId, webhook_id: &ObjectId) -> Result<Vec<WebhookDelivery>, ApiError> {
+        self.repository.get_by_id(org_id, webhook_id).await?;
+        self.delivery_repository.get_recent(org_id, webhook_id).await
+    }
+
+    pub async fn redeliver(&self, org_id: &ObjectId, webhook_id: &ObjectId, id: &ObjectId) -> Result<(), ApiError> {
+        if !self.delivery_repository.redeliver(org_id, webhook_id, id).await? {
+            return Err(ApiError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Queues `event` for every matching subscription of the organization.
+    /// Dispatching the same `event_id` again does not queue duplicates.
+    pub async fn dispatch<T: Serialize>(
+        &self,
+        event_id: &ObjectId,
+        org_id: &ObjectId,
+        project_id: Option<&ObjectId>,
+        event: WebhookEvent,
+        data: &T,
+    ) -> Result<usize, ApiError> {
+        let webhooks = self.repository.find_matching(org_id, project_id, event).await?;
+        let data = serde_json::to_value(data)
+            .map_err(|e| ApiError::InternalServerError(format!("Failed to serialize webhook data: {}", e)))?;
+        let now = Utc::now();
+
+        let deliveries: Vec<WebhookDelivery> = webhooks
+            .into_iter()
+            .filter_map(|webhook| {
+                let id = ObjectId::new();
+                let payload = json!({
+                    "id": id.to_hex(),
+                    "event": event,
+                    "org_id": org_id.to_hex(),
+                    "created_at": now,
+                    "data": data,
+                });
+                Some(WebhookDelivery {
+                    id: Some(id),
+                    org_id: *org_id,
+                    webhook_id: webhook.id?,
+                    event_id: Some(*event_id),
+                    event,
+                    payload: payload.to_string(),
+                    status: DeliveryStatus::Pending,
+                    attempts: 0,
+                    last_error: None,
+                    next_attempt_at: now,
+                    created_at: now,
+                    delivered_at: None,
+                })
+            })
+            .collect();
+        self.delivery_repository.create_many(&deliveries).await?;
+        Ok(deliveries.len())
+    }
+
+    /// Sends due deliveries, `DELIVERY_CONCURRENCY` at a time and one at a
+    /// time per subscription, returning how many were attempted.
+    pub async fn deliver_due(&self) -> Result<usize, ApiError> {
+        let mut attempted = 0;
+        while attempted < DELIVERIES_PER_PASS {
+            let sent = self.deliver_claimable(DELIVERIES_PER_PASS - attempted).await?;
+            if sent == 0 {
+                break;
+            }
+            attempted += sent;
+        }
+        Ok(attempted)
+    }
+
+    // Sends up to `limit` deliveries while any can be claimed. Deliveries that
+    // become due meanwhile are left to the next call.
+    async fn deliver_claimable(&self, limit: usize) -> Result<usize, ApiError> {
+        let in_flight = Arc::new(Mutex::new(HashSet::new()));
+        let claims = stream::unfold((self.clone(), in_flight.clone()), |(service, in_flight)| async move {
+            let claim = service.claim_next(&in_flight).await.transpose()?;
+            Some((claim, (service, in_flight)))
+        });
+        let mut sends = pin!(claims
+            .take(limit)
+            .map(|claim| {
+                let (service, in_flight) = (self.clone(), in_flight.clone());
+                async move {
+                    let delivery = claim?;
+                    let webhook_id = delivery.webhook_id;
+                    let result = service.deliver(delivery).await;
+                    in_flight.lock().unwrap().remove(&webhook_id);
+                    result
+                }
+            })
+            .buffer_unordered(DELIVERY_CONCURRENCY));
+
+        let mut attempted = 0;
+        while let Some(result) = sends.next().await {
+            result?;
+            attempted += 1;
+        }
+        Ok(attempted)
+    }
+
+    // Skips subscriptions with a delivery in flight or backing off
+    async fn claim_next(&self, in_flight: &Mutex<HashSet<ObjectId>>) -> Result<Option<WebhookDelivery>, ApiError> {
+        let now = Utc::now();
+        let mut skip: Vec<ObjectId> = in_flight.lock().unwrap().iter().copied().collect();
+        skip.extend(
+            self.backoff
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, backoff)| backoff.until > now)
+                .map(|(webhook_id, _)| *webhook_id),
+        );
+        let delivery = self.delivery_repository.claim_due(now, now + DELIVERY_LEASE, &skip).await?;
+        if let Some(delivery) = &delivery {
+            in_flight.lock().unwrap().insert(delivery.webhook_id);
+        }
+        Ok(delivery)
+    }
+
+    async fn deliver(&self, delivery: WebhookDelivery) -> Result<(), ApiError> {
+        let id = delivery.id.ok_or_else(|| ApiError::InternalServerError("Delivery has no id".into()))?;
+        let attempts = delivery.attempts + 1;
+
+        let result = match self.repository.get_by_id(&delivery.org_id, &delivery.webhook_id).await {
+            Ok(webhook) => self.send(&webhook, &id, &delivery).await,
+            Err(ApiError::NotFound) => {
+                self.backoff.lock().unwrap().remove(&delivery.webhook_id);
+                self.delivery_repository.mark_failed(&id, attempts, "Webhook was deleted", None).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        self.record_outcome(&delivery.webhook_id, result.is_ok());
+
+        match result {
+            Ok(()) => {
+                metrics::global().record_webhook_delivery("delivered");
+                self.delivery_repository.mark_delivered(&id, attempts).await
+            }
+            Err(error) if attempts >= self.max_attempts => {
+                metrics::global().record_webhook_delivery("dead");
+                warn!(delivery_id = %id, attempts, %error, "Webhook delivery dead-lettered");
+                self.delivery_repository.mark_failed(&id, attempts, &error, None).await
+            }
+            Err(error) => {
+                metrics::global().record_webhook_delivery("retry");
+                let next_attempt_at = Utc::now() + retry_delay(self.retry_base, attempts);
+                info!(delivery_id = %id, attempts, %error, "Webhook delivery failed, will retry");
+                self.delivery_repository
+                    .mark_failed(&id, attempts, &error, Some(next_attempt_at))
+                    .await
+            }
+        }
+    }
+
+    // Each failure in a row doubles how long the subscription is skipped
+    fn record_outcome(&self, webhook_id: &ObjectId, succeeded: bool) {
+        let mut backoff = self.backoff.lock().unwrap();
+        if succeeded {
+            backoff.remove(webhook_id);
+            return;
+        }
+        let failures = backoff.get(webhook_id).map_or(0, |backoff| backoff.failures) + 1;
+        let until = Utc::now() + retry_delay(self.retry_base, failures);
+        backoff.insert(*webhook_id, EndpointBackoff { failures, until });
+    }
+
+    /// Re-encrypts signing secrets under the active key, returning how many
+    /// webhooks were or, with `dry_run`, would be affected.
+    pub async fn rotate_encryption_key(&self, dry_run: bool) -> Result<u64, ApiError> {
+        self.repository.rotate_secrets(dry_run).await
+    }
+
+    async fn send(&self, webhook: &WebhookSubscription, id: &ObjectId, delivery: &WebhookDelivery) -> Result<(), String> {
+        let timestamp = Utc::now().timestamp();
+        let headers = [
+            (DELIVERY_ID_HEADER, id.to_hex()),
+            (EVENT_HEADER, delivery.event.to_string()),
+            (TIMESTAMP_HEADER, timestamp.to_string()),
+            (SIGNATURE_HEADER, sign(&webhook.secret, timestamp, &delivery.payload)),
+        ];
+        let status = self
+            .client
+            .post_json(&webhook.url, &headers, Bytes::from(delivery.payload.clone()))
+            .await?;
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(format!("target responded with {}", status))
+        }
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for WebhookService {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    async fn handle(&self, entry: &OutboxEntry) -> Result<(), ApiError> {
+        let id = entry.id.ok_or_else(|| ApiError::InternalServerError("Outbox entry has no id".into()))?;
+        let project_id = entry.event.project_id();
+        // Membership changes are not exposed as webhook events
+        let Some((event, data)) = entry.event.published::<Project>() else {
+            return Ok(());
+        };
+        self.dispatch(&id, &entry.org_id, project_id.as_ref(), event, &data).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use mongodb::bson::{oid::ObjectId, Document};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    config::{EncryptionConfig, EncryptionKey, WebhooksConfig},
+    crypto::SecretCipher,
+    error::ApiError,
+    models::{
+        project::{Project, ProjectStatus},
+        webhook::{CreateWebhook, DeliveryStatus, WebhookEvent},
+    },
+    repository::{
+        outbox_repository::OutboxRepository, project_repository::ProjectRepository,
+        webhook_delivery_repository::WebhookDeliveryRepository, webhook_repository::WebhookRepository,
+    },
+    service::{
+        event_bus::EventBus,
+        project_service::ProjectService,
+        webhook_service::{retry_delay, sign, WebhookService, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+    },
+    test_support::{test_db, test_webhook_service},
+};
+
+#[derive(Clone, Default)]
+struct Receiver {
+    status: Arc<AtomicU16>,
+    received: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+}
+
+async fn spawn_receiver() -> (SocketAddr, Receiver) {
+    let receiver = Receiver::default();
+    receiver.status.store(200, Ordering::SeqCst);
+    let state = receiver.clone();
+    let app = Router::new().route(
+        "/hook",
+        post(move |headers: HeaderMap, body: Bytes| async move {
+            state.received.lock().unwrap().push((headers, body));
+            StatusCode::from_u16(state.status.load(Ordering::SeqCst)).unwrap()
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (addr, receiver)
+}
+
+fn test_cipher() -> SecretCipher {
+    SecretCipher::new(&EncryptionConfig {
+        keys: vec![EncryptionKey { id: "k1".to_string(), key: [7; 32] }],
+        active_key_id: Some("k1".to_string()),
+    })
+}
+
+fn test_project(name: &str) -> Project {
+    Project {
+        id: None,
+        org_id: None,
+        name: name.to_string(),
+        telegram_chat_id: None,
+        ad_credentials: HashMap::new(),
+        package: None,
+        expires_at: None,
+        status: ProjectStatus::Active,
+        is_active: true,
+        status_history: vec![],
+        is_logging: false,
+        created_at: chrono::Utc::now(),
+        updated_at: chrono::Utc::now(),
+    }
+}
+
+#[test]
+fn test_sign_is_hmac_of_timestamp_and_payload() {
+    let signature = sign("whsec_test", 1700000000, r#"{"id":"1"}"#);
+    assert!(signature.starts_with("sha256="));
+    assert_eq!(signature.len(), "sha256=".len() + 64);
+    assert_eq!(signature, sign("whsec_test", 1700000000, r#"{"id":"1"}"#));
+    assert_ne!(signature, sign("whsec_other", 1700000000, r#"{"id":"1"}"#));
+    assert_ne!(signature, sign("whsec_test", 1700000001, r#"{"id":"1"}"#));
+}
+
+#[test]
+fn test_retry_delay_doubles_up_to_an_hour() {
+    let base = Duration::from_secs(10);
+    assert_eq!(retry_delay(base, 1), Duration::from_secs(10));
+    assert_eq!(retry_delay(base, 2), Duration::from_secs(20));
+    assert_eq!(retry_delay(base, 4), Duration::from_secs(80));
+    assert_eq!(retry_delay(base, 20), Duration::from_secs(3600));
+    assert_eq!(retry_delay(base, 200), Duration::from_secs(3600));
+}
+
+#[tokio::test]
+async fn test_deliveries_are_signed_retried_and_redelivered() {
+    let db = test_db("_webhooks").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let webhooks = test_webhook_service(db.clone(), 2);
+    let outbox = OutboxRepository::new(db.clone());
+    let projects = ProjectService::new(ProjectRepository::new(db.clone()), outbox.clone());
+    let bus = EventBus::new(outbox).subscribe(Arc::new(webhooks.clone()));
+    let (addr, receiver) = spawn_receiver().await;
+    let org_id = ObjectId::new();
+
+    let unknown_project = webhooks
+        .create_webhook(
+            &org_id,
+            CreateWebhook {
+                url: format!("http://{}/hook", addr),
+                events: vec![WebhookEvent::ProjectCreated],
+                project_id: Some(ObjectId::new()),
+            },
+        )
+        .await;
+    assert!(matches!(unknown_project, Err(ApiError::BadRequest(_))));
+
+    let created = webhooks
+        .create_webhook(
+            &org_id,
+            CreateWebhook {
+                url: format!("http://{}/hook", addr),
+                events: vec![WebhookEvent::ProjectCreated],
+                project_id: None,
+            },
+        )
+        .await
+        .expect("Failed to create webhook");
+    let webhook_id = ObjectId::parse_str(created.webhook.id.as_ref().unwrap()).unwrap();
+
+    // Events of other organizations or not subscribed to are not queued
+    projects.create_project(&ObjectId::new(), test_project("Other")).await.unwrap();
+    let project = projects.create_project(&org_id, test_project("First")).await.unwrap();
+    projects.delete_project(&org_id, &project.id.unwrap()).await.unwrap();
+
+    assert_eq!(bus.dispatch_due().await.unwrap(), 3);
+    assert_eq!(webhooks.deliver_due().await.unwrap(), 1);
+    {
+        let received = receiver.received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (headers, body) = &received[0];
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        let body = std::str::from_utf8(body).unwrap();
+        assert_eq!(headers[SIGNATURE_HEADER], sign(&created.secret, timestamp, body));
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["event"], "project.created");
+        assert_eq!(payload["data"]["name"], "First");
+    }
+
+    // Failing targets are retried until the delivery is dead-lettered
+    receiver.status.store(500, Ordering::SeqCst);
+    projects.create_project(&org_id, test_project("Second")).await.unwrap();
+    assert_eq!(bus.dispatch_due().await.unwrap(), 1);
+    assert_eq!(webhooks.deliver_due().await.unwrap(), 2);
+    let deliveries = webhooks.get_deliveries(&org_id, &webhook_id).await.unwrap();
+    let dead = deliveries
+        .iter()
+        .find(|delivery| delivery.status == DeliveryStatus::Dead)
+        .expect("Delivery was not dead-lettered");
+    assert_eq!(dead.attempts, 2);
+    assert!(dead.last_error.as_deref().unwrap().contains("500"));
+    assert_eq!(webhooks.deliver_due().await.unwrap(), 0);
+
+    receiver.status.store(200, Ordering::SeqCst);
+    webhooks.redeliver(&org_id, &webhook_id, &dead.id.unwrap()).await.unwrap();
+    assert!(matches!(
+        webhooks.redeliver(&ObjectId::new(), &webhook_id, &dead.id.unwrap()).await,
+        Err(ApiError::NotFound)
+    ));
+    assert_eq!(webhooks.deliver_due().await.unwrap(), 1);
+    let deliveries = webhooks.get_deliveries(&org_id, &webhook_id).await.unwrap();
+    assert!(deliveries.iter().all(|delivery| delivery.status == DeliveryStatus::Delivered));
+    assert_eq!(receiver.received.lock().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn test_failing_endpoints_back_off_without_holding_up_others() {
+    let db = test_db("_webhooks_backoff").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let config = WebhooksConfig {
+        max_attempts: 5,
+        retry_base: Duration::from_secs(60),
+        allow_private_targets: true,
+    };
+    let cipher = test_cipher();
+    let webhooks = WebhookService::new(
+        WebhookRepository::new(db.clone()).with_cipher(cipher),
+        WebhookDeliveryRepository::new(db.clone()),
+        ProjectRepository::new(db.clone()),
+        &config,
+    );
+    let (failing_addr, failing) = spawn_receiver().await;
+    failing.status.store(503, Ordering::SeqCst);
+    let (healthy_addr, healthy) = spawn_receiver().await;
+    let org_id = ObjectId::new();
+    let mut secrets = Vec::new();
+    for addr in [failing_addr, healthy_addr] {
+        let created = webhooks
+            .create_webhook(
+                &org_id,
+                CreateWebhook {
+                    url: format!("http://{}/hook", addr),
+                    events: vec![WebhookEvent::ProjectCreated],
+                    project_id: None,
+                },
+            )
+            .await
+            .unwrap();
+        secrets.push(created.secret);
+    }
+
+    // Signing secrets are stored encrypted and read back as issued
+    let stored = db.collection::<Document>("webhooks").find_one(None, None).await.unwrap().unwrap();
+    assert!(stored.get_str("secret").unwrap().starts_with("enc:v1:"));
+    let listed = WebhookRepository::new(db.clone()).with_cipher(test_cipher()).get_all(&org_id).await.unwrap();
+    assert!(listed.iter().all(|webhook| secrets.contains(&webhook.secret)));
+
+    for _ in 0..3 {
+        webhooks.dispatch(&ObjectId::new(), &org_id, None, WebhookEvent::ProjectCreated, &json!({})).await.unwrap();
+    }
+    // The failing endpoint gets one attempt, then backs off while the other drains
+    assert_eq!(webhooks.deliver_due().await.unwrap(), 4);
+    assert_eq!(failing.received.lock().unwrap().len(), 1);
+    assert_eq!(healthy.received.lock().unwrap().len(), 3);
+    assert_eq!(webhooks.deliver_due().await.unwrap(), 0);
+}
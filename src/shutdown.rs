@@ -0,0 +1,75 @@
+use axum::Router;
+use tracing::{info, warn};
+use std::{future::IntoFuture, io, net::SocketAddr, time::Duration};
+use tokio::{net::TcpListener, time::Instant};
+use tokio_util::sync::CancellationToken;
+
+/// Resolves when the process receives SIGINT (Ctrl+C) or SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!(error = %e, "Failed to install Ctrl+C handler");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to install SIGTERM handler");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+/// Resolves once `token` is cancelled, with the deadline `timeout` later that
+/// draining requests, stopping workers and closing the database share.
+pub async fn shutdown_deadline(token: CancellationToken, timeout: Duration) -> Instant {
+    token.cancelled().await;
+    Instant::now() + timeout
+}
+
+/// Serves `app` until `token` is cancelled, then stops accepting connections and
+/// waits up to `drain_timeout` for in-flight requests to finish.
+pub async fn serve_with_shutdown(
+    listener: TcpListener,
+    app: Router,
+    token: CancellationToken,
+    drain_timeout: Duration,
+) -> io::Result<()> {
+    let graceful = token.clone();
+    // Peer addresses are needed by the rate limiter's IP fallback
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move { graceful.cancelled().await })
+        .into_future();
+
+    let drain_deadline = async {
+        token.cancelled().await;
+        info!(?drain_timeout, "Shutting down, draining in-flight requests");
+        tokio::time::sleep(drain_timeout).await;
+    };
+
+    tokio::select! {
+        result = server => {
+            info!("All in-flight requests completed");
+            result
+        }
+        _ = drain_deadline => {
+            warn!("Drain timeout elapsed, dropping remaining connections");
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use axum::{routing::get, Router};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::oneshot,
+    time::Instant,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    shutdown::{serve_with_shutdown, shutdown_deadline},
+    worker::Workers,
+};
+
+async fn send_request(addr: SocketAddr, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).await.expect("Failed to connect");
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path);
+    stream.write_all(request.as_bytes()).await.expect("Failed to write request");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.expect("Failed to read response");
+    response
+}
+
+fn slow_router(delay: Duration, started: oneshot::Sender<()>) -> Router {
+    let started = Arc::new(Mutex::new(Some(started)));
+    Router::new().route(
+        "/slow",
+        get(move || {
+            if let Some(tx) = started.lock().unwrap().take() {
+                let _ = tx.send(());
+            }
+            async move {
+                tokio::time::sleep(delay).await;
+                "done"
+            }
+        }),
+    )
+}
+
+#[tokio::test]
+async fn test_in_flight_request_completes_after_shutdown_signal() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (started_tx, started_rx) = oneshot::channel();
+    let token = CancellationToken::new();
+
+    let server = tokio::spawn(serve_with_shutdown(
+        listener,
+        slow_router(Duration::from_millis(300), started_tx),
+        token.clone(),
+        Duration::from_secs(5),
+    ));
+
+    let request = tokio::spawn(send_request(addr, "/slow"));
+    started_rx.await.expect("Handler never started");
+    token.cancel();
+
+    let response = request.await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.ends_with("done"));
+
+    tokio::time::timeout(Duration::from_secs(2), server)
+        .await
+        .expect("Server did not stop")
+        .unwrap()
+        .expect("Server returned an error");
+
+    // New connections are refused once the server has stopped
+    assert!(TcpStream::connect(addr).await.is_err());
+}
+
+#[tokio::test]
+async fn test_drain_timeout_bounds_shutdown() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (started_tx, started_rx) = oneshot::channel();
+    let token = CancellationToken::new();
+
+    let server = tokio::spawn(serve_with_shutdown(
+        listener,
+        slow_router(Duration::from_secs(60), started_tx),
+        token.clone(),
+        Duration::from_millis(200),
+    ));
+
+    let _request = tokio::spawn(send_request(addr, "/slow"));
+    started_rx.await.expect("Handler never started");
+    token.cancel();
+
+    tokio::time::timeout(Duration::from_secs(2), server)
+        .await
+        .expect("Drain timeout was not enforced")
+        .unwrap()
+        .expect("Server returned an error");
+}
+
+#[tokio::test]
+async fn test_workers_stop_on_cancellation() {
+    let token = CancellationToken::new();
+    let workers = Workers::new(token.clone());
+    let (stopped_tx, stopped_rx) = oneshot::channel();
+
+    workers.spawn("test_worker", move |token| async move {
+        token.cancelled().await;
+        let _ = stopped_tx.send(());
+    });
+
+    assert!(workers.shutdown(Instant::now() + Duration::from_secs(1)).await);
+    assert!(token.is_cancelled());
+    stopped_rx.await.expect("Worker did not observe cancellation");
+}
+
+#[tokio::test]
+async fn test_workers_shutdown_reports_stuck_worker() {
+    let workers = Workers::new(CancellationToken::new());
+    workers.spawn("stuck_worker", |_token| async {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    });
+
+    assert!(!workers.shutdown(Instant::now() + Duration::from_millis(50)).await);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_shutdown_deadline_starts_at_cancellation() {
+    let token = CancellationToken::new();
+    let deadline = tokio::spawn(shutdown_deadline(token.clone(), Duration::from_secs(30)));
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    let cancelled_at = Instant::now();
+    token.cancel();
+
+    assert_eq!(deadline.await.unwrap(), cancelled_at + Duration::from_secs(30));
+}
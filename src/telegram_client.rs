@@ -0,0 +1,49 @@
+use axum::{async_trait, body::Bytes};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::{config::Secret, http_client::HttpClient};
+
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+const TELEGRAM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends messages to project Telegram chats.
+#[async_trait]
+pub trait TelegramNotifier: Send + Sync {
+    async fn send_message(&self, chat_id: &str, text: &str) -> Result<(), String>;
+}
+
+/// `TelegramNotifier` using the Bot API.
+#[derive(Debug, Clone)]
+pub struct HttpTelegramClient {
+    http: HttpClient,
+    base_url: String,
+    bot_token: Secret,
+}
+
+impl HttpTelegramClient {
+    pub fn new(bot_token: Secret) -> Self {
+        Self::with_base_url(bot_token, TELEGRAM_API_URL)
+    }
+
+    pub fn with_base_url(bot_token: Secret, base_url: &str) -> Self {
+        Self {
+            http: HttpClient::new(TELEGRAM_TIMEOUT, true),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            bot_token,
+        }
+    }
+}
+
+#[async_trait]
+impl TelegramNotifier for HttpTelegramClient {
+    async fn send_message(&self, chat_id: &str, text: &str) -> Result<(), String> {
+        let url = format!("{}/bot{}/sendMessage", self.base_url, self.bot_token.expose());
+        let body = json!({ "chat_id": chat_id, "text": text }).to_string();
+        let status = self.http.post_json(&url, &[], Bytes::from(body)).await?;
+        if !status.is_success() {
+            return Err(format!("Telegram responded with {}", status));
+        }
+        Ok(())
+    }
+}
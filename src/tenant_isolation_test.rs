@@ -0,0 +1,303 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    app,
+    error::ApiError,
+    models::{account::Account, project::{Project, ProjectStatus}},
+    repository::{
+        account_repository::AccountRepository, project_repository::ProjectRepository, transaction::Transaction,
+    },
+    test_support::{test_db, test_state, BOOTSTRAP_KEY},
+};
+
+async fn send(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    key: &str,
+    org_id: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri).header("x-api-key", key);
+    if let Some(org_id) = org_id {
+        request = request.header("x-org-id", org_id);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, json)
+}
+
+fn oid(value: &Value) -> String {
+    value["_id"]["$oid"].as_str().unwrap().to_string()
+}
+
+fn project(org_id: ObjectId) -> Project {
+    Project {
+        id: None,
+        org_id: Some(org_id),
+        name: "Tenant Project".to_string(),
+        telegram_chat_id: None,
+        ad_credentials: HashMap::new(),
+        package: None,
+        expires_at: None,
+        status: ProjectStatus::Active,
+        is_active: true,
+        status_history: vec![],
+        is_logging: false,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn account(org_id: ObjectId) -> Account {
+    Account {
+        id: None,
+        org_id: Some(org_id),
+        wallet_address: "0xabc".to_string(),
+        email: "shared@example.com".to_string(),
+        account_name: "Tenant Account".to_string(),
+        project_ids: vec![],
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn test_repositories_never_cross_tenants() {
+    let db = test_db("_tenants_repo").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let projects = ProjectRepository::new(db.clone());
+    let accounts = AccountRepository::new(db.clone());
+    accounts.ensure_indexes().await.expect("Failed to ensure indexes");
+
+    let org_a = ObjectId::new();
+    let org_b = ObjectId::new();
+    let project_a = projects.create(&mut Transaction::none(), project(org_a)).await.unwrap();
+    let project_id = project_a.id.unwrap();
+
+    assert!(matches!(projects.get_by_id(&org_b, &project_id).await, Err(ApiError::NotFound)));
+    assert!(matches!(projects.update(&mut Transaction::none(), &org_b, &project_id, project(org_b)).await, Err(ApiError::NotFound)));
+    assert!(!projects.delete(&mut Transaction::none(), &org_b, &project_id).await.unwrap());
+    assert!(projects.get_all(&org_b).await.unwrap().is_empty());
+    assert_eq!(projects.get_all(&org_a).await.unwrap().len(), 1);
+    assert_eq!(projects.get_by_id(&org_a, &project_id).await.unwrap().name, "Tenant Project");
+
+    // The same email may exist once per organization
+    let account_a = accounts.create(&mut Transaction::none(), account(org_a)).await.unwrap();
+    accounts.create(&mut Transaction::none(), account(org_b)).await.unwrap();
+    assert!(accounts.create(&mut Transaction::none(), account(org_a)).await.is_err());
+
+    let account_id = account_a.id.unwrap();
+    assert!(matches!(accounts.get_by_id(&org_b, &account_id).await, Err(ApiError::NotFound)));
+    assert!(matches!(accounts.update(&mut Transaction::none(), &org_b, &account_id, account(org_b)).await, Err(ApiError::NotFound)));
+    assert!(!accounts.delete(&mut Transaction::none(), &org_b, &account_id).await.unwrap());
+    assert_eq!(accounts.get_all(&org_a).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_api_cannot_reach_other_tenants() {
+    let db = test_db("_tenants_http").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let app = app::router(test_state(db), CorsLayer::permissive());
+
+    let mut orgs = Vec::new();
+    let mut keys = Vec::new();
+    for name in ["Alpha", "Beta"] {
+        let (status, org) = send(&app, "POST", "/organizations", BOOTSTRAP_KEY, None, Some(json!({ "name": name }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let org_id = oid(&org);
+        let (status, created) = send(
+            &app,
+            "POST",
+            "/api-keys",
+            BOOTSTRAP_KEY,
+            None,
+            Some(json!({
+                "name": name,
+                "scopes": ["projects:read", "projects:write", "accounts:admin", "keys:admin"],
+                "org_id": org_id,
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        keys.push(created["key"].as_str().unwrap().to_string());
+        orgs.push(org_id);
+    }
+    let (alpha, beta) = (&keys[0], &keys[1]);
+
+    let (status, created) = send(
+        &app,
+        "POST",
+        "/projects",
+        alpha,
+        None,
+        Some(json!({
+            "name": "Alpha Project",
+            "facebook_credentials": {},
+            "is_active": true,
+            "is_logging": false,
+            "created_at": { "$date": { "$numberLong": "0" } },
+            "updated_at": { "$date": { "$numberLong": "0" } },
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+    let project_uri = format!("/projects/{}", oid(&created));
+
+    // Beta cannot read, change, delete or list Alpha's project
+    let (status, _) = send(&app, "GET", &project_uri, beta, None, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, "PUT", &project_uri, beta, None, Some(created.clone())).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, deleted) = send(&app, "DELETE", &project_uri, beta, None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(deleted, json!(false));
+    let (_, listed) = send(&app, "GET", "/projects", beta, None, None).await;
+    assert_eq!(listed, json!([]));
+
+    // Nor link it to one of its accounts
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/accounts",
+        beta,
+        None,
+        Some(json!({
+            "wallet_address": "0xabc",
+            "email": "beta@example.com",
+            "account_name": "Beta",
+            "project_ids": [created["_id"]],
+            "created_at": { "$date": { "$numberLong": "0" } },
+            "updated_at": { "$date": { "$numberLong": "0" } },
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // An org key cannot switch tenants with the header
+    let (status, _) = send(&app, "GET", "/projects", beta, Some(&orgs[0]), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "GET", &format!("/organizations/{}", orgs[0]), beta, None, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, "GET", "/organizations", beta, None, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Org keys only see their own organization's keys
+    let (_, listed) = send(&app, "GET", "/api-keys", beta, None, None).await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["org_id"], json!(orgs[1]));
+
+    let (_, listed) = send(&app, "GET", "/projects", alpha, None, None).await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    let (_, listed) = send(&app, "GET", "/projects", BOOTSTRAP_KEY, Some(&orgs[0]), None).await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_member_keys_are_capped_by_role() {
+    let db = test_db("_tenants_roles").await;
+    db.drop(None).await.expect("Failed to drop database");
+    let app = app::router(test_state(db), CorsLayer::permissive());
+
+    let (_, org) = send(&app, "POST", "/organizations", BOOTSTRAP_KEY, None, Some(json!({ "name": "Roles" }))).await;
+    let org_id = oid(&org);
+    let (status, account) = send(
+        &app,
+        "POST",
+        "/accounts",
+        BOOTSTRAP_KEY,
+        Some(&org_id),
+        Some(json!({
+            "wallet_address": "0xabc",
+            "email": "viewer@example.com",
+            "account_name": "Viewer",
+            "created_at": { "$date": { "$numberLong": "0" } },
+            "updated_at": { "$date": { "$numberLong": "0" } },
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", account);
+    let account_id = oid(&account);
+    assert_eq!(account["org_id"]["$oid"], json!(org_id));
+
+    let member_uri = format!("/organizations/{}/members/{}", org_id, account_id);
+    let (status, _) = send(&app, "PUT", &member_uri, BOOTSTRAP_KEY, None, Some(json!({ "role": "viewer" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, created) = send(
+        &app,
+        "POST",
+        "/api-keys",
+        BOOTSTRAP_KEY,
+        None,
+        Some(json!({
+            "name": "viewer",
+            "scopes": ["projects:read", "projects:write"],
+            "org_id": org_id,
+            "account_id": account_id,
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let key = created["key"].as_str().unwrap().to_string();
+
+    let (status, _) = send(&app, "GET", "/projects", &key, None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "POST", "/projects", &key, None, Some(json!({}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Removing the member disables their keys
+    let (_, removed) = send(&app, "DELETE", &member_uri, BOOTSTRAP_KEY, None, None).await;
+    assert_eq!(removed, json!(true));
+    let (status, _) = send(&app, "GET", "/projects", &key, None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_deleting_an_account_removes_its_membership() {
+    let db = test_db("_tenants_delete").await;
+    db.drop(None).await.expect("Failed to drop database");
+    AccountRepository::new(db.clone()).ensure_indexes().await.expect("Failed to ensure indexes");
+    let app = app::router(test_state(db), CorsLayer::permissive());
+
+    let (_, org) = send(&app, "POST", "/organizations", BOOTSTRAP_KEY, None, Some(json!({ "name": "Members" }))).await;
+    let org_id = oid(&org);
+    let body = json!({
+        "wallet_address": "0xabc",
+        "email": "member@example.com",
+        "account_name": "Member",
+        "created_at": { "$date": { "$numberLong": "0" } },
+        "updated_at": { "$date": { "$numberLong": "0" } },
+    });
+    let (_, account) = send(&app, "POST", "/accounts", BOOTSTRAP_KEY, Some(&org_id), Some(body.clone())).await;
+    let account_id = oid(&account);
+    let (status, _) = send(&app, "POST", "/accounts", BOOTSTRAP_KEY, Some(&org_id), Some(body)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let member_uri = format!("/organizations/{}/members/{}", org_id, account_id);
+    send(&app, "PUT", &member_uri, BOOTSTRAP_KEY, None, Some(json!({ "role": "admin" }))).await;
+    let (_, deleted) = send(&app, "DELETE", &format!("/accounts/{}", account_id), BOOTSTRAP_KEY, Some(&org_id), None).await;
+    assert_eq!(deleted, json!(true));
+
+    let (_, org) = send(&app, "GET", &format!("/organizations/{}", org_id), BOOTSTRAP_KEY, None, None).await;
+    assert_eq!(org["members"], json!([]));
+}
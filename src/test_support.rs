@@ -0,0 +1,164 @@
+use dotenv::dotenv;
+use mongodb::{options::ClientOptions, Client, Database};
+use axum::async_trait;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    ad_platform_client::MockAdPlatformClient,
+    app::AppState,
+    config::{ApiConfig, FacebookConfig, LinksConfig, RateLimitConfig, Secret, WebhooksConfig},
+    graph_client::MockGraphClient,
+    middleware::rate_limit::RateLimiter,
+    repository::{
+        account_repository::AccountRepository, api_key_repository::ApiKeyRepository, event_id_repository::EventIdRepository,
+        idempotency_repository::IdempotencyRepository, lead_repository::LeadRepository, link_repository::LinkRepository,
+        organization_repository::OrganizationRepository,
+        outbox_repository::OutboxRepository, project_repository::ProjectRepository, webhook_delivery_repository::WebhookDeliveryRepository,
+        webhook_repository::WebhookRepository,
+    },
+    service::{
+        account_service::AccountService, api_key_service::ApiKeyService, event_stream_service::EventStreamService,
+        health_service::HealthService, idempotency_service::IdempotencyService,
+        ad_platform_service::AdPlatformService, lead_service::LeadService, link_service::LinkService,
+        organization_service::OrganizationService, project_service::ProjectService,
+        webhook_service::WebhookService,
+    },
+    telegram_client::TelegramNotifier,
+    worker::Workers,
+};
+
+pub const BOOTSTRAP_KEY: &str = "test-bootstrap-key-0123456789abcdef";
+pub const FACEBOOK_VERIFY_TOKEN: &str = "test-verify-token";
+pub const LINK_BASE_URL: &str = "https://go.example.com";
+
+/// Database on the test MongoDB server, suffixed so tests that drop
+/// collections do not race with each other.
+pub async fn test_db(suffix: &str) -> Database {
+    dotenv().ok();
+
+    let mongodb_uri = std::env::var("MONGODB_URL")
+        .unwrap_or_else(|_| "mongodb://localhost:27017".to_string());
+    let database_name = std::env::var("DATABASE_NAME")
+        .unwrap_or_else(|_| "test_database".to_string());
+
+    let client = Client::with_uri_str(&mongodb_uri)
+        .await
+        .expect("Failed to create MongoDB client");
+
+    client.database(&format!("{}{}", database_name, suffix))
+}
+
+/// Points at a port nothing listens on, so database calls fail fast without a server.
+pub async fn unreachable_db() -> Database {
+    let mut options = ClientOptions::parse("mongodb://127.0.0.1:1")
+        .await
+        .expect("Failed to parse MongoDB URI");
+    options.server_selection_timeout = Some(Duration::from_millis(100));
+    Client::with_options(options)
+        .expect("Failed to create MongoDB client")
+        .database("test_database")
+}
+
+/// Webhook service that may call local receivers and retries without waiting.
+pub fn test_webhook_service(db: Database, max_attempts: u32) -> WebhookService {
+    let config = WebhooksConfig {
+        max_attempts,
+        retry_base: Duration::ZERO,
+        allow_private_targets: true,
+    };
+    WebhookService::new(
+        WebhookRepository::new(db.clone()),
+        WebhookDeliveryRepository::new(db.clone()),
+        ProjectRepository::new(db),
+        &config,
+    )
+}
+
+/// Telegram notifier keeping the messages it was asked to send.
+#[derive(Clone, Default)]
+pub struct RecordingNotifier {
+    pub sent: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+#[async_trait]
+impl TelegramNotifier for RecordingNotifier {
+    async fn send_message(&self, chat_id: &str, text: &str) -> Result<(), String> {
+        self.sent.lock().unwrap().push((chat_id.to_string(), text.to_string()));
+        Ok(())
+    }
+}
+
+pub fn test_facebook_config() -> FacebookConfig {
+    FacebookConfig {
+        graph_api_url: "https://graph.facebook.com/v19.0".to_string(),
+        token_warning: Duration::from_secs(7 * 24 * 60 * 60),
+        webhook_verify_token: Some(Secret::new(FACEBOOK_VERIFY_TOKEN)),
+    }
+}
+
+pub fn test_lead_service(
+    db: Database,
+    graph: MockGraphClient,
+    notifier: Option<Arc<dyn TelegramNotifier>>,
+) -> LeadService {
+    LeadService::new(
+        LeadRepository::new(db.clone()),
+        ProjectRepository::new(db),
+        Arc::new(graph),
+        notifier,
+        &test_facebook_config(),
+    )
+}
+
+pub fn test_ad_platform_service(db: Database, client: MockAdPlatformClient) -> AdPlatformService {
+    AdPlatformService::new(
+        ProjectRepository::new(db.clone()),
+        EventIdRepository::new(db),
+        Arc::new(client),
+        Duration::from_secs(48 * 60 * 60),
+    )
+}
+
+pub fn test_state(db: Database) -> AppState {
+    let webhook_service = test_webhook_service(db.clone(), WebhooksConfig::default().max_attempts);
+    let api_key_service = ApiKeyService::new(
+        ApiKeyRepository::new(db.clone()),
+        OrganizationRepository::new(db.clone()),
+        Some(&Secret::new(BOOTSTRAP_KEY)),
+    );
+    AppState {
+        project_service: ProjectService::new(ProjectRepository::new(db.clone()), OutboxRepository::new(db.clone())),
+        account_service: AccountService::new(
+            AccountRepository::new(db.clone()),
+            ProjectRepository::new(db.clone()),
+            OrganizationRepository::new(db.clone()),
+            OutboxRepository::new(db.clone()),
+        ),
+        rate_limiter: RateLimiter::in_memory(RateLimitConfig::default()).with_keys(api_key_service.clone()),
+        api_key_service,
+        organization_service: OrganizationService::new(
+            OrganizationRepository::new(db.clone()),
+            AccountRepository::new(db.clone()),
+            OutboxRepository::new(db.clone()),
+        ),
+        idempotency_service: IdempotencyService::new(
+            IdempotencyRepository::new(db.clone()),
+            Duration::from_secs(60),
+        ),
+        lead_service: test_lead_service(db.clone(), MockGraphClient::new(), None),
+        ad_platform_service: test_ad_platform_service(db.clone(), MockAdPlatformClient::new()),
+        link_service: LinkService::new(
+            LinkRepository::new(db.clone()),
+            ProjectRepository::new(db.clone()),
+            &LinksConfig { base_url: Some(LINK_BASE_URL.to_string()) },
+        ),
+        health_service: HealthService::new(db.clone(), Workers::new(CancellationToken::new())),
+        webhook_service,
+        event_stream_service: EventStreamService::new(OutboxRepository::new(db), CancellationToken::new()),
+        api: ApiConfig::default(),
+    }
+}
@@ -0,0 +1,72 @@
+pub mod project_expiry_worker;
+pub mod outbox_dispatch_worker;
+pub mod webhook_delivery_worker;
+pub mod token_health_worker;
+
+use tracing::{info, warn};
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tokio::time::Instant;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+/// Owns the background tasks of the service and stops them through a shared
+/// cancellation token on shutdown.
+#[derive(Clone)]
+pub struct Workers {
+    token: CancellationToken,
+    tracker: TaskTracker,
+    running: Arc<Mutex<BTreeMap<&'static str, bool>>>,
+}
+
+impl Workers {
+    pub fn new(token: CancellationToken) -> Self {
+        Self {
+            token,
+            tracker: TaskTracker::new(),
+            running: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    pub fn spawn<F, Fut>(&self, name: &'static str, worker: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let task = worker(self.token.child_token());
+        let running = self.running.clone();
+        running.lock().unwrap().insert(name, true);
+        self.tracker.spawn(async move {
+            info!(worker = name, "Worker started");
+            task.await;
+            running.lock().unwrap().insert(name, false);
+            info!(worker = name, "Worker stopped");
+        });
+    }
+
+    /// Name and running state of every worker spawned so far.
+    pub fn statuses(&self) -> Vec<(&'static str, bool)> {
+        self.running
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, running)| (*name, *running))
+            .collect()
+    }
+
+    /// Cancels every worker and waits until `deadline` for them to return.
+    /// Returns `false` if some workers were still running at the deadline.
+    pub async fn shutdown(&self, deadline: Instant) -> bool {
+        self.token.cancel();
+        self.tracker.close();
+        match tokio::time::timeout_at(deadline, self.tracker.wait()).await {
+            Ok(()) => true,
+            Err(_) => {
+                warn!(remaining = self.tracker.len(), "Workers did not stop in time");
+                false
+            }
+        }
+    }
+}
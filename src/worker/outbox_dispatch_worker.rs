@@ -0,0 +1,25 @@
+use tracing::{error, debug};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::service::event_bus::EventBus;
+
+pub async fn run(bus: EventBus, interval: Duration, token: CancellationToken) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+
+        // Entries abandoned mid-dispatch are picked up again once their lease expires
+        tokio::select! {
+            _ = token.cancelled() => break,
+            result = bus.dispatch_due() => match result {
+                Ok(0) => {}
+                Ok(count) => debug!(count, "Dispatched outbox entries"),
+                Err(e) => error!(error = %e, "Failed to dispatch outbox entries"),
+            },
+        }
+    }
+}
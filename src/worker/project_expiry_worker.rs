@@ -0,0 +1,33 @@
+use tracing::{error, info};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::{metrics, service::project_service::ProjectService};
+
+const JOB_NAME: &str = "project_expiry";
+
+pub async fn run(service: ProjectService, interval: Duration, token: CancellationToken) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+
+        // A pass is idempotent, so it is safe to abandon it when shutting down
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = run_once(&service) => {}
+        }
+    }
+}
+
+async fn run_once(service: &ProjectService) {
+    let result = service.expire_projects().await;
+    metrics::global().record_job(JOB_NAME, result.is_ok());
+    match result {
+        Ok(0) => {}
+        Ok(count) => info!(count, "Expired projects"),
+        Err(e) => error!(error = %e, "Failed to expire projects"),
+    }
+}
@@ -0,0 +1,39 @@
+use tracing::{error, info};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::{metrics, service::token_health_service::TokenHealthService};
+
+const JOB_NAME: &str = "token_health";
+
+pub async fn run(service: TokenHealthService, interval: Duration, token: CancellationToken) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+
+        // Results are only stored per project, so an abandoned pass loses nothing already written
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = run_once(&service) => {}
+        }
+    }
+}
+
+async fn run_once(service: &TokenHealthService) {
+    let result = service.check_tokens().await;
+    metrics::global().record_job(JOB_NAME, result.is_ok());
+    match result {
+        Ok(summary) => info!(
+            checked = summary.checked,
+            exchanged = summary.exchanged,
+            invalid = summary.invalid,
+            warned = summary.warned,
+            failed = summary.failed,
+            "Checked Facebook access tokens"
+        ),
+        Err(e) => error!(error = %e, "Failed to check Facebook access tokens"),
+    }
+}
@@ -0,0 +1,33 @@
+use tracing::{error, info};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::{metrics, service::webhook_service::WebhookService};
+
+const JOB_NAME: &str = "webhook_delivery";
+
+pub async fn run(service: WebhookService, interval: Duration, token: CancellationToken) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+
+        // Deliveries abandoned mid-send are picked up again once their lease expires
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = run_once(&service) => {}
+        }
+    }
+}
+
+async fn run_once(service: &WebhookService) {
+    let result = service.deliver_due().await;
+    metrics::global().record_job(JOB_NAME, result.is_ok());
+    match result {
+        Ok(0) => {}
+        Ok(count) => info!(count, "Attempted webhook deliveries"),
+        Err(e) => error!(error = %e, "Failed to deliver webhooks"),
+    }
+}